@@ -0,0 +1,117 @@
+//! ARP / 鄰居快取：GetIpNetTable2 同時回傳 IPv4 ARP 與 IPv6 Neighbor Discovery 項目
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use windows::Win32::{
+    NetworkManagement::IpHelper::{FreeMibTable, GetIpNetTable2, MIB_IPNET_TABLE2},
+    Networking::WinSock::{AF_UNSPEC, SOCKADDR},
+};
+
+use crate::{
+    net::interface_alias,
+    win::{format_mac, sockaddr_to_ip},
+    MetricError,
+};
+
+/// 路由器型主機的快取可能有上千筆，預設只回傳前 500 筆
+const DEFAULT_LIMIT: usize = 500;
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct ArpEntry {
+    pub ip: String,
+    /// 尚未解析完成（incomplete）時為 None
+    pub mac: Option<String>,
+    pub interface: Option<String>,
+    /// unreachable / incomplete / probe / delay / stale / reachable / permanent
+    pub state: String,
+}
+
+#[derive(Deserialize, Default)]
+pub struct ArpQuery {
+    /// 介面名稱（不分大小寫）
+    #[serde(rename = "if")]
+    pub interface: Option<String>,
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub offset: usize,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+/// 依介面、IP 排序後以 offset / limit 分頁
+pub fn gather_arp(query: &ArpQuery, errors: &mut Vec<MetricError>) -> Vec<ArpEntry> {
+    let mut entries = match query_table() {
+        Ok(list) => list,
+        Err(e) => {
+            errors.push(MetricError {
+                metric: vec!["arp".into()],
+                err: e,
+            });
+            return Vec::new();
+        }
+    };
+
+    if let Some(name) = &query.interface {
+        entries.retain(|e| {
+            e.interface
+                .as_deref()
+                .is_some_and(|i| i.eq_ignore_ascii_case(name))
+        });
+    }
+    entries.sort_by(|a, b| (&a.interface, &a.ip).cmp(&(&b.interface, &b.ip)));
+    entries
+        .into_iter()
+        .skip(query.offset)
+        .take(query.limit.unwrap_or(DEFAULT_LIMIT))
+        .collect()
+}
+
+fn query_table() -> Result<Vec<ArpEntry>, String> {
+    unsafe {
+        let mut table: *mut MIB_IPNET_TABLE2 = std::ptr::null_mut();
+        let status = GetIpNetTable2(AF_UNSPEC, &mut table);
+        if status.is_err() {
+            return Err(format!("GetIpNetTable2 failed: {}", status.0));
+        }
+        let rows =
+            std::slice::from_raw_parts((*table).Table.as_ptr(), (*table).NumEntries as usize);
+
+        // 同一介面的項目很多，名稱只查一次
+        let mut aliases: HashMap<u64, Option<String>> = HashMap::new();
+        let entries = rows
+            .iter()
+            .filter_map(|r| {
+                let ip = sockaddr_to_ip(&r.Address as *const _ as *const SOCKADDR)?;
+                let len = (r.PhysicalAddressLength as usize).min(r.PhysicalAddress.len());
+                let interface = aliases
+                    .entry(r.InterfaceLuid.Value)
+                    .or_insert_with(|| interface_alias(&r.InterfaceLuid))
+                    .clone();
+                Some(ArpEntry {
+                    ip: ip.to_string(),
+                    mac: (len > 0).then(|| format_mac(&r.PhysicalAddress[..len])),
+                    interface,
+                    state: state_name(r.State.0).into(),
+                })
+            })
+            .collect();
+
+        FreeMibTable(table as *const _);
+        Ok(entries)
+    }
+}
+
+/// NL_NEIGHBOR_STATE
+fn state_name(state: i32) -> &'static str {
+    match state {
+        0 => "unreachable",
+        1 => "incomplete",
+        2 => "probe",
+        3 => "delay",
+        4 => "stale",
+        5 => "reachable",
+        6 => "permanent",
+        _ => "unknown",
+    }
+}
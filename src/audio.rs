@@ -0,0 +1,182 @@
+//! 音訊裝置：MMDevice API 列出播放與錄音端點，預設裝置另讀主音量與靜音
+//!
+//! COM 呼叫放在 spawn_blocking 的執行緒上，由 ComGuard 初始化 MTA；
+//! 所有 COM 介面都在 ComGuard 之前 drop，確保 CoUninitialize 時沒有殘留的參考。
+use serde::Serialize;
+use windows::{
+    core::PWSTR,
+    Win32::{
+        Devices::FunctionDiscovery::PKEY_Device_FriendlyName,
+        Media::Audio::{
+            eCapture, eConsole, eRender, EDataFlow, Endpoints::IAudioEndpointVolume, IMMDevice,
+            IMMDeviceEnumerator, MMDeviceEnumerator, DEVICE_STATE, DEVICE_STATEMASK_ALL,
+            DEVICE_STATE_ACTIVE, DEVICE_STATE_DISABLED, DEVICE_STATE_NOTPRESENT,
+            DEVICE_STATE_UNPLUGGED,
+        },
+        System::Com::{CoCreateInstance, CoTaskMemFree, CLSCTX_INPROC_SERVER, STGM_READ},
+    },
+};
+
+use crate::{com::ComGuard, MetricError};
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct AudioEndpoint {
+    /// MMDevice 端點 ID
+    pub id: String,
+    pub name: Option<String>,
+    /// playback / capture
+    pub flow: &'static str,
+    /// active / disabled / unplugged / not_present
+    pub state: &'static str,
+    /// 是否為該方向的預設（主控台）裝置
+    pub default: bool,
+    /// 0–1；只有預設裝置才讀取
+    pub volume_percent: Option<f32>,
+    pub muted: Option<bool>,
+}
+
+#[derive(Serialize, Default)]
+pub struct AudioData {
+    pub endpoints: Vec<AudioEndpoint>,
+    /// 沒有任何 active 的播放端點；數位看板「沒有聲音」最常見的原因
+    pub no_active_playback: bool,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub async fn gather_audio(errors: &mut Vec<MetricError>) -> AudioData {
+    let result = tokio::task::spawn_blocking(query_audio)
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+    match result {
+        Ok((data, mut errs)) => {
+            errors.append(&mut errs);
+            data
+        }
+        Err(e) => {
+            errors.push(MetricError {
+                metric: vec!["audio".into()],
+                err: e,
+            });
+            AudioData::default()
+        }
+    }
+}
+
+fn query_audio() -> Result<(AudioData, Vec<MetricError>), String> {
+    let _com = ComGuard::init()?;
+    let mut errors = Vec::new();
+    let mut endpoints = Vec::new();
+    {
+        let enumerator: IMMDeviceEnumerator =
+            unsafe { CoCreateInstance(&MMDeviceEnumerator, None, CLSCTX_INPROC_SERVER) }
+                .map_err(|e| format!("MMDeviceEnumerator unavailable: {e}"))?;
+        for (flow, name) in [(eRender, "playback"), (eCapture, "capture")] {
+            if let Err(err) = list_flow(&enumerator, flow, name, &mut endpoints, &mut errors) {
+                errors.push(MetricError {
+                    metric: vec![format!("audio.{name}")],
+                    err,
+                });
+            }
+        }
+    }
+    let no_active_playback = !endpoints
+        .iter()
+        .any(|e| e.flow == "playback" && e.state == "active");
+    Ok((
+        AudioData {
+            endpoints,
+            no_active_playback,
+        },
+        errors,
+    ))
+}
+
+fn list_flow(
+    enumerator: &IMMDeviceEnumerator,
+    flow: EDataFlow,
+    flow_name: &'static str,
+    endpoints: &mut Vec<AudioEndpoint>,
+    errors: &mut Vec<MetricError>,
+) -> Result<(), String> {
+    // 沒有預設裝置（例如所有播放端點都被停用）時 GetDefaultAudioEndpoint 會失敗，不算錯誤
+    let default_id = unsafe { enumerator.GetDefaultAudioEndpoint(flow, eConsole) }
+        .ok()
+        .and_then(|d| device_id(&d));
+    let devices =
+        unsafe { enumerator.EnumAudioEndpoints(flow, DEVICE_STATE(DEVICE_STATEMASK_ALL)) }
+            .map_err(|e| format!("EnumAudioEndpoints failed: {e}"))?;
+    let count = unsafe { devices.GetCount() }.map_err(|e| format!("GetCount failed: {e}"))?;
+
+    for i in 0..count {
+        let Ok(device) = (unsafe { devices.Item(i) }) else {
+            continue;
+        };
+        let Some(id) = device_id(&device) else {
+            continue;
+        };
+        let default = default_id.as_deref() == Some(id.as_str());
+        let (volume_percent, muted) = if default {
+            match volume(&device) {
+                Ok((v, m)) => (Some(v), Some(m)),
+                Err(err) => {
+                    errors.push(MetricError {
+                        metric: vec![format!("audio.{flow_name}.volume")],
+                        err,
+                    });
+                    (None, None)
+                }
+            }
+        } else {
+            (None, None)
+        };
+        endpoints.push(AudioEndpoint {
+            name: friendly_name(&device),
+            flow: flow_name,
+            state: state_name(&device),
+            default,
+            volume_percent,
+            muted,
+            id,
+        });
+    }
+    Ok(())
+}
+
+/// GetId 回傳的字串由 COM 配置，讀完要 CoTaskMemFree
+fn device_id(device: &IMMDevice) -> Option<String> {
+    let id: PWSTR = unsafe { device.GetId() }.ok()?;
+    let s = unsafe { id.to_string() }.ok();
+    unsafe { CoTaskMemFree(Some(id.0 as *const _)) };
+    s
+}
+
+fn friendly_name(device: &IMMDevice) -> Option<String> {
+    let store = unsafe { device.OpenPropertyStore(STGM_READ) }.ok()?;
+    let value = unsafe { store.GetValue(&PKEY_Device_FriendlyName) }.ok()?;
+    Some(value.to_string()).filter(|s| !s.is_empty())
+}
+
+fn state_name(device: &IMMDevice) -> &'static str {
+    let mut state = 0u32;
+    let _ = unsafe { device.GetState(&mut state) };
+    match DEVICE_STATE(state) {
+        DEVICE_STATE_ACTIVE => "active",
+        DEVICE_STATE_DISABLED => "disabled",
+        DEVICE_STATE_UNPLUGGED => "unplugged",
+        DEVICE_STATE_NOTPRESENT => "not_present",
+        _ => "unknown",
+    }
+}
+
+/// (主音量 0–1, 是否靜音)
+fn volume(device: &IMMDevice) -> Result<(f32, bool), String> {
+    let endpoint: IAudioEndpointVolume = unsafe { device.Activate(CLSCTX_INPROC_SERVER, None) }
+        .map_err(|e| format!("IAudioEndpointVolume unavailable: {e}"))?;
+    let level = unsafe { endpoint.GetMasterVolumeLevelScalar() }
+        .map_err(|e| format!("GetMasterVolumeLevelScalar failed: {e}"))?;
+    let muted = unsafe { endpoint.GetMute() }.map_err(|e| format!("GetMute failed: {e}"))?;
+    Ok((level, muted.as_bool()))
+}
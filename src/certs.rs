@@ -0,0 +1,200 @@
+//! 憑證到期：列舉 LocalMachine 憑證存放區（預設 My，CAPTURE_CERT_STORES 可改）
+//!
+//! - /api/v1/metrics/certs?expiring_within=30 回傳每一張憑證
+//! - /api/v1/metrics 只放摘要：CAPTURE_CERT_EXPIRY_DAYS（預設 30）天內到期與已過期的張數
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+use windows::{
+    core::HSTRING,
+    Win32::Security::Cryptography::{
+        CertCloseStore, CertEnumCertificatesInStore, CertGetCertificateContextProperty,
+        CertGetNameStringW, CertOpenStore, CERT_CONTEXT, CERT_NAME_ISSUER_FLAG,
+        CERT_NAME_SIMPLE_DISPLAY_TYPE, CERT_OPEN_STORE_FLAGS, CERT_QUERY_ENCODING_TYPE,
+        CERT_SHA1_HASH_PROP_ID, CERT_STORE_OPEN_EXISTING_FLAG, CERT_STORE_PROV_SYSTEM_W,
+        CERT_STORE_READONLY_FLAG, CERT_SYSTEM_STORE_LOCAL_MACHINE_ID, HCERTSTORE,
+    },
+};
+
+use crate::{config, win::filetime_to_datetime, MetricError};
+
+/// CERT_SYSTEM_STORE_LOCAL_MACHINE：位置 ID 左移 CERT_SYSTEM_STORE_LOCATION_SHIFT（16）
+const LOCAL_MACHINE: u32 = CERT_SYSTEM_STORE_LOCAL_MACHINE_ID << 16;
+
+static STORES: LazyLock<Vec<String>> = LazyLock::new(|| {
+    let stores = config::env_list("CAPTURE_CERT_STORES");
+    if stores.is_empty() {
+        vec!["My".into()]
+    } else {
+        stores
+    }
+});
+static EXPIRY_DAYS: LazyLock<i64> =
+    LazyLock::new(|| config::env_parse("CAPTURE_CERT_EXPIRY_DAYS", 30));
+
+/// Drop 時 CertCloseStore
+struct Store(HCERTSTORE);
+
+impl Drop for Store {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CertCloseStore(self.0, 0);
+        }
+    }
+}
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct CertData {
+    pub store: String,
+    pub subject: String,
+    pub issuer: String,
+    /// SHA-1 指紋，大寫十六進位（與 certlm.msc 顯示的相同）
+    pub thumbprint: String,
+    /// RFC3339（UTC）
+    pub not_after: String,
+    /// 已過期時為負數
+    pub days_until_expiry: i64,
+}
+
+#[derive(Serialize)]
+pub struct CertSummary {
+    pub total: usize,
+    /// 摘要使用的天數（CAPTURE_CERT_EXPIRY_DAYS）
+    pub window_days: i64,
+    /// window_days 天內到期、尚未過期
+    pub expiring_count: usize,
+    pub expired_count: usize,
+}
+
+#[derive(Deserialize)]
+pub struct CertQuery {
+    /// 只回傳這麼多天內到期（含已過期）的憑證
+    pub expiring_within: Option<i64>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub fn gather_certs(q: &CertQuery, errors: &mut Vec<MetricError>) -> Vec<CertData> {
+    let mut certs: Vec<CertData> = STORES
+        .iter()
+        .flat_map(|store| match read_store(store) {
+            Ok(list) => list,
+            Err(e) => {
+                errors.push(MetricError {
+                    metric: vec![format!("certs.{store}")],
+                    err: e,
+                });
+                Vec::new()
+            }
+        })
+        .filter(|c| q.expiring_within.is_none_or(|d| c.days_until_expiry <= d))
+        .collect();
+    certs.sort_by_key(|c| c.days_until_expiry);
+    certs
+}
+
+pub fn gather_cert_summary(errors: &mut Vec<MetricError>) -> CertSummary {
+    let certs = gather_certs(
+        &CertQuery {
+            expiring_within: None,
+        },
+        errors,
+    );
+    let window_days = *EXPIRY_DAYS;
+    CertSummary {
+        total: certs.len(),
+        window_days,
+        expiring_count: certs
+            .iter()
+            .filter(|c| (0..=window_days).contains(&c.days_until_expiry))
+            .count(),
+        expired_count: certs.iter().filter(|c| c.days_until_expiry < 0).count(),
+    }
+}
+
+fn read_store(name: &str) -> Result<Vec<CertData>, String> {
+    let wide = HSTRING::from(name);
+    let store = unsafe {
+        CertOpenStore(
+            CERT_STORE_PROV_SYSTEM_W,
+            CERT_QUERY_ENCODING_TYPE(0),
+            None,
+            CERT_OPEN_STORE_FLAGS(LOCAL_MACHINE)
+                | CERT_STORE_READONLY_FLAG
+                | CERT_STORE_OPEN_EXISTING_FLAG,
+            Some(wide.as_ptr().cast()),
+        )
+    }
+    .map(Store)
+    .map_err(|e| format!("CertOpenStore(LocalMachine\\{name}) failed: {e}"))?;
+
+    let now = Utc::now();
+    let mut certs = Vec::new();
+    let mut ctx: *mut CERT_CONTEXT = std::ptr::null_mut();
+    loop {
+        // 傳入前一個 context 會由 API 釋放，迴圈跑完不需另外 CertFreeCertificateContext
+        ctx = unsafe {
+            CertEnumCertificatesInStore(store.0, (!ctx.is_null()).then_some(ctx as *const _))
+        };
+        if ctx.is_null() {
+            break;
+        }
+        let Some(not_after) = not_after(ctx) else {
+            continue;
+        };
+        certs.push(CertData {
+            store: name.into(),
+            subject: cert_name(ctx, 0),
+            issuer: cert_name(ctx, CERT_NAME_ISSUER_FLAG),
+            thumbprint: thumbprint(ctx).unwrap_or_default(),
+            not_after: not_after.to_rfc3339(),
+            days_until_expiry: (not_after - now).num_days(),
+        });
+    }
+    Ok(certs)
+}
+
+/// 憑證的到期時間；HTTP 探測取得的伺服器憑證也用這個
+pub fn not_after(ctx: *const CERT_CONTEXT) -> Option<DateTime<Utc>> {
+    let info = unsafe { &*(*ctx).pCertInfo };
+    let ft = ((info.NotAfter.dwHighDateTime as i64) << 32) | info.NotAfter.dwLowDateTime as i64;
+    filetime_to_datetime(ft)
+}
+
+/// 回傳值為字元數（含 NUL），沒有名稱時為只有 NUL 的 1
+fn cert_name(ctx: *const CERT_CONTEXT, flags: u32) -> String {
+    unsafe {
+        let len = CertGetNameStringW(ctx, CERT_NAME_SIMPLE_DISPLAY_TYPE, flags, None, None);
+        let mut buf = vec![0u16; len as usize];
+        CertGetNameStringW(
+            ctx,
+            CERT_NAME_SIMPLE_DISPLAY_TYPE,
+            flags,
+            None,
+            Some(&mut buf),
+        );
+        String::from_utf16_lossy(&buf[..len.saturating_sub(1) as usize])
+    }
+}
+
+fn thumbprint(ctx: *const CERT_CONTEXT) -> Option<String> {
+    let mut hash = [0u8; 20];
+    let mut size = hash.len() as u32;
+    unsafe {
+        CertGetCertificateContextProperty(
+            ctx,
+            CERT_SHA1_HASH_PROP_ID,
+            Some(hash.as_mut_ptr().cast()),
+            &mut size,
+        )
+    }
+    .ok()?;
+    Some(
+        hash[..size as usize]
+            .iter()
+            .map(|b| format!("{b:02X}"))
+            .collect(),
+    )
+}
@@ -0,0 +1,30 @@
+//! COM 初始化：每個 blocking 執行緒自行 CoInitializeEx，離開時配對 CoUninitialize
+use windows::Win32::{
+    Foundation::RPC_E_CHANGED_MODE,
+    System::Com::{CoInitializeEx, CoUninitialize, COINIT_MULTITHREADED},
+};
+
+/// 持有期間目前執行緒處於 MTA；Drop 時只在確實由自己初始化時才反初始化
+pub struct ComGuard {
+    owned: bool,
+}
+
+impl ComGuard {
+    pub fn init() -> Result<Self, String> {
+        let hr = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) };
+        if hr == RPC_E_CHANGED_MODE {
+            // 執行緒已被別人設成 STA，仍可使用 COM，但不能由我們反初始化
+            return Ok(Self { owned: false });
+        }
+        hr.ok().map_err(|e| format!("CoInitializeEx failed: {e}"))?;
+        Ok(Self { owned: true })
+    }
+}
+
+impl Drop for ComGuard {
+    fn drop(&mut self) {
+        if self.owned {
+            unsafe { CoUninitialize() };
+        }
+    }
+}
@@ -0,0 +1,36 @@
+//! 設定：目前全部來自環境變數
+/// 逗號分隔的清單，空白項目略過；未設定時為空陣列
+pub fn env_list(key: &str) -> Vec<String> {
+    std::env::var(key)
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// 單一字串設定；未設定或只有空白時為 None
+pub fn env_string(key: &str) -> Option<String> {
+    std::env::var(key)
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+}
+
+/// 數值型設定；未設定或格式不對時使用預設值
+pub fn env_parse<T: std::str::FromStr>(key: &str, default: T) -> T {
+    std::env::var(key)
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(default)
+}
+
+/// 開關型設定：1 / true / yes / on（不分大小寫）為開啟，其餘與未設定皆為關閉
+pub fn env_flag(key: &str) -> bool {
+    env_string(key).is_some_and(|v| {
+        ["1", "true", "yes", "on"]
+            .iter()
+            .any(|t| v.eq_ignore_ascii_case(t))
+    })
+}
@@ -0,0 +1,158 @@
+//! CPU 型號與拓撲：sysinfo 的 brand / vendor、CPUID 的 family / model / stepping，
+//! GetLogicalProcessorInformationEx 的快取大小與核心類型（混合架構的 P/E 核心）
+//!
+//! 執行期間不會變動，第一次讀取後快取。
+use serde::Serialize;
+use std::{collections::BTreeSet, sync::LazyLock};
+use sysinfo::{CpuRefreshKind, RefreshKind, System};
+use windows::Win32::System::SystemInformation::{
+    GetLogicalProcessorInformationEx, RelationAll, RelationCache, RelationProcessorCore,
+    SYSTEM_LOGICAL_PROCESSOR_INFORMATION_EX,
+};
+
+use crate::MetricError;
+
+static CACHE: LazyLock<(CpuIdentity, Option<String>)> = LazyLock::new(read_identity);
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize, Clone, Default)]
+pub struct CpuIdentity {
+    /// 例如 `13th Gen Intel(R) Core(TM) i7-13700K`
+    pub model_name: Option<String>,
+    /// GenuineIntel / AuthenticAMD…
+    pub vendor: Option<String>,
+    /// CPUID 的顯示值（已合併 extended family / model）；非 x86 為 None
+    pub family: Option<u32>,
+    pub model: Option<u32>,
+    pub stepping: Option<u32>,
+    /// 全部核心的總和，L1 含指令與資料快取
+    pub l1_cache_bytes: Option<u64>,
+    pub l2_cache_bytes: Option<u64>,
+    pub l3_cache_bytes: Option<u64>,
+    /// 核心的 EfficiencyClass 不只一種時為 true
+    pub is_hybrid: bool,
+    /// 只有混合架構才有值；EfficiencyClass 最高者為 P 核心
+    pub performance_core_count: Option<u32>,
+    pub efficiency_core_count: Option<u32>,
+}
+
+/// 拓撲統計，不輸出
+#[derive(Default)]
+struct Topology {
+    cache: [u64; 3],
+    core_classes: Vec<u8>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+/// 啟動時先算好，第一個請求不必等
+pub fn preload() {
+    LazyLock::force(&CACHE);
+}
+
+pub fn gather_identity(errors: &mut Vec<MetricError>) -> CpuIdentity {
+    let (identity, err) = &*CACHE;
+    if let Some(e) = err {
+        errors.push(MetricError {
+            metric: vec!["cpu.topology".into()],
+            err: e.clone(),
+        });
+    }
+    identity.clone()
+}
+
+fn read_identity() -> (CpuIdentity, Option<String>) {
+    let sys =
+        System::new_with_specifics(RefreshKind::nothing().with_cpu(CpuRefreshKind::nothing()));
+    let first = sys.cpus().first();
+    let mut id = CpuIdentity {
+        model_name: first.map(|c| c.brand().trim().to_string()),
+        vendor: first.map(|c| c.vendor_id().to_string()),
+        ..Default::default()
+    };
+    if let Some((family, model, stepping)) = cpuid_signature() {
+        id.family = Some(family);
+        id.model = Some(model);
+        id.stepping = Some(stepping);
+    }
+
+    let topo = match query_topology() {
+        Ok(t) => t,
+        Err(e) => return (id, Some(e)),
+    };
+    let level = |i: usize| (topo.cache[i] > 0).then_some(topo.cache[i]);
+    id.l1_cache_bytes = level(0);
+    id.l2_cache_bytes = level(1);
+    id.l3_cache_bytes = level(2);
+
+    let classes: BTreeSet<u8> = topo.core_classes.iter().copied().collect();
+    id.is_hybrid = classes.len() > 1;
+    if id.is_hybrid {
+        let top = classes.last().copied().unwrap_or(0);
+        let p = topo.core_classes.iter().filter(|&&c| c == top).count() as u32;
+        id.performance_core_count = Some(p);
+        id.efficiency_core_count = Some(topo.core_classes.len() as u32 - p);
+    }
+    (id, None)
+}
+
+/// CPUID leaf 1 的 EAX → (family, model, stepping)
+#[cfg(target_arch = "x86_64")]
+fn cpuid_signature() -> Option<(u32, u32, u32)> {
+    let eax = std::arch::x86_64::__cpuid(1).eax;
+    let stepping = eax & 0xF;
+    let base_model = (eax >> 4) & 0xF;
+    let base_family = (eax >> 8) & 0xF;
+    let ext_model = (eax >> 16) & 0xF;
+    let ext_family = (eax >> 20) & 0xFF;
+
+    let family = if base_family == 0xF {
+        base_family + ext_family
+    } else {
+        base_family
+    };
+    let model = if base_family == 0x6 || base_family == 0xF {
+        (ext_model << 4) + base_model
+    } else {
+        base_model
+    };
+    Some((family, model, stepping))
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn cpuid_signature() -> Option<(u32, u32, u32)> {
+    None
+}
+
+fn query_topology() -> Result<Topology, String> {
+    unsafe {
+        let mut len = 0u32;
+        let _ = GetLogicalProcessorInformationEx(RelationAll, None, &mut len);
+        let mut buf = vec![0u64; (len as usize).div_ceil(8)];
+        GetLogicalProcessorInformationEx(RelationAll, Some(buf.as_mut_ptr().cast()), &mut len)
+            .map_err(|e| format!("GetLogicalProcessorInformationEx failed: {e}"))?;
+
+        // 每筆紀錄長度不一，依 Size 前進
+        let base = buf.as_ptr() as *const u8;
+        let mut topo = Topology::default();
+        let mut offset = 0usize;
+        while offset < len as usize {
+            let info = &*(base.add(offset) as *const SYSTEM_LOGICAL_PROCESSOR_INFORMATION_EX);
+            if info.Relationship == RelationProcessorCore {
+                topo.core_classes
+                    .push(info.Anonymous.Processor.EfficiencyClass);
+            } else if info.Relationship == RelationCache {
+                let cache = &info.Anonymous.Cache;
+                if (1..=3).contains(&cache.Level) {
+                    topo.cache[cache.Level as usize - 1] += cache.CacheSize as u64;
+                }
+            }
+            if info.Size == 0 {
+                break;
+            }
+            offset += info.Size as usize;
+        }
+        Ok(topo)
+    }
+}
@@ -0,0 +1,100 @@
+//! 藍底當機紀錄：System 記錄中最新的 BugCheck 事件（Event ID 1001），以及 %SystemRoot%\Minidump 的傾印檔
+//!
+//! 兩個來源各自獨立，受限帳號讀不到其中一個時只有該部分為 None 並記錄在 errors。
+use serde::Serialize;
+use std::{io::ErrorKind, path::PathBuf, time::SystemTime};
+
+use crate::{eventlog, MetricError};
+
+/// WER 在重新開機後寫入的 BugCheck 事件
+const BUGCHECK_XPATH: &str =
+    "*[System[Provider[@Name='Microsoft-Windows-WER-SystemErrorReporting'] and EventID=1001]]";
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize, Default)]
+pub struct CrashData {
+    /// RFC3339（UTC）；從未藍底或事件已被清除時為 None
+    pub last_bugcheck_time: Option<String>,
+    /// 例如 `0x0000009f`
+    pub bugcheck_code: Option<String>,
+    /// 資料夾不存在時為 0
+    pub minidump_count: Option<usize>,
+    pub newest_minidump_age_seconds: Option<u64>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub async fn gather_crash(errors: &mut Vec<MetricError>) -> CrashData {
+    let mut data = CrashData::default();
+
+    // EvtQuery 最多等 5 秒、讀資料夾也是同步 I/O，兩者在同一個 blocking 執行緒依序執行
+    let (event, dumps) = tokio::task::spawn_blocking(|| {
+        (
+            eventlog::latest_event("System", BUGCHECK_XPATH),
+            minidumps(),
+        )
+    })
+    .await
+    .unwrap_or_else(|e| (Err(e.to_string()), Err(e.to_string())));
+
+    match event {
+        Ok(Some(event)) => {
+            data.last_bugcheck_time = event.time;
+            // param1 形如 `0x0000009f (0xffffe001..., ...)`，只取停止碼
+            data.bugcheck_code = event
+                .strings
+                .first()
+                .and_then(|p| p.split_whitespace().next())
+                .map(str::to_lowercase);
+        }
+        Ok(None) => {}
+        Err(e) => errors.push(MetricError {
+            metric: vec!["crash.bugcheck".into()],
+            err: e,
+        }),
+    }
+
+    match dumps {
+        Ok((count, newest)) => {
+            data.minidump_count = Some(count);
+            data.newest_minidump_age_seconds = newest
+                .and_then(|t| SystemTime::now().duration_since(t).ok())
+                .map(|d| d.as_secs());
+        }
+        Err(e) => errors.push(MetricError {
+            metric: vec!["crash.minidump".into()],
+            err: e,
+        }),
+    }
+    data
+}
+
+/// (.dmp 檔數, 最新的修改時間)
+fn minidumps() -> Result<(usize, Option<SystemTime>), String> {
+    let root = std::env::var("SystemRoot").unwrap_or_else(|_| r"C:\Windows".into());
+    let dir = PathBuf::from(root).join("Minidump");
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(e) => e,
+        // 從未產生過傾印時資料夾不存在
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok((0, None)),
+        Err(e) => return Err(format!("{}: {e}", dir.display())),
+    };
+
+    let mut count = 0;
+    let mut newest: Option<SystemTime> = None;
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("dmp"))
+        {
+            continue;
+        }
+        count += 1;
+        if let Ok(modified) = entry.metadata().and_then(|m| m.modified()) {
+            newest = newest.max(Some(modified));
+        }
+    }
+    Ok((count, newest))
+}
@@ -0,0 +1,233 @@
+//! CSV：/api/v1/metrics?format=csv，一列一個樣本，給 Excel 之類的工具直接開啟
+//!
+//! 資料來自 samples::collect，與 Prometheus、Influx 等格式同一份扁平化結果。
+//! metric 是 dotted_name 表裡對應 JSON 欄位路徑的點分名稱（`disk.C:.free_bytes`），labels 為 `device=C:;…`，
+//! timestamp 為 RFC3339（UTC）。開頭帶 UTF-8 BOM，Excel 才不會把中文介面名稱解成亂碼。
+use chrono::Utc;
+use std::fmt::Write;
+
+use crate::samples::Sample;
+
+pub const CONTENT_TYPE: &str = "text/csv; charset=utf-8";
+/// 欄位順序固定，新增欄位只能加在最後
+const COLUMNS: &[&str] = &["metric", "labels", "value", "timestamp"];
+
+pub fn render(samples: &[Sample]) -> String {
+    let timestamp = Utc::now().to_rfc3339();
+    let mut out = String::from("\u{feff}");
+    let _ = write!(out, "{}\r\n", COLUMNS.join(","));
+    for s in samples {
+        let labels: Vec<String> = s.labels.iter().map(|(k, v)| format!("{k}={v}")).collect();
+        let row = [
+            dotted_name(s),
+            labels.join(";"),
+            s.value.to_string(),
+            timestamp.clone(),
+        ];
+        let row: Vec<String> = row.iter().map(|f| field(f)).collect();
+        let _ = write!(out, "{}\r\n", row.join(","));
+    }
+    out
+}
+
+/// 名稱中的 `{標籤}` 換成該標籤的值；表裡沒有的樣本保留原名
+fn dotted_name(s: &Sample) -> String {
+    let direction = s.labels.iter().find(|(k, _)| *k == "direction");
+    let Some(template) = template(s.name, direction.map(|(_, v)| v.as_str())) else {
+        return s.name.to_string();
+    };
+    let mut name = template.to_string();
+    for (k, v) in &s.labels {
+        name = name.replace(&format!("{{{k}}}"), v);
+    }
+    name
+}
+
+/// 樣本名稱（與 direction 標籤）→ /api/v1/metrics JSON 裡的欄位路徑。值是 samples 換算過的
+/// 基本單位，和 JSON 單位不同的欄位（頻率、ping 延遲、開機時間）改以單位結尾
+fn template(name: &str, direction: Option<&str>) -> Option<&'static str> {
+    Some(match (name, direction) {
+        ("cpu_usage_ratio", _) => "cpu.usage_percent",
+        ("cpu_core_usage_ratio", _) => "cpu.per_core_usage.{core}",
+        ("cpu_user_ratio", _) => "cpu.user_percent",
+        ("cpu_kernel_ratio", _) => "cpu.kernel_percent",
+        ("cpu_frequency_hertz", _) => "cpu.current_frequency_hertz",
+        ("cpu_max_frequency_hertz", _) => "cpu.frequency_hertz",
+        ("cpu_temperature_celsius", _) => "cpu.temperature_c",
+        ("cpu_logical_cores", _) => "cpu.logical_core",
+        ("cpu_context_switches_per_second", _) => "cpu.context_switches_per_sec",
+        ("cpu_interrupts_per_second", _) => "cpu.interrupts_per_sec",
+        ("memory_total_bytes", _) => "memory.total_bytes",
+        ("memory_available_bytes", _) => "memory.available_bytes",
+        ("memory_used_bytes", _) => "memory.used_bytes",
+        ("memory_usage_ratio", _) => "memory.usage_percent",
+        ("memory_committed_bytes", _) => "memory.committed_bytes",
+        ("memory_commit_limit_bytes", _) => "memory.commit_limit_bytes",
+        ("memory_paged_pool_bytes", _) => "memory.paged_pool_bytes",
+        ("memory_nonpaged_pool_bytes", _) => "memory.nonpaged_pool_bytes",
+        ("memory_pages_per_second", _) => "memory.pages_per_sec",
+        ("swap_total_bytes", _) => "swap.total_bytes",
+        ("swap_used_bytes", _) => "swap.used_bytes",
+        ("disk_total_bytes", _) => "disk.{device}.total_bytes",
+        ("disk_free_bytes", _) => "disk.{device}.free_bytes",
+        ("disk_used_bytes", _) => "disk.{device}.used_bytes",
+        ("disk_usage_ratio", _) => "disk.{device}.usage_percent",
+        ("disk_read_bytes_per_second", _) => "disk.{device}.read_bytes_per_sec",
+        ("disk_write_bytes_per_second", _) => "disk.{device}.write_bytes_per_sec",
+        ("disk_reads_per_second", _) => "disk.{device}.reads_per_sec",
+        ("disk_writes_per_second", _) => "disk.{device}.writes_per_sec",
+        ("disk_queue_length", _) => "disk.{device}.avg_disk_queue_length",
+        ("disk_read_latency_seconds", _) => "disk.{device}.avg_sec_per_read",
+        ("disk_write_latency_seconds", _) => "disk.{device}.avg_sec_per_write",
+        ("disk_recycle_bin_bytes", _) => "disk.{device}.recycle_bin_bytes",
+        ("disk_temp_bytes", _) => "disk.{device}.temp_bytes",
+        ("net_bytes", Some("rx")) => "net.{interface}.bytes_recv",
+        ("net_bytes", Some("tx")) => "net.{interface}.bytes_sent",
+        ("net_packets", Some("rx")) => "net.{interface}.packets_recv",
+        ("net_packets", Some("tx")) => "net.{interface}.packets_sent",
+        ("net_errors", Some("rx")) => "net.{interface}.err_in",
+        ("net_errors", Some("tx")) => "net.{interface}.err_out",
+        ("net_drops", Some("rx")) => "net.{interface}.drop_in",
+        ("net_drops", Some("tx")) => "net.{interface}.drop_out",
+        ("net_utilization_ratio", Some("rx")) => "net.{interface}.rx_utilization_percent",
+        ("net_utilization_ratio", Some("tx")) => "net.{interface}.tx_utilization_percent",
+        ("net_speed_bits_per_second", _) => "net.{interface}.speed_bps",
+        ("net_up", _) => "net.{interface}.is_up",
+        ("ping_loss_ratio", _) => "ping.{target}.loss_percent",
+        ("ping_rtt_seconds", _) => "ping.{target}.avg_seconds",
+        ("gpu_usage_ratio", _) => "gpu.{adapter}.utilization_percent",
+        ("gpu_dedicated_memory_used_bytes", _) => "gpu.{adapter}.dedicated_memory_used_bytes",
+        ("system_processes", _) => "system.process_count",
+        ("system_threads", _) => "system.thread_count",
+        ("system_handles", _) => "system.handle_count",
+        ("system_processor_queue_length", _) => "system.processor_queue_length",
+        ("host_uptime_seconds", _) => "host.uptime_seconds",
+        ("host_boot_time_seconds", _) => "host.boot_time_seconds",
+        ("time_offset_seconds", _) => "time.offset_seconds",
+        ("updates_pending", _) => "updates.pending_update_count",
+        ("updates_reboot_pending", _) => "updates.pending_reboot",
+        ("eventlog_system_errors", _) => "event_log.system_errors",
+        ("eventlog_application_errors", _) => "event_log.application_errors",
+        ("collect_errors", _) => "errors.count",
+        _ => return None,
+    })
+}
+
+/// 含逗號、雙引號或換行時加上雙引號，內部的雙引號重複一次（RFC 4180）
+fn field(s: &str) -> String {
+    if s.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::samples::Kind;
+
+    fn sample(name: &'static str, labels: &[(&'static str, &str)], value: f64) -> Sample {
+        Sample {
+            name,
+            help: "",
+            kind: Kind::Gauge,
+            labels: labels.iter().map(|(k, v)| (*k, v.to_string())).collect(),
+            value,
+        }
+    }
+
+    fn rows(text: &str) -> Vec<&str> {
+        text.strip_prefix('\u{feff}')
+            .expect("BOM")
+            .split_terminator("\r\n")
+            .collect()
+    }
+
+    #[test]
+    fn header_is_fixed() {
+        let text = render(&[]);
+        assert_eq!(rows(&text), vec!["metric,labels,value,timestamp"]);
+    }
+
+    #[test]
+    fn rows_follow_sample_order() {
+        let samples = [
+            sample("disk_free_bytes", &[("device", "D:")], 2.0),
+            sample("cpu_usage_ratio", &[], 0.5),
+            sample("disk_free_bytes", &[("device", "C:")], 1.0),
+        ];
+        let first = render(&samples);
+        let rows = rows(&first);
+        assert_eq!(rows.len(), 4);
+        let columns: Vec<Vec<&str>> = rows[1..]
+            .iter()
+            .map(|r| r.split(',').take(3).collect())
+            .collect();
+        assert_eq!(
+            columns,
+            vec![
+                vec!["disk.D:.free_bytes", "device=D:", "2"],
+                vec!["cpu.usage_percent", "", "0.5"],
+                vec!["disk.C:.free_bytes", "device=C:", "1"],
+            ]
+        );
+        // 時間戳之外兩次輸出完全相同
+        let strip = |t: &str| -> Vec<String> {
+            t.lines()
+                .map(|l| l.rsplit_once(',').map_or(l, |(head, _)| head).to_string())
+                .collect()
+        };
+        assert_eq!(strip(&first), strip(&render(&samples)));
+    }
+
+    #[test]
+    fn special_characters_are_quoted() {
+        let samples = [
+            sample("net_up", &[("interface", "Wi-Fi, 2")], 1.0),
+            sample("net_up", &[("interface", "say \"hi\"")], 1.0),
+            sample("net_up", &[("interface", "two\nlines")], 1.0),
+            sample("net_up", &[("interface", "a"), ("direction", "rx")], 1.0),
+        ];
+        let text = render(&samples);
+        let body: Vec<&str> = text.split("\r\n").skip(1).collect();
+        assert!(body[0].starts_with("\"net.Wi-Fi, 2.is_up\",\"interface=Wi-Fi, 2\",1,"));
+        assert!(body[1].starts_with("\"net.say \"\"hi\"\".is_up\",\"interface=say \"\"hi\"\"\",1,"));
+        assert!(body[2].starts_with("\"net.two\nlines.is_up\",\"interface=two\nlines\",1,"));
+        assert!(body[3].starts_with("net.a.is_up,interface=a;direction=rx,1,"));
+    }
+
+    #[test]
+    fn names_follow_json_paths() {
+        let samples = [
+            sample("cpu_usage_ratio", &[], 0.5),
+            sample("disk_free_bytes", &[("device", "C:")], 1.0),
+            sample(
+                "net_bytes",
+                &[("interface", "Ethernet"), ("direction", "rx")],
+                2.0,
+            ),
+            sample(
+                "net_bytes",
+                &[("interface", "Ethernet"), ("direction", "tx")],
+                3.0,
+            ),
+            sample("time_offset_seconds", &[], 0.1),
+            sample("collect_errors", &[], 0.0),
+            sample("not_in_table", &[], 0.0),
+        ];
+        let names: Vec<String> = samples.iter().map(dotted_name).collect();
+        assert_eq!(
+            names,
+            vec![
+                "cpu.usage_percent",
+                "disk.C:.free_bytes",
+                "net.Ethernet.bytes_recv",
+                "net.Ethernet.bytes_sent",
+                "time.offset_seconds",
+                "errors.count",
+                "not_in_table",
+            ]
+        );
+    }
+}
@@ -0,0 +1,170 @@
+//! 自訂 PDH 計數器：CAPTURE_CUSTOM_COUNTERS_FILE 指向的 JSON 檔，結果放在 /api/v1/metrics 的 custom 區段
+//!
+//! 檔案內容為陣列，例如
+//! `[{"name": "tcp_retransmits", "path": "\\TCPv4\\Segments Retransmitted/sec", "format": "double"}]`；
+//! format 為 large / double（預設 double），rate 預設 true。rate = false 時回報未經速率換算的原始值，
+//! 例如 `…/sec` 計數器的開機以來累計數。
+//!
+//! 自訂計數器有自己的取樣執行緒與 PDH query，每次取樣前檢查檔案修改時間；
+//! 內容變了就重建 query，不需重啟即可增刪計數器。重新載入失敗時沿用上一份設定。
+use serde::Deserialize;
+use std::{
+    collections::BTreeMap,
+    fs,
+    sync::{LazyLock, RwLock},
+    thread,
+    time::SystemTime,
+};
+
+use crate::{
+    config,
+    pdh::{self, Counter, Format, PathSample, Query, Sample},
+    MetricError,
+};
+
+static FILE: LazyLock<Option<String>> =
+    LazyLock::new(|| config::env_string("CAPTURE_CUSTOM_COUNTERS_FILE"));
+
+static LATEST: RwLock<Custom> = RwLock::new(Custom {
+    load_error: None,
+    samples: Vec::new(),
+});
+
+#[derive(Deserialize, Clone, PartialEq)]
+struct CustomCounter {
+    name: String,
+    path: String,
+    #[serde(default = "default_format")]
+    format: Format,
+    #[serde(default = "default_rate")]
+    rate: bool,
+}
+
+fn default_format() -> Format {
+    Format::Double
+}
+
+fn default_rate() -> bool {
+    true
+}
+
+struct Custom {
+    /// 讀檔或 JSON 解析失敗；此時 samples 是上一份設定的結果
+    load_error: Option<String>,
+    /// (名稱, 是否含萬用字元, 結果)，順序與設定檔相同
+    samples: Vec<(String, bool, Sample)>,
+}
+
+/// 目前使用中的 query 與各計數器（加入失敗的保留錯誤訊息）
+struct Registered {
+    query: Query,
+    counters: Vec<(CustomCounter, Result<Counter, String>)>,
+}
+
+/* ---------- 背景取樣 ---------- */
+
+/// 沒有設定檔時不啟動
+pub fn start_sampler() {
+    let Some(file) = FILE.clone() else {
+        return;
+    };
+    thread::spawn(move || {
+        let mut modified: Option<SystemTime> = None;
+        let mut active: Vec<CustomCounter> = Vec::new();
+        let mut registered: Option<Registered> = None;
+
+        loop {
+            let mtime = fs::metadata(&file).and_then(|m| m.modified()).ok();
+            if registered.is_none() || mtime != modified {
+                modified = mtime;
+                match load(&file) {
+                    Ok(counters) => {
+                        LATEST.write().unwrap().load_error = None;
+                        if registered.is_none() || counters != active {
+                            registered = build(&counters);
+                            active = counters;
+                        }
+                    }
+                    Err(e) => LATEST.write().unwrap().load_error = Some(e),
+                }
+            }
+
+            thread::sleep(pdh::SAMPLE_INTERVAL);
+            let Some(r) = &registered else {
+                continue;
+            };
+            let collected = r.query.collect();
+            let samples = r
+                .counters
+                .iter()
+                .map(|(c, counter)| {
+                    let sample = match (counter, &collected) {
+                        (Err(e), _) | (_, Err(e)) => Err(e.clone()),
+                        (Ok(counter), Ok(())) if c.rate => counter.array(c.format),
+                        (Ok(counter), Ok(())) => counter.raw_array(),
+                    };
+                    (c.name.clone(), c.path.contains('*'), sample)
+                })
+                .collect();
+            LATEST.write().unwrap().samples = samples;
+        }
+    });
+}
+
+fn load(file: &str) -> Result<Vec<CustomCounter>, String> {
+    let text = fs::read_to_string(file).map_err(|e| format!("{file}: {e}"))?;
+    serde_json::from_str(&text).map_err(|e| format!("{file}: {e}"))
+}
+
+/// 加不進去的計數器保留錯誤訊息，其餘照常取樣；第一次 collect 作為速率的基準點
+fn build(counters: &[CustomCounter]) -> Option<Registered> {
+    let query = match Query::open() {
+        Ok(q) => q,
+        Err(e) => {
+            LATEST.write().unwrap().load_error = Some(e);
+            return None;
+        }
+    };
+    let counters = counters
+        .iter()
+        .map(|c| (c.clone(), query.add(&c.path)))
+        .collect();
+    let _ = query.collect();
+    Some(Registered { query, counters })
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+/// 沒有設定檔時回傳 None；名稱 → 值（萬用字元路徑為 instance → 值，同名 instance 帶 `#N`）
+pub fn gather_custom(errors: &mut Vec<MetricError>) -> Option<BTreeMap<String, PathSample>> {
+    FILE.as_ref()?;
+    let latest = LATEST.read().unwrap();
+    if let Some(e) = &latest.load_error {
+        errors.push(MetricError {
+            metric: vec!["custom".into()],
+            err: e.clone(),
+        });
+    }
+
+    let mut values = BTreeMap::new();
+    for (name, wildcard, sample) in &latest.samples {
+        match sample {
+            Ok(list) if *wildcard => {
+                values.insert(
+                    name.clone(),
+                    PathSample::Instances(list.iter().cloned().collect()),
+                );
+            }
+            Ok(list) => {
+                if let Some((_, v)) = list.first() {
+                    values.insert(name.clone(), PathSample::Single(*v));
+                }
+            }
+            Err(e) => errors.push(MetricError {
+                metric: vec![format!("custom.{name}")],
+                err: e.clone(),
+            }),
+        }
+    }
+    Some(values)
+}
@@ -0,0 +1,150 @@
+//! 資料夾大小：CAPTURE_DIR_PATHS 列出的資料夾，由背景執行緒每 CAPTURE_DIR_SCAN_INTERVAL_SECS（預設 600）秒走訪一次
+//!
+//! 大型目錄樹走一次可能要好幾分鐘，請求只讀最近一次結果。不跟隨符號連結與 junction，避免繞圈或重複計算。
+use chrono::Utc;
+use serde::Serialize;
+use std::{
+    fs,
+    path::Path,
+    sync::{LazyLock, RwLock},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{config, MetricError};
+
+static PATHS: LazyLock<Vec<String>> = LazyLock::new(|| config::env_list("CAPTURE_DIR_PATHS"));
+static INTERVAL: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_secs(config::env_parse("CAPTURE_DIR_SCAN_INTERVAL_SECS", 600)));
+/// 每個設定路徑最近一次的走訪結果，順序與 PATHS 相同
+static LATEST: RwLock<Option<Vec<Result<DirData, String>>>> = RwLock::new(None);
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize, Clone)]
+pub struct DirData {
+    pub path: String,
+    /// 所有檔案大小的合計（邏輯大小，不是佔用的叢集）
+    pub size_bytes: u64,
+    pub file_count: u64,
+    pub dir_count: u64,
+    /// 沒有權限而略過的子資料夾數；大於 0 時 size_bytes 偏低
+    pub skipped_dirs: u64,
+    /// 走訪完成的時間，RFC3339（UTC）
+    pub completed_at: String,
+}
+
+/// 走訪上限；超過任一項就停下，已累計的部分照常回報
+pub struct WalkLimit {
+    pub deadline: Instant,
+    pub max_entries: u64,
+}
+
+/// 一棵目錄樹的合計
+#[derive(Default)]
+pub struct TreeSize {
+    pub size_bytes: u64,
+    pub file_count: u64,
+    pub dir_count: u64,
+    /// 沒有權限而略過的子資料夾數
+    pub skipped_dirs: u64,
+    /// 碰到 WalkLimit 而提早停止
+    pub truncated: bool,
+}
+
+/* ---------- 背景取樣 ---------- */
+
+/// 沒有設定路徑時不啟動
+pub fn start_sampler() {
+    if PATHS.is_empty() {
+        return;
+    }
+    thread::spawn(|| loop {
+        let results = PATHS.iter().map(|path| walk(path)).collect();
+        *LATEST.write().unwrap() = Some(results);
+        thread::sleep(*INTERVAL);
+    });
+}
+
+pub fn gather_dirs(errors: &mut Vec<MetricError>) -> Vec<DirData> {
+    if PATHS.is_empty() {
+        errors.push(MetricError {
+            metric: vec!["dirs".into()],
+            err: "set CAPTURE_DIR_PATHS to monitor directory sizes".into(),
+        });
+        return Vec::new();
+    }
+    let Some(results) = LATEST.read().unwrap().clone() else {
+        errors.push(MetricError {
+            metric: vec!["dirs".into()],
+            err: "directory scan has not completed yet".into(),
+        });
+        return Vec::new();
+    };
+    // 路徑消失時記 error，不讓它從清單中悄悄不見
+    PATHS
+        .iter()
+        .zip(results)
+        .filter_map(|(path, r)| {
+            r.map_err(|err| {
+                errors.push(MetricError {
+                    metric: vec![format!("dirs.{path}")],
+                    err,
+                })
+            })
+            .ok()
+        })
+        .collect()
+}
+
+fn walk(root: &str) -> Result<DirData, String> {
+    let meta = fs::metadata(root).map_err(|e| format!("{root}: {e}"))?;
+    if !meta.is_dir() {
+        return Err(format!("{root} is not a directory"));
+    }
+    let tree = tree_size(Path::new(root), None);
+    Ok(DirData {
+        path: root.into(),
+        size_bytes: tree.size_bytes,
+        file_count: tree.file_count,
+        dir_count: tree.dir_count,
+        skipped_dirs: tree.skipped_dirs,
+        completed_at: Utc::now().to_rfc3339(),
+    })
+}
+
+/// 以堆疊走訪，不用遞迴，很深的目錄樹也不會爆堆疊；root 本身讀不到時算一個 skipped_dirs
+pub fn tree_size(root: &Path, limit: Option<&WalkLimit>) -> TreeSize {
+    let mut tree = TreeSize::default();
+    let mut stack = vec![root.to_path_buf()];
+    while let Some(dir) = stack.pop() {
+        if let Some(limit) = limit
+            && (tree.file_count + tree.dir_count >= limit.max_entries
+                || Instant::now() >= limit.deadline)
+        {
+            tree.truncated = true;
+            break;
+        }
+        let Ok(entries) = fs::read_dir(&dir) else {
+            tree.skipped_dirs += 1;
+            continue;
+        };
+        for entry in entries.flatten() {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            // Windows 上 junction 與目錄符號連結的 is_symlink() 都是 true
+            if file_type.is_symlink() {
+                continue;
+            }
+            if file_type.is_dir() {
+                tree.dir_count += 1;
+                stack.push(entry.path());
+            } else if let Ok(meta) = entry.metadata() {
+                tree.file_count += 1;
+                tree.size_bytes += meta.len();
+            }
+        }
+    }
+    tree
+}
@@ -0,0 +1,564 @@
+//! 磁碟容量：FindFirstVolumeW / FindNextVolumeW 列舉所有磁碟區（含掛載到資料夾、沒有代號的），
+//! 逐一以 GetDiskFreeSpaceExW 查詢
+//! 磁碟 I/O：由背景 PDH 取樣器提供 \\PhysicalDisk(*) 的速率值
+//! BitLocker：root\\CIMV2\\Security\\MicrosoftVolumeEncryption 的 Win32_EncryptableVolume（需系統管理員）
+//! MFT：NTFS 磁碟區以 FSCTL_GET_NTFS_VOLUME_DATA 讀取 MFT 大小、MFT 保留區與剩餘叢集（需系統管理員）
+//! 遠端路徑：CAPTURE_REMOTE_PATHS 指定的 UNC 路徑 / 網路磁碟機，各自在 blocking 執行緒查詢並有逾時上限
+//! 可清理空間：資源回收筒以 SHQueryRecycleBinW 每次查詢，暫存資料夾讀 tempdirs 背景走訪的結果
+use serde::Serialize;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{LazyLock, Mutex},
+    time::Duration,
+};
+use tokio::time::{timeout_at, Instant};
+use windows::{
+    core::HSTRING,
+    Win32::{
+        Foundation::{GENERIC_READ, HANDLE},
+        Storage::FileSystem::{
+            CreateFileW, FindFirstVolumeW, FindNextVolumeW, FindVolumeClose, GetDiskFreeSpaceExW,
+            GetDriveTypeW, GetVolumeInformationW, GetVolumePathNamesForVolumeNameW,
+            FILE_FLAGS_AND_ATTRIBUTES, FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING,
+        },
+        System::{
+            Ioctl::{FSCTL_GET_NTFS_VOLUME_DATA, NTFS_VOLUME_DATA_BUFFER},
+            WindowsProgramming::{DRIVE_FIXED, DRIVE_REMOVABLE},
+            IO::DeviceIoControl,
+        },
+        UI::Shell::{SHQueryRecycleBinW, SHQUERYRBINFO},
+    },
+};
+
+use crate::{
+    config,
+    pdh::{self, Format, SampledCounter},
+    tempdirs,
+    win::{wide_to_string, OwnedHandle},
+    wmi, MetricError,
+};
+
+/// 磁碟區 GUID 路徑 `\\?\Volume{…}\` 的長度上限（含 NUL）
+const VOLUME_NAME_LEN: usize = 64;
+
+/// 額外監看的遠端路徑，例如 `\\\\fileserver\\backups`
+static REMOTE_PATHS: LazyLock<Vec<String>> =
+    LazyLock::new(|| config::env_list("CAPTURE_REMOTE_PATHS"));
+
+/// 所有遠端路徑共用的查詢上限；SMB 逾時預設長達數十秒，不能讓整個請求跟著卡住
+static REMOTE_TIMEOUT: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(config::env_parse("CAPTURE_REMOTE_PATH_TIMEOUT_SECS", 3u64))
+});
+/// 上一次查詢還沒結束的遠端路徑；卡在斷線的伺服器時不再重複 spawn，避免 blocking 執行緒越積越多
+static REMOTE_IN_FLIGHT: LazyLock<Mutex<HashSet<String>>> = LazyLock::new(Default::default);
+
+/// mft_in_use_percent 達到此值時 mft_near_full 為 true。CAPTURE_MFT_WARN_PERCENT 以百分比（0–100）
+/// 設定，這裡換算成與 mft_in_use_percent 相同的 0–1 比例
+static MFT_WARN: LazyLock<f32> =
+    LazyLock::new(|| config::env_parse("CAPTURE_MFT_WARN_PERCENT", 90.0) / 100.0);
+
+/// Drop 時 FindVolumeClose
+struct VolumeFind(HANDLE);
+
+impl Drop for VolumeFind {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = FindVolumeClose(self.0);
+        }
+    }
+}
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct DiskData {
+    /// 第一個掛載點；有磁碟機代號時一定是代號（例如 `C:\\`），與舊版相容
+    pub device: String,
+    /// 所有掛載點，包含掛載到資料夾的路徑（例如 `C:\\Data\\`）
+    pub mount_points: Vec<String>,
+    pub volume_label: Option<String>,
+    /// NTFS / ReFS / exFAT / FAT32…
+    pub filesystem: Option<String>,
+    /// 磁碟區序號，格式同 `vol` 指令（例如 `1A2B-3C4D`）
+    pub serial: Option<String>,
+    /// CAPTURE_REMOTE_PATHS 指定的網路路徑；沒有 I/O 速率
+    pub remote: bool,
+    /// fully_encrypted / encryption_in_progress / decryption_in_progress / decrypted / locked / unknown；
+    /// 遠端路徑為 None
+    pub bitlocker_status: Option<String>,
+    pub total_bytes: Option<u64>,
+    pub free_bytes: Option<u64>,
+    pub used_bytes: Option<u64>,
+    pub usage_percent: Option<f32>,
+    /// 所在實體磁碟的 I/O 速率；同一顆實體磁碟上的磁碟機數值相同。
+    /// 以磁碟機代號對應 PhysicalDisk instance，沒有代號的磁碟區為 None
+    pub read_bytes_per_sec: Option<u64>,
+    pub write_bytes_per_sec: Option<u64>,
+    pub reads_per_sec: Option<u64>,
+    pub writes_per_sec: Option<u64>,
+    /// 佇列長度與平均延遲（秒）；計數器讀不到時為 None
+    pub avg_disk_queue_length: Option<f64>,
+    pub avg_sec_per_read: Option<f64>,
+    pub avg_sec_per_write: Option<f64>,
+    /// MFT 目前的大小；非 NTFS 或讀不到時為 None
+    pub mft_size_bytes: Option<u64>,
+    /// MFT 還能連續成長的空間：剩餘的 MFT 保留區，以磁碟區剩餘空間為上限
+    pub mft_growth_headroom_bytes: Option<u64>,
+    /// 0–1，MFT 佔「MFT + 成長空間」的比例。NTFS 依需要才擴大 MFT，所以看的是還能長多少，
+    /// 接近 1 代表 MFT 快無處成長，即使剩餘空間看起來還夠，建立新檔也可能失敗
+    pub mft_in_use_percent: Option<f32>,
+    /// mft_in_use_percent 達到 MFT_WARN（CAPTURE_MFT_WARN_PERCENT，預設 90）
+    pub mft_near_full: Option<bool>,
+    /// 此磁碟區上資源回收筒（所有使用者）的合計；遠端路徑為 None
+    pub recycle_bin_bytes: Option<u64>,
+    /// 位於此磁碟機的暫存資料夾（%SystemRoot%\Temp 與各使用者的 AppData\Local\Temp）合計；
+    /// 沒有磁碟機代號或第一輪走訪還沒完成時為 None
+    pub temp_bytes: Option<u64>,
+    /// 走訪碰到時間或項目數上限，temp_bytes 偏低
+    pub temp_truncated: Option<bool>,
+    /// temp_bytes 的走訪完成時間，RFC3339（UTC）
+    pub temp_scanned_at: Option<String>,
+    // 其餘欄位暫時省略
+}
+
+/// 交給背景取樣器的計數器
+pub const PDH_COUNTERS: &[SampledCounter] = &[
+    SampledCounter::new("disk.read_bytes_per_sec", "\\PhysicalDisk(*)\\Disk Read Bytes/sec", Format::Large),
+    SampledCounter::new("disk.write_bytes_per_sec", "\\PhysicalDisk(*)\\Disk Write Bytes/sec", Format::Large),
+    SampledCounter::new("disk.reads_per_sec", "\\PhysicalDisk(*)\\Disk Reads/sec", Format::Large),
+    SampledCounter::new("disk.writes_per_sec", "\\PhysicalDisk(*)\\Disk Writes/sec", Format::Large),
+    SampledCounter::new("disk.avg_disk_queue_length", "\\PhysicalDisk(*)\\Avg. Disk Queue Length", Format::Double),
+    SampledCounter::new("disk.avg_sec_per_read", "\\PhysicalDisk(*)\\Avg. Disk sec/Read", Format::Double),
+    SampledCounter::new("disk.avg_sec_per_write", "\\PhysicalDisk(*)\\Avg. Disk sec/Write", Format::Double),
+];
+
+/* ---------- 指標蒐集 ---------- */
+
+pub async fn gather_disk(errors: &mut Vec<MetricError>) -> Vec<DiskData> {
+    // 未提權時 WMI 連線會被拒：全部磁碟區記為 unknown，容量照常回報
+    let bitlocker = tokio::task::spawn_blocking(bitlocker_statuses)
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()))
+        .unwrap_or_else(|e| {
+            errors.push(MetricError {
+                metric: vec!["disk.bitlocker_status".into()],
+                err: e,
+            });
+            HashMap::new()
+        });
+
+    // FSCTL、資源回收筒查詢都是同步呼叫，每個磁碟區一次，不能佔住 async worker
+    let (mut disks, local_errors) = tokio::task::spawn_blocking(move || {
+        let mut errors = Vec::new();
+        (gather_local(&mut errors, &bitlocker), errors)
+    })
+    .await
+    .unwrap_or_else(|e| {
+        let err = MetricError {
+            metric: vec!["disk".into()],
+            err: e.to_string(),
+        };
+        (Vec::new(), vec![err])
+    });
+    errors.extend(local_errors);
+    disks.extend(gather_remote(errors).await);
+    disks
+}
+
+fn gather_local(
+    errors: &mut Vec<MetricError>,
+    bitlocker: &HashMap<String, String>,
+) -> Vec<DiskData> {
+    let mut disks = Vec::new();
+    let volumes = match list_volumes() {
+        Ok(v) => v,
+        Err(e) => {
+            errors.push(MetricError {
+                metric: vec!["disk".into()],
+                err: e,
+            });
+            Vec::new()
+        }
+    };
+
+    // 第一輪走訪還沒完成是正常的暖機，temp 欄位維持 None，不算錯誤
+    let temp = tempdirs::latest();
+
+    for volume in volumes {
+        let drive_type = unsafe { GetDriveTypeW(&HSTRING::from(volume.as_str())) };
+
+        // 光碟機、網路磁碟等略過；只看固定磁碟與插著的卸除式磁碟
+        if drive_type != DRIVE_FIXED && drive_type != DRIVE_REMOVABLE {
+            continue;
+        }
+
+        // 沒有掛載點的磁碟區（系統保留、修復分割區）看不到也用不到
+        let mount_points = mount_points(&volume);
+        let Some(device) = mount_points.first().cloned() else {
+            continue;
+        };
+
+        match query_capacity(&volume, device) {
+            Ok(mut d) => {
+                d.mount_points = mount_points;
+                fill_volume_info(&volume, &mut d);
+                if d.filesystem.as_deref() == Some("NTFS")
+                    && let Err(e) = fill_mft(&volume, &mut d)
+                {
+                    errors.push(MetricError {
+                        metric: vec![format!("disk.{}.mft", d.device)],
+                        err: e,
+                    });
+                }
+                d.bitlocker_status = Some(
+                    bitlocker
+                        .get(&volume.to_ascii_lowercase())
+                        .cloned()
+                        .unwrap_or_else(|| "unknown".into()),
+                );
+                let letter = d.device.get(..2).filter(|l| l.ends_with(':'));
+                let physical = |key| letter.and_then(|l| physical_value(key, l));
+                let rate = |key| physical(key).map(|v| v.as_i64().max(0) as u64);
+                let double = |key| physical(key).map(|v| v.as_f64());
+                d.read_bytes_per_sec = rate("disk.read_bytes_per_sec");
+                d.write_bytes_per_sec = rate("disk.write_bytes_per_sec");
+                d.reads_per_sec = rate("disk.reads_per_sec");
+                d.writes_per_sec = rate("disk.writes_per_sec");
+                d.avg_disk_queue_length = double("disk.avg_disk_queue_length");
+                d.avg_sec_per_read = double("disk.avg_sec_per_read");
+                d.avg_sec_per_write = double("disk.avg_sec_per_write");
+                match recycle_bin_bytes(&d.device) {
+                    Ok(bytes) => d.recycle_bin_bytes = Some(bytes),
+                    Err(e) => errors.push(MetricError {
+                        metric: vec![format!("disk.{}.recycle_bin_bytes", d.device)],
+                        err: e,
+                    }),
+                }
+                if let (Some(scan), Some(l)) = (&temp, letter) {
+                    let bytes = scan.bytes_by_drive.get(&l.to_ascii_uppercase());
+                    d.temp_bytes = Some(bytes.copied().unwrap_or(0));
+                    d.temp_truncated = Some(scan.truncated);
+                    d.temp_scanned_at = Some(scan.completed_at.clone());
+                }
+                disks.push(d);
+            }
+            // 讀卡機沒插卡時一定失敗，不算錯誤
+            Err(_) if drive_type == DRIVE_REMOVABLE => {}
+            Err(e) => errors.push(MetricError {
+                metric: vec![format!("disk.{}", mount_points[0])],
+                err: e,
+            }),
+        }
+    }
+
+    disks.sort_by(|a, b| a.device.cmp(&b.device));
+
+    // 取樣器層級的失敗每個計數器只回報一次，不逐磁碟重複
+    for c in PDH_COUNTERS {
+        if let Some(Err(e)) = pdh::latest(c.key) {
+            errors.push(MetricError {
+                metric: vec![c.key.into()],
+                err: e,
+            });
+        }
+    }
+
+    disks
+}
+
+async fn gather_remote(errors: &mut Vec<MetricError>) -> Vec<DiskData> {
+    // 先全部 spawn 再依序 await，全部共用同一個期限，N 個路徑斷線也只等一次逾時。
+    // 逾時的 blocking 工作無法取消，會在背景自行結束，結束前同一路徑不再重新查詢
+    let deadline = Instant::now() + *REMOTE_TIMEOUT;
+    let tasks: Vec<_> = REMOTE_PATHS
+        .iter()
+        .map(|path| {
+            if !REMOTE_IN_FLIGHT.lock().unwrap().insert(path.clone()) {
+                return None;
+            }
+            let in_flight = InFlight(path.clone());
+            Some(tokio::task::spawn_blocking(move || {
+                query_remote(&in_flight.0)
+            }))
+        })
+        .collect();
+
+    let mut disks = Vec::new();
+    for (task, path) in tasks.into_iter().zip(REMOTE_PATHS.iter()) {
+        let result = match task {
+            None => Err(format!(
+                "previous query has not returned yet (timeout {}s)",
+                REMOTE_TIMEOUT.as_secs()
+            )),
+            Some(task) => match timeout_at(deadline, task).await {
+                Ok(Ok(r)) => r,
+                Ok(Err(e)) => Err(e.to_string()),
+                Err(_) => Err(format!("timed out after {}s", REMOTE_TIMEOUT.as_secs())),
+            },
+        };
+        match result {
+            Ok(d) => disks.push(d),
+            Err(e) => errors.push(MetricError {
+                metric: vec![format!("disk.{path}")],
+                err: e,
+            }),
+        }
+    }
+    disks
+}
+
+/// Drop 時把路徑移出 REMOTE_IN_FLIGHT；查詢 panic 時也會執行
+struct InFlight(String);
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        REMOTE_IN_FLIGHT.lock().unwrap().remove(&self.0);
+    }
+}
+
+/// GetDiskFreeSpaceExW / GetVolumeInformationW 都要求以反斜線結尾的根目錄
+fn query_remote(path: &str) -> Result<DiskData, String> {
+    let root = if path.ends_with('\\') {
+        path.to_string()
+    } else {
+        format!("{path}\\")
+    };
+    let mut d = query_capacity(&root, path.into())?;
+    d.mount_points = vec![path.into()];
+    d.remote = true;
+    fill_volume_info(&root, &mut d);
+    Ok(d)
+}
+
+/* ---------- BitLocker ---------- */
+
+/// 磁碟區 GUID 路徑（小寫）→ 狀態
+fn bitlocker_statuses() -> Result<HashMap<String, String>, String> {
+    let rows = wmi::query(
+        "root\\CIMV2\\Security\\MicrosoftVolumeEncryption",
+        "SELECT DeviceID, ConversionStatus, ProtectionStatus FROM Win32_EncryptableVolume",
+    )
+    .map_err(|e| format!("{e} (reading BitLocker status requires elevation)"))?;
+
+    Ok(rows
+        .iter()
+        .filter_map(|r| {
+            let id = r.get_string("DeviceID")?.to_ascii_lowercase();
+            let status =
+                bitlocker_name(r.get_u32("ConversionStatus"), r.get_u32("ProtectionStatus"));
+            Some((id, status.to_string()))
+        })
+        .collect())
+}
+
+/// ProtectionStatus 2 代表無法判斷保護狀態，實際上就是磁碟區被鎖住
+fn bitlocker_name(conversion: Option<u32>, protection: Option<u32>) -> &'static str {
+    if protection == Some(2) {
+        return "locked";
+    }
+    match conversion {
+        Some(0) => "decrypted",
+        Some(1) => "fully_encrypted",
+        // 4 = 加密暫停，仍視為進行中
+        Some(2) | Some(4) => "encryption_in_progress",
+        Some(3) | Some(5) => "decryption_in_progress",
+        _ => "unknown",
+    }
+}
+
+/// PhysicalDisk instance 形如 "0 C: D:"：磁碟編號後接其上的磁碟機代號
+fn physical_value(key: &str, letter: &str) -> Option<pdh::Value> {
+    let values = pdh::latest(key)?.ok()?;
+    values
+        .into_iter()
+        .find(|(name, _)| {
+            name.split_whitespace()
+                .skip(1)
+                .any(|t| t.eq_ignore_ascii_case(letter))
+        })
+        .map(|(_, v)| v)
+}
+
+/// 所有磁碟區的 GUID 路徑（`\\\\?\\Volume{…}\\`）
+pub fn list_volumes() -> Result<Vec<String>, String> {
+    let mut name = [0u16; VOLUME_NAME_LEN];
+    let find = unsafe { FindFirstVolumeW(&mut name) }
+        .map(VolumeFind)
+        .map_err(|e| format!("FindFirstVolumeW failed: {e}"))?;
+
+    let mut volumes = vec![wide_to_string(&name)];
+    loop {
+        name.fill(0);
+        // ERROR_NO_MORE_FILES 代表列舉結束
+        if unsafe { FindNextVolumeW(find.0, &mut name) }.is_err() {
+            break;
+        }
+        volumes.push(wide_to_string(&name));
+    }
+    Ok(volumes)
+}
+
+/// 磁碟機代號的根目錄排在前面，其餘掛載到資料夾的路徑依字母排序
+pub fn mount_points(volume: &str) -> Vec<String> {
+    let volume = HSTRING::from(volume);
+    let mut len = 0u32;
+    let _ = unsafe { GetVolumePathNamesForVolumeNameW(&volume, None, &mut len) };
+    if len == 0 {
+        return Vec::new();
+    }
+    let mut buf = vec![0u16; len as usize];
+    if unsafe { GetVolumePathNamesForVolumeNameW(&volume, Some(&mut buf), &mut len) }.is_err() {
+        return Vec::new();
+    }
+
+    // REG_MULTI_SZ 格式：以 NUL 分隔，連續兩個 NUL 結尾
+    let mut paths: Vec<String> = buf
+        .split(|&c| c == 0)
+        .filter(|p| !p.is_empty())
+        .map(String::from_utf16_lossy)
+        .collect();
+    paths.sort_by_key(|p| (p.len() != 3, p.clone()));
+    paths
+}
+
+/// 讀不到（例如 BitLocker 鎖定中）時保留 None
+fn fill_volume_info(volume: &str, d: &mut DiskData) {
+    let mut label = [0u16; 261];
+    let mut fs = [0u16; 261];
+    let mut serial = 0u32;
+    let ok = unsafe {
+        GetVolumeInformationW(
+            &HSTRING::from(volume),
+            Some(&mut label),
+            Some(&mut serial),
+            None,
+            None,
+            Some(&mut fs),
+        )
+    }
+    .is_ok();
+    if !ok {
+        return;
+    }
+    let label = wide_to_string(&label);
+    d.volume_label = (!label.is_empty()).then_some(label);
+    d.filesystem = Some(wide_to_string(&fs)).filter(|f| !f.is_empty());
+    d.serial = Some(format!("{:04X}-{:04X}", serial >> 16, serial & 0xffff));
+}
+
+fn query_capacity(volume: &str, device: String) -> Result<DiskData, String> {
+    let mut free:    u64 = 0;
+    let mut total:   u64 = 0;
+    let mut _unused: u64 = 0;
+
+    unsafe {
+        GetDiskFreeSpaceExW(
+            &HSTRING::from(volume),
+            Some(&mut _unused),      // caller 可用空間（未用）
+            Some(&mut total),        // 總容量
+            Some(&mut free),         // 剩餘容量
+        )
+    }
+    .map_err(|e| format!("GetDiskFreeSpaceExW failed: {e}"))?;
+
+    if total == 0 {
+        return Err("GetDiskFreeSpaceExW reported zero capacity".into());
+    }
+
+    let used = total.saturating_sub(free);
+    let percent = used as f32 / total as f32;
+
+    Ok(DiskData {
+        device,
+        mount_points: Vec::new(),
+        volume_label: None,
+        filesystem: None,
+        serial: None,
+        remote: false,
+        bitlocker_status: None,
+        total_bytes: Some(total),
+        free_bytes: Some(free),
+        used_bytes: Some(used),
+        usage_percent: Some(percent),
+        read_bytes_per_sec: None,
+        write_bytes_per_sec: None,
+        reads_per_sec: None,
+        writes_per_sec: None,
+        avg_disk_queue_length: None,
+        avg_sec_per_read: None,
+        avg_sec_per_write: None,
+        mft_size_bytes: None,
+        mft_in_use_percent: None,
+        mft_growth_headroom_bytes: None,
+        mft_near_full: None,
+        recycle_bin_bytes: None,
+        temp_bytes: None,
+        temp_truncated: None,
+        temp_scanned_at: None,
+    })
+}
+
+/// 掛載點可以是磁碟機代號或資料夾，查的是它所在磁碟區的資源回收筒
+fn recycle_bin_bytes(root: &str) -> Result<u64, String> {
+    let mut info = SHQUERYRBINFO {
+        cbSize: std::mem::size_of::<SHQUERYRBINFO>() as u32,
+        ..Default::default()
+    };
+    unsafe { SHQueryRecycleBinW(&HSTRING::from(root), &mut info) }
+        .map_err(|e| format!("SHQueryRecycleBinW({root}) failed: {e}"))?;
+    Ok(info.i64Size.max(0) as u64)
+}
+
+/* ---------- MFT ---------- */
+
+fn fill_mft(volume: &str, d: &mut DiskData) -> Result<(), String> {
+    let data = ntfs_volume_data(&open_volume(volume)?)?;
+    let mft = data.MftValidDataLength.max(0) as u64;
+    // 保留區是空的叢集，磁碟區快滿時一般檔案也會寫進去，所以不能超過剩餘叢集
+    let zone = (data.MftZoneEnd - data.MftZoneStart).max(0) as u64;
+    let free = data.FreeClusters.max(0) as u64;
+    let headroom = zone.min(free).saturating_mul(data.BytesPerCluster as u64);
+    let in_use = (mft > 0).then(|| mft as f32 / mft.saturating_add(headroom) as f32);
+    d.mft_size_bytes = Some(mft);
+    d.mft_growth_headroom_bytes = Some(headroom);
+    d.mft_in_use_percent = in_use;
+    d.mft_near_full = in_use.map(|p| p >= *MFT_WARN);
+    Ok(())
+}
+
+/// 開啟磁碟區本身（GUID 路徑去掉結尾的 `\`）送出 FSCTL
+fn open_volume(volume: &str) -> Result<OwnedHandle, String> {
+    let path = HSTRING::from(volume.trim_end_matches('\\'));
+    unsafe {
+        CreateFileW(
+            &path,
+            GENERIC_READ.0,
+            FILE_SHARE_READ | FILE_SHARE_WRITE,
+            None,
+            OPEN_EXISTING,
+            FILE_FLAGS_AND_ATTRIBUTES(0),
+            HANDLE::default(),
+        )
+    }
+    .map(OwnedHandle)
+    .map_err(|e| format!("CreateFileW({volume}) failed: {e}"))
+}
+
+fn ntfs_volume_data(handle: &OwnedHandle) -> Result<NTFS_VOLUME_DATA_BUFFER, String> {
+    let mut data = NTFS_VOLUME_DATA_BUFFER::default();
+    let mut returned = 0u32;
+    unsafe {
+        DeviceIoControl(
+            handle.0,
+            FSCTL_GET_NTFS_VOLUME_DATA,
+            None,
+            0,
+            Some((&mut data as *mut NTFS_VOLUME_DATA_BUFFER).cast()),
+            std::mem::size_of::<NTFS_VOLUME_DATA_BUFFER>() as u32,
+            Some(&mut returned),
+            None,
+        )
+    }
+    .map_err(|e| format!("FSCTL_GET_NTFS_VOLUME_DATA failed: {e}"))?;
+    Ok(data)
+}
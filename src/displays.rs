@@ -0,0 +1,110 @@
+//! 顯示器：每次請求以 EnumDisplayDevices 重新列舉，插拔螢幕不需重啟即可反映
+//!
+//! 以服務身分在 session 0 執行時看不到使用者桌面的顯示器，monitor_attached 會是 false。
+use serde::Serialize;
+use windows::{
+    core::{HSTRING, PCWSTR},
+    Win32::Graphics::Gdi::{
+        EnumDisplayDevicesW, EnumDisplaySettingsW, DEVMODEW, DISPLAY_DEVICEW,
+        DISPLAY_DEVICE_ACTIVE, DISPLAY_DEVICE_ATTACHED_TO_DESKTOP, DISPLAY_DEVICE_MIRRORING_DRIVER,
+        DISPLAY_DEVICE_PRIMARY_DEVICE, ENUM_CURRENT_SETTINGS,
+    },
+};
+
+use crate::{win::wide_to_string, MetricError};
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct DisplayData {
+    /// 例如 `\\.\DISPLAY1`
+    pub device_name: String,
+    /// 顯示卡描述
+    pub adapter: String,
+    /// 螢幕描述（例如 Generic PnP Monitor）；輸出端沒有接螢幕時為 None
+    pub monitor: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub refresh_rate_hz: Option<u32>,
+    pub primary: bool,
+}
+
+#[derive(Serialize)]
+pub struct DisplaysData {
+    /// 至少有一個輸出端接到桌面且有螢幕；false 代表 headless
+    pub monitor_attached: bool,
+    /// 只列出接到桌面的輸出端
+    pub displays: Vec<DisplayData>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub fn gather_displays(errors: &mut Vec<MetricError>) -> DisplaysData {
+    let mut displays = Vec::new();
+    for adapter in enum_devices(None) {
+        // 鏡像驅動（遠端桌面、螢幕錄影軟體）不是實體輸出
+        if adapter.StateFlags & DISPLAY_DEVICE_ATTACHED_TO_DESKTOP == 0
+            || adapter.StateFlags & DISPLAY_DEVICE_MIRRORING_DRIVER != 0
+        {
+            continue;
+        }
+        let device_name = wide_to_string(&adapter.DeviceName);
+        let monitor = enum_devices(Some(&device_name))
+            .into_iter()
+            .find(|m| m.StateFlags & DISPLAY_DEVICE_ACTIVE != 0)
+            .map(|m| wide_to_string(&m.DeviceString));
+
+        let mut mode = DEVMODEW {
+            dmSize: std::mem::size_of::<DEVMODEW>() as u16,
+            ..Default::default()
+        };
+        let has_mode = unsafe {
+            EnumDisplaySettingsW(
+                &HSTRING::from(&device_name),
+                ENUM_CURRENT_SETTINGS,
+                &mut mode,
+            )
+        }
+        .as_bool();
+        if !has_mode {
+            errors.push(MetricError {
+                metric: vec![format!("displays.{device_name}")],
+                err: "EnumDisplaySettingsW failed".into(),
+            });
+        }
+
+        displays.push(DisplayData {
+            device_name,
+            adapter: wide_to_string(&adapter.DeviceString),
+            monitor,
+            width: has_mode.then_some(mode.dmPelsWidth),
+            height: has_mode.then_some(mode.dmPelsHeight),
+            // 0 與 1 代表「硬體預設」，不是實際頻率
+            refresh_rate_hz: Some(mode.dmDisplayFrequency).filter(|&hz| has_mode && hz > 1),
+            primary: adapter.StateFlags & DISPLAY_DEVICE_PRIMARY_DEVICE != 0,
+        });
+    }
+
+    DisplaysData {
+        monitor_attached: displays.iter().any(|d| d.monitor.is_some()),
+        displays,
+    }
+}
+
+/// device 為 None 時列舉顯示卡輸出端，否則列舉該輸出端上的螢幕
+fn enum_devices(device: Option<&str>) -> Vec<DISPLAY_DEVICEW> {
+    let name = device.map(HSTRING::from);
+    let name = name.as_ref().map_or(PCWSTR::null(), |n| PCWSTR(n.as_ptr()));
+    let mut devices = Vec::new();
+    for index in 0.. {
+        let mut dev = DISPLAY_DEVICEW {
+            cb: std::mem::size_of::<DISPLAY_DEVICEW>() as u32,
+            ..Default::default()
+        };
+        if !unsafe { EnumDisplayDevicesW(name, index, &mut dev, 0) }.as_bool() {
+            break;
+        }
+        devices.push(dev);
+    }
+    devices
+}
@@ -0,0 +1,94 @@
+//! 主機名稱與網域：GetComputerNameExW 取得 DNS 名稱，NetGetJoinInformation 判斷網域 / 工作群組
+use serde::Serialize;
+use windows::{
+    core::{PCWSTR, PWSTR},
+    Win32::{
+        NetworkManagement::NetManagement::{
+            NetApiBufferFree, NetGetJoinInformation, NetSetupDomainName, NETSETUP_JOIN_STATUS,
+        },
+        System::SystemInformation::{
+            ComputerNameDnsFullyQualified, ComputerNameDnsHostname, GetComputerNameExW,
+            COMPUTER_NAME_FORMAT,
+        },
+    },
+};
+
+use crate::MetricError;
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct DomainInfo {
+    /// DNS 主機名稱（不含網域），例如 `web01`
+    pub hostname: Option<String>,
+    /// 例如 `web01.corp.example.com`；沒有主要 DNS 尾碼時與 hostname 相同
+    pub fqdn: Option<String>,
+    pub domain_joined: bool,
+    /// 加入網域時為 NetBIOS 網域名稱，否則為工作群組名稱
+    pub domain_or_workgroup_name: Option<String>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub fn gather_domain(errors: &mut Vec<MetricError>) -> DomainInfo {
+    let mut name = |format, key: &str| match computer_name(format) {
+        Ok(n) => Some(n),
+        Err(e) => {
+            errors.push(MetricError {
+                metric: vec![format!("host.{key}")],
+                err: e,
+            });
+            None
+        }
+    };
+    let hostname = name(ComputerNameDnsHostname, "hostname");
+    let fqdn = name(ComputerNameDnsFullyQualified, "fqdn");
+
+    let (domain_joined, domain_or_workgroup_name) = match join_information() {
+        Ok((joined, name)) => (joined, name),
+        Err(e) => {
+            errors.push(MetricError {
+                metric: vec!["host.domain_or_workgroup_name".into()],
+                err: e,
+            });
+            (false, None)
+        }
+    };
+
+    DomainInfo {
+        hostname,
+        fqdn,
+        domain_joined,
+        domain_or_workgroup_name,
+    }
+}
+
+/// 只取 DNS 主機名稱，給聚合回應的頂層欄位使用
+pub fn hostname() -> Option<String> {
+    computer_name(ComputerNameDnsHostname).ok()
+}
+
+fn computer_name(format: COMPUTER_NAME_FORMAT) -> Result<String, String> {
+    unsafe {
+        let mut len = 0u32;
+        let _ = GetComputerNameExW(format, PWSTR::null(), &mut len);
+        let mut buf = vec![0u16; len as usize + 1];
+        GetComputerNameExW(format, PWSTR(buf.as_mut_ptr()), &mut len)
+            .map_err(|e| format!("GetComputerNameExW failed: {e}"))?;
+        Ok(String::from_utf16_lossy(&buf[..len as usize]))
+    }
+}
+
+fn join_information() -> Result<(bool, Option<String>), String> {
+    unsafe {
+        let mut name = PWSTR::null();
+        let mut status = NETSETUP_JOIN_STATUS::default();
+        let rc = NetGetJoinInformation(PCWSTR::null(), &mut name, &mut status);
+        if rc != 0 {
+            return Err(format!("NetGetJoinInformation failed: {rc}"));
+        }
+        let text = name.to_string().ok().filter(|n| !n.is_empty());
+        NetApiBufferFree(Some(name.0 as *const _));
+        Ok((status == NetSetupDomainName, text))
+    }
+}
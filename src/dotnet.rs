@@ -0,0 +1,122 @@
+//! .NET CLR 計數器：CAPTURE_DOTNET_PROCESSES 列出的行程（instance 名稱，即不含 .exe 的執行檔名）
+//!
+//! 多個同名行程的 instance 會被加上 `#1`、`#2`，這裡逐一列出、不合併。
+//! 只有 .NET Framework 會發佈這些計數器；.NET Core / .NET 5+ 的行程不會出現。
+use serde::Serialize;
+use std::{collections::BTreeMap, sync::LazyLock};
+
+use crate::{
+    config,
+    pdh::{self, Format, SampledCounter},
+    MetricError,
+};
+
+static PROCESSES: LazyLock<Vec<String>> =
+    LazyLock::new(|| config::env_list("CAPTURE_DOTNET_PROCESSES"));
+
+const PDH_COUNTERS: &[SampledCounter] = &[
+    SampledCounter::new("dotnet.time_in_gc", "\\.NET CLR Memory(*)\\% Time in GC", Format::Double),
+    SampledCounter::new("dotnet.gen0_collections", "\\.NET CLR Memory(*)\\# Gen 0 Collections", Format::Large),
+    SampledCounter::new("dotnet.gen1_collections", "\\.NET CLR Memory(*)\\# Gen 1 Collections", Format::Large),
+    SampledCounter::new("dotnet.gen2_collections", "\\.NET CLR Memory(*)\\# Gen 2 Collections", Format::Large),
+    SampledCounter::new("dotnet.heap_bytes", "\\.NET CLR Memory(*)\\# Bytes in all Heaps", Format::Large),
+    SampledCounter::new("dotnet.exceptions_per_sec", "\\.NET CLR Exceptions(*)\\# of Exceps Thrown / sec", Format::Double),
+];
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize, Default)]
+pub struct DotnetProcessData {
+    /// PDH instance 名稱，例如 `MyService` 或 `MyService#1`
+    pub instance: String,
+    /// 對應的設定名稱（去掉 `#N`）
+    pub process: String,
+    /// 0–1
+    pub time_in_gc_percent: Option<f64>,
+    /// 以下三個為行程啟動以來的累計次數
+    pub gen0_collections: Option<u64>,
+    pub gen1_collections: Option<u64>,
+    pub gen2_collections: Option<u64>,
+    pub heap_bytes: Option<u64>,
+    pub exceptions_per_sec: Option<f64>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+/// 沒有設定行程時為空，不註冊任何計數器
+pub fn pdh_counters() -> &'static [SampledCounter] {
+    if PROCESSES.is_empty() {
+        &[]
+    } else {
+        PDH_COUNTERS
+    }
+}
+
+pub fn gather_dotnet(errors: &mut Vec<MetricError>) -> Vec<DotnetProcessData> {
+    if PROCESSES.is_empty() {
+        errors.push(MetricError {
+            metric: vec!["dotnet".into()],
+            err: "set CAPTURE_DOTNET_PROCESSES to collect .NET CLR counters".into(),
+        });
+        return Vec::new();
+    }
+
+    let mut by_instance: BTreeMap<String, DotnetProcessData> = BTreeMap::new();
+    for c in PDH_COUNTERS {
+        let values = match pdh::latest(c.key) {
+            Some(Ok(v)) => v,
+            Some(Err(e)) => {
+                errors.push(MetricError {
+                    metric: vec![c.key.into()],
+                    err: e,
+                });
+                continue;
+            }
+            None => continue,
+        };
+        for (instance, value) in values {
+            let Some(process) = configured_name(&instance) else {
+                continue;
+            };
+            let entry = by_instance
+                .entry(instance.clone())
+                .or_insert_with(|| DotnetProcessData {
+                    instance,
+                    process,
+                    ..Default::default()
+                });
+            match c.key {
+                "dotnet.time_in_gc" => entry.time_in_gc_percent = Some(value.as_f64() / 100.0),
+                "dotnet.gen0_collections" => entry.gen0_collections = Some(value.as_i64() as u64),
+                "dotnet.gen1_collections" => entry.gen1_collections = Some(value.as_i64() as u64),
+                "dotnet.gen2_collections" => entry.gen2_collections = Some(value.as_i64() as u64),
+                "dotnet.heap_bytes" => entry.heap_bytes = Some(value.as_i64() as u64),
+                "dotnet.exceptions_per_sec" => entry.exceptions_per_sec = Some(value.as_f64()),
+                _ => {}
+            }
+        }
+    }
+
+    // 設定了卻完全沒有 instance，多半是行程沒在跑或不是 .NET Framework
+    for name in PROCESSES.iter() {
+        if !by_instance.values().any(|d| d.process.eq_ignore_ascii_case(name)) {
+            errors.push(MetricError {
+                metric: vec![format!("dotnet.{name}")],
+                err: format!("no .NET CLR counter instance for {name}"),
+            });
+        }
+    }
+    by_instance.into_values().collect()
+}
+
+/// instance 去掉 `#N` 後與設定比對，回傳設定中的寫法
+fn configured_name(instance: &str) -> Option<String> {
+    let base = match instance.rsplit_once('#') {
+        Some((base, n)) if n.bytes().all(|b| b.is_ascii_digit()) => base,
+        _ => instance,
+    };
+    PROCESSES
+        .iter()
+        .find(|p| p.trim_end_matches(".exe").eq_ignore_ascii_case(base))
+        .cloned()
+}
@@ -0,0 +1,468 @@
+//! Windows 事件記錄：EvtQuery 以 XPath 條件篩選，只讀取需要的事件
+//!
+//! - 錯誤計數：視窗內 System / Application 的 Critical + Error 事件數
+//! - 最近事件：/api/v1/eventlog，EvtRender 取系統欄位、EvtFormatMessage 產生訊息文字
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, sync::LazyLock};
+use windows::{
+    core::{HSTRING, PCWSTR},
+    Win32::{
+        Foundation::{
+            ERROR_ACCESS_DENIED, ERROR_EVT_CHANNEL_NOT_FOUND, ERROR_EVT_INVALID_CHANNEL_PATH,
+            ERROR_NO_MORE_ITEMS,
+        },
+        System::EventLog::{
+            EvtClose, EvtCreateRenderContext, EvtFormatMessage, EvtFormatMessageEvent, EvtNext,
+            EvtOpenPublisherMetadata, EvtQuery, EvtQueryChannelPath, EvtQueryReverseDirection,
+            EvtRender, EvtRenderContextSystem, EvtRenderContextUser, EvtRenderEventValues,
+            EvtSystemEventID, EvtSystemLevel, EvtSystemPropertyIdEND, EvtSystemProviderName,
+            EvtSystemTimeCreated, EvtVarTypeString, EVT_HANDLE, EVT_VARIANT,
+        },
+    },
+};
+
+use crate::{
+    config,
+    win::{filetime_to_rfc3339, wide_to_string},
+    MetricError,
+};
+
+/// 統計視窗，CAPTURE_EVENT_LOG_WINDOW_MINUTES 可調整
+static WINDOW_SECS: LazyLock<u64> =
+    LazyLock::new(|| config::env_parse("CAPTURE_EVENT_LOG_WINDOW_MINUTES", 15u64) * 60);
+
+/// 單次 EvtNext 取回的事件數
+const BATCH: usize = 64;
+/// EvtNext 等待上限（毫秒）
+const NEXT_TIMEOUT_MS: u32 = 5_000;
+
+const DEFAULT_TAIL_LIMIT: usize = 50;
+const MAX_TAIL_LIMIT: usize = 200;
+
+/// 訊息截斷長度（bytes），CAPTURE_EVENT_LOG_MESSAGE_BYTES 可調整
+static MESSAGE_BYTES: LazyLock<usize> =
+    LazyLock::new(|| config::env_parse("CAPTURE_EVENT_LOG_MESSAGE_BYTES", 512usize));
+
+/// EVT_VARIANT.Type 為 EvtVarTypeNull 時代表欄位不存在
+const EVT_VAR_TYPE_NULL: u32 = 0;
+
+/// Drop 時 EvtClose 的 EVT_HANDLE
+struct EvtHandle(EVT_HANDLE);
+
+impl Drop for EvtHandle {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = EvtClose(self.0);
+        }
+    }
+}
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct EventLogData {
+    /// Level 1（Critical）與 2（Error）的事件數
+    pub system_errors: Option<u64>,
+    pub application_errors: Option<u64>,
+    pub window_seconds: u64,
+}
+
+#[derive(Serialize)]
+pub struct EventEntry {
+    /// RFC3339（UTC）
+    pub time: Option<String>,
+    pub provider: String,
+    pub event_id: u16,
+    /// critical / error / warning / information / verbose
+    pub level: String,
+    /// 依 CAPTURE_EVENT_LOG_MESSAGE_BYTES 截斷；provider 沒有訊息資源時為 None
+    pub message: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct EventQuery {
+    #[serde(default = "default_log")]
+    pub log: String,
+    /// 只回傳此嚴重度以上的事件；未指定時不篩選
+    pub level: Option<String>,
+    pub limit: Option<usize>,
+}
+
+fn default_log() -> String {
+    "System".into()
+}
+
+/// 讓路由層對應 HTTP 狀態碼
+pub enum TailError {
+    /// 記錄名稱或 level 不正確（400）
+    BadRequest(String),
+    /// 例如非系統管理員讀取 Security（403）
+    AccessDenied(String),
+    Failed(String),
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub async fn gather_event_log(errors: &mut Vec<MetricError>) -> EventLogData {
+    let window_ms = *WINDOW_SECS * 1000;
+    let xpath = format!(
+        "*[System[(Level=1 or Level=2) and TimeCreated[timediff(@SystemTime) <= {window_ms}]]]"
+    );
+
+    // EvtNext 走訪會阻塞，兩個記錄在同一個 blocking 執行緒依序計數
+    let (system, application) = tokio::task::spawn_blocking(move || {
+        (
+            count_events("System", &xpath),
+            count_events("Application", &xpath),
+        )
+    })
+    .await
+    .unwrap_or_else(|e| (Err(e.to_string()), Err(e.to_string())));
+
+    let mut count = |result: Result<u64, String>, key: &str| match result {
+        Ok(n) => Some(n),
+        Err(e) => {
+            errors.push(MetricError {
+                metric: vec![format!("event_log.{key}")],
+                err: e,
+            });
+            None
+        }
+    };
+
+    EventLogData {
+        system_errors: count(system, "system_errors"),
+        application_errors: count(application, "application_errors"),
+        window_seconds: *WINDOW_SECS,
+    }
+}
+
+fn count_events(channel: &str, xpath: &str) -> Result<u64, String> {
+    let query = query(channel, xpath)?;
+
+    let mut total = 0u64;
+    let mut handles = [0isize; BATCH];
+    loop {
+        let mut returned = 0u32;
+        match unsafe { EvtNext(query.0, &mut handles, NEXT_TIMEOUT_MS, 0, &mut returned) } {
+            Ok(()) => {
+                total += returned as u64;
+                for &h in &handles[..returned as usize] {
+                    drop(EvtHandle(EVT_HANDLE(h)));
+                }
+            }
+            Err(e) if e.code() == ERROR_NO_MORE_ITEMS.to_hresult() => return Ok(total),
+            Err(e) => return Err(format!("EvtNext failed: {e}")),
+        }
+    }
+}
+
+/// 由新到舊查詢 `channel`
+fn query(channel: &str, xpath: &str) -> Result<EvtHandle, String> {
+    open_query(channel, xpath).map_err(|e| format!("EvtQuery({channel}) failed: {e}"))
+}
+
+fn open_query(channel: &str, xpath: &str) -> windows::core::Result<EvtHandle> {
+    unsafe {
+        EvtQuery(
+            EVT_HANDLE::default(),
+            &HSTRING::from(channel),
+            &HSTRING::from(xpath),
+            EvtQueryChannelPath.0 | EvtQueryReverseDirection.0,
+        )
+    }
+    .map(EvtHandle)
+}
+
+/* ---------- 最近事件 ---------- */
+
+pub fn tail_events(q: &EventQuery) -> Result<Vec<EventEntry>, TailError> {
+    let xpath = match q.level.as_deref() {
+        None => "*".to_string(),
+        Some(level) => {
+            let max = level_rank(level).ok_or_else(|| {
+                TailError::BadRequest(format!(
+                    "invalid level {level:?} (expected critical, error, warning, information or verbose)"
+                ))
+            })?;
+            // Level 0（LogAlways）在事件檢視器中顯示為「資訊」
+            let mut levels: Vec<String> = (1..=max).map(|l| format!("Level={l}")).collect();
+            if max >= 4 {
+                levels.push("Level=0".into());
+            }
+            format!("*[System[{}]]", levels.join(" or "))
+        }
+    };
+
+    let query = open_query(&q.log, &xpath).map_err(|e| {
+        let msg = format!("EvtQuery({}) failed: {e}", q.log);
+        if e.code() == ERROR_ACCESS_DENIED.to_hresult() {
+            TailError::AccessDenied(msg)
+        } else if e.code() == ERROR_EVT_CHANNEL_NOT_FOUND.to_hresult()
+            || e.code() == ERROR_EVT_INVALID_CHANNEL_PATH.to_hresult()
+        {
+            TailError::BadRequest(msg)
+        } else {
+            TailError::Failed(msg)
+        }
+    })?;
+
+    let context = unsafe { EvtCreateRenderContext(None, EvtRenderContextSystem.0) }
+        .map(EvtHandle)
+        .map_err(|e| TailError::Failed(format!("EvtCreateRenderContext failed: {e}")))?;
+
+    let limit = q.limit.unwrap_or(DEFAULT_TAIL_LIMIT).min(MAX_TAIL_LIMIT);
+    let mut publishers: HashMap<String, Option<EvtHandle>> = HashMap::new();
+    let mut entries = Vec::new();
+    let mut handles = [0isize; BATCH];
+
+    while entries.len() < limit {
+        let want = (limit - entries.len()).min(BATCH);
+        let mut returned = 0u32;
+        match unsafe {
+            EvtNext(
+                query.0,
+                &mut handles[..want],
+                NEXT_TIMEOUT_MS,
+                0,
+                &mut returned,
+            )
+        } {
+            Ok(()) => {}
+            Err(e) if e.code() == ERROR_NO_MORE_ITEMS.to_hresult() => break,
+            Err(e) => return Err(TailError::Failed(format!("EvtNext failed: {e}"))),
+        }
+        for &h in &handles[..returned as usize] {
+            let event = EvtHandle(EVT_HANDLE(h));
+            if let Some(entry) = render_event(&context, &event, &mut publishers) {
+                entries.push(entry);
+            }
+        }
+    }
+    Ok(entries)
+}
+
+/// level 名稱 → 最大的 Level 值（數字越小越嚴重）
+fn level_rank(level: &str) -> Option<u8> {
+    Some(match level.to_ascii_lowercase().as_str() {
+        "critical" => 1,
+        "error" => 2,
+        "warning" => 3,
+        "information" | "info" => 4,
+        "verbose" => 5,
+        _ => return None,
+    })
+}
+
+fn level_name(level: u8) -> &'static str {
+    match level {
+        1 => "critical",
+        2 => "error",
+        3 => "warning",
+        0 | 4 => "information",
+        5 => "verbose",
+        _ => "unknown",
+    }
+}
+
+fn render_event(
+    context: &EvtHandle,
+    event: &EvtHandle,
+    publishers: &mut HashMap<String, Option<EvtHandle>>,
+) -> Option<EventEntry> {
+    let buf = render_system(context, event)?;
+    let value = |id| system_value(&buf, id);
+    unsafe {
+        let provider = value(EvtSystemProviderName.0)
+            .and_then(|v| v.Anonymous.StringVal.to_string().ok())
+            .unwrap_or_default();
+        let event_id = value(EvtSystemEventID.0).map_or(0, |v| v.Anonymous.UInt16Val);
+        let level = value(EvtSystemLevel.0).map_or(0, |v| v.Anonymous.ByteVal);
+        let time = value(EvtSystemTimeCreated.0)
+            .and_then(|v| filetime_to_rfc3339(v.Anonymous.FileTimeVal as i64));
+
+        let metadata = publishers
+            .entry(provider.clone())
+            .or_insert_with(|| {
+                EvtOpenPublisherMetadata(
+                    EVT_HANDLE::default(),
+                    &HSTRING::from(provider.as_str()),
+                    PCWSTR::null(),
+                    0,
+                    0,
+                )
+                .ok()
+                .map(EvtHandle)
+            })
+            .as_ref();
+        let message = metadata.and_then(|m| format_message(m, event));
+
+        Some(EventEntry {
+            time,
+            provider,
+            event_id,
+            level: level_name(level).into(),
+            message,
+        })
+    }
+}
+
+/// 以 EvtRenderContextSystem 取出系統欄位，回傳 EVT_VARIANT 陣列所在的緩衝區
+fn render_system(context: &EvtHandle, event: &EvtHandle) -> Option<Vec<u64>> {
+    unsafe {
+        let mut used = 0u32;
+        let mut count = 0u32;
+        let _ = EvtRender(
+            context.0,
+            event.0,
+            EvtRenderEventValues.0,
+            0,
+            None,
+            &mut used,
+            &mut count,
+        );
+        let mut buf = vec![0u64; (used as usize).div_ceil(8)];
+        EvtRender(
+            context.0,
+            event.0,
+            EvtRenderEventValues.0,
+            (buf.len() * 8) as u32,
+            Some(buf.as_mut_ptr().cast()),
+            &mut used,
+            &mut count,
+        )
+        .ok()?;
+        (count as usize >= EvtSystemPropertyIdEND.0 as usize).then_some(buf)
+    }
+}
+
+/// 欄位不存在（EvtVarTypeNull）時回傳 None
+fn system_value(buf: &[u64], id: i32) -> Option<&EVT_VARIANT> {
+    let values = unsafe {
+        std::slice::from_raw_parts(
+            buf.as_ptr() as *const EVT_VARIANT,
+            EvtSystemPropertyIdEND.0 as usize,
+        )
+    };
+    let v = &values[id as usize];
+    (v.Type != EVT_VAR_TYPE_NULL).then_some(v)
+}
+
+/// 最近一筆符合 `xpath` 的事件時間（RFC3339）；沒有符合的事件時為 None
+pub fn latest_event_time(channel: &str, xpath: &str) -> Result<Option<String>, String> {
+    Ok(latest_event(channel, xpath)?.and_then(|e| e.time))
+}
+
+/// latest_event 的結果
+pub struct LatestEvent {
+    /// RFC3339（UTC）
+    pub time: Option<String>,
+    /// EventData 中的字串值，依事件定義的順序；其他型別的值略過
+    pub strings: Vec<String>,
+}
+
+/// 最近一筆符合 `xpath` 的事件；沒有符合的事件時為 None
+pub fn latest_event(channel: &str, xpath: &str) -> Result<Option<LatestEvent>, String> {
+    let query = query(channel, xpath)?;
+    let context = unsafe { EvtCreateRenderContext(None, EvtRenderContextSystem.0) }
+        .map(EvtHandle)
+        .map_err(|e| format!("EvtCreateRenderContext failed: {e}"))?;
+
+    let mut handles = [0isize; 1];
+    let mut returned = 0u32;
+    match unsafe { EvtNext(query.0, &mut handles, NEXT_TIMEOUT_MS, 0, &mut returned) } {
+        Ok(()) if returned > 0 => {}
+        Ok(()) => return Ok(None),
+        Err(e) if e.code() == ERROR_NO_MORE_ITEMS.to_hresult() => return Ok(None),
+        Err(e) => return Err(format!("EvtNext failed: {e}")),
+    }
+    let event = EvtHandle(EVT_HANDLE(handles[0]));
+    let buf = render_system(&context, &event).ok_or("EvtRender failed")?;
+    let time = system_value(&buf, EvtSystemTimeCreated.0)
+        .and_then(|v| filetime_to_rfc3339(unsafe { v.Anonymous.FileTimeVal } as i64));
+    Ok(Some(LatestEvent {
+        time,
+        strings: user_strings(&event),
+    }))
+}
+
+/// 以 EvtRenderContextUser 取出 EventData，只留字串值；讀不到時為空陣列
+fn user_strings(event: &EvtHandle) -> Vec<String> {
+    let Ok(context) =
+        unsafe { EvtCreateRenderContext(None, EvtRenderContextUser.0) }.map(EvtHandle)
+    else {
+        return Vec::new();
+    };
+    unsafe {
+        let mut used = 0u32;
+        let mut count = 0u32;
+        let _ = EvtRender(
+            context.0,
+            event.0,
+            EvtRenderEventValues.0,
+            0,
+            None,
+            &mut used,
+            &mut count,
+        );
+        let mut buf = vec![0u64; (used as usize).div_ceil(8)];
+        if EvtRender(
+            context.0,
+            event.0,
+            EvtRenderEventValues.0,
+            (buf.len() * 8) as u32,
+            Some(buf.as_mut_ptr().cast()),
+            &mut used,
+            &mut count,
+        )
+        .is_err()
+        {
+            return Vec::new();
+        }
+        std::slice::from_raw_parts(buf.as_ptr() as *const EVT_VARIANT, count as usize)
+            .iter()
+            .filter(|v| v.Type == EvtVarTypeString.0 as u32)
+            .filter_map(|v| v.Anonymous.StringVal.to_string().ok())
+            .collect()
+    }
+}
+
+fn format_message(metadata: &EvtHandle, event: &EvtHandle) -> Option<String> {
+    unsafe {
+        let mut used = 0u32;
+        let _ = EvtFormatMessage(
+            metadata.0,
+            event.0,
+            0,
+            None,
+            EvtFormatMessageEvent.0,
+            None,
+            &mut used,
+        );
+        let mut buf = vec![0u16; used as usize];
+        EvtFormatMessage(
+            metadata.0,
+            event.0,
+            0,
+            None,
+            EvtFormatMessageEvent.0,
+            Some(&mut buf),
+            &mut used,
+        )
+        .ok()?;
+        let text = wide_to_string(&buf);
+        Some(truncate(text.trim(), *MESSAGE_BYTES))
+    }
+}
+
+/// 依 bytes 截斷，但不切斷 UTF-8 字元
+fn truncate(s: &str, max: usize) -> String {
+    if s.len() <= max {
+        return s.into();
+    }
+    let mut end = max;
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}…", &s[..end])
+}
@@ -0,0 +1,133 @@
+//! 檔案探測：CAPTURE_FILE_PROBES 列出的檔案是否存在、大小與最後修改距今多久
+//!
+//! 每一項為 `路徑` 或 `路徑|最長秒數`，例如 `D:\Backups\db-*.bak|86400`；設了最長秒數才會判斷 stale。
+//! 檔名部分可用 `*` / `?`（不分大小寫），符合多個時取最後修改時間最新的那個；資料夾部分不支援萬用字元。
+use serde::Serialize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::LazyLock,
+    time::SystemTime,
+};
+
+use crate::{config, MetricError};
+
+static PROBES: LazyLock<Vec<(String, Option<u64>)>> = LazyLock::new(|| {
+    config::env_list("CAPTURE_FILE_PROBES")
+        .into_iter()
+        .map(|item| match item.rsplit_once('|') {
+            Some((path, max)) => (path.trim().to_string(), max.trim().parse().ok()),
+            None => (item, None),
+        })
+        .collect()
+});
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct FileProbe {
+    /// 設定中的路徑（可能含萬用字元）
+    pub pattern: String,
+    /// 實際比對到的檔案；沒有符合時為 None
+    pub path: Option<String>,
+    pub exists: bool,
+    pub size_bytes: Option<u64>,
+    pub modified_age_seconds: Option<u64>,
+    pub max_age_seconds: Option<u64>,
+    /// 超過 max_age_seconds 或檔案不存在；沒有設定最長秒數時為 None
+    pub stale: Option<bool>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub fn gather_files(errors: &mut Vec<MetricError>) -> Vec<FileProbe> {
+    if PROBES.is_empty() {
+        errors.push(MetricError {
+            metric: vec!["files".into()],
+            err: "set CAPTURE_FILE_PROBES to probe files".into(),
+        });
+        return Vec::new();
+    }
+
+    let now = SystemTime::now();
+    PROBES
+        .iter()
+        .map(|(pattern, max_age)| {
+            let found = newest_match(pattern);
+            let age = found.as_ref().map(|(_, meta)| {
+                let modified = meta.modified().unwrap_or(now);
+                // 修改時間在未來（時鐘被調過）時視為剛修改
+                now.duration_since(modified).map_or(0, |d| d.as_secs())
+            });
+            let stale = max_age.map(|max| age.is_none_or(|a| a > max));
+            if let (Some(true), Some(max)) = (stale, max_age) {
+                errors.push(MetricError {
+                    metric: vec![format!("files.{pattern}")],
+                    err: match age {
+                        Some(a) => format!("last modified {a}s ago (max {max}s)"),
+                        None => "no matching file".into(),
+                    },
+                });
+            }
+            FileProbe {
+                pattern: pattern.clone(),
+                path: found.as_ref().map(|(p, _)| p.display().to_string()),
+                exists: found.is_some(),
+                size_bytes: found.as_ref().map(|(_, meta)| meta.len()),
+                modified_age_seconds: age,
+                max_age_seconds: *max_age,
+                stale,
+            }
+        })
+        .collect()
+}
+
+/// 沒有萬用字元時直接查該檔；只看一般檔案，資料夾不算
+fn newest_match(pattern: &str) -> Option<(PathBuf, fs::Metadata)> {
+    let path = Path::new(pattern);
+    let name = path.file_name()?.to_str()?;
+    if !name.contains(['*', '?']) {
+        let meta = fs::metadata(path).ok().filter(|m| m.is_file())?;
+        return Some((path.to_path_buf(), meta));
+    }
+
+    let dir = path.parent().filter(|p| !p.as_os_str().is_empty())?;
+    fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .filter(|e| {
+            e.file_name()
+                .to_str()
+                .is_some_and(|n| wildcard_match(name, n))
+        })
+        .filter_map(|e| {
+            let meta = e.metadata().ok().filter(|m| m.is_file())?;
+            Some((e.path(), meta))
+        })
+        .max_by_key(|(_, meta)| meta.modified().ok())
+}
+
+/// `*` 任意長度、`?` 單一字元，不分大小寫（與檔案總管相同）
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let p: Vec<char> = pattern.to_lowercase().chars().collect();
+    let n: Vec<char> = name.to_lowercase().chars().collect();
+    let (mut pi, mut ni) = (0, 0);
+    // 最近一個 `*` 的位置與當時對到的 name 位置，比對失敗時從這裡回溯
+    let mut star: Option<(usize, usize)> = None;
+    while ni < n.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == n[ni]) {
+            pi += 1;
+            ni += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ni));
+            pi += 1;
+        } else if let Some((sp, sn)) = star {
+            pi = sp + 1;
+            ni = sn + 1;
+            star = Some((sp, sn + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|&c| c == '*')
+}
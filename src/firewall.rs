@@ -0,0 +1,101 @@
+//! Windows 防火牆：INetFwPolicy2 讀取各設定檔的啟用狀態、預設輸入動作與目前作用中的設定檔
+//!
+//! COM 呼叫放在 spawn_blocking 的執行緒上，由 ComGuard 初始化 MTA，不碰 async runtime 的執行緒。
+use serde::Serialize;
+use windows::Win32::{
+    NetworkManagement::WindowsFirewall::{
+        INetFwPolicy2, NetFwPolicy2, NET_FW_ACTION_ALLOW, NET_FW_ACTION_BLOCK,
+        NET_FW_PROFILE2_DOMAIN, NET_FW_PROFILE2_PRIVATE, NET_FW_PROFILE2_PUBLIC,
+        NET_FW_PROFILE_TYPE2,
+    },
+    System::Com::{CoCreateInstance, CLSCTX_INPROC_SERVER},
+};
+
+use crate::{com::ComGuard, MetricError};
+
+const PROFILES: [(NET_FW_PROFILE_TYPE2, &str); 3] = [
+    (NET_FW_PROFILE2_DOMAIN, "domain"),
+    (NET_FW_PROFILE2_PRIVATE, "private"),
+    (NET_FW_PROFILE2_PUBLIC, "public"),
+];
+
+/* ---------- 資料結構 ---------- */
+
+/// 各設定檔的預設輸入動作：allow / block
+#[derive(Serialize, Default)]
+pub struct InboundActions {
+    pub domain: Option<String>,
+    pub private: Option<String>,
+    pub public: Option<String>,
+}
+
+#[derive(Serialize, Default)]
+pub struct FirewallData {
+    pub domain_enabled: Option<bool>,
+    pub private_enabled: Option<bool>,
+    pub public_enabled: Option<bool>,
+    /// 目前連線的網路所套用的設定檔，例如 `["domain"]`；可能同時有多個
+    pub active_profiles: Vec<String>,
+    pub default_inbound_action: InboundActions,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub async fn gather_firewall(errors: &mut Vec<MetricError>) -> FirewallData {
+    let result = tokio::task::spawn_blocking(query_firewall)
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+    result.unwrap_or_else(|e| {
+        errors.push(MetricError {
+            metric: vec!["firewall".into()],
+            err: e,
+        });
+        FirewallData::default()
+    })
+}
+
+fn query_firewall() -> Result<FirewallData, String> {
+    let _com = ComGuard::init()?;
+    unsafe {
+        let policy: INetFwPolicy2 = CoCreateInstance(&NetFwPolicy2, None, CLSCTX_INPROC_SERVER)
+            .map_err(|e| format!("CoCreateInstance(NetFwPolicy2) failed: {e}"))?;
+
+        let enabled = |profile| {
+            policy
+                .get_FirewallEnabled(profile)
+                .ok()
+                .map(|b| b.as_bool())
+        };
+        let inbound = |profile| {
+            policy
+                .get_DefaultInboundAction(profile)
+                .ok()
+                .and_then(|a| match a {
+                    NET_FW_ACTION_ALLOW => Some("allow".to_string()),
+                    NET_FW_ACTION_BLOCK => Some("block".to_string()),
+                    _ => None,
+                })
+        };
+
+        let current = policy
+            .CurrentProfileTypes()
+            .map_err(|e| format!("INetFwPolicy2::CurrentProfileTypes failed: {e}"))?;
+        let active_profiles = PROFILES
+            .iter()
+            .filter(|(p, _)| current & p.0 != 0)
+            .map(|(_, name)| name.to_string())
+            .collect();
+
+        Ok(FirewallData {
+            domain_enabled: enabled(NET_FW_PROFILE2_DOMAIN),
+            private_enabled: enabled(NET_FW_PROFILE2_PRIVATE),
+            public_enabled: enabled(NET_FW_PROFILE2_PUBLIC),
+            active_profiles,
+            default_inbound_action: InboundActions {
+                domain: inbound(NET_FW_PROFILE2_DOMAIN),
+                private: inbound(NET_FW_PROFILE2_PRIVATE),
+                public: inbound(NET_FW_PROFILE2_PUBLIC),
+            },
+        })
+    }
+}
@@ -0,0 +1,217 @@
+//! GPU 使用率：背景取樣 \GPU Engine(*)\Utilization Percentage，依介面卡與引擎類型彙總
+//! GPU 記憶體：\GPU Adapter Memory(*)\Dedicated / Shared Usage，名稱由 DXGI 依 LUID 對應
+//!
+//! instance 名稱形如 `pid_1234_luid_0x00000000_0x0000D1B5_phys_0_eng_3_engtype_VideoDecode`，
+//! 同一個引擎會依行程拆成多個 instance，要先加總再取最大值（與工作管理員一致）。
+//!
+//! GPU 溫度見 gputemp.rs；廠商 API 沒有 LUID，依同廠商介面卡在 DXGI 中的列舉順序對應。
+use serde::Serialize;
+use std::collections::BTreeMap;
+use windows::Win32::Graphics::Dxgi::{CreateDXGIFactory1, IDXGIFactory1, DXGI_ADAPTER_DESC1};
+
+use crate::{
+    gputemp,
+    pdh::{self, Format, SampledCounter},
+    win::wide_to_string,
+    MetricError,
+};
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct GpuEngineData {
+    /// 3D、Copy、VideoDecode、VideoEncode…
+    pub engine_type: String,
+    /// 0–1；同類型多個引擎時取最忙的那個
+    pub utilization_percent: f64,
+}
+
+#[derive(Serialize)]
+pub struct GpuData {
+    /// 介面卡 LUID，例如 `0x00000000_0x0000D1B5`
+    pub adapter_luid: String,
+    /// DXGI 介面卡描述；對應不到時為 None
+    pub name: Option<String>,
+    /// 0–1；所有引擎中最高者
+    pub utilization_percent: f64,
+    pub engines: Vec<GpuEngineData>,
+    pub dedicated_memory_used_bytes: Option<u64>,
+    pub shared_memory_used_bytes: Option<u64>,
+    pub temperature_celsius: Option<f64>,
+    /// nvapi / adl；沒有廠商驅動或不支援的廠商為 unsupported
+    pub temperature_source: &'static str,
+}
+
+/// DXGI 列舉到的介面卡，順序與 EnumAdapters1 相同
+struct DxgiAdapter {
+    luid: String,
+    name: String,
+    vendor_id: u32,
+}
+
+pub const PDH_COUNTERS: &[SampledCounter] = &[
+    SampledCounter::new(
+        "gpu.engine_utilization",
+        "\\GPU Engine(*)\\Utilization Percentage",
+        Format::Double,
+    ),
+    SampledCounter::new(
+        "gpu.dedicated_usage",
+        "\\GPU Adapter Memory(*)\\Dedicated Usage",
+        Format::Large,
+    ),
+    SampledCounter::new(
+        "gpu.shared_usage",
+        "\\GPU Adapter Memory(*)\\Shared Usage",
+        Format::Large,
+    ),
+];
+
+/* ---------- 指標蒐集 ---------- */
+
+/// 沒有 GPU 計數器（舊系統、無 WDDM 2.x 驅動）時回傳空陣列
+pub fn gather_gpu(errors: &mut Vec<MetricError>) -> Vec<GpuData> {
+    let mut adapters: BTreeMap<String, GpuData> = BTreeMap::new();
+
+    for (luid, by_type) in engine_utilization() {
+        let gpu = adapter_entry(&mut adapters, luid);
+        gpu.utilization_percent = by_type.values().copied().fold(0.0, f64::max);
+        gpu.engines = by_type
+            .into_iter()
+            .map(|(engine_type, utilization_percent)| GpuEngineData {
+                engine_type,
+                utilization_percent,
+            })
+            .collect();
+    }
+
+    for (luid, bytes) in adapter_memory("gpu.dedicated_usage") {
+        adapter_entry(&mut adapters, luid).dedicated_memory_used_bytes = Some(bytes);
+    }
+    for (luid, bytes) in adapter_memory("gpu.shared_usage") {
+        adapter_entry(&mut adapters, luid).shared_memory_used_bytes = Some(bytes);
+    }
+
+    let dxgi = dxgi_adapters();
+    for a in &dxgi {
+        if let Some(gpu) = adapters.get_mut(&a.luid) {
+            gpu.name = Some(a.name.clone());
+        }
+    }
+    apply_temperatures(&mut adapters, &dxgi, errors);
+    adapters.into_values().collect()
+}
+
+/// 每個廠商只呼叫一次廠商 API，第 n 個溫度給該廠商在 DXGI 中的第 n 張卡
+fn apply_temperatures(
+    adapters: &mut BTreeMap<String, GpuData>,
+    dxgi: &[DxgiAdapter],
+    errors: &mut Vec<MetricError>,
+) {
+    let mut vendors: Vec<u32> = dxgi.iter().map(|a| a.vendor_id).collect();
+    vendors.sort_unstable();
+    vendors.dedup();
+    for vendor in vendors {
+        let Some((source, temps)) = gputemp::temperatures(vendor, errors) else {
+            continue;
+        };
+        let same_vendor = dxgi.iter().filter(|a| a.vendor_id == vendor);
+        for (a, temp) in same_vendor.zip(temps) {
+            if let Some(gpu) = adapters.get_mut(&a.luid) {
+                gpu.temperature_celsius = temp;
+                gpu.temperature_source = source;
+            }
+        }
+    }
+}
+
+fn adapter_entry(adapters: &mut BTreeMap<String, GpuData>, luid: String) -> &mut GpuData {
+    adapters.entry(luid.clone()).or_insert_with(|| GpuData {
+        adapter_luid: luid,
+        name: None,
+        utilization_percent: 0.0,
+        engines: Vec::new(),
+        dedicated_memory_used_bytes: None,
+        shared_memory_used_bytes: None,
+        temperature_celsius: None,
+        temperature_source: "unsupported",
+    })
+}
+
+/// luid → engtype → 最忙引擎的使用率（0–1）
+fn engine_utilization() -> BTreeMap<String, BTreeMap<String, f64>> {
+    let mut adapters: BTreeMap<String, BTreeMap<String, f64>> = BTreeMap::new();
+    let Some(Ok(values)) = pdh::latest("gpu.engine_utilization") else {
+        return adapters;
+    };
+
+    // (luid, phys, eng) → (engtype, 各行程加總)
+    let mut engines: BTreeMap<(String, String, String), (String, f64)> = BTreeMap::new();
+    for (name, v) in values {
+        if let Some((luid, phys, eng, engtype)) = parse_instance(&name) {
+            let slot = engines
+                .entry((luid, phys, eng))
+                .or_insert_with(|| (engtype, 0.0));
+            slot.1 += v.as_f64();
+        }
+    }
+
+    for ((luid, _, _), (engtype, total)) in engines {
+        let ratio = (total / 100.0).clamp(0.0, 1.0);
+        let by_type = adapters.entry(luid).or_default();
+        let slot = by_type.entry(engtype).or_insert(0.0);
+        *slot = slot.max(ratio);
+    }
+    adapters
+}
+
+/// GPU Adapter Memory 的 instance 形如 `luid_0x00000000_0x0000D1B5_phys_0`，同一 LUID 的各 phys 加總
+fn adapter_memory(key: &str) -> BTreeMap<String, u64> {
+    let mut out = BTreeMap::new();
+    let Some(Ok(values)) = pdh::latest(key) else {
+        return out;
+    };
+    for (name, v) in values {
+        let Some(rest) = name.strip_prefix("luid_") else {
+            continue;
+        };
+        let luid = rest.split_once("_phys_").map_or(rest, |(l, _)| l);
+        *out.entry(luid.to_string()).or_insert(0) += v.as_i64().max(0) as u64;
+    }
+    out
+}
+
+/// LUID 字串與 PDH instance 同格式
+fn dxgi_adapters() -> Vec<DxgiAdapter> {
+    let mut adapters = Vec::new();
+    let Ok(factory) = (unsafe { CreateDXGIFactory1::<IDXGIFactory1>() }) else {
+        return adapters;
+    };
+    let mut index = 0;
+    while let Ok(adapter) = unsafe { factory.EnumAdapters1(index) } {
+        let mut desc = DXGI_ADAPTER_DESC1::default();
+        if unsafe { adapter.GetDesc1(&mut desc) }.is_ok() {
+            let luid = format!(
+                "0x{:08X}_0x{:08X}",
+                desc.AdapterLuid.HighPart as u32, desc.AdapterLuid.LowPart
+            );
+            adapters.push(DxgiAdapter {
+                luid,
+                name: wide_to_string(&desc.Description),
+                vendor_id: desc.VendorId,
+            });
+        }
+        index += 1;
+    }
+    adapters
+}
+
+/// 拆出 (luid, phys, eng, engtype)
+fn parse_instance(name: &str) -> Option<(String, String, String, String)> {
+    let rest = &name[name.find("luid_")? + 5..];
+    let (luid, rest) = rest.split_once("_phys_")?;
+    let (phys, rest) = rest.split_once("_eng_")?;
+    let (eng, engtype) = rest.split_once("_engtype_")?;
+    let engtype = if engtype.is_empty() { "Other" } else { engtype };
+    Some((luid.into(), phys.into(), eng.into(), engtype.into()))
+}
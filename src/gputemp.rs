@@ -0,0 +1,313 @@
+//! GPU 溫度：NVIDIA 走 NVAPI（nvapi64.dll），AMD 走 ADL（atiadlxx.dll）
+//!
+//! 兩者都在執行時以 LoadLibraryEx 從 System32 載入，沒有安裝驅動的機器照常執行，只回報 unsupported。
+//! 函式庫第一次使用時載入並初始化，之後沿用；各廠商 API 依自己的列舉順序回傳溫度，
+//! 由 gpu.rs 依 DXGI 列舉順序對應到同廠商的介面卡。
+use std::{
+    ffi::c_void,
+    sync::{LazyLock, Mutex},
+};
+use windows::{
+    core::{s, HSTRING, PCSTR},
+    Win32::{
+        Foundation::HMODULE,
+        System::LibraryLoader::{GetProcAddress, LoadLibraryExW, LOAD_LIBRARY_SEARCH_SYSTEM32},
+    },
+};
+
+use crate::MetricError;
+
+pub const VENDOR_NVIDIA: u32 = 0x10DE;
+pub const VENDOR_AMD: u32 = 0x1002;
+
+/// 載入結果：None 代表驅動未安裝（unsupported），Some(Err) 代表已安裝但初始化失敗
+static NVAPI: LazyLock<Option<Result<Nvapi, String>>> = LazyLock::new(Nvapi::load);
+static ADL: LazyLock<Option<Result<Adl, String>>> = LazyLock::new(Adl::load);
+/// ADL 不是執行緒安全的，所有呼叫都要持有這把鎖
+static ADL_LOCK: Mutex<()> = Mutex::new(());
+
+/// 有讀到溫度時回傳 (來源, 各 GPU 攝氏溫度)，順序為廠商 API 的列舉順序；讀不到的 GPU 為 None
+///
+/// 不支援的廠商或沒有驅動時回傳 None；驅動存在但呼叫失敗時另記 error（注明廠商）
+pub fn temperatures(
+    vendor_id: u32,
+    errors: &mut Vec<MetricError>,
+) -> Option<(&'static str, Vec<Option<f64>>)> {
+    let (vendor, source, result) = match vendor_id {
+        VENDOR_NVIDIA => match NVAPI.as_ref()? {
+            Ok(nv) => ("nvidia", "nvapi", nv.temperatures()),
+            Err(e) => ("nvidia", "nvapi", Err(e.clone())),
+        },
+        VENDOR_AMD => match ADL.as_ref()? {
+            Ok(adl) => ("amd", "adl", adl.temperatures()),
+            Err(e) => ("amd", "adl", Err(e.clone())),
+        },
+        _ => return None,
+    };
+    match result {
+        Ok(list) => Some((source, list)),
+        Err(err) => {
+            errors.push(MetricError {
+                metric: vec![format!("gpu.temperature.{vendor}")],
+                err,
+            });
+            None
+        }
+    }
+}
+
+/// 從 System32 載入；找不到檔案時回傳 None
+fn load_system_library(name: &str) -> Option<HMODULE> {
+    unsafe { LoadLibraryExW(&HSTRING::from(name), None, LOAD_LIBRARY_SEARCH_SYSTEM32) }.ok()
+}
+
+/// GetProcAddress 並轉成指定的函式指標型別
+///
+/// # Safety
+/// T 必須是與該匯出函式簽章相符的函式指標型別
+unsafe fn proc_address<T: Copy>(module: HMODULE, name: PCSTR) -> Result<T, String> {
+    unsafe {
+        let f = GetProcAddress(module, name)
+            .ok_or_else(|| format!("{} not exported", name.display()))?;
+        Ok(std::mem::transmute_copy(&f))
+    }
+}
+
+/* ---------- NVAPI ---------- */
+
+type NvQueryInterface = unsafe extern "C" fn(u32) -> *const c_void;
+type NvInitialize = unsafe extern "C" fn() -> i32;
+type NvEnumPhysicalGpus = unsafe extern "C" fn(*mut [*mut c_void; 64], *mut u32) -> i32;
+type NvGetThermalSettings = unsafe extern "C" fn(*mut c_void, u32, *mut NvThermalSettings) -> i32;
+
+/// nvapi_QueryInterface 的函式 ID（NVAPI SDK 公開的值）
+const NVAPI_INITIALIZE: u32 = 0x0150_E828;
+const NVAPI_ENUM_PHYSICAL_GPUS: u32 = 0xE5AC_921F;
+const NVAPI_GPU_GET_THERMAL_SETTINGS: u32 = 0xE364_0A56;
+const NVAPI_THERMAL_TARGET_ALL: u32 = 15;
+const NVAPI_THERMAL_TARGET_GPU: i32 = 1;
+
+#[repr(C)]
+#[derive(Default, Clone, Copy)]
+struct NvThermalSensor {
+    controller: i32,
+    default_min_temp: i32,
+    default_max_temp: i32,
+    current_temp: i32,
+    target: i32,
+}
+
+/// NV_GPU_THERMAL_SETTINGS_V2
+#[repr(C)]
+#[derive(Default)]
+struct NvThermalSettings {
+    version: u32,
+    count: u32,
+    sensor: [NvThermalSensor; 3],
+}
+
+struct Nvapi {
+    enum_physical_gpus: NvEnumPhysicalGpus,
+    get_thermal_settings: NvGetThermalSettings,
+}
+
+impl Nvapi {
+    fn load() -> Option<Result<Self, String>> {
+        let module = load_system_library("nvapi64.dll")?;
+        Some(unsafe { Self::init(module) })
+    }
+
+    unsafe fn init(module: HMODULE) -> Result<Self, String> {
+        unsafe {
+            let query: NvQueryInterface = proc_address(module, s!("nvapi_QueryInterface"))?;
+            let lookup = |id: u32| {
+                let f = query(id);
+                (!f.is_null())
+                    .then_some(f)
+                    .ok_or_else(|| format!("NVAPI function 0x{id:08X} not available"))
+            };
+            let initialize =
+                std::mem::transmute::<*const c_void, NvInitialize>(lookup(NVAPI_INITIALIZE)?);
+            let status = initialize();
+            if status != 0 {
+                return Err(format!("NvAPI_Initialize failed: status {status}"));
+            }
+            Ok(Self {
+                enum_physical_gpus: std::mem::transmute::<*const c_void, NvEnumPhysicalGpus>(
+                    lookup(NVAPI_ENUM_PHYSICAL_GPUS)?,
+                ),
+                get_thermal_settings: std::mem::transmute::<*const c_void, NvGetThermalSettings>(
+                    lookup(NVAPI_GPU_GET_THERMAL_SETTINGS)?,
+                ),
+            })
+        }
+    }
+
+    fn temperatures(&self) -> Result<Vec<Option<f64>>, String> {
+        let mut handles = [std::ptr::null_mut(); 64];
+        let mut count = 0u32;
+        let status = unsafe { (self.enum_physical_gpus)(&mut handles, &mut count) };
+        if status != 0 {
+            return Err(format!("NvAPI_EnumPhysicalGPUs failed: status {status}"));
+        }
+        Ok(handles[..count.min(64) as usize]
+            .iter()
+            .map(|&gpu| {
+                let mut settings = NvThermalSettings {
+                    version: std::mem::size_of::<NvThermalSettings>() as u32 | (2 << 16),
+                    ..Default::default()
+                };
+                let status = unsafe {
+                    (self.get_thermal_settings)(gpu, NVAPI_THERMAL_TARGET_ALL, &mut settings)
+                };
+                if status != 0 {
+                    return None;
+                }
+                let sensors = &settings.sensor[..settings.count.min(3) as usize];
+                // 優先取 GPU 核心感測器，沒有時取第一個（記憶體、電源等）
+                sensors
+                    .iter()
+                    .find(|s| s.target == NVAPI_THERMAL_TARGET_GPU)
+                    .or(sensors.first())
+                    .map(|s| s.current_temp as f64)
+            })
+            .collect())
+    }
+}
+
+/* ---------- ADL ---------- */
+
+type AdlMalloc = unsafe extern "C" fn(i32) -> *mut c_void;
+type AdlMainControlCreate = unsafe extern "C" fn(AdlMalloc, i32) -> i32;
+type AdlNumberOfAdapters = unsafe extern "C" fn(*mut i32) -> i32;
+type AdlAdapterInfoGet = unsafe extern "C" fn(*mut AdapterInfo, i32) -> i32;
+type AdlOd5Temperature = unsafe extern "C" fn(i32, i32, *mut AdlTemperature) -> i32;
+type AdlOd6Temperature = unsafe extern "C" fn(i32, *mut i32) -> i32;
+
+const ADL_MAX_PATH: usize = 256;
+
+/// ADL 的 AdapterInfo（Windows 版，含 driver path 等欄位）
+#[repr(C)]
+struct AdapterInfo {
+    size: i32,
+    adapter_index: i32,
+    udid: [u8; ADL_MAX_PATH],
+    bus_number: i32,
+    device_number: i32,
+    function_number: i32,
+    vendor_id: i32,
+    adapter_name: [u8; ADL_MAX_PATH],
+    display_name: [u8; ADL_MAX_PATH],
+    present: i32,
+    exist: i32,
+    driver_path: [u8; ADL_MAX_PATH],
+    driver_path_ext: [u8; ADL_MAX_PATH],
+    pnp_string: [u8; ADL_MAX_PATH],
+    os_display_index: i32,
+}
+
+#[repr(C)]
+struct AdlTemperature {
+    size: i32,
+    /// 千分之一度
+    temperature: i32,
+}
+
+struct Adl {
+    number_of_adapters: AdlNumberOfAdapters,
+    adapter_info_get: AdlAdapterInfoGet,
+    od5_temperature: Option<AdlOd5Temperature>,
+    od6_temperature: Option<AdlOd6Temperature>,
+}
+
+/// ADL 以這個 callback 配置要交給呼叫端釋放的記憶體；這裡用到的函式都由呼叫端提供緩衝區，
+/// 實際上不會被呼叫，保守起見仍配置一塊永不釋放的記憶體
+unsafe extern "C" fn adl_malloc(size: i32) -> *mut c_void {
+    Box::leak(vec![0u8; size.max(0) as usize].into_boxed_slice())
+        .as_mut_ptr()
+        .cast()
+}
+
+impl Adl {
+    fn load() -> Option<Result<Self, String>> {
+        let module = load_system_library("atiadlxx.dll")?;
+        Some(unsafe { Self::init(module) })
+    }
+
+    unsafe fn init(module: HMODULE) -> Result<Self, String> {
+        unsafe {
+            let create: AdlMainControlCreate = proc_address(module, s!("ADL_Main_Control_Create"))?;
+            let _lock = ADL_LOCK.lock().unwrap();
+            // 第二個參數 1：只列舉目前存在的介面卡
+            let status = create(adl_malloc, 1);
+            if status != 0 {
+                return Err(format!("ADL_Main_Control_Create failed: status {status}"));
+            }
+            let adl = Self {
+                number_of_adapters: proc_address(module, s!("ADL_Adapter_NumberOfAdapters_Get"))?,
+                adapter_info_get: proc_address(module, s!("ADL_Adapter_AdapterInfo_Get"))?,
+                od5_temperature: proc_address(module, s!("ADL_Overdrive5_Temperature_Get")).ok(),
+                od6_temperature: proc_address(module, s!("ADL_Overdrive6_Temperature_Get")).ok(),
+            };
+            if adl.od5_temperature.is_none() && adl.od6_temperature.is_none() {
+                return Err("ADL exports no Overdrive temperature function".into());
+            }
+            Ok(adl)
+        }
+    }
+
+    /// ADL 為每個顯示輸出各列一個 adapter index，依 PCI bus 去重，每張實體卡只讀一次
+    fn temperatures(&self) -> Result<Vec<Option<f64>>, String> {
+        let _lock = ADL_LOCK.lock().unwrap();
+        let mut count = 0i32;
+        let status = unsafe { (self.number_of_adapters)(&mut count) };
+        if status != 0 {
+            return Err(format!(
+                "ADL_Adapter_NumberOfAdapters_Get failed: status {status}"
+            ));
+        }
+        if count <= 0 {
+            return Ok(Vec::new());
+        }
+
+        let mut infos: Vec<AdapterInfo> =
+            (0..count).map(|_| unsafe { std::mem::zeroed() }).collect();
+        for info in &mut infos {
+            info.size = std::mem::size_of::<AdapterInfo>() as i32;
+        }
+        let bytes = (infos.len() * std::mem::size_of::<AdapterInfo>()) as i32;
+        let status = unsafe { (self.adapter_info_get)(infos.as_mut_ptr(), bytes) };
+        if status != 0 {
+            return Err(format!(
+                "ADL_Adapter_AdapterInfo_Get failed: status {status}"
+            ));
+        }
+
+        let mut buses = Vec::new();
+        let mut temps = Vec::new();
+        for info in &infos {
+            if info.vendor_id as u32 != VENDOR_AMD || buses.contains(&info.bus_number) {
+                continue;
+            }
+            buses.push(info.bus_number);
+            temps.push(self.temperature(info.adapter_index));
+        }
+        Ok(temps)
+    }
+
+    /// 舊卡走 Overdrive5，較新的卡走 Overdrive6；單位為千分之一度
+    fn temperature(&self, adapter: i32) -> Option<f64> {
+        if let Some(od5) = self.od5_temperature {
+            let mut t = AdlTemperature {
+                size: std::mem::size_of::<AdlTemperature>() as i32,
+                temperature: 0,
+            };
+            if unsafe { od5(adapter, 0, &mut t) } == 0 {
+                return Some(t.temperature as f64 / 1000.0);
+            }
+        }
+        let od6 = self.od6_temperature?;
+        let mut t = 0i32;
+        (unsafe { od6(adapter, &mut t) } == 0).then(|| t as f64 / 1000.0)
+    }
+}
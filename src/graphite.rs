@@ -0,0 +1,166 @@
+//! Graphite plaintext：設定 CAPTURE_GRAPHITE_ADDR（carbon 的 host:port）時，
+//! 每 CAPTURE_GRAPHITE_INTERVAL_SECS（預設 60）秒以 TCP 送出 `prefix.host.cpu.usage_ratio 0.42 1699999999`
+//!
+//! 路徑為 CAPTURE_GRAPHITE_PREFIX（預設 capture）、主機名稱、樣本名稱第一段、各標籤值、其餘名稱，
+//! 例如 `capture.web01.disk.C_.free_bytes`。Graphite 的路徑沒有跳脫方式，各段的點與空白等字元換成 `_`。
+//! 連線保留到寫入失敗為止；失敗時丟棄該輪資料，依 push::backoff 等待後重新連線。
+//! /api/v1/export/graphite 回傳同樣的內容，未設定位址時也能確認對應方式。最近一次結果放在 capture.graphite。
+use chrono::Utc;
+use serde::Serialize;
+use std::{
+    io::Write,
+    net::{TcpStream, ToSocketAddrs},
+    sync::{LazyLock, RwLock},
+    time::Duration,
+};
+
+use crate::{config, push, samples, AllMetrics};
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+static ADDR: LazyLock<Option<String>> =
+    LazyLock::new(|| config::env_string("CAPTURE_GRAPHITE_ADDR"));
+static PREFIX: LazyLock<String> = LazyLock::new(|| {
+    config::env_string("CAPTURE_GRAPHITE_PREFIX").unwrap_or_else(|| "capture".into())
+});
+static INTERVAL: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(config::env_parse("CAPTURE_GRAPHITE_INTERVAL_SECS", 60u64).max(1))
+});
+static STATUS: RwLock<Option<GraphiteStatus>> = RwLock::new(None);
+
+#[derive(Serialize, Clone, Default)]
+pub struct GraphiteStatus {
+    pub addr: String,
+    /// RFC3339（UTC）
+    pub last_success_time: Option<String>,
+    /// 最近一次失敗的原因；成功後清除
+    pub last_error: Option<String>,
+    pub consecutive_failures: u32,
+}
+
+/* ---------- 輸出 ---------- */
+
+/// 每個樣本一行；時間戳為 Unix 秒
+pub fn render(all: &AllMetrics, timestamp: i64) -> String {
+    let host = component(all.hostname.as_deref().unwrap_or("unknown"));
+    let mut out = String::new();
+    for s in samples::collect(all) {
+        if !s.value.is_finite() {
+            continue;
+        }
+        let (group, rest) = s.name.split_once('_').unwrap_or((s.name, "value"));
+        let mut path = format!("{}.{host}.{group}", *PREFIX);
+        for (_, v) in &s.labels {
+            path.push('.');
+            path.push_str(&component(v));
+        }
+        path.push('.');
+        path.push_str(rest);
+        out.push_str(&format!("{path} {} {timestamp}\n", s.value));
+    }
+    out
+}
+
+/// 只保留英數字、`_` 與 `-`
+pub fn component(s: &str) -> String {
+    s.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/* ---------- 背景傳送 ---------- */
+
+/// 未設定 CAPTURE_GRAPHITE_ADDR 時什麼都不做
+pub fn start() {
+    let Some(addr) = ADDR.clone() else {
+        return;
+    };
+    *STATUS.write().unwrap() = Some(GraphiteStatus {
+        addr: addr.clone(),
+        ..Default::default()
+    });
+    tokio::spawn(async move {
+        let mut conn: Option<TcpStream> = None;
+        let mut failures = 0u32;
+        loop {
+            let result = match crate::collect_all_blocking().await {
+                Ok(all) => {
+                    let payload = render(&all, Utc::now().timestamp()).into_bytes();
+                    let addr = addr.clone();
+                    let (returned, result) = tokio::task::spawn_blocking(move || {
+                        let result = send(&mut conn, &addr, &payload);
+                        (conn, result)
+                    })
+                    .await
+                    .unwrap_or_else(|e| (None, Err(e.to_string())));
+                    conn = returned;
+                    result
+                }
+                Err(e) => Err(format!("collect: {e}")),
+            };
+
+            {
+                let mut status = STATUS.write().unwrap();
+                let status = status.get_or_insert_with(Default::default);
+                match result {
+                    Ok(()) => {
+                        failures = 0;
+                        status.last_success_time = Some(Utc::now().to_rfc3339());
+                        status.last_error = None;
+                    }
+                    Err(e) => {
+                        failures += 1;
+                        status.last_error = Some(e);
+                    }
+                }
+                status.consecutive_failures = failures;
+            }
+            tokio::time::sleep(if failures == 0 {
+                *INTERVAL
+            } else {
+                push::backoff(failures)
+            })
+            .await;
+        }
+    });
+}
+
+/// 未設定 CAPTURE_GRAPHITE_ADDR 時為 None
+pub fn status() -> Option<GraphiteStatus> {
+    STATUS.read().unwrap().clone()
+}
+
+/// 寫入失敗時關閉連線，下一輪重新連線
+fn send(conn: &mut Option<TcpStream>, addr: &str, payload: &[u8]) -> Result<(), String> {
+    let stream = match conn {
+        Some(s) => s,
+        None => conn.insert(connect(addr)?),
+    };
+    stream.write_all(payload).map_err(|e| {
+        *conn = None;
+        format!("write to {addr}: {e}")
+    })
+}
+
+fn connect(addr: &str) -> Result<TcpStream, String> {
+    let resolved = addr
+        .to_socket_addrs()
+        .map_err(|e| format!("resolve {addr}: {e}"))?;
+    let mut last = format!("{addr} did not resolve to any address");
+    for sock in resolved {
+        match TcpStream::connect_timeout(&sock, TIMEOUT) {
+            Ok(s) => {
+                let _ = s.set_write_timeout(Some(TIMEOUT));
+                return Ok(s);
+            }
+            Err(e) => last = format!("connect {sock}: {e}"),
+        }
+    }
+    Err(last)
+}
@@ -0,0 +1,162 @@
+//! 硬體資產資訊：SMBIOS Type 0（BIOS）與 Type 1（System Information）
+//! 記憶體模組：Win32_PhysicalMemory，WMI 壞掉時改解析 SMBIOS Type 17（Memory Device）
+//!
+//! 開機後不會變動，第一次讀取後快取；WMI 會阻塞，main 啟動時先在 blocking 執行緒預載。
+use serde::Serialize;
+use std::sync::LazyLock;
+
+use crate::{smbios, wmi, MetricError};
+
+/// SMBIOS 結構 type
+const TYPE_BIOS: u8 = 0;
+const TYPE_SYSTEM: u8 = 1;
+const TYPE_MEMORY_DEVICE: u8 = 17;
+
+/// 讀取結果與過程中的錯誤（metric 名稱, 訊息），每次請求都重新回報錯誤
+static CACHE: LazyLock<(HardwareData, Vec<(&'static str, String)>)> = LazyLock::new(read_hardware);
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize, Clone)]
+pub struct MemoryModule {
+    pub capacity_bytes: Option<u64>,
+    pub speed_mhz: Option<u32>,
+    pub manufacturer: Option<String>,
+    pub part_number: Option<String>,
+    /// 主機板上的插槽標示，例如 `DIMM A1`、`ChannelA-DIMM0`
+    pub slot: Option<String>,
+    pub bank: Option<String>,
+}
+
+/// 字串欄位照韌體內容回傳，「To be filled by O.E.M.」等佔位字串不過濾
+#[derive(Serialize, Clone, Default)]
+pub struct HardwareData {
+    pub manufacturer: Option<String>,
+    pub product_name: Option<String>,
+    pub serial_number: Option<String>,
+    pub bios_vendor: Option<String>,
+    pub bios_version: Option<String>,
+    /// 韌體原始格式 mm/dd/yyyy
+    pub bios_release_date: Option<String>,
+    /// 只列出有插記憶體的插槽
+    pub memory_modules: Vec<MemoryModule>,
+    /// SMBIOS 中記憶體插槽的總數（含空插槽）；讀不到表格時為 None
+    pub memory_slots: Option<u32>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+/// 觸發第一次讀取；在 spawn_blocking 內呼叫
+pub fn preload() {
+    LazyLock::force(&CACHE);
+}
+
+/// 給其他模組讀取快取內容，不回報錯誤
+pub fn cached() -> &'static HardwareData {
+    &CACHE.0
+}
+
+pub fn gather_hardware(errors: &mut Vec<MetricError>) -> HardwareData {
+    let (hw, errs) = &*CACHE;
+    for (metric, err) in errs {
+        errors.push(MetricError {
+            metric: vec![(*metric).into()],
+            err: err.clone(),
+        });
+    }
+    hw.clone()
+}
+
+fn read_hardware() -> (HardwareData, Vec<(&'static str, String)>) {
+    let mut hw = HardwareData::default();
+    let mut errors = Vec::new();
+
+    let structures = match smbios::structures() {
+        Ok(s) => s,
+        Err(e) => {
+            errors.push(("hardware", e));
+            Vec::new()
+        }
+    };
+
+    // 規格上各只有一個；多個時取第一個
+    if let Some(bios) = structures.iter().find(|s| s.kind == TYPE_BIOS) {
+        hw.bios_vendor = bios.string(0x04);
+        hw.bios_version = bios.string(0x05);
+        hw.bios_release_date = bios.string(0x08);
+    }
+    if let Some(system) = structures.iter().find(|s| s.kind == TYPE_SYSTEM) {
+        hw.manufacturer = system.string(0x04);
+        hw.product_name = system.string(0x05);
+        hw.serial_number = system.string(0x07);
+    }
+
+    let devices: Vec<_> = structures
+        .iter()
+        .filter(|s| s.kind == TYPE_MEMORY_DEVICE)
+        .collect();
+    if !devices.is_empty() {
+        hw.memory_slots = Some(devices.len() as u32);
+    }
+
+    hw.memory_modules = match wmi_modules() {
+        Ok(m) => m,
+        // SMBIOS 有資料就不算失敗
+        Err(_) if !devices.is_empty() => devices.iter().filter_map(|d| smbios_module(d)).collect(),
+        Err(e) => {
+            errors.push(("hardware.memory_modules", e));
+            Vec::new()
+        }
+    };
+
+    (hw, errors)
+}
+
+fn wmi_modules() -> Result<Vec<MemoryModule>, String> {
+    let rows = wmi::query(
+        "root\\CIMV2",
+        "SELECT Capacity, Speed, Manufacturer, PartNumber, DeviceLocator, BankLabel \
+         FROM Win32_PhysicalMemory",
+    )?;
+    if rows.is_empty() {
+        return Err("no Win32_PhysicalMemory instances".into());
+    }
+    Ok(rows
+        .iter()
+        .map(|r| MemoryModule {
+            capacity_bytes: r.get_u64("Capacity"),
+            speed_mhz: r.get_u32("Speed").filter(|&s| s > 0),
+            manufacturer: r.get_string("Manufacturer").map(|s| s.trim().to_string()),
+            part_number: r.get_string("PartNumber").map(|s| s.trim().to_string()),
+            slot: r.get_string("DeviceLocator"),
+            bank: r.get_string("BankLabel"),
+        })
+        .collect())
+}
+
+/// Type 17：空插槽的 Size 為 0，回傳 None
+fn smbios_module(d: &smbios::Structure) -> Option<MemoryModule> {
+    let size = d.word(0x0C)?;
+    let capacity_bytes = match size {
+        0 => return None,
+        0xFFFF => None,
+        // 0x7FFF：實際大小在 Extended Size（MB）
+        0x7FFF => d
+            .dword(0x1C)
+            .map(|mb| (mb & 0x7FFF_FFFF) as u64 * 1024 * 1024),
+        // bit 15 = 1 時單位為 KB，否則為 MB
+        s if s & 0x8000 != 0 => Some((s & 0x7FFF) as u64 * 1024),
+        s => Some(s as u64 * 1024 * 1024),
+    };
+    Some(MemoryModule {
+        capacity_bytes,
+        speed_mhz: d
+            .word(0x15)
+            .filter(|&s| s > 0 && s != 0xFFFF)
+            .map(u32::from),
+        manufacturer: d.string(0x17),
+        part_number: d.string(0x1A),
+        slot: d.string(0x10),
+        bank: d.string(0x11),
+    })
+}
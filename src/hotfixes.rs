@@ -0,0 +1,105 @@
+//! 已安裝的 Hotfix（KB）：Win32_QuickFixEngineering
+//!
+//! WMI 查詢要好幾秒，完整清單快取 CAPTURE_HOTFIX_CACHE_SECS 秒（預設 600）；
+//! 所有函式都會阻塞，呼叫端請放在 spawn_blocking 內。
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::{
+    sync::{Arc, LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+use crate::{config, win::filetime_to_rfc3339, wmi};
+
+static TTL: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_secs(config::env_parse("CAPTURE_HOTFIX_CACHE_SECS", 600)));
+static CACHE: Mutex<Option<(Instant, Arc<Vec<Hotfix>>)>> = Mutex::new(None);
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct Hotfix {
+    /// 例如 `KB5031356`
+    pub kb: String,
+    /// Update / Security Update…
+    pub description: Option<String>,
+    /// `YYYY-MM-DD`；WMI 沒有記錄安裝日期時為 None
+    pub installed_on: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct KbStatus {
+    pub kb: String,
+    pub installed: bool,
+    pub installed_on: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct HotfixQuery {
+    /// `KB5031356` 或 `5031356`，不分大小寫
+    pub kb: Option<String>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+/// 快取過期才重新查詢；持有鎖期間查詢，避免同時多個請求重複打 WMI
+pub fn gather_hotfixes() -> Result<Arc<Vec<Hotfix>>, String> {
+    let mut cache = CACHE.lock().unwrap();
+    if let Some((at, list)) = cache.as_ref()
+        && at.elapsed() < *TTL
+    {
+        return Ok(list.clone());
+    }
+    let list = Arc::new(query_hotfixes()?);
+    *cache = Some((Instant::now(), list.clone()));
+    Ok(list)
+}
+
+pub fn kb_status(kb: &str) -> Result<KbStatus, String> {
+    let kb = normalize_kb(kb);
+    let list = gather_hotfixes()?;
+    let found = list.iter().find(|h| h.kb == kb);
+    Ok(KbStatus {
+        installed: found.is_some(),
+        installed_on: found.and_then(|h| h.installed_on.clone()),
+        kb,
+    })
+}
+
+fn query_hotfixes() -> Result<Vec<Hotfix>, String> {
+    let rows = wmi::query(
+        "root\\CIMV2",
+        "SELECT HotFixID, Description, InstalledOn FROM Win32_QuickFixEngineering",
+    )?;
+    let mut list: Vec<Hotfix> = rows
+        .iter()
+        .filter_map(|r| {
+            Some(Hotfix {
+                kb: normalize_kb(&r.get_string("HotFixID")?),
+                description: r.get_string("Description").filter(|d| !d.is_empty()),
+                installed_on: r.get_string("InstalledOn").and_then(|d| parse_installed_on(&d)),
+            })
+        })
+        .collect();
+    list.sort_by(|a, b| a.kb.cmp(&b.kb));
+    Ok(list)
+}
+
+fn normalize_kb(kb: &str) -> String {
+    let kb = kb.trim().to_ascii_uppercase();
+    if kb.starts_with("KB") {
+        kb
+    } else {
+        format!("KB{kb}")
+    }
+}
+
+/// InstalledOn 通常是 `M/D/YYYY`（不隨地區設定改變），少數舊紀錄是十六進位的 FILETIME
+fn parse_installed_on(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    if let Ok(d) = NaiveDate::parse_from_str(raw, "%m/%d/%Y") {
+        return Some(d.format("%Y-%m-%d").to_string());
+    }
+    let ft = i64::from_str_radix(raw, 16).ok()?;
+    filetime_to_rfc3339(ft).map(|t| t[..10].to_string())
+}
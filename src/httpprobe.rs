@@ -0,0 +1,386 @@
+//! HTTP(S) 探測：WinHTTP（同步模式）對 CAPTURE_HTTP_PROBES 指定的 URL 發出請求
+//!
+//! 設定為以空白（空格、tab 或換行）分隔的 `url`、`url|method` 或 `url|method|contains`：
+//! 空白與 `|` 都不能未經編碼出現在 URL 裡，URL 中的逗號、分號不受影響。method 為 GET（預設）或 HEAD，
+//! contains 是回應內容必須包含的字串（不能含空白；只讀前 MAX_BODY_BYTES，HEAD 不檢查）。
+//! 格式不對的項目不會探測，以 probe.http.<項目> 記在 errors。
+//! 逾時、TLS 驗證失敗、重新導向過多都記在該探測的 error，不影響其他探測。
+//! 推送模式的 POST 也用這裡的 post。所有函式都會阻塞，呼叫端請放在 spawn_blocking 內。
+use chrono::Utc;
+use serde::Serialize;
+use std::{
+    ffi::c_void,
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+use windows::{
+    core::{w, Error, HSTRING, PCWSTR, PWSTR},
+    Win32::{
+        Networking::WinHttp::{
+            WinHttpCloseHandle, WinHttpConnect, WinHttpCrackUrl, WinHttpOpen, WinHttpOpenRequest,
+            WinHttpQueryHeaders, WinHttpQueryOption, WinHttpReadData, WinHttpReceiveResponse,
+            WinHttpSendRequest, WinHttpSetOption, WinHttpSetTimeouts, ERROR_WINHTTP_CANNOT_CONNECT,
+            ERROR_WINHTTP_CONNECTION_ERROR, ERROR_WINHTTP_INVALID_URL,
+            ERROR_WINHTTP_NAME_NOT_RESOLVED, ERROR_WINHTTP_REDIRECT_FAILED,
+            ERROR_WINHTTP_SECURE_FAILURE, ERROR_WINHTTP_TIMEOUT, ERROR_WINHTTP_UNRECOGNIZED_SCHEME,
+            URL_COMPONENTS, WINHTTP_ACCESS_TYPE_DEFAULT_PROXY, WINHTTP_FLAG_SECURE,
+            WINHTTP_INTERNET_SCHEME_HTTPS, WINHTTP_OPEN_REQUEST_FLAGS,
+            WINHTTP_OPTION_MAX_HTTP_AUTOMATIC_REDIRECTS, WINHTTP_OPTION_REDIRECT_POLICY,
+            WINHTTP_OPTION_REDIRECT_POLICY_DISALLOW_HTTPS_TO_HTTP,
+            WINHTTP_OPTION_REDIRECT_POLICY_NEVER, WINHTTP_OPTION_SERVER_CERT_CONTEXT,
+            WINHTTP_QUERY_FLAG_NUMBER, WINHTTP_QUERY_STATUS_CODE,
+        },
+        Security::Cryptography::{CertFreeCertificateContext, CERT_CONTEXT},
+    },
+};
+
+use crate::{certs, config};
+
+/// contains 比對時最多讀取的回應內容
+const MAX_BODY_BYTES: usize = 1 << 20;
+
+/// 每個項目的解析結果；格式不對時為 (原始項目, 原因)
+pub static TARGETS: LazyLock<Vec<Result<HttpTarget, (String, String)>>> = LazyLock::new(|| {
+    std::env::var("CAPTURE_HTTP_PROBES")
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(|entry| parse_target(entry).map_err(|e| (entry.to_string(), e)))
+        .collect()
+});
+/// WinHTTP 的解析、連線、傳送、接收各自套用此上限
+static TIMEOUT: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(config::env_parse("CAPTURE_HTTP_PROBE_TIMEOUT_SECS", 5u64))
+});
+/// 0 代表不跟隨重新導向，直接回報 3xx
+static MAX_REDIRECTS: LazyLock<u32> =
+    LazyLock::new(|| config::env_parse("CAPTURE_HTTP_PROBE_MAX_REDIRECTS", 5u32));
+
+/// Drop 時 WinHttpCloseHandle
+struct Internet(*mut c_void);
+
+impl Drop for Internet {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = WinHttpCloseHandle(self.0);
+        }
+    }
+}
+
+/* ---------- 資料結構 ---------- */
+
+pub struct HttpTarget {
+    pub url: String,
+    pub method: String,
+    pub contains: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct HttpProbe {
+    pub url: String,
+    pub method: String,
+    /// 跟隨重新導向後的最終狀態碼；連線失敗時為 None
+    pub status_code: Option<u32>,
+    /// 失敗時為到失敗（或逾時）為止的時間
+    pub latency_ms: f64,
+    /// https 目標的伺服器憑證剩餘天數，已過期時為負數
+    pub tls_days_until_expiry: Option<i64>,
+    /// 有設定 contains 且方法為 GET 時才有值
+    pub body_matched: Option<bool>,
+    /// 沒有 error、狀態碼小於 400 且內容比對沒有失敗
+    pub healthy: bool,
+    pub error: Option<String>,
+}
+
+struct Response {
+    status_code: u32,
+    tls_days_until_expiry: Option<i64>,
+    body_matched: Option<bool>,
+}
+
+/// 開好的 request handle；欄位依宣告順序釋放，request 先於 connect 與 session
+struct Opened {
+    req: Internet,
+    _connect: Internet,
+    _session: Internet,
+    https: bool,
+}
+
+/* ---------- 設定 ---------- */
+
+fn parse_target(entry: &str) -> Result<HttpTarget, String> {
+    let mut parts = entry.splitn(3, '|');
+    let url = parts.next().unwrap_or_default();
+    let scheme = url.split_once("://").map(|(s, _)| s.to_ascii_lowercase());
+    if !matches!(scheme.as_deref(), Some("http" | "https")) {
+        return Err("expected an http:// or https:// URL".into());
+    }
+    let method = match parts.next().unwrap_or_default().to_ascii_uppercase() {
+        m if m.is_empty() => "GET".to_string(),
+        m if m == "GET" || m == "HEAD" => m,
+        m => return Err(format!("unsupported method {m} (expected GET or HEAD)")),
+    };
+    Ok(HttpTarget {
+        url: url.to_string(),
+        method,
+        contains: parts.next().filter(|c| !c.is_empty()).map(String::from),
+    })
+}
+
+/* ---------- 探測 ---------- */
+
+pub fn probe(target: &HttpTarget) -> HttpProbe {
+    let started = Instant::now();
+    let result = request(target);
+    let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+    let mut probe = HttpProbe {
+        url: target.url.clone(),
+        method: target.method.clone(),
+        status_code: None,
+        latency_ms,
+        tls_days_until_expiry: None,
+        body_matched: None,
+        healthy: false,
+        error: None,
+    };
+    match result {
+        Ok(r) => {
+            probe.healthy = r.status_code < 400 && r.body_matched != Some(false);
+            probe.status_code = Some(r.status_code);
+            probe.tls_days_until_expiry = r.tls_days_until_expiry;
+            probe.body_matched = r.body_matched;
+        }
+        Err(e) => probe.error = Some(e),
+    }
+    probe
+}
+
+fn request(target: &HttpTarget) -> Result<Response, String> {
+    let opened = open_request(&target.url, &target.method, *TIMEOUT)?;
+    let req = &opened.req;
+
+    let (policy, max) = if *MAX_REDIRECTS == 0 {
+        (WINHTTP_OPTION_REDIRECT_POLICY_NEVER, 0)
+    } else {
+        (
+            WINHTTP_OPTION_REDIRECT_POLICY_DISALLOW_HTTPS_TO_HTTP,
+            *MAX_REDIRECTS,
+        )
+    };
+    set_option(req, WINHTTP_OPTION_REDIRECT_POLICY, policy)?;
+    if max > 0 {
+        set_option(req, WINHTTP_OPTION_MAX_HTTP_AUTOMATIC_REDIRECTS, max)?;
+    }
+
+    unsafe { WinHttpSendRequest(req.0, None, None, 0, 0, 0) }
+        .map_err(|e| describe("request", e))?;
+    unsafe { WinHttpReceiveResponse(req.0, std::ptr::null_mut()) }
+        .map_err(|e| describe("response", e))?;
+    let status_code = status_code(req)?;
+
+    let tls_days_until_expiry = if opened.https { cert_days(req) } else { None };
+    let body_matched = match &target.contains {
+        Some(needle) if target.method == "GET" => Some(read_body(req)?.contains(needle.as_str())),
+        _ => None,
+    };
+    Ok(Response {
+        status_code,
+        tls_days_until_expiry,
+        body_matched,
+    })
+}
+
+/// POST body 到 url，回傳狀態碼；不跟隨重新導向。headers 為額外的標頭行，以 `\r\n` 分隔
+pub fn post(
+    url: &str,
+    content_type: &str,
+    headers: Option<&str>,
+    body: &[u8],
+    timeout: Duration,
+) -> Result<u32, String> {
+    let opened = open_request(url, "POST", timeout)?;
+    let req = &opened.req;
+    set_option(
+        req,
+        WINHTTP_OPTION_REDIRECT_POLICY,
+        WINHTTP_OPTION_REDIRECT_POLICY_NEVER,
+    )?;
+
+    let mut lines = format!("Content-Type: {content_type}");
+    if let Some(h) = headers {
+        lines.push_str("\r\n");
+        lines.push_str(h);
+    }
+    let lines: Vec<u16> = lines.encode_utf16().collect();
+    let len = body.len() as u32;
+    unsafe { WinHttpSendRequest(req.0, Some(&lines), Some(body.as_ptr().cast()), len, len, 0) }
+        .map_err(|e| describe("request", e))?;
+    unsafe { WinHttpReceiveResponse(req.0, std::ptr::null_mut()) }
+        .map_err(|e| describe("response", e))?;
+    status_code(req)
+}
+
+fn open_request(url: &str, method: &str, timeout: Duration) -> Result<Opened, String> {
+    let url: Vec<u16> = url.encode_utf16().collect();
+    // 長度設為 u32::MAX 時，各欄位指標直接指回 url 緩衝區
+    let mut parts = URL_COMPONENTS {
+        dwStructSize: std::mem::size_of::<URL_COMPONENTS>() as u32,
+        dwHostNameLength: u32::MAX,
+        dwUrlPathLength: u32::MAX,
+        dwExtraInfoLength: u32::MAX,
+        ..Default::default()
+    };
+    unsafe { WinHttpCrackUrl(&url, 0, &mut parts) }.map_err(|e| describe("WinHttpCrackUrl", e))?;
+    let host = component(parts.lpszHostName, parts.dwHostNameLength);
+    // 查詢字串緊接在路徑之後，一起當作 object name
+    let object = component(
+        parts.lpszUrlPath,
+        parts.dwUrlPathLength + parts.dwExtraInfoLength,
+    );
+    let https = parts.nScheme == WINHTTP_INTERNET_SCHEME_HTTPS;
+
+    let session = open(
+        unsafe {
+            WinHttpOpen(
+                w!("capture-windows"),
+                WINHTTP_ACCESS_TYPE_DEFAULT_PROXY,
+                PCWSTR::null(),
+                PCWSTR::null(),
+                0,
+            )
+        },
+        "WinHttpOpen",
+    )?;
+    let ms = timeout.as_millis() as i32;
+    unsafe { WinHttpSetTimeouts(session.0, ms, ms, ms, ms) }
+        .map_err(|e| describe("WinHttpSetTimeouts", e))?;
+    let connect = open(
+        unsafe { WinHttpConnect(session.0, &HSTRING::from(host), parts.nPort, 0) },
+        "WinHttpConnect",
+    )?;
+    let req = open(
+        unsafe {
+            WinHttpOpenRequest(
+                connect.0,
+                &HSTRING::from(method),
+                &HSTRING::from(object),
+                PCWSTR::null(),
+                PCWSTR::null(),
+                std::ptr::null(),
+                if https {
+                    WINHTTP_FLAG_SECURE
+                } else {
+                    WINHTTP_OPEN_REQUEST_FLAGS(0)
+                },
+            )
+        },
+        "WinHttpOpenRequest",
+    )?;
+    Ok(Opened {
+        req,
+        _connect: connect,
+        _session: session,
+        https,
+    })
+}
+
+fn status_code(req: &Internet) -> Result<u32, String> {
+    let mut status_code = 0u32;
+    let mut len = std::mem::size_of::<u32>() as u32;
+    unsafe {
+        WinHttpQueryHeaders(
+            req.0,
+            WINHTTP_QUERY_STATUS_CODE | WINHTTP_QUERY_FLAG_NUMBER,
+            PCWSTR::null(),
+            Some(&mut status_code as *mut u32 as *mut c_void),
+            &mut len,
+            std::ptr::null_mut(),
+        )
+    }
+    .map_err(|e| describe("WinHttpQueryHeaders", e))?;
+    Ok(status_code)
+}
+
+/// URL 沒有該部分時指標為 null
+fn component(ptr: PWSTR, len: u32) -> String {
+    if ptr.is_null() || len == 0 {
+        return String::new();
+    }
+    String::from_utf16_lossy(unsafe { std::slice::from_raw_parts(ptr.0, len as usize) })
+}
+
+fn open(handle: *mut c_void, step: &str) -> Result<Internet, String> {
+    if handle.is_null() {
+        Err(describe(step, Error::from_win32()))
+    } else {
+        Ok(Internet(handle))
+    }
+}
+
+fn set_option(req: &Internet, option: u32, value: u32) -> Result<(), String> {
+    unsafe { WinHttpSetOption(Some(req.0), option, Some(&value.to_ne_bytes())) }
+        .map_err(|e| describe("WinHttpSetOption", e))
+}
+
+/// 最終回應所用連線的伺服器憑證；取得的 context 要自行釋放
+fn cert_days(req: &Internet) -> Option<i64> {
+    let mut ctx: *const CERT_CONTEXT = std::ptr::null();
+    let mut len = std::mem::size_of::<*const CERT_CONTEXT>() as u32;
+    unsafe {
+        WinHttpQueryOption(
+            req.0,
+            WINHTTP_OPTION_SERVER_CERT_CONTEXT,
+            Some(&mut ctx as *mut *const CERT_CONTEXT as *mut c_void),
+            &mut len,
+        )
+    }
+    .ok()?;
+    if ctx.is_null() {
+        return None;
+    }
+    let not_after = certs::not_after(ctx);
+    unsafe {
+        let _ = CertFreeCertificateContext(Some(ctx));
+    }
+    not_after.map(|t| (t - Utc::now()).num_days())
+}
+
+/// 讀到結尾或 MAX_BODY_BYTES 為止；非 UTF-8 的位元組以替代字元取代
+fn read_body(req: &Internet) -> Result<String, String> {
+    let mut body = Vec::new();
+    let mut chunk = [0u8; 8192];
+    while body.len() < MAX_BODY_BYTES {
+        let mut read = 0u32;
+        unsafe {
+            WinHttpReadData(
+                req.0,
+                chunk.as_mut_ptr().cast(),
+                chunk.len() as u32,
+                &mut read,
+            )
+        }
+        .map_err(|e| describe("WinHttpReadData", e))?;
+        if read == 0 {
+            break;
+        }
+        body.extend_from_slice(&chunk[..read as usize]);
+    }
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+/// WinHTTP 的錯誤碼沒有系統訊息文字，常見的幾個換成可讀說明
+fn describe(step: &str, e: Error) -> String {
+    let reason = match (e.code().0 as u32) & 0xFFFF {
+        ERROR_WINHTTP_TIMEOUT => "timed out".into(),
+        ERROR_WINHTTP_NAME_NOT_RESOLVED => "host name could not be resolved".into(),
+        ERROR_WINHTTP_CANNOT_CONNECT => "connection refused or unreachable".into(),
+        ERROR_WINHTTP_CONNECTION_ERROR => "connection reset".into(),
+        ERROR_WINHTTP_SECURE_FAILURE => "TLS certificate validation failed".into(),
+        ERROR_WINHTTP_REDIRECT_FAILED => {
+            format!("redirect failed (limit {})", *MAX_REDIRECTS)
+        }
+        ERROR_WINHTTP_INVALID_URL | ERROR_WINHTTP_UNRECOGNIZED_SCHEME => "invalid URL".into(),
+        _ => return format!("{step} failed: {e}"),
+    };
+    format!("{step}: {reason}")
+}
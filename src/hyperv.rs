@@ -0,0 +1,158 @@
+//! Hyper-V 主機：列出本機 VM 的狀態、指派記憶體與 CPU 使用率
+//!
+//! - 狀態：root\virtualization\v2 的 Msvm_ComputerSystem（含已關機的 VM）
+//! - CPU：`Hyper-V Hypervisor Virtual Processor` 的 % Guest Run Time，依 VM 平均所有虛擬處理器
+//! - 記憶體：`Hyper-V Dynamic Memory VM` 的 Physical Memory（MB）
+//!
+//! 啟動時以 PdhEnumObjectsW 判斷有沒有 Hyper-V 計數器；沒有的主機不註冊計數器，整個區段省略。
+use serde::Serialize;
+use std::{collections::BTreeMap, sync::LazyLock};
+
+use crate::{
+    pdh::{self, Format, SampledCounter},
+    wmi, MetricError,
+};
+
+const VP_OBJECT: &str = "Hyper-V Hypervisor Virtual Processor";
+
+static PRESENT: LazyLock<bool> = LazyLock::new(|| {
+    pdh::objects().is_ok_and(|objects| objects.iter().any(|o| o.eq_ignore_ascii_case(VP_OBJECT)))
+});
+
+const PDH_COUNTERS: &[SampledCounter] = &[
+    SampledCounter::new("hyperv.guest_run_time", "\\Hyper-V Hypervisor Virtual Processor(*)\\% Guest Run Time", Format::Double),
+    SampledCounter::new("hyperv.physical_memory", "\\Hyper-V Dynamic Memory VM(*)\\Physical Memory", Format::Large),
+];
+
+/// Msvm_ComputerSystem.EnabledState
+const VM_STATES: &[(u32, &str)] = &[
+    (2, "running"),
+    (3, "off"),
+    (6, "saved"),
+    (9, "paused"),
+    (10, "starting"),
+    (11, "resetting"),
+    (32773, "saving"),
+    (32774, "stopping"),
+    (32776, "pausing"),
+    (32777, "resuming"),
+];
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize, Default)]
+pub struct VmData {
+    pub name: String,
+    /// running / off / saved / paused…；WMI 讀不到時為 None
+    pub state: Option<&'static str>,
+    /// 目前指派給 VM 的實體記憶體（計數器單位為 MB）；VM 未執行時為 None
+    pub assigned_memory_bytes: Option<u64>,
+    /// 0–1，VM 所有虛擬處理器的平均；VM 未執行時為 None
+    pub cpu_percent: Option<f64>,
+    pub virtual_processors: Option<u32>,
+}
+
+#[derive(Serialize)]
+pub struct HypervData {
+    pub vms: Vec<VmData>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+/// 沒有 Hyper-V 的主機為空
+pub fn pdh_counters() -> &'static [SampledCounter] {
+    if *PRESENT {
+        PDH_COUNTERS
+    } else {
+        &[]
+    }
+}
+
+/// 沒有 Hyper-V 時回傳 None
+pub async fn gather_hyperv(errors: &mut Vec<MetricError>) -> Option<HypervData> {
+    if !*PRESENT {
+        return None;
+    }
+
+    let mut vms: BTreeMap<String, VmData> = BTreeMap::new();
+    let states = tokio::task::spawn_blocking(query_states)
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+    match states {
+        Ok(list) => {
+            for (name, state) in list {
+                vm_entry(&mut vms, name).state = state;
+            }
+        }
+        // 計數器仍能列出執行中的 VM，只是沒有狀態
+        Err(e) => errors.push(MetricError {
+            metric: vec!["hyperv.state".into()],
+            err: e,
+        }),
+    }
+
+    let mut sampled = |key: &str| match pdh::latest(key)? {
+        Ok(values) => Some(values),
+        Err(e) => {
+            errors.push(MetricError {
+                metric: vec![key.into()],
+                err: e,
+            });
+            None
+        }
+    };
+
+    // instance 名稱為 `VM 名稱:Hv VP 0`
+    let mut cpu: BTreeMap<String, (f64, u32)> = BTreeMap::new();
+    for (instance, value) in sampled("hyperv.guest_run_time").unwrap_or_default() {
+        let Some((vm, _)) = instance.rsplit_once(":Hv VP ") else {
+            continue;
+        };
+        let entry = cpu.entry(vm.to_string()).or_default();
+        entry.0 += value.as_f64();
+        entry.1 += 1;
+    }
+    for (name, (total, count)) in cpu {
+        let vm = vm_entry(&mut vms, name);
+        vm.cpu_percent = Some(total / count as f64 / 100.0);
+        vm.virtual_processors = Some(count);
+    }
+
+    for (name, value) in sampled("hyperv.physical_memory").unwrap_or_default() {
+        if name == "_Total" {
+            continue;
+        }
+        vm_entry(&mut vms, name).assigned_memory_bytes = Some((value.as_i64() as u64) << 20);
+    }
+
+    Some(HypervData {
+        vms: vms.into_values().collect(),
+    })
+}
+
+fn vm_entry(vms: &mut BTreeMap<String, VmData>, name: String) -> &mut VmData {
+    vms.entry(name.clone()).or_insert_with(|| VmData {
+        name,
+        ..Default::default()
+    })
+}
+
+/// (VM 名稱, 狀態)；Caption 過濾掉代表主機本身的那筆 Msvm_ComputerSystem
+fn query_states() -> Result<Vec<(String, Option<&'static str>)>, String> {
+    let rows = wmi::query(
+        "root\\virtualization\\v2",
+        "SELECT ElementName, EnabledState FROM Msvm_ComputerSystem WHERE Caption = 'Virtual Machine'",
+    )?;
+    Ok(rows
+        .iter()
+        .filter_map(|r| {
+            let state = r.get_u32("EnabledState").map(|s| {
+                VM_STATES
+                    .iter()
+                    .find(|(code, _)| *code == s)
+                    .map_or("other", |(_, name)| *name)
+            });
+            Some((r.get_string("ElementName")?, state))
+        })
+        .collect())
+}
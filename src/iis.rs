@@ -0,0 +1,104 @@
+//! IIS：CAPTURE_IIS 開啟時才取樣 Web Service / HTTP Service Request Queues 計數器，
+//! 並依 w3wp.exe 命令列的 `-ap "集區名稱"` 彙總各應用程式集區的 CPU / 記憶體
+//!
+//! 是否有 IIS 以「計數器加不加得進去」判斷：沒裝 IIS 的機器直接省略整個區段，不記 error。
+use serde::Serialize;
+use std::{collections::BTreeMap, sync::LazyLock};
+
+use crate::{
+    config,
+    pdh::{self, Format, SampledCounter},
+    process, MetricError,
+};
+
+static ENABLED: LazyLock<bool> = LazyLock::new(|| config::env_flag("CAPTURE_IIS"));
+
+const PDH_COUNTERS: &[SampledCounter] = &[
+    SampledCounter::new("iis.requests_per_sec", "\\Web Service(_Total)\\Total Method Requests/sec", Format::Double),
+    SampledCounter::new("iis.current_connections", "\\Web Service(_Total)\\Current Connections", Format::Large),
+    SampledCounter::new("iis.queued_requests", "\\HTTP Service Request Queues(*)\\CurrentQueueSize", Format::Large),
+];
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct AppPoolData {
+    pub name: String,
+    /// 同一集區可能有多個 worker（Web Garden）
+    pub worker_pids: Vec<u32>,
+    /// 與 ProcessData.cpu_percent 同尺度（佔整台機器的 0–1）
+    pub cpu_percent: f32,
+    pub memory_bytes: u64,
+}
+
+#[derive(Serialize)]
+pub struct IisData {
+    pub requests_per_sec: Option<f64>,
+    pub current_connections: Option<u64>,
+    /// 所有集區的要求佇列長度合計；持續大於 0 代表 worker 處理不過來
+    pub queued_requests: Option<u64>,
+    pub app_pools: Vec<AppPoolData>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+/// 未開啟時為空，背景取樣器不會註冊 IIS 計數器
+pub fn pdh_counters() -> &'static [SampledCounter] {
+    if *ENABLED {
+        PDH_COUNTERS
+    } else {
+        &[]
+    }
+}
+
+/// 未開啟或這台機器沒有 IIS 時回傳 None
+pub fn gather_iis(errors: &mut Vec<MetricError>) -> Option<IisData> {
+    if !*ENABLED {
+        return None;
+    }
+    // Web Service 物件不存在就是沒裝 IIS；取樣器還沒跑完第一輪時也先省略
+    let Some(Ok(_)) = pdh::latest("iis.requests_per_sec") else {
+        return None;
+    };
+
+    let mut sum = |key: &str| match pdh::latest(key)? {
+        Ok(values) => Some(values.iter().map(|(_, v)| v.as_f64()).sum::<f64>()),
+        Err(e) => {
+            errors.push(MetricError {
+                metric: vec![key.into()],
+                err: e,
+            });
+            None
+        }
+    };
+
+    Some(IisData {
+        requests_per_sec: sum("iis.requests_per_sec"),
+        current_connections: sum("iis.current_connections").map(|v| v as u64),
+        queued_requests: sum("iis.queued_requests").map(|v| v as u64),
+        app_pools: app_pools(),
+    })
+}
+
+fn app_pools() -> Vec<AppPoolData> {
+    let mut pools: BTreeMap<String, AppPoolData> = BTreeMap::new();
+    for w in process::usage_by_name("w3wp.exe") {
+        // 讀不到命令列時仍列出，歸到 unknown，避免記憶體用量憑空消失
+        let name = pool_name(&w.args).unwrap_or_else(|| "unknown".into());
+        let pool = pools.entry(name.clone()).or_insert_with(|| AppPoolData {
+            name,
+            worker_pids: Vec::new(),
+            cpu_percent: 0.0,
+            memory_bytes: 0,
+        });
+        pool.worker_pids.push(w.pid);
+        pool.cpu_percent += w.cpu_percent;
+        pool.memory_bytes += w.memory_bytes;
+    }
+    pools.into_values().collect()
+}
+
+fn pool_name(args: &[String]) -> Option<String> {
+    let i = args.iter().position(|a| a.eq_ignore_ascii_case("-ap"))?;
+    args.get(i + 1).map(|n| n.trim_matches('"').to_string())
+}
@@ -0,0 +1,73 @@
+//! InfluxDB line protocol：/api/v1/metrics?format=influx，推送模式設 CAPTURE_PUSH_FORMAT=influx 時也用這裡
+//!
+//! 資料來自 samples::collect。樣本名稱第一段為 measurement、其餘為 field（`disk_free_bytes` →
+//! `disk` 的 `free_bytes`），標籤加上 host 後變成 tag，同 measurement 同 tag 的樣本合成一行。
+//! Influx 遇到同一 field 型別改變會拒收，型別只看名稱決定：計數器與 `_bytes` 結尾為整數（`i`），
+//! 其餘一律浮點數。所有行共用同一個奈秒時間戳。
+use chrono::Utc;
+use std::{collections::HashMap, fmt::Write};
+
+use crate::samples::{Kind, Sample};
+
+pub const CONTENT_TYPE: &str = "text/plain; charset=utf-8";
+
+pub fn render(samples: &[Sample], host: Option<&str>) -> String {
+    let timestamp = Utc::now().timestamp_nanos_opt().unwrap_or_default();
+
+    // (measurement, 排序後的 tags) → 該行的 fields，保持第一次出現的順序
+    let mut lines: Vec<(String, Vec<String>)> = Vec::new();
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for s in samples {
+        if !s.value.is_finite() {
+            continue;
+        }
+        let (measurement, field) = s.name.split_once('_').unwrap_or((s.name, "value"));
+
+        let mut tags: Vec<(&str, &str)> = s
+            .labels
+            .iter()
+            .map(|(k, v)| (*k, v.as_str()))
+            .chain(host.map(|h| ("host", h)))
+            .filter(|(_, v)| !v.is_empty())
+            .collect();
+        tags.sort();
+        let mut key = escape(measurement, false);
+        for (k, v) in tags {
+            let _ = write!(key, ",{}={}", escape(k, true), escape(v, true));
+        }
+
+        let value = if s.kind == Kind::Counter || s.name.ends_with("_bytes") {
+            format!("{}i", s.value as i64)
+        } else {
+            // 沒有後綴的數值一律是浮點數，整數值也一樣
+            s.value.to_string()
+        };
+        let field = format!("{}={value}", escape(field, true));
+        match index.get(&key) {
+            Some(&i) => lines[i].1.push(field),
+            None => {
+                index.insert(key.clone(), lines.len());
+                lines.push((key, vec![field]));
+            }
+        }
+    }
+
+    let mut out = String::new();
+    for (key, fields) in lines {
+        let _ = writeln!(out, "{key} {} {timestamp}", fields.join(","));
+    }
+    out
+}
+
+/// measurement 跳脫逗號與空白；tag 與 field 名稱、tag 值另外跳脫等號
+fn escape(s: &str, equals: bool) -> String {
+    let mut out = String::with_capacity(s.len());
+    // 換行無法跳脫，換成空白
+    for c in s.chars().map(|c| if c == '\n' { ' ' } else { c }) {
+        if c == ',' || c == ' ' || c == '\\' || (equals && c == '=') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
@@ -0,0 +1,300 @@
+//! 磁碟分割配置：IOCTL_DISK_GET_DRIVE_LAYOUT_EX 讀每顆實體磁碟的分割表，
+//! 再以 IOCTL_VOLUME_GET_VOLUME_DISK_EXTENTS 對應每個分割承載的磁碟區
+//!
+//! 分割表很少變動，結果快取 CAPTURE_DISK_LAYOUT_CACHE_SECS 秒（預設 300），`?refresh=1` 強制重讀。
+//! trailing_unallocated_bytes 是最後一個分割之後沒用到的空間，
+//! 複製到較大磁碟後忘了延伸分割時就會看到一大塊。所有函式都會阻塞，呼叫端請放在 spawn_blocking 內。
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{Arc, LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+use windows::{
+    core::{GUID, HSTRING},
+    Win32::{
+        Foundation::{GENERIC_ACCESS_RIGHTS, HANDLE},
+        Storage::FileSystem::{
+            CreateFileW, FILE_FLAGS_AND_ATTRIBUTES, FILE_SHARE_READ, FILE_SHARE_WRITE,
+            IOCTL_VOLUME_GET_VOLUME_DISK_EXTENTS, OPEN_EXISTING,
+        },
+        System::Ioctl::{
+            DISK_EXTENT, DISK_GEOMETRY_EX, DRIVE_LAYOUT_INFORMATION_EX,
+            IOCTL_DISK_GET_DRIVE_GEOMETRY_EX, IOCTL_DISK_GET_DRIVE_LAYOUT_EX,
+            PARTITION_INFORMATION_EX, PARTITION_STYLE_GPT, PARTITION_STYLE_MBR,
+            VOLUME_DISK_EXTENTS,
+        },
+    },
+};
+
+use crate::{
+    config, disk,
+    smart::{self, ioctl},
+    win::{wide_to_string, OwnedHandle},
+};
+
+static TTL: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_secs(config::env_parse("CAPTURE_DISK_LAYOUT_CACHE_SECS", 300)));
+static CACHE: Mutex<Option<(Instant, Arc<Layout>)>> = Mutex::new(None);
+
+/// GPT 分割類型 GUID → 名稱
+const GPT_TYPES: &[(u128, &str)] = &[
+    (0xc12a7328_f81f_11d2_ba4b_00a0c93ec93b, "efi_system"),
+    (0xe3c9e316_0b5c_4db8_817d_f92df00215ae, "microsoft_reserved"),
+    (0xebd0a0a2_b9e5_4433_87c0_68b6b72699c7, "basic_data"),
+    (0xde94bba4_06d1_4d40_a16a_bfd50179d6ac, "recovery"),
+    (0x5808c8aa_7e8f_42e0_85d2_e1e90434cfb3, "ldm_metadata"),
+    (0xaf9b60a0_1431_4f62_bc68_3311714a69ad, "ldm_data"),
+    (0xe75caf8f_f680_4cee_afa3_b001e56efc2d, "storage_spaces"),
+];
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct PartitionData {
+    pub number: u32,
+    pub offset_bytes: u64,
+    pub size_bytes: u64,
+    /// efi_system / basic_data / recovery / ntfs / fat32 / extended…；不認得的顯示 GUID 或 `0xNN`
+    pub partition_type: String,
+    /// GPT 分割名稱；MBR 為 None
+    pub name: Option<String>,
+    /// 承載的磁碟區 GUID 路徑；沒有格式化或隱藏的分割為 None
+    pub volume: Option<String>,
+    pub mount_points: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct DiskLayout {
+    /// \\.\PhysicalDriveN 的 N，與 /api/v1/metrics/disk/health 相同
+    pub disk: u32,
+    /// gpt / mbr / raw（未初始化）
+    pub partition_style: &'static str,
+    pub size_bytes: u64,
+    /// 可用範圍內沒有被任何分割使用的空間
+    pub unallocated_bytes: u64,
+    /// 最後一個分割結尾到可用範圍結尾
+    pub trailing_unallocated_bytes: u64,
+    pub partitions: Vec<PartitionData>,
+}
+
+pub struct Layout {
+    pub disks: Vec<DiskLayout>,
+    /// (metric, err)
+    pub errors: Vec<(String, String)>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct LayoutQuery {
+    /// 1 時忽略快取
+    pub refresh: Option<u8>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+/// 持有鎖期間讀取，避免同時多個請求重複開磁碟
+pub fn gather_layout(q: &LayoutQuery) -> Arc<Layout> {
+    let mut cache = CACHE.lock().unwrap();
+    if q.refresh != Some(1)
+        && let Some((at, layout)) = cache.as_ref()
+        && at.elapsed() < *TTL
+    {
+        return layout.clone();
+    }
+    let layout = Arc::new(read_layout());
+    *cache = Some((Instant::now(), layout.clone()));
+    layout
+}
+
+fn read_layout() -> Layout {
+    let mut errors = Vec::new();
+    let volumes = volume_extents(&mut errors);
+
+    let mut disks = Vec::new();
+    for n in 0..smart::MAX_DISKS {
+        let handle = match smart::open_disk(n) {
+            Ok(Some(h)) => h,
+            Ok(None) => continue,
+            Err(e) => {
+                errors.push((format!("disk_layout.{n}"), e));
+                continue;
+            }
+        };
+        match read_disk(n, &handle, &volumes) {
+            Ok(d) => disks.push(d),
+            Err(e) => errors.push((format!("disk_layout.{n}"), e)),
+        }
+    }
+    Layout { disks, errors }
+}
+
+fn read_disk(
+    n: u32,
+    handle: &OwnedHandle,
+    volumes: &HashMap<(u32, u64), (String, Vec<String>)>,
+) -> Result<DiskLayout, String> {
+    let mut geometry = vec![0u64; 32];
+    ioctl(handle, IOCTL_DISK_GET_DRIVE_GEOMETRY_EX, &mut geometry, 0)
+        .map_err(|e| format!("IOCTL_DISK_GET_DRIVE_GEOMETRY_EX failed: {e}"))?;
+    let size_bytes = unsafe { (*(geometry.as_ptr() as *const DISK_GEOMETRY_EX)).DiskSize } as u64;
+
+    // 128 個 GPT 分割約 18 KB
+    let mut buf = vec![0u64; 8192];
+    ioctl(handle, IOCTL_DISK_GET_DRIVE_LAYOUT_EX, &mut buf, 0)
+        .map_err(|e| format!("IOCTL_DISK_GET_DRIVE_LAYOUT_EX failed: {e}"))?;
+    let layout = unsafe { &*(buf.as_ptr() as *const DRIVE_LAYOUT_INFORMATION_EX) };
+    let entry_size = std::mem::size_of::<PARTITION_INFORMATION_EX>();
+    let max_entries = (buf.len() * 8
+        - std::mem::offset_of!(DRIVE_LAYOUT_INFORMATION_EX, PartitionEntry))
+        / entry_size;
+    let entries = unsafe {
+        std::slice::from_raw_parts(
+            layout.PartitionEntry.as_ptr(),
+            (layout.PartitionCount as usize).min(max_entries),
+        )
+    };
+
+    let style = layout.PartitionStyle as i32;
+    let (partition_style, usable_start, usable_end) = if style == PARTITION_STYLE_GPT.0 {
+        let gpt = unsafe { layout.Anonymous.Gpt };
+        let start = gpt.StartingUsableOffset as u64;
+        ("gpt", start, start + gpt.UsableLength as u64)
+    } else if style == PARTITION_STYLE_MBR.0 {
+        ("mbr", 0, size_bytes)
+    } else {
+        ("raw", 0, size_bytes)
+    };
+
+    let mut partitions = Vec::new();
+    let mut used = 0u64;
+    let mut last_end = usable_start;
+    for p in entries {
+        let offset = p.StartingOffset as u64;
+        let len = p.PartitionLength as u64;
+        let (partition_type, name, container) = if partition_style == "gpt" {
+            let gpt = unsafe { p.Anonymous.Gpt };
+            let name = Some(wide_to_string(&gpt.Name)).filter(|n| !n.is_empty());
+            (gpt_type_name(gpt.PartitionType), name, false)
+        } else {
+            let mbr = unsafe { p.Anonymous.Mbr };
+            // MBR 分割表固定 4 個一組，沒用到的項目類型為 0
+            if mbr.PartitionType == 0 || len == 0 {
+                continue;
+            }
+            let extended = matches!(mbr.PartitionType, 0x05 | 0x0F);
+            (mbr_type_name(mbr.PartitionType), None, extended)
+        };
+        // 延伸分割只是容器，裡面的邏輯分割另外列出，不重複計算
+        if !container {
+            used += len;
+        }
+        last_end = last_end.max(offset + len);
+        let (volume, mount_points) = volumes
+            .get(&(n, offset))
+            .cloned()
+            .map_or((None, Vec::new()), |(v, m)| (Some(v), m));
+        partitions.push(PartitionData {
+            number: p.PartitionNumber,
+            offset_bytes: offset,
+            size_bytes: len,
+            partition_type,
+            name,
+            volume,
+            mount_points,
+        });
+    }
+    partitions.sort_by_key(|p| p.offset_bytes);
+
+    let usable = usable_end.saturating_sub(usable_start);
+    Ok(DiskLayout {
+        disk: n,
+        partition_style,
+        size_bytes,
+        unallocated_bytes: if partition_style == "raw" {
+            0
+        } else {
+            usable.saturating_sub(used)
+        },
+        trailing_unallocated_bytes: if partition_style == "raw" {
+            0
+        } else {
+            usable_end.saturating_sub(last_end)
+        },
+        partitions,
+    })
+}
+
+/// (磁碟編號, 分割起始位移) → (磁碟區 GUID 路徑, 掛載點)；跨磁碟的磁碟區每段各一筆
+fn volume_extents(
+    errors: &mut Vec<(String, String)>,
+) -> HashMap<(u32, u64), (String, Vec<String>)> {
+    let mut map = HashMap::new();
+    let volumes = match disk::list_volumes() {
+        Ok(v) => v,
+        Err(e) => {
+            errors.push(("disk_layout.volumes".into(), e));
+            return map;
+        }
+    };
+    for volume in volumes {
+        // 存取權 0 即可查詢 extents；光碟機等沒有 extents 的磁碟區會失敗，略過
+        let Ok(handle) = (unsafe {
+            CreateFileW(
+                &HSTRING::from(volume.trim_end_matches('\\')),
+                GENERIC_ACCESS_RIGHTS(0).0,
+                FILE_SHARE_READ | FILE_SHARE_WRITE,
+                None,
+                OPEN_EXISTING,
+                FILE_FLAGS_AND_ATTRIBUTES(0),
+                HANDLE::default(),
+            )
+        })
+        .map(OwnedHandle) else {
+            continue;
+        };
+        let mut buf = vec![0u64; 64];
+        if ioctl(&handle, IOCTL_VOLUME_GET_VOLUME_DISK_EXTENTS, &mut buf, 0).is_err() {
+            continue;
+        }
+        let header = unsafe { &*(buf.as_ptr() as *const VOLUME_DISK_EXTENTS) };
+        let max = (buf.len() * 8 - std::mem::offset_of!(VOLUME_DISK_EXTENTS, Extents))
+            / std::mem::size_of::<DISK_EXTENT>();
+        let extents = unsafe {
+            std::slice::from_raw_parts(
+                header.Extents.as_ptr(),
+                (header.NumberOfDiskExtents as usize).min(max),
+            )
+        };
+        let mounts = disk::mount_points(&volume);
+        for e in extents {
+            map.insert(
+                (e.DiskNumber, e.StartingOffset as u64),
+                (volume.clone(), mounts.clone()),
+            );
+        }
+    }
+    map
+}
+
+fn gpt_type_name(ty: GUID) -> String {
+    GPT_TYPES
+        .iter()
+        .find(|(g, _)| GUID::from_u128(*g) == ty)
+        .map_or_else(
+            || format!("{ty:?}").to_lowercase(),
+            |(_, name)| name.to_string(),
+        )
+}
+
+fn mbr_type_name(ty: u8) -> String {
+    match ty {
+        // 0x07 也用於 exFAT，分割表本身分不出來
+        0x07 => "ntfs".into(),
+        0x0B | 0x0C => "fat32".into(),
+        0x05 | 0x0F => "extended".into(),
+        0x27 => "recovery".into(),
+        0x42 => "ldm".into(),
+        0xEE => "gpt_protective".into(),
+        _ => format!("0x{ty:02X}"),
+    }
+}
@@ -0,0 +1,99 @@
+//! Windows 授權啟用狀態：WMI SoftwareLicensingProduct 中有部分金鑰的 Windows 產品
+//!
+//! 這個類別查一次要數百毫秒到數秒，由背景執行緒每 CAPTURE_LICENSE_CHECK_INTERVAL_SECS
+//! （預設 21600，即 6 小時）秒查一次，請求只讀最近一次結果。
+use chrono::Utc;
+use serde::Serialize;
+use std::{
+    sync::{LazyLock, RwLock},
+    thread,
+    time::Duration,
+};
+
+use crate::{config, wmi, MetricError};
+
+/// Windows 作業系統的 ApplicationID；Office 等其他產品也在同一個類別裡
+const WINDOWS_APPLICATION_ID: &str = "55c92734-d682-4d71-983e-d6ec3f16059f";
+
+static INTERVAL: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(config::env_parse(
+        "CAPTURE_LICENSE_CHECK_INTERVAL_SECS",
+        21600,
+    ))
+});
+static LATEST: RwLock<Option<Result<LicenseData, String>>> = RwLock::new(None);
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize, Clone, Default)]
+pub struct LicenseData {
+    /// licensed / unlicensed / oob_grace / oot_grace / non_genuine_grace / notification / extended_grace
+    pub activation_status: Option<String>,
+    /// 產品金鑰的最後 5 碼
+    pub partial_product_key: Option<String>,
+    /// Retail / OEM:DM / Volume:MAK / Volume:GVLK…；Windows 7 沒有這個屬性
+    pub license_channel: Option<String>,
+    /// 例如 `Windows(R), ServerStandard edition`
+    pub product_name: Option<String>,
+    /// 背景查詢完成的時間，RFC3339（UTC）
+    pub collected_at: Option<String>,
+}
+
+/* ---------- 背景取樣 ---------- */
+
+pub fn start_sampler() {
+    thread::spawn(|| loop {
+        let result = query();
+        *LATEST.write().unwrap() = Some(result);
+        thread::sleep(*INTERVAL);
+    });
+}
+
+pub fn gather_license(errors: &mut Vec<MetricError>) -> LicenseData {
+    let err = match LATEST.read().unwrap().clone() {
+        Some(Ok(data)) => return data,
+        Some(Err(e)) => e,
+        None => "license check has not completed yet".into(),
+    };
+    errors.push(MetricError {
+        metric: vec!["license".into()],
+        err,
+    });
+    LicenseData::default()
+}
+
+fn query() -> Result<LicenseData, String> {
+    // 不列欄位：Windows 7 沒有 ProductKeyChannel，指名不存在的屬性會讓整個查詢失敗
+    let wql = format!(
+        "SELECT * FROM SoftwareLicensingProduct \
+         WHERE ApplicationID = '{WINDOWS_APPLICATION_ID}' AND PartialProductKey IS NOT NULL"
+    );
+    let rows = wmi::query("root\\CIMV2", &wql)?;
+    // 正常只有一筆；升級後可能殘留舊版本的項目，優先取已啟用的
+    let row = rows
+        .iter()
+        .find(|r| r.get_u32("LicenseStatus") == Some(1))
+        .or(rows.first())
+        .ok_or("no Windows product with an installed product key")?;
+    Ok(LicenseData {
+        activation_status: row.get_u32("LicenseStatus").map(|s| status_name(s).into()),
+        partial_product_key: row.get_string("PartialProductKey"),
+        license_channel: row.get_string("ProductKeyChannel"),
+        product_name: row.get_string("Name"),
+        collected_at: Some(Utc::now().to_rfc3339()),
+    })
+}
+
+/// SoftwareLicensingProduct.LicenseStatus
+fn status_name(status: u32) -> &'static str {
+    match status {
+        0 => "unlicensed",
+        1 => "licensed",
+        2 => "oob_grace",
+        3 => "oot_grace",
+        4 => "non_genuine_grace",
+        5 => "notification",
+        6 => "extended_grace",
+        _ => "unknown",
+    }
+}
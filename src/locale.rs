@@ -0,0 +1,74 @@
+//! 時區與語系：GetDynamicTimeZoneInformation、GetSystemDefaultLocaleName / GetUserDefaultLocaleName
+//!
+//! 時區可能在執行期間被改掉，每次請求重新讀取。
+use serde::Serialize;
+use windows::Win32::{
+    Globalization::{
+        GetSystemDefaultLocaleName, GetSystemDefaultUILanguage, GetUserDefaultLocaleName,
+        LCIDToLocaleName,
+    },
+    System::Time::{GetDynamicTimeZoneInformation, DYNAMIC_TIME_ZONE_INFORMATION},
+};
+
+use crate::{win::wide_to_string, MetricError};
+
+/// LOCALE_NAME_MAX_LENGTH
+const LOCALE_NAME_LEN: usize = 85;
+/// GetDynamicTimeZoneInformation 的回傳值
+const TIME_ZONE_ID_DAYLIGHT: u32 = 2;
+const TIME_ZONE_ID_INVALID: u32 = u32::MAX;
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct LocaleInfo {
+    /// Windows 時區鍵名，例如 `Taipei Standard Time`；不受 DST 影響，需搭配 utc_offset_minutes 判讀
+    pub timezone_name: Option<String>,
+    /// 目前的 UTC 偏移（已含 DST），例如 UTC+8 為 480
+    pub utc_offset_minutes: Option<i32>,
+    pub dst_active: Option<bool>,
+    /// 例如 `zh-TW`、`en-US`
+    pub system_locale: Option<String>,
+    /// 執行 agent 的帳號的使用者語系；以服務執行時通常與 system_locale 相同
+    pub user_locale: Option<String>,
+    /// 系統 UI 語言（安裝時的顯示語言）
+    pub system_language: Option<String>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub fn gather_locale(errors: &mut Vec<MetricError>) -> LocaleInfo {
+    let mut tz = DYNAMIC_TIME_ZONE_INFORMATION::default();
+    let id = unsafe { GetDynamicTimeZoneInformation(&mut tz) };
+    let (timezone_name, utc_offset_minutes, dst_active) = if id == TIME_ZONE_ID_INVALID {
+        errors.push(MetricError {
+            metric: vec!["host.timezone_name".into()],
+            err: "GetDynamicTimeZoneInformation failed".into(),
+        });
+        (None, None, None)
+    } else {
+        // Bias 定義為 UTC = 當地時間 + Bias，正負號與一般的 UTC 偏移相反
+        let dst = id == TIME_ZONE_ID_DAYLIGHT;
+        let extra = if dst { tz.DaylightBias } else { tz.StandardBias };
+        let bias = tz.Bias + extra;
+        let key = wide_to_string(&tz.TimeZoneKeyName);
+        (Some(key).filter(|k| !k.is_empty()), Some(-bias), Some(dst))
+    };
+
+    LocaleInfo {
+        timezone_name,
+        utc_offset_minutes,
+        dst_active,
+        system_locale: locale_name(|buf| unsafe { GetSystemDefaultLocaleName(buf) }),
+        user_locale: locale_name(|buf| unsafe { GetUserDefaultLocaleName(buf) }),
+        system_language: locale_name(|buf| unsafe {
+            LCIDToLocaleName(GetSystemDefaultUILanguage() as u32, Some(buf), 0)
+        }),
+    }
+}
+
+/// 回傳值為寫入的字元數（含 NUL），0 代表失敗
+fn locale_name(call: impl FnOnce(&mut [u16]) -> i32) -> Option<String> {
+    let mut buf = [0u16; LOCALE_NAME_LEN];
+    (call(&mut buf) > 0).then(|| wide_to_string(&buf))
+}
@@ -1,89 +1,376 @@
 //! Windows-only Metrics Server：current_frequency 透過 PDH 讀取
 use axum::{
     body::Body,
-    http::StatusCode,
+    extract::{Path, Query},
+    http::{header, HeaderMap, StatusCode},
     middleware::{from_fn, Next},
-    response::IntoResponse,
+    response::{IntoResponse, Response},
     routing::get,
     Json, Router,
 };
-use chrono::Local;
-use serde::Serialize;
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
 use std::{
     net::SocketAddr,
+    sync::LazyLock,
     thread,
     time::{Duration, Instant},
 };
 use sysinfo::{CpuRefreshKind, RefreshKind, System};
 use tokio::net::TcpListener;
-use windows::{
-    core::{w, PCWSTR},
-    Win32::System::Performance::{
-        PdhAddCounterW, PdhCloseQuery, PdhCollectQueryData, PdhGetFormattedCounterValue,
-        PdhOpenQueryW, PDH_FMT_COUNTERVALUE, PDH_FMT_LARGE,
-    },
-};
-use windows::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+use windows::Win32::System::ProcessStatus::{GetPerformanceInfo, PERFORMANCE_INFORMATION};
 // use windows::Win32::Foundation::BOOL;
 
+mod arp;
+mod audio;
+mod certs;
+mod com;
+mod config;
+mod cpuinfo;
+mod crash;
+mod csv;
+mod custom;
+mod dirs;
+mod disk;
+mod displays;
+mod domain;
+mod dotnet;
+mod eventlog;
+mod files;
+mod firewall;
+mod gpu;
+mod gputemp;
+mod graphite;
+mod hardware;
+mod hotfixes;
+mod httpprobe;
+mod hyperv;
+mod iis;
+mod influx;
+mod layout;
+mod license;
+mod locale;
+mod net;
+mod numa;
+mod osversion;
+#[cfg(feature = "otlp")]
+mod otlp;
+mod pagefile;
+mod pdh;
+mod ping;
+mod platsec;
+mod ports;
+mod power;
+mod probes;
+mod process;
+mod profiles;
+mod prometheus;
+mod push;
+mod registry;
+mod routes;
+mod samples;
+mod security;
+mod sensors;
+mod services;
+mod sessions;
+mod smart;
+mod smbios;
+mod software;
+mod spooler;
+mod sqlserver;
+mod statsd;
+mod talkers;
+mod tasks;
+mod tcp;
+mod tempdirs;
+mod timesync;
+mod updates;
+mod usb;
+mod virt;
+mod vss;
+mod wifi;
+mod win;
+mod wmi;
+
+use arp::{gather_arp, ArpQuery};
+use audio::{gather_audio, AudioData};
+use certs::{gather_cert_summary, gather_certs, CertQuery, CertSummary};
+use cpuinfo::{gather_identity, CpuIdentity};
+use crash::{gather_crash, CrashData};
+use disk::{gather_disk, DiskData};
+use displays::{gather_displays, DisplaysData};
+use domain::{gather_domain, DomainInfo};
+use dotnet::gather_dotnet;
+use eventlog::{gather_event_log, tail_events, EventLogData, EventQuery, TailError};
+use firewall::{gather_firewall, FirewallData};
+use gpu::{gather_gpu, GpuData};
+use hardware::{gather_hardware, HardwareData};
+use hotfixes::HotfixQuery;
+use hyperv::{gather_hyperv, HypervData};
+use iis::{gather_iis, IisData};
+use layout::{gather_layout, LayoutQuery};
+use license::{gather_license, LicenseData};
+use locale::{gather_locale, LocaleInfo};
+use net::{gather_net, NetData};
+use numa::{gather_numa, NumaData};
+use osversion::{gather_os_version, OsVersionInfo};
+use pagefile::{gather_pagefiles, PagefilesData};
+use ping::{gather_ping, PingQuery, PingResult};
+use platsec::{gather_platform_security, PlatformSecurityData};
+use ports::{check_port, gather_ports, PortQuery};
+use power::{gather_power, PowerData};
+use probes::gather_probes;
+use process::{
+    gather_process_detail, gather_processes, gather_watched_processes, ProcessData, ProcessQuery,
+    WatchedProcessData,
+};
+use profiles::gather_profiles;
+use routes::gather_routes;
+use security::{gather_security, SecurityData};
+use sensors::{gather_sensors, SensorsData};
+use services::{gather_services, gather_watched_services, ServiceQuery, WatchedServiceData};
+use sessions::{gather_sessions, SessionData};
+use smart::gather_disk_health;
+use software::{gather_software, SoftwareQuery};
+use spooler::{gather_spooler, SpoolerData};
+use sqlserver::{gather_sqlserver, SqlServerData};
+use tasks::gather_tasks;
+use tcp::{gather_tcp, TcpData};
+use timesync::{gather_time, TimeData};
+use updates::{gather_updates, UpdatesData};
+use usb::gather_usb;
+use virt::{gather_virt, VirtInfo};
+use vss::{gather_vss, VssVolume};
+use wifi::{gather_wifi, WifiData};
+
 /* ---------- 資料結構 ---------- */
 
 #[derive(Serialize)]
 struct CPUData {
+    #[serde(flatten)]
+    identity: CpuIdentity,
     physical_core: usize,
     logical_core: usize,
     frequency: u64,
     current_frequency: Option<u64>,
+    /// 各邏輯核心目前頻率（MHz）；current_frequency 取其最大值
+    per_core_frequency: Vec<u64>,
+    avg_current_frequency: Option<u64>,
     temperature_c: Option<f32>,
     free_percent: f32,
     usage_percent: f32,
+    /// 各邏輯核心使用率（依核心編號排序，長度 = logical_core）
+    per_core_usage: Vec<f32>,
+    /// 以下為背景取樣器最近 1 秒的速率；context switch 異常高通常是鎖競爭
+    context_switches_per_sec: Option<f64>,
+    interrupts_per_sec: Option<f64>,
+    dpcs_queued_per_sec: Option<f64>,
+    system_calls_per_sec: Option<f64>,
+    /// usage 的細分，與 usage_percent 同為 0–1；kernel 已包含 interrupt 與 DPC
+    user_percent: Option<f64>,
+    kernel_percent: Option<f64>,
+    idle_percent: Option<f64>,
+    interrupt_percent: Option<f64>,
+    dpc_percent: Option<f64>,
+    throttling: ThrottlingData,
+    /// 只有 ?detail=full 時才輸出
+    #[serde(skip_serializing_if = "Option::is_none")]
+    advanced: Option<CpuAdvanced>,
+}
+
+/// C-state 駐留時間比例（0–1）；部分虛擬機沒有這些計數器，此時為 None 且不記 error
+#[derive(Serialize)]
+struct CpuAdvanced {
+    c1_percent: Option<f64>,
+    c2_percent: Option<f64>,
+    c3_percent: Option<f64>,
 }
 
+#[derive(Serialize)]
+struct ThrottlingData {
+    /// 目前頻率相對於最大頻率的比例（0–1，Turbo 時會超過 1），最近 1 秒的平均
+    percent_of_maximum_frequency: Option<f64>,
+    /// 連續 THROTTLE_WINDOW 次取樣都是使用率高但頻率比例低於 CAPTURE_THROTTLE_THRESHOLD_PERCENT；
+    /// 閒置時降頻是正常省電，短暫的頻率下探也不算
+    likely_throttled: Option<bool>,
+}
+
+/// 使用率超過這個值才判斷是否被降頻
+const THROTTLE_MIN_USAGE: f64 = 0.5;
+/// 需要連續成立的取樣次數（取樣間隔 1 秒）
+const THROTTLE_WINDOW: usize = 5;
+
+static THROTTLE_THRESHOLD: LazyLock<f64> =
+    LazyLock::new(|| config::env_parse("CAPTURE_THROTTLE_THRESHOLD_PERCENT", 80.0) / 100.0);
+
+const CPU_PDH_COUNTERS: &[pdh::SampledCounter] = &[
+    pdh::SampledCounter::new(
+        "cpu.context_switches_per_sec",
+        "\\System\\Context Switches/sec",
+        pdh::Format::Double,
+    ),
+    pdh::SampledCounter::new(
+        "cpu.interrupts_per_sec",
+        "\\Processor Information(_Total)\\Interrupts/sec",
+        pdh::Format::Double,
+    ),
+    pdh::SampledCounter::new(
+        "cpu.dpcs_queued_per_sec",
+        "\\Processor Information(_Total)\\DPCs Queued/sec",
+        pdh::Format::Double,
+    ),
+    pdh::SampledCounter::new(
+        "cpu.system_calls_per_sec",
+        "\\System\\System Calls/sec",
+        pdh::Format::Double,
+    ),
+    pdh::SampledCounter::new(
+        "cpu.user_percent",
+        "\\Processor Information(_Total)\\% User Time",
+        pdh::Format::Double,
+    ),
+    pdh::SampledCounter::new(
+        "cpu.kernel_percent",
+        "\\Processor Information(_Total)\\% Privileged Time",
+        pdh::Format::Double,
+    ),
+    pdh::SampledCounter::new(
+        "cpu.idle_percent",
+        "\\Processor Information(_Total)\\% Idle Time",
+        pdh::Format::Double,
+    ),
+    pdh::SampledCounter::new(
+        "cpu.interrupt_percent",
+        "\\Processor Information(_Total)\\% Interrupt Time",
+        pdh::Format::Double,
+    ),
+    pdh::SampledCounter::new(
+        "cpu.dpc_percent",
+        "\\Processor Information(_Total)\\% DPC Time",
+        pdh::Format::Double,
+    ),
+    pdh::SampledCounter::new(
+        "cpu.percent_of_maximum_frequency",
+        "\\Processor Information(_Total)\\% of Maximum Frequency",
+        pdh::Format::Double,
+    ),
+    pdh::SampledCounter::new(
+        "cpu.c1_percent",
+        "\\Processor Information(_Total)\\% C1 Time",
+        pdh::Format::Double,
+    ),
+    pdh::SampledCounter::new(
+        "cpu.c2_percent",
+        "\\Processor Information(_Total)\\% C2 Time",
+        pdh::Format::Double,
+    ),
+    pdh::SampledCounter::new(
+        "cpu.c3_percent",
+        "\\Processor Information(_Total)\\% C3 Time",
+        pdh::Format::Double,
+    ),
+];
+
 #[derive(Serialize)]
 struct MemoryData {
     total_bytes: u64,
     available_bytes: u64,
     used_bytes: u64,
     usage_percent: f32,
+    /// 認可費用（Task Manager 的「已認可」），超過上限時配置會失敗
+    committed_bytes: Option<u64>,
+    commit_limit_bytes: Option<u64>,
+    commit_percent: Option<f32>,
+    /// 核心分頁 / 非分頁集區，驅動程式洩漏時會持續上升
+    paged_pool_bytes: Option<u64>,
+    nonpaged_pool_bytes: Option<u64>,
+    /// available_bytes 的細分：standby 快取與 free/zero 清單
+    cached_bytes: Option<u64>,
+    free_bytes: Option<u64>,
+    /// 硬分頁錯誤（需要讀寫磁碟）換入換出的分頁數，記憶體壓力最直接的指標
+    pages_per_sec: Option<f64>,
+    /// 含軟分頁錯誤（從 standby / 其他工作集取回，不碰磁碟）
+    page_faults_per_sec: Option<f64>,
+    /// 硬分頁錯誤的讀取次數（每次可讀多個分頁）
+    page_reads_per_sec: Option<f64>,
 }
 
+const MEMORY_PDH_COUNTERS: &[pdh::SampledCounter] = &[
+    pdh::SampledCounter::new("memory.pages_per_sec", "\\Memory\\Pages/sec", pdh::Format::Double),
+    pdh::SampledCounter::new(
+        "memory.page_faults_per_sec",
+        "\\Memory\\Page Faults/sec",
+        pdh::Format::Double,
+    ),
+    pdh::SampledCounter::new(
+        "memory.page_reads_per_sec",
+        "\\Memory\\Page Reads/sec",
+        pdh::Format::Double,
+    ),
+];
+
 #[derive(Serialize)]
-struct DiskData {
-    device: String,
-    total_bytes: Option<u64>,
-    free_bytes: Option<u64>,
-    used_bytes: Option<u64>,
-    usage_percent: Option<f32>,
-    // 其餘欄位暫時省略
+struct SwapData {
+    total_bytes: u64,
+    used_bytes: u64,
+    free_bytes: u64,
+    usage_percent: f32,
 }
 
+/// 系統層級的物件數量；handle 洩漏通常最先在這裡看出來
+#[derive(Serialize)]
+struct SystemCounters {
+    process_count: Option<u32>,
+    thread_count: Option<u32>,
+    handle_count: Option<u32>,
+    /// 等待 CPU 的執行緒數；持續高於核心數代表瓶頸在 CPU
+    processor_queue_length: Option<u32>,
+}
+
+/// 系統層級的 PDH 計數器，由背景取樣器共用同一個 query
+const SYSTEM_PDH_COUNTERS: &[pdh::SampledCounter] = &[pdh::SampledCounter::new(
+    "system.processor_queue_length",
+    "\\System\\Processor Queue Length",
+    pdh::Format::Large,
+)];
+
 #[derive(Serialize)]
 struct HostData {
     os: String,
     platform: String,
     kernel_version: String,
     pretty_name: String,
-}
-
-#[derive(Serialize, Default)]
-struct NetData {
-    name: String,
-    bytes_sent: u64,
-    bytes_recv: u64,
-    packets_sent: u64,
-    packets_recv: u64,
-    err_in: u64,
-    err_out: u64,
-    drop_in: u64,
-    drop_out: u64,
-    fifo_in: u64,
-    fifo_out: u64,
+    /// 每次請求即時計算，不快取
+    uptime_seconds: u64,
+    /// RFC3339，固定以 UTC（+00:00）表示
+    boot_time: String,
+    #[serde(flatten)]
+    domain: DomainInfo,
+    #[serde(flatten)]
+    locale: LocaleInfo,
+    #[serde(flatten)]
+    virt: VirtInfo,
+    #[serde(flatten)]
+    os_version: OsVersionInfo,
 }
 
 #[derive(Serialize)]
 struct CaptureMeta {
     version: String,
     mode: String,
+    /// 只有設定 CAPTURE_PUSH_URL 時才輸出
+    #[serde(skip_serializing_if = "Option::is_none")]
+    push: Option<push::PushStatus>,
+    /// 只有設定 CAPTURE_GRAPHITE_ADDR 時才輸出
+    #[serde(skip_serializing_if = "Option::is_none")]
+    graphite: Option<graphite::GraphiteStatus>,
+    /// 只有設定 CAPTURE_STATSD_ADDR 時才輸出
+    #[serde(skip_serializing_if = "Option::is_none")]
+    statsd: Option<statsd::StatsdStatus>,
+    /// 只有以 otlp feature 建置且設定 OTLP 端點時才輸出
+    #[cfg(feature = "otlp")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    otlp: Option<otlp::OtlpStatus>,
 }
 
 #[derive(Serialize)]
@@ -94,6 +381,8 @@ struct MetricError {
 
 #[derive(Serialize)]
 struct AllMetrics {
+    /// 與 data.host.hostname 相同，放在頂層方便 log pipeline 以主機分組
+    hostname: Option<String>,
     data: AllData,
     capture: CaptureMeta,
     errors: Vec<MetricError>,
@@ -103,43 +392,178 @@ struct AllMetrics {
 struct AllData {
     cpu: CPUData,
     memory: MemoryData,
+    swap: SwapData,
+    /// 分頁檔的設定與實際大小
+    pagefiles: PagefilesData,
     disk: Vec<DiskData>,
+    /// 有陰影儲存區的磁碟區
+    vss: Vec<VssVolume>,
     host: HostData,
+    hardware: HardwareData,
+    system: SystemCounters,
+    numa: NumaData,
+    time: TimeData,
     net: Vec<NetData>,
+    /// CAPTURE_PING_TARGETS 的最新背景 ping 結果
+    ping: Vec<PingResult>,
+    /// 無線網卡；有線機器為空陣列
+    wifi: Vec<WifiData>,
+    gpu: Vec<GpuData>,
+    /// 每次請求重新列舉；monitor_attached = false 代表 headless
+    displays: DisplaysData,
+    /// 播放與錄音端點；預設裝置含音量與靜音
+    audio: AudioData,
+    power: PowerData,
+    /// 風扇轉速；CPU 溫度在 cpu.temperature_c
+    sensors: SensorsData,
+    /// 視窗內 System / Application 的錯誤事件數
+    event_log: EventLogData,
+    /// 最近一次藍底當機與 minidump 傾印檔
+    crash: CrashData,
+    /// 有使用者登入的主控台與 RDP 工作階段
+    sessions: Vec<SessionData>,
+    /// CAPTURE_WATCH_SERVICES 指定的服務；未設定時為空陣列
+    services: Vec<WatchedServiceData>,
+    /// CAPTURE_WATCH_PROCESSES 指定的行程；未設定時為空陣列
+    watched_processes: Vec<WatchedProcessData>,
+    /// 待安裝更新數由背景定期檢查，collected_at 為該次檢查的時間
+    updates: UpdatesData,
+    /// 防毒產品與 Defender 狀態
+    security: SecurityData,
+    firewall: FirewallData,
+    /// 開機模式、Secure Boot 與 TPM
+    platform_security: PlatformSecurityData,
+    /// 背景定期查詢的 Windows 啟用狀態
+    license: LicenseData,
+    /// 完整清單見 /api/v1/metrics/certs
+    certs: CertSummary,
+    /// 列印佇列；Spooler 停止時只有 service_state
+    spooler: SpoolerData,
+    /// 只有設定 CAPTURE_IIS 且這台機器有 IIS 時才輸出
+    #[serde(skip_serializing_if = "Option::is_none")]
+    iis: Option<IisData>,
+    /// 只有設定 CAPTURE_SQLSERVER_INSTANCE 時才輸出
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sqlserver: Option<SqlServerData>,
+    /// 只有啟用 Hyper-V 角色的主機才輸出
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hyperv: Option<HypervData>,
+    /// CAPTURE_CUSTOM_COUNTERS_FILE 定義的計數器，名稱 → 值
+    #[serde(skip_serializing_if = "Option::is_none")]
+    custom: Option<std::collections::BTreeMap<String, pdh::PathSample>>,
+    /// 只有 ?include=processes 時才輸出
+    #[serde(skip_serializing_if = "Option::is_none")]
+    processes: Option<Vec<ProcessData>>,
+    /// 只有 ?include=tcp 時才輸出
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tcp: Option<TcpData>,
+}
+
+/// /api/v1/metrics 的查詢參數；include 為逗號分隔的選用區段
+///
+/// detail=full 時 CPU 多輸出 advanced（/api/v1/metrics/cpu 也適用）；
+/// format=influx / csv 時 /api/v1/metrics 改輸出 InfluxDB line protocol / CSV
+#[derive(Deserialize, Default)]
+struct MetricsQuery {
+    include: Option<String>,
+    detail: Option<String>,
+    format: Option<String>,
+}
+
+impl MetricsQuery {
+    fn format_is(&self, format: &str) -> bool {
+        self.format
+            .as_deref()
+            .is_some_and(|f| f.eq_ignore_ascii_case(format))
+    }
+
+    fn full_detail(&self) -> bool {
+        self.detail
+            .as_deref()
+            .is_some_and(|d| d.eq_ignore_ascii_case("full"))
+    }
+
+    fn includes(&self, section: &str) -> bool {
+        self.include.as_deref().is_some_and(|s| {
+            s.split(',').any(|x| x.trim().eq_ignore_ascii_case(section))
+        })
+    }
 }
 
 /* ---------- PDH 讀取 CPU 目前頻率 ---------- */
 
-fn query_current_freq_mhz() -> Result<u64, String> {
-    unsafe {
-        let mut query: isize = 0;
-        let status: u32 = PdhOpenQueryW(PCWSTR::null(), 0, &mut query);
-        if status != 0 {
-            return Err(format!("PdhOpenQueryW failed: {status}"));
-        }
+/// 各邏輯核心目前頻率（MHz），依 "群組,編號" instance 排序
+fn query_core_freqs_mhz() -> Result<Vec<u64>, String> {
+    let query = pdh::Query::open()?;
+    let counter = query.add("\\Processor Information(*)\\Processor Frequency")?;
 
-        let mut counter: isize = 0;
-        let path = w!("\\Processor Information(0,0)\\Processor Frequency");
-        let status: u32 = PdhAddCounterW(query, path, 0, &mut counter);
-        if status != 0 {
-            PdhCloseQuery(query);
-            return Err(format!("PdhAddCounterW failed: {status}"));
-        }
+    query.collect()?;
+    thread::sleep(Duration::from_millis(120));
+    query.collect()?;
 
-        PdhCollectQueryData(query);
-        thread::sleep(Duration::from_millis(120));
-        PdhCollectQueryData(query);
+    let mut cores: Vec<((u32, u32), u64)> = counter
+        .array(pdh::Format::Large)?
+        .into_iter()
+        .filter_map(|(name, v)| {
+            // 略過 "_Total" 與 "0,_Total" 之類的彙總 instance
+            let (group, index) = name.split_once(',')?;
+            Some(((group.parse().ok()?, index.parse().ok()?), v.as_i64() as u64))
+        })
+        .collect();
+    if cores.is_empty() {
+        return Err("no Processor Information instances".into());
+    }
+    cores.sort_by_key(|(key, _)| *key);
+    Ok(cores.into_iter().map(|(_, v)| v).collect())
+}
 
-        let mut val: PDH_FMT_COUNTERVALUE = std::mem::zeroed();
-        let status: u32 =
-            PdhGetFormattedCounterValue(counter, PDH_FMT_LARGE, None, &mut val);
-        PdhCloseQuery(query);
+/* ---------- GetPerformanceInfo ---------- */
 
-        if status != 0 {
-            return Err(format!("PdhGetFormattedCounterValue failed: {status}"));
-        }
-        Ok(val.Anonymous.largeValue as u64)
+/// 系統整體計數（單位多為 page，需乘上 PageSize）
+fn query_performance_info() -> Result<PERFORMANCE_INFORMATION, String> {
+    let mut info = PERFORMANCE_INFORMATION {
+        cb: std::mem::size_of::<PERFORMANCE_INFORMATION>() as u32,
+        ..Default::default()
+    };
+    unsafe { GetPerformanceInfo(&mut info, info.cb) }
+        .map_err(|e| format!("GetPerformanceInfo failed: {e}"))?;
+    Ok(info)
+}
+
+/* ---------- PDH 讀取記憶體清單 ---------- */
+
+/// (standby 快取, free & zero 清單)，皆為瞬時值，collect 一次即可
+fn query_memory_lists() -> Result<(u64, u64), String> {
+    let query = pdh::Query::open()?;
+    let standby = [
+        query.add("\\Memory\\Standby Cache Core Bytes")?,
+        query.add("\\Memory\\Standby Cache Normal Priority Bytes")?,
+        query.add("\\Memory\\Standby Cache Reserve Bytes")?,
+    ];
+    let free = query.add("\\Memory\\Free & Zero Page List Bytes")?;
+    query.collect()?;
+
+    let mut cached = 0;
+    for c in standby {
+        cached += c.value(pdh::Format::Large)?.as_i64() as u64;
     }
+    Ok((cached, free.value(pdh::Format::Large)?.as_i64() as u64))
+}
+
+/* ---------- WMI 讀取 CPU 溫度 ---------- */
+
+/// MSAcpi_ThermalZoneTemperature 以 0.1 K 為單位；多個 thermal zone 取最高者
+fn query_cpu_temperature_c() -> Result<f32, String> {
+    let rows = wmi::query(
+        "root\\WMI",
+        "SELECT CurrentTemperature FROM MSAcpi_ThermalZoneTemperature",
+    )?;
+
+    rows.iter()
+        .filter_map(|r| r.get_u32("CurrentTemperature"))
+        .map(|dk| dk as f32 / 10.0 - 273.15)
+        .reduce(f32::max)
+        .ok_or_else(|| "no MSAcpi_ThermalZoneTemperature instances".into())
 }
 
 /* ---------- Apache-style Middleware ---------- */
@@ -170,32 +594,453 @@ async fn log_apache(req: axum::http::Request<Body>, next: Next) -> impl IntoResp
 
 /* ---------- 路由 ---------- */
 
-async fn all_metrics() -> impl IntoResponse {
+async fn all_metrics(Query(params): Query<MetricsQuery>) -> Response {
+    let all = collect_all(&params).await;
+    if params.format_is("influx") {
+        let samples = samples::collect(&all);
+        return (
+            [(header::CONTENT_TYPE, influx::CONTENT_TYPE)],
+            influx::render(&samples, all.hostname.as_deref()),
+        )
+            .into_response();
+    }
+    if params.format_is("csv") {
+        return (
+            [(header::CONTENT_TYPE, csv::CONTENT_TYPE)],
+            csv::render(&samples::collect(&all)),
+        )
+            .into_response();
+    }
+    Json(all).into_response()
+}
+
+/// /api/v1/metrics 的完整內容；其他輸出格式也從這裡取資料，不另外蒐集
+async fn collect_all(params: &MetricsQuery) -> AllMetrics {
     let mut errors: Vec<MetricError> = Vec::new();
 
-    let cpu = gather_cpu(&mut errors);
+    let cpu = gather_cpu(&mut errors, params.full_detail()).await;
 
-    Json(AllMetrics {
+    AllMetrics {
+        hostname: domain::hostname(),
         data: AllData {
             cpu,
-            memory: gather_memory(),
-            disk: gather_disk(),
-            host: gather_host(),
-            net: gather_net(),
+            memory: gather_memory(&mut errors),
+            swap: gather_swap(),
+            pagefiles: gather_pagefiles(&mut errors).await,
+            disk: gather_disk(&mut errors).await,
+            vss: gather_vss(&mut errors).await,
+            host: gather_host(&mut errors),
+            hardware: gather_hardware(&mut errors),
+            system: gather_system_counters(&mut errors),
+            numa: gather_numa(&mut errors),
+            time: gather_time(&mut errors).await,
+            net: gather_net(&mut errors),
+            ping: gather_ping(&mut errors),
+            wifi: gather_wifi(&mut errors),
+            gpu: gather_gpu(&mut errors),
+            displays: gather_displays(&mut errors),
+            audio: gather_audio(&mut errors).await,
+            power: gather_power(&mut errors),
+            sensors: gather_sensors(&mut errors).await,
+            event_log: gather_event_log(&mut errors).await,
+            crash: gather_crash(&mut errors).await,
+            sessions: gather_sessions(&mut errors),
+            services: gather_watched_services(&mut errors),
+            watched_processes: gather_watched_processes(&mut errors),
+            updates: gather_updates(&mut errors),
+            security: gather_security(&mut errors).await,
+            firewall: gather_firewall(&mut errors).await,
+            platform_security: gather_platform_security(&mut errors).await,
+            license: gather_license(&mut errors),
+            certs: gather_cert_summary(&mut errors),
+            spooler: gather_spooler(&mut errors),
+            iis: gather_iis(&mut errors),
+            sqlserver: gather_sqlserver(&mut errors),
+            hyperv: gather_hyperv(&mut errors).await,
+            custom: custom::gather_custom(&mut errors),
+            processes: params
+                .includes("processes")
+                .then(|| gather_processes(&ProcessQuery::default())),
+            tcp: params.includes("tcp").then(|| gather_tcp(&mut errors)),
         },
         capture: CaptureMeta {
             version: "1.2.0".into(),
             mode: "debug".into(),
+            push: push::status(),
+            graphite: graphite::status(),
+            statsd: statsd::status(),
+            #[cfg(feature = "otlp")]
+            otlp: otlp::status(),
         },
         errors,
+    }
+}
+
+/// 給背景匯出迴圈用：整個 collect_all 在 blocking 執行緒上 block_on，
+/// 裡面同步的蒐集函式（CPU 取樣的 sleep、事件記錄、WMI…）不會佔住 runtime worker 拖慢 HTTP 服務
+async fn collect_all_blocking() -> Result<AllMetrics, String> {
+    let handle = tokio::runtime::Handle::current();
+    tokio::task::spawn_blocking(move || handle.block_on(collect_all(&MetricsQuery::default())))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Prometheus 文字格式，Accept 要求時改為 OpenMetrics；與 /api/v1/metrics 同一份蒐集結果
+async fn prometheus_metrics(headers: HeaderMap) -> impl IntoResponse {
+    let accept = headers.get(header::ACCEPT).and_then(|v| v.to_str().ok());
+    let format = prometheus::Format::negotiate(accept);
+    let all = collect_all(&MetricsQuery::default()).await;
+    (
+        [(header::CONTENT_TYPE, format.content_type())],
+        prometheus::render(&samples::collect(&all), format),
+    )
+}
+
+/// Graphite 的試算：回傳背景傳送時會送出的內容，不實際連線
+async fn graphite_export() -> impl IntoResponse {
+    let all = collect_all(&MetricsQuery::default()).await;
+    (
+        [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+        graphite::render(&all, Utc::now().timestamp()),
+    )
+}
+
+async fn cpu_metrics(Query(params): Query<MetricsQuery>) -> impl IntoResponse {
+    Json(gather_cpu(&mut Vec::new(), params.full_detail()).await)
+}
+async fn cert_metrics(Query(q): Query<CertQuery>) -> impl IntoResponse {
+    let mut errors = Vec::new();
+    let data = gather_certs(&q, &mut errors);
+    Json(serde_json::json!({ "data": data, "errors": errors }))
+}
+async fn disk_metrics() -> impl IntoResponse {
+    Json(gather_disk(&mut Vec::new()).await)
+}
+async fn disk_layout_metrics(Query(q): Query<LayoutQuery>) -> impl IntoResponse {
+    match tokio::task::spawn_blocking(move || gather_layout(&q)).await {
+        Ok(layout) => {
+            let errors: Vec<MetricError> = layout
+                .errors
+                .iter()
+                .map(|(metric, err)| MetricError {
+                    metric: vec![metric.clone()],
+                    err: err.clone(),
+                })
+                .collect();
+            Json(serde_json::json!({ "data": layout.disks, "errors": errors }))
+        }
+        Err(e) => Json(serde_json::json!({
+            "data": [],
+            "errors": [MetricError {
+                metric: vec!["disk_layout".into()],
+                err: e.to_string(),
+            }],
+        })),
+    }
+}
+async fn disk_health_metrics() -> impl IntoResponse {
+    // 溫度的 WMI 備援查詢會阻塞
+    let (data, errors) = tokio::task::spawn_blocking(|| {
+        let mut errors = Vec::new();
+        (gather_disk_health(&mut errors), errors)
     })
+    .await
+    .unwrap_or_else(|e| {
+        (
+            Vec::new(),
+            vec![MetricError {
+                metric: vec!["disk_health".into()],
+                err: e.to_string(),
+            }],
+        )
+    });
+    Json(serde_json::json!({ "data": data, "errors": errors }))
+}
+async fn hotfix_metrics(Query(q): Query<HotfixQuery>) -> impl IntoResponse {
+    // WMI 查詢會阻塞；?kb= 只回傳單一 KB 是否已安裝
+    let result = tokio::task::spawn_blocking(move || match q.kb {
+        Some(kb) => hotfixes::kb_status(&kb).map(|s| serde_json::json!(s)),
+        None => hotfixes::gather_hotfixes().map(|l| serde_json::json!(*l)),
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()));
+    match result {
+        Ok(data) => Json(serde_json::json!({ "data": data, "errors": [] })),
+        Err(err) => Json(serde_json::json!({
+            "data": null,
+            "errors": [MetricError { metric: vec!["hotfixes".into()], err }],
+        })),
+    }
+}
+async fn dotnet_metrics() -> impl IntoResponse {
+    let mut errors = Vec::new();
+    let data = gather_dotnet(&mut errors);
+    Json(serde_json::json!({ "data": data, "errors": errors }))
+}
+async fn dir_metrics() -> impl IntoResponse {
+    let mut errors = Vec::new();
+    let data = dirs::gather_dirs(&mut errors);
+    Json(serde_json::json!({ "data": data, "errors": errors }))
+}
+
+async fn file_metrics() -> impl IntoResponse {
+    let mut errors = Vec::new();
+    let data = files::gather_files(&mut errors);
+    Json(serde_json::json!({ "data": data, "errors": errors }))
+}
+
+async fn registry_metrics() -> impl IntoResponse {
+    let mut errors = Vec::new();
+    let data = registry::gather_registry(&mut errors);
+    Json(serde_json::json!({ "data": data, "errors": errors }))
+}
+
+async fn task_metrics() -> impl IntoResponse {
+    let mut errors = Vec::new();
+    let data = gather_tasks(&mut errors).await;
+    Json(serde_json::json!({ "data": data, "errors": errors }))
 }
 
-async fn cpu_metrics() -> impl IntoResponse {
-    Json(gather_cpu(&mut Vec::new()))
+async fn firewall_metrics() -> impl IntoResponse {
+    let mut errors = Vec::new();
+    let data = gather_firewall(&mut errors).await;
+    Json(serde_json::json!({ "data": data, "errors": errors }))
+}
+async fn hardware_metrics() -> impl IntoResponse {
+    Json(gather_hardware(&mut Vec::new()))
+}
+async fn time_metrics() -> impl IntoResponse {
+    let mut errors = Vec::new();
+    let data = gather_time(&mut errors).await;
+    Json(serde_json::json!({ "data": data, "errors": errors }))
+}
+async fn security_metrics() -> impl IntoResponse {
+    let mut errors = Vec::new();
+    let data = gather_security(&mut errors).await;
+    Json(serde_json::json!({ "data": data, "errors": errors }))
+}
+async fn sensor_metrics() -> impl IntoResponse {
+    let mut errors = Vec::new();
+    let data = gather_sensors(&mut errors).await;
+    Json(serde_json::json!({ "data": data, "errors": errors }))
+}
+async fn hyperv_metrics() -> impl IntoResponse {
+    let mut errors = Vec::new();
+    match gather_hyperv(&mut errors).await {
+        Some(data) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "data": data, "errors": errors })),
+        ),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({
+                "data": null,
+                "errors": [MetricError {
+                    metric: vec!["hyperv".into()],
+                    err: "Hyper-V counters not found on this machine".into(),
+                }],
+            })),
+        ),
+    }
+}
+
+async fn sqlserver_metrics() -> impl IntoResponse {
+    let mut errors = Vec::new();
+    match gather_sqlserver(&mut errors) {
+        Some(data) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "data": data, "errors": errors })),
+        ),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({
+                "data": null,
+                "errors": [MetricError {
+                    metric: vec!["sqlserver".into()],
+                    err: "set CAPTURE_SQLSERVER_INSTANCE to enable SQL Server metrics".into(),
+                }],
+            })),
+        ),
+    }
+}
+async fn numa_metrics() -> impl IntoResponse {
+    Json(gather_numa(&mut Vec::new()))
 }
 async fn memory_metrics() -> impl IntoResponse {
-    Json(gather_memory())
+    Json(gather_memory(&mut Vec::new()))
+}
+async fn gpu_metrics() -> impl IntoResponse {
+    Json(gather_gpu(&mut Vec::new()))
+}
+async fn profile_metrics() -> impl IntoResponse {
+    let mut errors = Vec::new();
+    let data = gather_profiles(&mut errors);
+    Json(serde_json::json!({ "data": data, "errors": errors }))
+}
+async fn pagefile_metrics() -> impl IntoResponse {
+    let mut errors = Vec::new();
+    let data = gather_pagefiles(&mut errors).await;
+    Json(serde_json::json!({ "data": data, "errors": errors }))
+}
+async fn platform_security_metrics() -> impl IntoResponse {
+    let mut errors = Vec::new();
+    let data = gather_platform_security(&mut errors).await;
+    Json(serde_json::json!({ "data": data, "errors": errors }))
+}
+async fn license_metrics() -> impl IntoResponse {
+    let mut errors = Vec::new();
+    let data = gather_license(&mut errors);
+    Json(serde_json::json!({ "data": data, "errors": errors }))
+}
+async fn audio_metrics() -> impl IntoResponse {
+    let mut errors = Vec::new();
+    let data = gather_audio(&mut errors).await;
+    Json(serde_json::json!({ "data": data, "errors": errors }))
+}
+async fn display_metrics() -> impl IntoResponse {
+    let mut errors = Vec::new();
+    let data = gather_displays(&mut errors);
+    Json(serde_json::json!({ "data": data, "errors": errors }))
+}
+async fn power_metrics() -> impl IntoResponse {
+    Json(gather_power(&mut Vec::new()))
+}
+async fn swap_metrics() -> impl IntoResponse {
+    Json(gather_swap())
+}
+async fn process_metrics(Query(q): Query<ProcessQuery>) -> impl IntoResponse {
+    Json(gather_processes(&q))
+}
+async fn network_talkers(Query(q): Query<talkers::TalkerQuery>) -> impl IntoResponse {
+    let mut errors = Vec::new();
+    let data = talkers::gather_talkers(&q, &mut errors);
+    Json(serde_json::json!({ "data": data, "errors": errors }))
+}
+async fn process_detail(Path(pid): Path<u32>) -> impl IntoResponse {
+    let mut errors: Vec<MetricError> = Vec::new();
+    match gather_process_detail(pid, &mut errors) {
+        Some(detail) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "data": detail, "errors": errors })),
+        ),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({
+                "data": null,
+                "errors": [MetricError {
+                    metric: vec!["process".into()],
+                    err: format!("process {pid} not found"),
+                }],
+            })),
+        ),
+    }
+}
+async fn service_metrics(Query(q): Query<ServiceQuery>) -> impl IntoResponse {
+    Json(gather_services(&q, &mut Vec::new()))
+}
+async fn tcp_metrics() -> impl IntoResponse {
+    Json(gather_tcp(&mut Vec::new()))
+}
+async fn port_metrics(Query(q): Query<PortQuery>) -> impl IntoResponse {
+    // ?port=N 時回傳單一 {listening, pid, process}
+    if q.port.is_some() {
+        Json(check_port(&q, &mut Vec::new())).into_response()
+    } else {
+        Json(gather_ports(&q, &mut Vec::new())).into_response()
+    }
+}
+async fn wifi_metrics() -> impl IntoResponse {
+    Json(gather_wifi(&mut Vec::new()))
+}
+async fn arp_metrics(Query(q): Query<ArpQuery>) -> impl IntoResponse {
+    Json(gather_arp(&q, &mut Vec::new()))
+}
+async fn route_metrics() -> impl IntoResponse {
+    Json(gather_routes(&mut Vec::new()))
+}
+async fn probe_metrics() -> impl IntoResponse {
+    let mut errors: Vec<MetricError> = Vec::new();
+    let data = gather_probes(&mut errors).await;
+    Json(serde_json::json!({ "data": data, "errors": errors }))
+}
+async fn ping_probe(Query(q): Query<PingQuery>) -> impl IntoResponse {
+    // 不可達的目標回傳 100% loss 而非 HTTP 錯誤；只有 worker panic 才回 500
+    match tokio::task::spawn_blocking(move || ping::ping(&q.target, q.count)).await {
+        Ok(result) => Json(result).into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({
+                "data": null,
+                "errors": [MetricError {
+                    metric: vec!["probe.ping".into()],
+                    err: e.to_string(),
+                }],
+            })),
+        )
+            .into_response(),
+    }
+}
+async fn session_metrics() -> impl IntoResponse {
+    Json(gather_sessions(&mut Vec::new()))
+}
+async fn event_log_tail(Query(q): Query<EventQuery>) -> impl IntoResponse {
+    // EvtFormatMessage 每筆都要載入訊息資源，放在 blocking 執行緒
+    let (status, err) = match tokio::task::spawn_blocking(move || tail_events(&q))
+        .await
+        .unwrap_or_else(|e| Err(TailError::Failed(e.to_string())))
+    {
+        Ok(entries) => {
+            return (
+                StatusCode::OK,
+                Json(serde_json::json!({ "data": entries, "errors": [] })),
+            )
+        }
+        Err(TailError::BadRequest(e)) => (StatusCode::BAD_REQUEST, e),
+        Err(TailError::AccessDenied(e)) => (StatusCode::FORBIDDEN, e),
+        Err(TailError::Failed(e)) => (StatusCode::INTERNAL_SERVER_ERROR, e),
+    };
+    (
+        status,
+        Json(serde_json::json!({
+            "data": null,
+            "errors": [MetricError { metric: vec!["eventlog".into()], err }],
+        })),
+    )
+}
+async fn pdh_query(Query(q): Query<pdh::PathQuery>) -> impl IntoResponse {
+    // 取樣期間會 sleep，放在 blocking 執行緒
+    let result = tokio::task::spawn_blocking(move || pdh::sample_path(&q))
+        .await
+        .unwrap_or_else(|e| Err(pdh::PathError::Failed(e.to_string())));
+    match result {
+        Ok(samples) => (
+            StatusCode::OK,
+            Json(serde_json::json!({ "data": samples, "errors": [] })),
+        ),
+        Err(pdh::PathError::BadPath { status, err }) => (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "data": null,
+                "pdh_status": status,
+                "errors": [MetricError { metric: vec!["pdh".into()], err }],
+            })),
+        ),
+        Err(pdh::PathError::Failed(err)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({
+                "data": null,
+                "errors": [MetricError { metric: vec!["pdh".into()], err }],
+            })),
+        ),
+    }
+}
+async fn software_inventory(Query(q): Query<SoftwareQuery>) -> impl IntoResponse {
+    Json(gather_software(&q))
+}
+async fn usb_inventory() -> impl IntoResponse {
+    let mut errors = Vec::new();
+    let data = gather_usb(&mut errors);
+    Json(serde_json::json!({ "data": data, "errors": errors }))
 }
 async fn null_response() -> impl IntoResponse {
     (StatusCode::OK, Json(serde_json::json!({ "data": null })))
@@ -203,7 +1048,7 @@ async fn null_response() -> impl IntoResponse {
 
 /* ---------- 指標蒐集 ---------- */
 
-fn gather_cpu(errors: &mut Vec<MetricError>) -> CPUData {
+async fn gather_cpu(errors: &mut Vec<MetricError>, detail: bool) -> CPUData {
     // 讀系統靜態頻率與使用率
     let mut sys = System::new_with_specifics(
         RefreshKind::nothing().with_cpu(CpuRefreshKind::everything()),
@@ -213,6 +1058,7 @@ fn gather_cpu(errors: &mut Vec<MetricError>) -> CPUData {
     sys.refresh_cpu_specifics(CpuRefreshKind::everything());
 
     let usage = sys.global_cpu_usage();
+    let per_core_usage = sys.cpus().iter().map(|c| c.cpu_usage() / 100.0).collect();
     let base_freq = sys
         .cpus()
         .iter()
@@ -220,23 +1066,35 @@ fn gather_cpu(errors: &mut Vec<MetricError>) -> CPUData {
         .max()
         .unwrap_or(0);
 
-    // 讀取即時頻率
-    let current_freq = match query_current_freq_mhz() {
-        Ok(v) => Some(v),
+    // 讀取即時頻率（混合架構下 core 0 不具代表性，改看所有核心）
+    let per_core_frequency = match query_core_freqs_mhz() {
+        Ok(v) => v,
         Err(e) => {
             errors.push(MetricError {
                 metric: vec!["cpu.current_frequency".into()],
                 err: e,
             });
-            None
+            Vec::new()
         }
     };
+    let current_freq = per_core_frequency.iter().copied().max();
+    let avg_freq = (!per_core_frequency.is_empty())
+        .then(|| per_core_frequency.iter().sum::<u64>() / per_core_frequency.len() as u64);
 
-    // 溫度仍無法取得
-    errors.push(MetricError {
-        metric: vec!["cpu.temperature".into()],
-        err: "unable to read CPU temperature".into(),
-    });
+    // 溫度：WMI 會阻塞，丟到 blocking 執行緒；讀不到才回報錯誤
+    let temperature = match tokio::task::spawn_blocking(query_cpu_temperature_c)
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()))
+    {
+        Ok(v) => Some(v),
+        Err(e) => {
+            errors.push(MetricError {
+                metric: vec!["cpu.temperature".into()],
+                err: format!("unable to read CPU temperature: {e}"),
+            });
+            None
+        }
+    };
 
     // errors.push(MetricError {
     //     metric: vec!["cpu.current_frequency".into()],
@@ -244,66 +1102,185 @@ fn gather_cpu(errors: &mut Vec<MetricError>) -> CPUData {
     // });
 
     CPUData {
+        identity: gather_identity(errors),
         physical_core: System::physical_core_count().unwrap_or(0),
         logical_core: sys.cpus().len(),
         frequency: base_freq,
         current_frequency: current_freq,
-        temperature_c: None,
+        per_core_frequency,
+        avg_current_frequency: avg_freq,
+        temperature_c: temperature,
         free_percent: 1.0 - usage / 100.0,
         usage_percent: usage / 100.0,
+        per_core_usage,
+        context_switches_per_sec: sampled_value("cpu.context_switches_per_sec", errors)
+            .map(|v| v.as_f64()),
+        interrupts_per_sec: sampled_value("cpu.interrupts_per_sec", errors)
+            .map(|v| v.as_f64()),
+        dpcs_queued_per_sec: sampled_value("cpu.dpcs_queued_per_sec", errors)
+            .map(|v| v.as_f64()),
+        system_calls_per_sec: sampled_value("cpu.system_calls_per_sec", errors)
+            .map(|v| v.as_f64()),
+        user_percent: sampled_value("cpu.user_percent", errors).map(|v| v.as_f64() / 100.0),
+        kernel_percent: sampled_value("cpu.kernel_percent", errors).map(|v| v.as_f64() / 100.0),
+        idle_percent: sampled_value("cpu.idle_percent", errors).map(|v| v.as_f64() / 100.0),
+        interrupt_percent: sampled_value("cpu.interrupt_percent", errors)
+            .map(|v| v.as_f64() / 100.0),
+        dpc_percent: sampled_value("cpu.dpc_percent", errors).map(|v| v.as_f64() / 100.0),
+        throttling: gather_throttling(errors),
+        advanced: detail.then(gather_cpu_advanced),
     }
 }
 
-fn gather_memory() -> MemoryData {
+fn gather_cpu_advanced() -> CpuAdvanced {
+    let percent = |key| {
+        let values = pdh::latest(key)?.ok()?;
+        values.first().map(|(_, v)| v.as_f64() / 100.0)
+    };
+    CpuAdvanced {
+        c1_percent: percent("cpu.c1_percent"),
+        c2_percent: percent("cpu.c2_percent"),
+        c3_percent: percent("cpu.c3_percent"),
+    }
+}
+
+fn gather_throttling(errors: &mut Vec<MetricError>) -> ThrottlingData {
+    let ratio = sampled_value("cpu.percent_of_maximum_frequency", errors)
+        .map(|v| v.as_f64() / 100.0);
+    ThrottlingData {
+        percent_of_maximum_frequency: ratio,
+        likely_throttled: ratio.map(|_| sustained_throttling()),
+    }
+}
+
+/// 兩個計數器在同一次 collect 寫入，同位置的取樣屬於同一秒；使用率由閒置時間換算，
+/// 與頻率來自同一次取樣。取樣不足 THROTTLE_WINDOW 次或其中有失敗時為 false
+fn sustained_throttling() -> bool {
+    let first = |s: &pdh::Sample| Some(s.as_ref().ok()?.first()?.1.as_f64() / 100.0);
+    let frequency = pdh::history("cpu.percent_of_maximum_frequency", THROTTLE_WINDOW);
+    let idle = pdh::history("cpu.idle_percent", THROTTLE_WINDOW);
+    frequency.len() == THROTTLE_WINDOW
+        && idle.len() == THROTTLE_WINDOW
+        && frequency.iter().zip(&idle).all(|(f, i)| {
+            matches!((first(f), first(i)), (Some(r), Some(idle))
+                if 1.0 - idle >= THROTTLE_MIN_USAGE && r < *THROTTLE_THRESHOLD)
+        })
+}
+
+fn gather_memory(errors: &mut Vec<MetricError>) -> MemoryData {
     let mut sys = System::new();
     sys.refresh_memory();
     let total = sys.total_memory();
     let avail = sys.available_memory();
     let used = total.saturating_sub(avail);
 
+    let perf = match query_performance_info() {
+        Ok(p) => Some(p),
+        Err(e) => {
+            errors.push(MetricError {
+                metric: vec![
+                    "memory.committed".into(),
+                    "memory.paged_pool".into(),
+                    "memory.nonpaged_pool".into(),
+                ],
+                err: e,
+            });
+            None
+        }
+    };
+    let committed = perf.map(|p| (p.CommitTotal * p.PageSize) as u64);
+
+    // 舊版系統沒有這些計數器：新欄位給 None，其餘照常回傳
+    let lists = match query_memory_lists() {
+        Ok(v) => Some(v),
+        Err(e) => {
+            errors.push(MetricError {
+                metric: vec!["memory.cached".into(), "memory.free".into()],
+                err: e,
+            });
+            None
+        }
+    };
+    let commit_limit = perf.map(|p| (p.CommitLimit * p.PageSize) as u64);
+
     MemoryData {
         total_bytes: total * 1024,
         available_bytes: avail * 1024,
         used_bytes: used * 1024,
         usage_percent: used as f32 / total as f32,
+        committed_bytes: committed,
+        commit_limit_bytes: commit_limit,
+        commit_percent: committed
+            .zip(commit_limit)
+            .filter(|&(_, limit)| limit > 0)
+            .map(|(c, limit)| c as f32 / limit as f32),
+        paged_pool_bytes: perf.map(|p| (p.KernelPaged * p.PageSize) as u64),
+        nonpaged_pool_bytes: perf.map(|p| (p.KernelNonpaged * p.PageSize) as u64),
+        cached_bytes: lists.map(|(cached, _)| cached),
+        free_bytes: lists.map(|(_, free)| free),
+        pages_per_sec: sampled_value("memory.pages_per_sec", errors).map(|v| v.as_f64()),
+        page_faults_per_sec: sampled_value("memory.page_faults_per_sec", errors)
+            .map(|v| v.as_f64()),
+        page_reads_per_sec: sampled_value("memory.page_reads_per_sec", errors)
+            .map(|v| v.as_f64()),
     }
 }
 
-fn gather_disk() -> Vec<DiskData> {
-    // 只示範 C:\
-    let path = w!("C:\\");
-    let mut free:    u64 = 0;
-    let mut total:   u64 = 0;
-    let mut _unused: u64 = 0;
-
-    // 回傳 Result<(), Error>
-    let ok = unsafe {
-        GetDiskFreeSpaceExW(
-            PCWSTR(path.as_ptr()),
-            Some(&mut _unused),      // caller 可用空間（未用）
-            Some(&mut total),        // 總容量
-            Some(&mut free),         // 剩餘容量
-        )
-    };
+fn gather_swap() -> SwapData {
+    // sysinfo 的 swap 即分頁檔；停用分頁檔時全為 0，不視為錯誤
+    let mut sys = System::new();
+    sys.refresh_memory();
+    let total = sys.total_swap();
+    let used = sys.used_swap();
 
-    if ok.is_err() || total == 0 {
-        // 失敗 → 交由上層決定是否加入 errors
-        return Vec::new();
+    SwapData {
+        total_bytes: total,
+        used_bytes: used,
+        free_bytes: sys.free_swap(),
+        usage_percent: if total == 0 { 0.0 } else { used as f32 / total as f32 },
     }
+}
 
-    let used = total.saturating_sub(free);
-    let percent = used as f32 / total as f32;
+fn gather_system_counters(errors: &mut Vec<MetricError>) -> SystemCounters {
+    let perf = match query_performance_info() {
+        Ok(p) => Some(p),
+        Err(e) => {
+            errors.push(MetricError {
+                metric: vec![
+                    "system.process_count".into(),
+                    "system.thread_count".into(),
+                    "system.handle_count".into(),
+                ],
+                err: e,
+            });
+            None
+        }
+    };
 
-    vec![DiskData {
-        device: "C:\\".into(),
-        total_bytes: Some(total),
-        free_bytes: Some(free),
-        used_bytes: Some(used),
-        usage_percent: Some(percent),
-    }]
+    SystemCounters {
+        process_count: perf.map(|p| p.ProcessCount),
+        thread_count: perf.map(|p| p.ThreadCount),
+        handle_count: perf.map(|p| p.HandleCount),
+        processor_queue_length: sampled_value("system.processor_queue_length", errors)
+            .map(|v| v.as_i64() as u32),
+    }
 }
 
-fn gather_host() -> HostData {
+/// 單一 instance 計數器（\System、_Total 等）的最新取樣值；取樣器還沒有資料時為 None
+fn sampled_value(key: &str, errors: &mut Vec<MetricError>) -> Option<pdh::Value> {
+    match pdh::latest(key)? {
+        Ok(values) => values.first().map(|(_, v)| *v),
+        Err(e) => {
+            errors.push(MetricError {
+                metric: vec![key.into()],
+                err: e,
+            });
+            None
+        }
+    }
+}
+
+fn gather_host(errors: &mut Vec<MetricError>) -> HostData {
     // 取得漂亮名稱；可能回傳 None
     let pretty_name = sysinfo::System::long_os_version().unwrap_or_else(|| "unknown".into());
 
@@ -324,23 +1301,17 @@ fn gather_host() -> HostData {
         platform,
         kernel_version: sysinfo::System::kernel_version().unwrap_or_else(|| "unknown".into()),
         pretty_name,
+        uptime_seconds: sysinfo::System::uptime(),
+        boot_time: DateTime::<Utc>::from_timestamp(sysinfo::System::boot_time() as i64, 0)
+            .unwrap_or_default()
+            .to_rfc3339(),
+        domain: gather_domain(errors),
+        locale: gather_locale(errors),
+        virt: gather_virt(),
+        os_version: gather_os_version(),
     }
 }
 
-
-fn gather_net() -> Vec<NetData> {
-    vec![
-        NetData {
-            name: "lo".into(),
-            ..Default::default()
-        },
-        NetData {
-            name: "eth0".into(),
-            ..Default::default()
-        },
-    ]
-}
-
 /* ---------- 入口 ---------- */
 #[tokio::main]
 async fn main() {
@@ -349,10 +1320,89 @@ async fn main() {
         .parse()
         .unwrap_or(59232);
 
+    // 速率型 PDH 計數器由背景執行緒持續取樣
+    pdh::start_sampler(
+        [
+            CPU_PDH_COUNTERS,
+            MEMORY_PDH_COUNTERS,
+            SYSTEM_PDH_COUNTERS,
+            disk::PDH_COUNTERS,
+            gpu::PDH_COUNTERS,
+            iis::pdh_counters(),
+            dotnet::pdh_counters(),
+            &sqlserver::pdh_counters(),
+            hyperv::pdh_counters(),
+        ]
+        .concat(),
+    );
+    process::start_sampler();
+    net::start_sampler();
+    ping::start_sampler();
+    updates::start_sampler();
+    dirs::start_sampler();
+    custom::start_sampler();
+    talkers::start_sampler();
+    license::start_sampler();
+    tempdirs::start_sampler();
+    profiles::start_sampler();
+    cpuinfo::preload();
+    osversion::preload();
+    tokio::task::spawn_blocking(hardware::preload);
+    push::start();
+    graphite::start();
+    statsd::start();
+    #[cfg(feature = "otlp")]
+    otlp::start();
+
     let app = Router::new()
+        .route("/metrics", get(prometheus_metrics))
         .route("/api/v1/metrics", get(all_metrics))
         .route("/api/v1/metrics/cpu", get(cpu_metrics))
         .route("/api/v1/metrics/memory", get(memory_metrics))
+        .route("/api/v1/metrics/swap", get(swap_metrics))
+        .route("/api/v1/metrics/pagefiles", get(pagefile_metrics))
+        .route("/api/v1/metrics/disk", get(disk_metrics))
+        .route("/api/v1/metrics/disk/health", get(disk_health_metrics))
+        .route("/api/v1/metrics/disk/layout", get(disk_layout_metrics))
+        .route("/api/v1/metrics/certs", get(cert_metrics))
+        .route("/api/v1/metrics/dirs", get(dir_metrics))
+        .route("/api/v1/metrics/dotnet", get(dotnet_metrics))
+        .route("/api/v1/metrics/files", get(file_metrics))
+        .route("/api/v1/metrics/firewall", get(firewall_metrics))
+        .route("/api/v1/metrics/hardware", get(hardware_metrics))
+        .route("/api/v1/metrics/hotfixes", get(hotfix_metrics))
+        .route("/api/v1/metrics/hyperv", get(hyperv_metrics))
+        .route("/api/v1/metrics/license", get(license_metrics))
+        .route("/api/v1/metrics/numa", get(numa_metrics))
+        .route("/api/v1/metrics/registry", get(registry_metrics))
+        .route("/api/v1/metrics/security", get(security_metrics))
+        .route("/api/v1/metrics/platform_security", get(platform_security_metrics))
+        .route("/api/v1/metrics/sensors", get(sensor_metrics))
+        .route("/api/v1/metrics/sqlserver", get(sqlserver_metrics))
+        .route("/api/v1/metrics/tasks", get(task_metrics))
+        .route("/api/v1/metrics/time", get(time_metrics))
+        .route("/api/v1/metrics/gpu", get(gpu_metrics))
+        .route("/api/v1/metrics/displays", get(display_metrics))
+        .route("/api/v1/metrics/audio", get(audio_metrics))
+        .route("/api/v1/metrics/power", get(power_metrics))
+        .route("/api/v1/metrics/processes", get(process_metrics))
+        .route("/api/v1/metrics/processes/network", get(network_talkers))
+        .route("/api/v1/metrics/processes/:pid", get(process_detail))
+        .route("/api/v1/metrics/profiles", get(profile_metrics))
+        .route("/api/v1/metrics/services", get(service_metrics))
+        .route("/api/v1/metrics/tcp", get(tcp_metrics))
+        .route("/api/v1/metrics/ports", get(port_metrics))
+        .route("/api/v1/metrics/wifi", get(wifi_metrics))
+        .route("/api/v1/metrics/arp", get(arp_metrics))
+        .route("/api/v1/metrics/routes", get(route_metrics))
+        .route("/api/v1/metrics/probes", get(probe_metrics))
+        .route("/api/v1/metrics/sessions", get(session_metrics))
+        .route("/api/v1/probes/ping", get(ping_probe))
+        .route("/api/v1/pdh", get(pdh_query))
+        .route("/api/v1/eventlog", get(event_log_tail))
+        .route("/api/v1/export/graphite", get(graphite_export))
+        .route("/api/v1/inventory/software", get(software_inventory))
+        .route("/api/v1/inventory/usb", get(usb_inventory))
         .fallback(get(null_response))
         .layer(from_fn(log_apache));
 
@@ -361,3 +1411,40 @@ async fn main() {
     let listener = TcpListener::bind(addr).await.unwrap();
     axum::serve(listener, app).await.unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 等背景取樣器寫入第一筆資料，最多等 10 秒
+    fn wait_for(key: &str, after: Option<f64>) -> f64 {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        loop {
+            if let Some(Ok(values)) = pdh::latest(key)
+                && let Some((_, v)) = values.first()
+                && Some(v.as_f64()) != after
+            {
+                return v.as_f64();
+            }
+            assert!(Instant::now() < deadline, "no new sample for {key}");
+            thread::sleep(Duration::from_millis(100));
+        }
+    }
+
+    #[test]
+    #[cfg_attr(not(windows), ignore = "PDH is only available on Windows")]
+    fn system_calls_per_sec_is_sampled() {
+        let counter = CPU_PDH_COUNTERS
+            .iter()
+            .find(|c| c.key == "cpu.system_calls_per_sec")
+            .copied()
+            .unwrap();
+        pdh::start_sampler(vec![counter]);
+
+        let first = wait_for(counter.key, None);
+        let second = wait_for(counter.key, Some(first));
+        assert!(first >= 0.0, "{first}");
+        assert!(second >= 0.0, "{second}");
+        assert_ne!(first, second);
+    }
+}
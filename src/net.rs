@@ -0,0 +1,290 @@
+//! 網路介面統計：透過 GetIfTable2 讀取每張介面卡的累計計數
+//! 介面資訊：MAC、IP、連線速度、MTU 由 GetAdaptersAddresses 依 LUID 對應
+//! 使用率：背景執行緒每 SAMPLE_INTERVAL 讀一次計數，以兩次取樣的差值除以協商速度
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    net::IpAddr,
+    sync::{LazyLock, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+use windows::Win32::{
+    NetworkManagement::{
+        IpHelper::{
+            ConvertInterfaceLuidToAlias, FreeMibTable, GetAdaptersAddresses, GetIfTable2,
+            GAA_FLAG_SKIP_ANYCAST, GAA_FLAG_SKIP_DNS_SERVER, GAA_FLAG_SKIP_MULTICAST,
+            IP_ADAPTER_ADDRESSES_LH, MIB_IF_ROW2, MIB_IF_TABLE2,
+        },
+        Ndis::{IfOperStatusUp, NET_LUID_LH},
+    },
+    Networking::WinSock::AF_UNSPEC,
+};
+
+use crate::{
+    win::{format_mac, read_table, sockaddr_to_ip, wide_to_string},
+    MetricError,
+};
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize, Default)]
+pub struct NetData {
+    pub name: String,
+    pub bytes_sent: u64,
+    pub bytes_recv: u64,
+    pub packets_sent: u64,
+    pub packets_recv: u64,
+    /// 以下皆為開機（或驅動載入）以來的累計值：InErrors / OutErrors
+    pub err_in: u64,
+    pub err_out: u64,
+    /// InDiscards / OutDiscards：緩衝區不足等原因丟棄的封包
+    pub drop_in: u64,
+    pub drop_out: u64,
+    /// Windows 沒有 FIFO 溢位計數；沿用 psutil 欄位名稱，fifo_in 填 InUnknownProtos（無法辨識協定而丟棄的封包），
+    /// fifo_out 沒有對應值，固定為 0
+    pub fifo_in: u64,
+    pub fifo_out: u64,
+    /// 例如 `00:15:5D:01:02:03`；沒有實體位址的介面為 None
+    pub mac_address: Option<String>,
+    pub ipv4_addresses: Vec<String>,
+    pub ipv6_addresses: Vec<String>,
+    /// 傳送方向的連線速度；迴路、通道等虛擬介面為 None
+    pub speed_bps: Option<u64>,
+    pub mtu: Option<u32>,
+    /// 作業狀態為 Up；停用或未接線的介面仍會列出
+    pub is_up: bool,
+    /// 0–1；最近兩次背景取樣間的接收／傳送速率除以協商速度。
+    /// 速度不明、第一次取樣或計數器在兩次取樣間歸零時為 None
+    pub rx_utilization_percent: Option<f32>,
+    pub tx_utilization_percent: Option<f32>,
+}
+
+/// InterfaceAndOperStatusFlags 的 FilterInterface 位元（NDIS 過濾層的重複介面）
+const FILTER_INTERFACE_BIT: u8 = 0x02;
+
+/// IfType：軟體迴路與通道介面的連線速度沒有意義
+const IF_TYPE_SOFTWARE_LOOPBACK: u32 = 24;
+const IF_TYPE_TUNNEL: u32 = 131;
+
+/// 背景取樣間隔
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+
+/// LUID → 最近一次取樣
+static RATES: LazyLock<Mutex<HashMap<u64, RateSample>>> = LazyLock::new(Default::default);
+
+#[derive(Clone, Copy)]
+struct RateSample {
+    in_octets: u64,
+    out_octets: u64,
+    at: Instant,
+    rx_utilization: Option<f32>,
+    tx_utilization: Option<f32>,
+}
+
+/// GetAdaptersAddresses 中與單一介面相關的欄位
+#[derive(Default)]
+struct AdapterInfo {
+    mac_address: Option<String>,
+    ipv4_addresses: Vec<String>,
+    ipv6_addresses: Vec<String>,
+    speed_bps: Option<u64>,
+    mtu: Option<u32>,
+}
+
+/* ---------- 背景取樣 ---------- */
+
+pub fn start_sampler() {
+    thread::spawn(|| loop {
+        if let Ok(rows) = if_rows() {
+            sample_rates(&rows);
+        }
+        thread::sleep(SAMPLE_INTERVAL);
+    });
+}
+
+fn sample_rates(rows: &[MIB_IF_ROW2]) {
+    let previous = std::mem::take(&mut *RATES.lock().unwrap());
+    let at = Instant::now();
+    let mut current = HashMap::with_capacity(rows.len());
+    for r in rows {
+        let luid = unsafe { r.InterfaceLuid.Value };
+        let prev = previous.get(&luid);
+        // 累計值變小代表計數器繞回或驅動重新載入，這一輪不算使用率
+        let utilization = |now: u64, before: u64, speed: u64| {
+            let prev = prev?;
+            let secs = (at - prev.at).as_secs_f64();
+            let speed = link_speed(r.Type, speed)?;
+            (now >= before && secs > 0.0)
+                .then(|| ((now - before) as f64 * 8.0 / secs / speed as f64) as f32)
+        };
+        current.insert(
+            luid,
+            RateSample {
+                in_octets: r.InOctets,
+                out_octets: r.OutOctets,
+                at,
+                rx_utilization: utilization(
+                    r.InOctets,
+                    prev.map_or(0, |p| p.in_octets),
+                    r.ReceiveLinkSpeed,
+                ),
+                tx_utilization: utilization(
+                    r.OutOctets,
+                    prev.map_or(0, |p| p.out_octets),
+                    r.TransmitLinkSpeed,
+                ),
+            },
+        );
+    }
+    *RATES.lock().unwrap() = current;
+}
+
+/// 0 與 u64::MAX 都代表驅動未回報速度；迴路、通道介面的速度沒有意義
+fn link_speed(if_type: u32, speed: u64) -> Option<u64> {
+    let virtual_if = if_type == IF_TYPE_SOFTWARE_LOOPBACK || if_type == IF_TYPE_TUNNEL;
+    (!virtual_if && speed != 0 && speed != u64::MAX).then_some(speed)
+}
+
+/// GetIfTable2 的所有列，複製出來後立即釋放表格
+fn if_rows() -> Result<Vec<MIB_IF_ROW2>, String> {
+    unsafe {
+        let mut table: *mut MIB_IF_TABLE2 = std::ptr::null_mut();
+        let status = GetIfTable2(&mut table);
+        if status.is_err() {
+            return Err(format!("GetIfTable2 failed: {}", status.0));
+        }
+        let rows =
+            std::slice::from_raw_parts((*table).Table.as_ptr(), (*table).NumEntries as usize)
+                .to_vec();
+        FreeMibTable(table as *const _);
+        Ok(rows)
+    }
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub fn gather_net(errors: &mut Vec<MetricError>) -> Vec<NetData> {
+    let mut adapters = match query_adapters() {
+        Ok(a) => a,
+        Err(e) => {
+            errors.push(MetricError {
+                metric: vec!["net.adapters".into()],
+                err: e,
+            });
+            HashMap::new()
+        }
+    };
+
+    let rows = match if_rows() {
+        Ok(r) => r,
+        Err(e) => {
+            errors.push(MetricError {
+                metric: vec!["net".into()],
+                err: e,
+            });
+            return Vec::new();
+        }
+    };
+    let rates = RATES.lock().unwrap();
+
+    rows.iter()
+        // 每張實體卡都會多出數個 "-WFP/QoS ... Filter-0000" 之類的過濾介面，略過
+        .filter(|r| r.InterfaceAndOperStatusFlags._bitfield & FILTER_INTERFACE_BIT == 0)
+        .map(|r| {
+            // Alias 是「乙太網路」「Wi-Fi」這類使用者看到的名稱；沒有時退回驅動描述
+            let mut name = wide_to_string(&r.Alias);
+            if name.is_empty() {
+                name = wide_to_string(&r.Description);
+            }
+            let luid = unsafe { r.InterfaceLuid.Value };
+            let info = adapters.remove(&luid).unwrap_or_default();
+            let rate = rates.get(&luid);
+            NetData {
+                name,
+                bytes_sent: r.OutOctets,
+                bytes_recv: r.InOctets,
+                packets_sent: r.OutUcastPkts + r.OutNUcastPkts,
+                packets_recv: r.InUcastPkts + r.InNUcastPkts,
+                err_in: r.InErrors,
+                err_out: r.OutErrors,
+                drop_in: r.InDiscards,
+                drop_out: r.OutDiscards,
+                fifo_in: r.InUnknownProtos,
+                mac_address: info.mac_address,
+                ipv4_addresses: info.ipv4_addresses,
+                ipv6_addresses: info.ipv6_addresses,
+                speed_bps: info.speed_bps,
+                mtu: info.mtu,
+                is_up: r.OperStatus == IfOperStatusUp,
+                rx_utilization_percent: rate.and_then(|s| s.rx_utilization),
+                tx_utilization_percent: rate.and_then(|s| s.tx_utilization),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
+
+/// LUID → 介面資訊；GetAdaptersAddresses 回傳的是以 Next 串起的鏈結串列
+fn query_adapters() -> Result<HashMap<u64, AdapterInfo>, String> {
+    let flags = GAA_FLAG_SKIP_ANYCAST | GAA_FLAG_SKIP_MULTICAST | GAA_FLAG_SKIP_DNS_SERVER;
+    let buf = read_table(|p, size| unsafe {
+        GetAdaptersAddresses(AF_UNSPEC.0 as u32, flags, None, Some(p.cast()), size)
+    })
+    .map_err(|s| format!("GetAdaptersAddresses failed: {s}"))?;
+
+    let mut out = HashMap::new();
+    let mut cur = buf.as_ptr() as *const IP_ADAPTER_ADDRESSES_LH;
+    while let Some(a) = unsafe { cur.as_ref() } {
+        let mut info = AdapterInfo::default();
+
+        let len = (a.PhysicalAddressLength as usize).min(a.PhysicalAddress.len());
+        if len > 0 {
+            info.mac_address = Some(format_mac(&a.PhysicalAddress[..len]));
+        }
+
+        let mut addr = a.FirstUnicastAddress;
+        while let Some(u) = unsafe { addr.as_ref() } {
+            match unsafe { sockaddr_to_ip(u.Address.lpSockaddr) } {
+                Some(IpAddr::V4(ip)) => info.ipv4_addresses.push(ip.to_string()),
+                Some(IpAddr::V6(ip)) => info.ipv6_addresses.push(ip.to_string()),
+                None => {}
+            }
+            addr = u.Next;
+        }
+
+        info.speed_bps = link_speed(a.IfType, a.TransmitLinkSpeed);
+        // 迴路介面的 MTU 是 u32::MAX
+        info.mtu = (a.Mtu != u32::MAX).then_some(a.Mtu);
+
+        out.insert(unsafe { a.Luid.Value }, info);
+        cur = a.Next;
+    }
+    Ok(out)
+}
+
+/// 介面 LUID → 使用者看到的名稱（「乙太網路」「Wi-Fi」），給 ARP、路由表等只帶 LUID 的表格使用
+pub fn interface_alias(luid: &NET_LUID_LH) -> Option<String> {
+    let mut buf = [0u16; 257];
+    let status = unsafe { ConvertInterfaceLuidToAlias(luid, &mut buf) };
+    (!status.is_err()).then(|| wide_to_string(&buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg_attr(not(windows), ignore = "GetIfTable2 is only available on Windows")]
+    fn loopback_has_no_errors_or_discards() {
+        let rows = if_rows().unwrap();
+        let lo = rows
+            .iter()
+            .find(|r| r.Type == IF_TYPE_SOFTWARE_LOOPBACK)
+            .expect("loopback interface");
+        assert_eq!(lo.InErrors, 0);
+        assert_eq!(lo.OutErrors, 0);
+        assert_eq!(lo.InDiscards, 0);
+        assert_eq!(lo.OutDiscards, 0);
+    }
+}
@@ -0,0 +1,78 @@
+//! NUMA 拓撲：GetNumaHighestNodeNumber 列舉節點，逐一讀取處理器遮罩與可用記憶體
+//!
+//! 單節點機器也回傳一個節點，呼叫端不需特別處理。
+use serde::Serialize;
+use windows::Win32::System::{
+    SystemInformation::GROUP_AFFINITY,
+    Threading::{
+        GetNumaAvailableMemoryNodeEx, GetNumaHighestNodeNumber, GetNumaNodeProcessorMaskEx,
+    },
+};
+
+use crate::MetricError;
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct NumaNode {
+    pub node: u16,
+    /// 處理器群組（超過 64 個邏輯處理器的機器才會有 0 以外的值）
+    pub processor_group: u16,
+    /// 群組內的邏輯處理器遮罩，十六進位字串，例如 `0xff00`
+    pub processor_mask: String,
+    pub logical_processor_count: u32,
+    pub available_memory_bytes: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct NumaData {
+    pub node_count: usize,
+    pub nodes: Vec<NumaNode>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub fn gather_numa(errors: &mut Vec<MetricError>) -> NumaData {
+    let mut highest = 0u32;
+    if let Err(e) = unsafe { GetNumaHighestNodeNumber(&mut highest) } {
+        errors.push(MetricError {
+            metric: vec!["numa".into()],
+            err: format!("GetNumaHighestNodeNumber failed: {e}"),
+        });
+    }
+
+    let mut nodes = Vec::new();
+    for node in 0..=highest as u16 {
+        let mut affinity = GROUP_AFFINITY::default();
+        // 節點編號可能不連續，讀不到遮罩的編號視為不存在
+        if !unsafe { GetNumaNodeProcessorMaskEx(node, &mut affinity) }.as_bool() {
+            continue;
+        }
+
+        let mut available = 0u64;
+        let available_memory_bytes =
+            match unsafe { GetNumaAvailableMemoryNodeEx(node, &mut available) } {
+                Ok(()) => Some(available),
+                Err(e) => {
+                    errors.push(MetricError {
+                        metric: vec![format!("numa.{node}.available_memory_bytes")],
+                        err: format!("GetNumaAvailableMemoryNodeEx failed: {e}"),
+                    });
+                    None
+                }
+            };
+
+        nodes.push(NumaNode {
+            node,
+            processor_group: affinity.Group,
+            processor_mask: format!("{:#x}", affinity.Mask),
+            logical_processor_count: affinity.Mask.count_ones(),
+            available_memory_bytes,
+        });
+    }
+
+    NumaData {
+        node_count: nodes.len(),
+        nodes,
+    }
+}
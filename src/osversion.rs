@@ -0,0 +1,63 @@
+//! Windows 版本細節：HKLM\SOFTWARE\Microsoft\Windows NT\CurrentVersion 的版本、組建與 UBR
+//!
+//! 只有更新後重新開機才會變，啟動時讀一次後快取。
+use chrono::DateTime;
+use serde::Serialize;
+use std::sync::LazyLock;
+use windows::Win32::System::Registry::HKEY_LOCAL_MACHINE;
+
+use crate::win::{reg_dword, reg_string};
+
+const KEY: &str = "SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion";
+
+static CACHE: LazyLock<OsVersionInfo> = LazyLock::new(read);
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize, Clone)]
+pub struct OsVersionInfo {
+    /// ProductName，例如 `Windows Server 2022 Standard`
+    pub edition: Option<String>,
+    /// 例如 `23H2`；2004 以前的系統沒有 DisplayVersion，改用 ReleaseId（如 `1909`）
+    pub display_version: Option<String>,
+    pub build_number: Option<u32>,
+    /// Update Build Revision，完整版本為 `build_number.ubr`，代表累積更新層級
+    pub ubr: Option<u32>,
+    /// RFC3339（UTC）；功能更新（in-place upgrade）後會變成升級的時間
+    pub install_date: Option<String>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+/// 啟動時先讀好
+pub fn preload() {
+    LazyLock::force(&CACHE);
+}
+
+pub fn gather_os_version() -> OsVersionInfo {
+    CACHE.clone()
+}
+
+fn read() -> OsVersionInfo {
+    let string = |name| reg_string(HKEY_LOCAL_MACHINE, KEY, name).filter(|s| !s.is_empty());
+    let build_number = string("CurrentBuildNumber").and_then(|b| b.trim().parse().ok());
+    OsVersionInfo {
+        edition: string("ProductName").map(|name| fix_product_name(name, build_number)),
+        display_version: string("DisplayVersion").or_else(|| string("ReleaseId")),
+        build_number,
+        ubr: reg_dword(HKEY_LOCAL_MACHINE, KEY, "UBR"),
+        // InstallDate 是 Unix epoch 秒
+        install_date: reg_dword(HKEY_LOCAL_MACHINE, KEY, "InstallDate")
+            .filter(|&secs| secs > 0)
+            .and_then(|secs| DateTime::from_timestamp(secs as i64, 0))
+            .map(|t| t.to_rfc3339()),
+    }
+}
+
+/// Windows 11 的 ProductName 仍寫著 Windows 10，以組建 22000 起算修正（Server 沒有這個問題）
+fn fix_product_name(name: String, build: Option<u32>) -> String {
+    match name.strip_prefix("Windows 10") {
+        Some(rest) if build.is_some_and(|b| b >= 22000) => format!("Windows 11{rest}"),
+        _ => name,
+    }
+}
@@ -0,0 +1,307 @@
+//! OpenTelemetry OTLP 匯出（HTTP/protobuf）：只有以 `--features otlp` 建置才會編入
+//!
+//! 設定 OTEL_EXPORTER_OTLP_METRICS_ENDPOINT（完整 URL）或 OTEL_EXPORTER_OTLP_ENDPOINT（自動加上 `/v1/metrics`）時，
+//! 每 OTEL_METRIC_EXPORT_INTERVAL（毫秒，預設 60000）送出一次 CPU、記憶體、磁碟與網路的樣本。
+//! 另外讀取 OTEL_EXPORTER_OTLP_HEADERS、OTEL_EXPORTER_OTLP_TIMEOUT（毫秒，預設 10000）、
+//! OTEL_SERVICE_NAME 與 OTEL_RESOURCE_ATTRIBUTES；OTEL_EXPORTER_OTLP_PROTOCOL 只支援 http/protobuf。
+//!
+//! gauge 對應 Gauge，計數器對應 cumulative、monotonic 的 Sum（起點為開機時間）。
+//! 沒有引入 protobuf 套件，ExportMetricsServiceRequest 以下面的 Message 手動編碼。
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::{
+    sync::{LazyLock, RwLock},
+    time::Duration,
+};
+
+use crate::{
+    config, httpprobe, push,
+    samples::{self, Kind, Sample},
+    AllMetrics,
+};
+
+/// 只送這些區段的樣本
+const SECTIONS: &[&str] = &["cpu", "memory", "disk", "net"];
+/// AggregationTemporality
+const CUMULATIVE: u64 = 2;
+
+static ENDPOINT: LazyLock<Option<String>> = LazyLock::new(|| {
+    config::env_string("OTEL_EXPORTER_OTLP_METRICS_ENDPOINT").or_else(|| {
+        config::env_string("OTEL_EXPORTER_OTLP_ENDPOINT")
+            .map(|base| format!("{}/v1/metrics", base.trim_end_matches('/')))
+    })
+});
+static INTERVAL: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_millis(config::env_parse("OTEL_METRIC_EXPORT_INTERVAL", 60000u64).max(1000))
+});
+static TIMEOUT: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_millis(config::env_parse("OTEL_EXPORTER_OTLP_TIMEOUT", 10000u64))
+});
+/// `k1=v1,k2=v2` → `k1: v1\r\nk2: v2`
+static HEADERS: LazyLock<Option<String>> = LazyLock::new(|| {
+    let lines: Vec<String> = pairs("OTEL_EXPORTER_OTLP_HEADERS")
+        .into_iter()
+        .map(|(k, v)| format!("{k}: {v}"))
+        .collect();
+    (!lines.is_empty()).then(|| lines.join("\r\n"))
+});
+static STATUS: RwLock<Option<OtlpStatus>> = RwLock::new(None);
+
+#[derive(Serialize, Clone, Default)]
+pub struct OtlpStatus {
+    pub endpoint: String,
+    /// RFC3339（UTC）
+    pub last_success_time: Option<String>,
+    pub last_status_code: Option<u32>,
+    /// 最近一次失敗的原因；成功後清除
+    pub last_error: Option<String>,
+    pub consecutive_failures: u32,
+}
+
+/* ---------- 背景匯出 ---------- */
+
+/// 未設定端點時什麼都不做
+pub fn start() {
+    let Some(endpoint) = ENDPOINT.clone() else {
+        return;
+    };
+    *STATUS.write().unwrap() = Some(OtlpStatus {
+        endpoint: endpoint.clone(),
+        ..Default::default()
+    });
+    if let Some(protocol) = config::env_string("OTEL_EXPORTER_OTLP_PROTOCOL")
+        && protocol != "http/protobuf"
+    {
+        if let Some(status) = STATUS.write().unwrap().as_mut() {
+            status.last_error = Some(format!(
+                "OTEL_EXPORTER_OTLP_PROTOCOL={protocol} is not supported, only http/protobuf"
+            ));
+        }
+        return;
+    }
+
+    tokio::spawn(async move {
+        let mut failures = 0u32;
+        loop {
+            let result = match crate::collect_all_blocking().await {
+                Ok(all) => {
+                    let body = encode(&all);
+                    let url = endpoint.clone();
+                    tokio::task::spawn_blocking(move || {
+                        httpprobe::post(
+                            &url,
+                            "application/x-protobuf",
+                            HEADERS.as_deref(),
+                            &body,
+                            *TIMEOUT,
+                        )
+                    })
+                    .await
+                    .unwrap_or_else(|e| Err(e.to_string()))
+                }
+                Err(e) => Err(format!("collect: {e}")),
+            };
+
+            {
+                let mut status = STATUS.write().unwrap();
+                let status = status.get_or_insert_with(Default::default);
+                match result {
+                    Ok(code) if code < 300 => {
+                        failures = 0;
+                        status.last_status_code = Some(code);
+                        status.last_success_time = Some(Utc::now().to_rfc3339());
+                        status.last_error = None;
+                    }
+                    Ok(code) => {
+                        failures += 1;
+                        status.last_status_code = Some(code);
+                        status.last_error = Some(format!("collector responded with HTTP {code}"));
+                    }
+                    Err(e) => {
+                        failures += 1;
+                        status.last_error = Some(e);
+                    }
+                }
+                status.consecutive_failures = failures;
+            }
+            tokio::time::sleep(if failures == 0 {
+                *INTERVAL
+            } else {
+                push::backoff(failures)
+            })
+            .await;
+        }
+    });
+}
+
+/// 未設定端點時為 None
+pub fn status() -> Option<OtlpStatus> {
+    STATUS.read().unwrap().clone()
+}
+
+/// 逗號分隔的 `key=value`；格式不對的項目略過
+fn pairs(key: &str) -> Vec<(String, String)> {
+    config::env_list(key)
+        .iter()
+        .filter_map(|entry| {
+            let (k, v) = entry.split_once('=')?;
+            Some((k.trim().to_string(), v.trim().to_string()))
+        })
+        .collect()
+}
+
+/* ---------- ExportMetricsServiceRequest ---------- */
+
+fn encode(all: &AllMetrics) -> Vec<u8> {
+    let now = nanos(Utc::now());
+    let boot = DateTime::parse_from_rfc3339(&all.data.host.boot_time)
+        .map(|t| nanos(t.with_timezone(&Utc)))
+        .unwrap_or(now);
+
+    let mut resource = Message::default();
+    let service =
+        config::env_string("OTEL_SERVICE_NAME").unwrap_or_else(|| "capture-windows".into());
+    let mut attributes = vec![
+        ("service.name".to_string(), service),
+        ("service.version".to_string(), all.capture.version.clone()),
+        ("os.type".to_string(), "windows".to_string()),
+    ];
+    if let Some(host) = &all.hostname {
+        attributes.push(("host.name".into(), host.clone()));
+    }
+    // OTEL_RESOURCE_ATTRIBUTES 可以覆寫上面的預設值
+    for (k, v) in pairs("OTEL_RESOURCE_ATTRIBUTES") {
+        attributes.retain(|(existing, _)| *existing != k);
+        attributes.push((k, v));
+    }
+    for (k, v) in &attributes {
+        resource.message(1, key_value(k, v));
+    }
+
+    let mut scope = Message::default();
+    scope.string(1, "capture-windows");
+    scope.string(2, &all.capture.version);
+    let mut scope_metrics = Message::default();
+    scope_metrics.message(1, scope);
+
+    let samples: Vec<Sample> = samples::collect(all)
+        .into_iter()
+        .filter(|s| {
+            s.name
+                .split_once('_')
+                .is_some_and(|(group, _)| SECTIONS.contains(&group))
+        })
+        .collect();
+    // 同名樣本相鄰，合成一個 Metric
+    for family in samples.chunk_by(|a, b| a.name == b.name) {
+        let first = &family[0];
+        let mut points = Message::default();
+        for s in family {
+            let mut point = Message::default();
+            if s.kind == Kind::Counter {
+                point.fixed64(2, boot);
+            }
+            point.fixed64(3, now);
+            point.double(4, s.value);
+            for (k, v) in &s.labels {
+                point.message(7, key_value(k, v));
+            }
+            points.message(1, point);
+        }
+
+        let mut metric = Message::default();
+        metric.string(1, &format!("capture.{}", first.name.replacen('_', ".", 1)));
+        metric.string(2, first.help);
+        metric.string(3, unit(first.name));
+        match first.kind {
+            Kind::Gauge => metric.message(5, points),
+            Kind::Counter => {
+                points.uint(2, CUMULATIVE);
+                points.uint(3, 1);
+                metric.message(7, points);
+            }
+        }
+        scope_metrics.message(2, metric);
+    }
+
+    let mut resource_metrics = Message::default();
+    resource_metrics.message(1, resource);
+    resource_metrics.message(2, scope_metrics);
+    let mut request = Message::default();
+    request.message(1, resource_metrics);
+    request.0
+}
+
+/// UCUM 單位；名稱沒有單位後綴時為空字串
+fn unit(name: &str) -> &'static str {
+    [
+        ("_bytes", "By"),
+        ("_seconds", "s"),
+        ("_ratio", "1"),
+        ("_hertz", "Hz"),
+        ("_celsius", "Cel"),
+        ("_per_second", "1/s"),
+    ]
+    .iter()
+    .find(|(suffix, _)| name.ends_with(suffix))
+    .map(|(_, unit)| *unit)
+    .unwrap_or_default()
+}
+
+fn nanos(t: DateTime<Utc>) -> u64 {
+    t.timestamp_nanos_opt().unwrap_or_default().max(0) as u64
+}
+
+/// KeyValue{key, value: AnyValue{string_value}}
+fn key_value(key: &str, value: &str) -> Message {
+    let mut any = Message::default();
+    any.string(1, value);
+    let mut kv = Message::default();
+    kv.string(1, key);
+    kv.message(2, any);
+    kv
+}
+
+/// protobuf 編碼，只用到 varint、64-bit 與 length-delimited 三種 wire type
+#[derive(Default)]
+struct Message(Vec<u8>);
+
+impl Message {
+    fn varint(&mut self, mut v: u64) {
+        while v >= 0x80 {
+            self.0.push((v as u8) | 0x80);
+            v >>= 7;
+        }
+        self.0.push(v as u8);
+    }
+
+    fn tag(&mut self, field: u32, wire_type: u8) {
+        self.varint(((field as u64) << 3) | wire_type as u64);
+    }
+
+    fn uint(&mut self, field: u32, v: u64) {
+        self.tag(field, 0);
+        self.varint(v);
+    }
+
+    fn fixed64(&mut self, field: u32, v: u64) {
+        self.tag(field, 1);
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn double(&mut self, field: u32, v: f64) {
+        self.fixed64(field, v.to_bits());
+    }
+
+    fn string(&mut self, field: u32, s: &str) {
+        self.tag(field, 2);
+        self.varint(s.len() as u64);
+        self.0.extend_from_slice(s.as_bytes());
+    }
+
+    fn message(&mut self, field: u32, m: Message) {
+        self.tag(field, 2);
+        self.varint(m.0.len() as u64);
+        self.0.extend_from_slice(&m.0);
+    }
+}
@@ -0,0 +1,116 @@
+//! 分頁檔設定：Win32_PageFileSetting（設定的大小）與 Win32_PageFileUsage（目前配置與使用量）
+//!
+//! 「自動管理所有磁碟的分頁檔大小」（Win32_ComputerSystem.AutomaticManagedPagefile）開啟時
+//! Win32_PageFileSetting 沒有任何物件，所有分頁檔都視為系統管理。
+//! 個別分頁檔設為「系統管理的大小」時 InitialSize 與 MaximumSize 皆為 0。
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::{wmi, MetricError};
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct PagefileData {
+    /// 例如 `D:\pagefile.sys`
+    pub path: String,
+    /// 設定的初始 / 最大大小；系統管理時為 None
+    pub initial_size_bytes: Option<u64>,
+    pub maximum_size_bytes: Option<u64>,
+    /// 目前實際配置的大小；設定了但尚未重新開機生效的分頁檔為 None
+    pub current_size_bytes: Option<u64>,
+    pub current_usage_bytes: Option<u64>,
+    pub peak_usage_bytes: Option<u64>,
+    pub system_managed: bool,
+}
+
+#[derive(Serialize, Default)]
+pub struct PagefilesData {
+    pub automatic_managed: Option<bool>,
+    /// 既沒有設定也沒有使用中的分頁檔
+    pub pagefile_disabled: bool,
+    pub pagefiles: Vec<PagefileData>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub async fn gather_pagefiles(errors: &mut Vec<MetricError>) -> PagefilesData {
+    let result = tokio::task::spawn_blocking(query_pagefiles)
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+    result.unwrap_or_else(|e| {
+        errors.push(MetricError {
+            metric: vec!["pagefiles".into()],
+            err: e,
+        });
+        PagefilesData::default()
+    })
+}
+
+fn query_pagefiles() -> Result<PagefilesData, String> {
+    const MB: u64 = 1 << 20;
+    let automatic_managed = wmi::query(
+        "root\\CIMV2",
+        "SELECT AutomaticManagedPagefile FROM Win32_ComputerSystem",
+    )?
+    .first()
+    .and_then(|r| r.get_bool("AutomaticManagedPagefile"));
+
+    // 以小寫路徑合併兩個類別；WMI 的大小單位都是 MB
+    let mut files: BTreeMap<String, PagefileData> = BTreeMap::new();
+    let settings = wmi::query(
+        "root\\CIMV2",
+        "SELECT Name, InitialSize, MaximumSize FROM Win32_PageFileSetting",
+    )?;
+    for row in &settings {
+        let Some(path) = row.get_string("Name") else {
+            continue;
+        };
+        let initial = row.get_u32("InitialSize").unwrap_or(0) as u64;
+        let maximum = row.get_u32("MaximumSize").unwrap_or(0) as u64;
+        let system_managed = initial == 0 && maximum == 0;
+        files.insert(
+            path.to_lowercase(),
+            PagefileData {
+                path,
+                initial_size_bytes: (!system_managed).then_some(initial * MB),
+                maximum_size_bytes: (!system_managed).then_some(maximum * MB),
+                current_size_bytes: None,
+                current_usage_bytes: None,
+                peak_usage_bytes: None,
+                system_managed,
+            },
+        );
+    }
+
+    let usage = wmi::query(
+        "root\\CIMV2",
+        "SELECT Name, AllocatedBaseSize, CurrentUsage, PeakUsage FROM Win32_PageFileUsage",
+    )?;
+    for row in &usage {
+        let Some(path) = row.get_string("Name") else {
+            continue;
+        };
+        let entry = files
+            .entry(path.to_lowercase())
+            .or_insert_with(|| PagefileData {
+                path,
+                initial_size_bytes: None,
+                maximum_size_bytes: None,
+                current_size_bytes: None,
+                current_usage_bytes: None,
+                peak_usage_bytes: None,
+                // 沒有對應設定的使用中分頁檔只會出現在自動管理時
+                system_managed: automatic_managed == Some(true),
+            });
+        entry.current_size_bytes = row.get_u32("AllocatedBaseSize").map(|mb| mb as u64 * MB);
+        entry.current_usage_bytes = row.get_u32("CurrentUsage").map(|mb| mb as u64 * MB);
+        entry.peak_usage_bytes = row.get_u32("PeakUsage").map(|mb| mb as u64 * MB);
+    }
+
+    Ok(PagefilesData {
+        automatic_managed,
+        pagefile_disabled: files.is_empty() && automatic_managed != Some(true),
+        pagefiles: files.into_values().collect(),
+    })
+}
@@ -0,0 +1,416 @@
+//! PDH 效能計數器：query / counter 的 RAII 包裝與格式化讀值、背景取樣器，以及 /api/v1/pdh 的單次查詢
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    sync::{OnceLock, RwLock},
+    thread,
+    time::Duration,
+};
+use windows::{
+    core::{HSTRING, PCWSTR, PWSTR},
+    Win32::System::Performance::{
+        PdhAddCounterW, PdhCloseQuery, PdhCollectQueryData, PdhEnumObjectsW,
+        PdhGetFormattedCounterArrayW, PdhGetFormattedCounterValue, PdhGetRawCounterArrayW,
+        PdhOpenQueryW, PDH_FMT, PDH_FMT_COUNTERVALUE, PDH_FMT_COUNTERVALUE_ITEM_W, PDH_FMT_DOUBLE,
+        PDH_FMT_LARGE, PDH_MORE_DATA, PDH_RAW_COUNTER_ITEM_W, PERF_DETAIL_WIZARD,
+    },
+};
+
+/// PDH query handle；Drop 時關閉，底下所有 counter 一併釋放
+pub struct Query(isize);
+
+/// 屬於某個 Query 的 counter handle，不可在 Query 關閉後使用
+#[derive(Clone, Copy)]
+pub struct Counter(isize);
+
+impl Query {
+    pub fn open() -> Result<Self, String> {
+        let mut query: isize = 0;
+        let status = unsafe { PdhOpenQueryW(PCWSTR::null(), 0, &mut query) };
+        if status != 0 {
+            return Err(format!("PdhOpenQueryW failed: {status}"));
+        }
+        Ok(Self(query))
+    }
+
+    /// 加入計數器路徑，例如 `\Processor Information(*)\Processor Frequency`
+    pub fn add(&self, path: &str) -> Result<Counter, String> {
+        self.add_status(path)
+            .map_err(|status| format!("PdhAddCounterW({path}) failed: {status}"))
+    }
+
+    /// 同 add，失敗時回傳 PDH 狀態碼本身
+    fn add_status(&self, path: &str) -> Result<Counter, u32> {
+        let mut counter: isize = 0;
+        let status = unsafe { PdhAddCounterW(self.0, &HSTRING::from(path), 0, &mut counter) };
+        if status != 0 {
+            return Err(status);
+        }
+        Ok(Counter(counter))
+    }
+
+    pub fn collect(&self) -> Result<(), String> {
+        let status = unsafe { PdhCollectQueryData(self.0) };
+        if status != 0 {
+            return Err(format!("PdhCollectQueryData failed: {status}"));
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Query {
+    fn drop(&mut self) {
+        unsafe { PdhCloseQuery(self.0) };
+    }
+}
+
+/// 格式化方式：計數類用 Large，比例 / 秒數 / 佇列長度等小數用 Double
+#[derive(Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Large,
+    Double,
+}
+
+/// 依 Format 取得的格式化值；序列化為單純的數字
+#[derive(Clone, Copy, Serialize)]
+#[serde(untagged)]
+pub enum Value {
+    Large(i64),
+    Double(f64),
+}
+
+impl Value {
+    pub fn as_i64(self) -> i64 {
+        match self {
+            Value::Large(v) => v,
+            Value::Double(v) => v as i64,
+        }
+    }
+
+    pub fn as_f64(self) -> f64 {
+        match self {
+            Value::Large(v) => v as f64,
+            Value::Double(v) => v,
+        }
+    }
+}
+
+impl Format {
+    fn flag(self) -> PDH_FMT {
+        match self {
+            Format::Large => PDH_FMT_LARGE,
+            Format::Double => PDH_FMT_DOUBLE,
+        }
+    }
+
+    fn read(self, val: &PDH_FMT_COUNTERVALUE) -> Value {
+        unsafe {
+            match self {
+                Format::Large => Value::Large(val.Anonymous.largeValue),
+                Format::Double => Value::Double(val.Anonymous.doubleValue),
+            }
+        }
+    }
+}
+
+impl Counter {
+    /// 單一 instance 的值
+    pub fn value(&self, fmt: Format) -> Result<Value, String> {
+        let mut val: PDH_FMT_COUNTERVALUE = unsafe { std::mem::zeroed() };
+        let status = unsafe { PdhGetFormattedCounterValue(self.0, fmt.flag(), None, &mut val) };
+        if status != 0 {
+            return Err(format!("PdhGetFormattedCounterValue failed: {status}"));
+        }
+        Ok(fmt.read(&val))
+    }
+
+    /// 萬用字元 instance 的所有值，回傳 (instance 名稱, 值)
+    pub fn array(&self, fmt: Format) -> Result<Vec<(String, Value)>, String> {
+        let mut size: u32 = 0;
+        let mut count: u32 = 0;
+        let status = unsafe {
+            PdhGetFormattedCounterArrayW(self.0, fmt.flag(), &mut size, &mut count, None)
+        };
+        if status != PDH_MORE_DATA {
+            return Err(format!("PdhGetFormattedCounterArrayW failed: {status}"));
+        }
+
+        // instance 名稱字串緊接在 item 陣列後面、放在同一塊 buffer 裡
+        let item_size = std::mem::size_of::<PDH_FMT_COUNTERVALUE_ITEM_W>();
+        let mut buf: Vec<PDH_FMT_COUNTERVALUE_ITEM_W> =
+            vec![unsafe { std::mem::zeroed() }; size as usize / item_size + 1];
+        let status = unsafe {
+            PdhGetFormattedCounterArrayW(
+                self.0,
+                fmt.flag(),
+                &mut size,
+                &mut count,
+                Some(buf.as_mut_ptr()),
+            )
+        };
+        if status != 0 {
+            return Err(format!("PdhGetFormattedCounterArrayW failed: {status}"));
+        }
+
+        let mut seen = HashMap::new();
+        Ok(buf[..count as usize]
+            .iter()
+            .filter_map(|item| {
+                let name = unsafe { item.szName.to_string() }.unwrap_or_default();
+                let name = numbered(&mut seen, name);
+                // CStatus 0/1 = VALID_DATA/NEW_DATA，其餘代表該 instance 這次沒有有效值
+                (item.FmtValue.CStatus <= 1).then(|| (name, fmt.read(&item.FmtValue)))
+            })
+            .collect())
+    }
+
+    /// 未經速率換算的原始值（PDH_RAW_COUNTER.FirstValue），例如 `…/sec` 計數器的開機以來累計數
+    pub fn raw_array(&self) -> Result<Vec<(String, Value)>, String> {
+        let mut size: u32 = 0;
+        let mut count: u32 = 0;
+        let status = unsafe { PdhGetRawCounterArrayW(self.0, &mut size, &mut count, None) };
+        if status != PDH_MORE_DATA {
+            return Err(format!("PdhGetRawCounterArrayW failed: {status}"));
+        }
+
+        let item_size = std::mem::size_of::<PDH_RAW_COUNTER_ITEM_W>();
+        let mut buf: Vec<PDH_RAW_COUNTER_ITEM_W> =
+            vec![unsafe { std::mem::zeroed() }; size as usize / item_size + 1];
+        let status = unsafe {
+            PdhGetRawCounterArrayW(self.0, &mut size, &mut count, Some(buf.as_mut_ptr()))
+        };
+        if status != 0 {
+            return Err(format!("PdhGetRawCounterArrayW failed: {status}"));
+        }
+
+        let mut seen = HashMap::new();
+        Ok(buf[..count as usize]
+            .iter()
+            .filter_map(|item| {
+                let name = unsafe { item.szName.to_string() }.unwrap_or_default();
+                let name = numbered(&mut seen, name);
+                let value = Value::Large(item.RawValue.FirstValue);
+                (item.RawValue.CStatus <= 1).then_some((name, value))
+            })
+            .collect())
+    }
+}
+
+/// 同名 instance（例如多個 svchost）照 perfmon 的寫法依序加上 `#1`、`#2`…，以名稱為鍵時才不會
+/// 互相蓋掉。要在過濾無效值之前編號，編號才不會因為某個 instance 這次沒有值而跳動
+fn numbered(seen: &mut HashMap<String, u32>, name: String) -> String {
+    let n = seen.entry(name.clone()).or_insert(0);
+    let numbered = if *n == 0 { name } else { format!("{name}#{n}") };
+    *n += 1;
+    numbered
+}
+
+/// 本機所有效能物件名稱，例如 `Processor Information`、`MSSQL$SQLEXPRESS:Buffer Manager`
+///
+/// 會重新整理 PDH 的物件快取，才看得到 agent 啟動後才安裝的物件
+pub fn objects() -> Result<Vec<String>, String> {
+    let mut len = 0u32;
+    let status = unsafe {
+        PdhEnumObjectsW(
+            PCWSTR::null(),
+            PCWSTR::null(),
+            PWSTR::null(),
+            &mut len,
+            PERF_DETAIL_WIZARD,
+            true,
+        )
+    };
+    if status != PDH_MORE_DATA {
+        return Err(format!("PdhEnumObjectsW failed: {status}"));
+    }
+    let mut buf = vec![0u16; len as usize];
+    let status = unsafe {
+        PdhEnumObjectsW(
+            PCWSTR::null(),
+            PCWSTR::null(),
+            PWSTR(buf.as_mut_ptr()),
+            &mut len,
+            PERF_DETAIL_WIZARD,
+            false,
+        )
+    };
+    if status != 0 {
+        return Err(format!("PdhEnumObjectsW failed: {status}"));
+    }
+    // 以 NUL 分隔、連續兩個 NUL 結尾的字串清單
+    Ok(buf
+        .split(|&c| c == 0)
+        .filter(|s| !s.is_empty())
+        .map(String::from_utf16_lossy)
+        .collect())
+}
+
+/* ---------- 背景取樣 ---------- */
+
+/// 取樣間隔；速率型計數器讀到的就是這段期間的平均值
+pub const SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 一個計數器最近一次的結果：各 instance 的值，或失敗原因
+pub type Sample = Result<Vec<(String, Value)>, String>;
+
+/// 每個計數器保留的最近取樣數，給需要「連續幾次都成立」的判斷用
+pub const HISTORY_LEN: usize = 10;
+
+static SAMPLES: OnceLock<RwLock<HashMap<&'static str, VecDeque<Sample>>>> = OnceLock::new();
+
+/// 背景取樣器要註冊的計數器
+#[derive(Clone, Copy)]
+pub struct SampledCounter {
+    pub key: &'static str,
+    pub path: &'static str,
+    pub format: Format,
+}
+
+impl SampledCounter {
+    pub const fn new(key: &'static str, path: &'static str, format: Format) -> Self {
+        Self { key, path, format }
+    }
+}
+
+/// 啟動背景取樣執行緒
+///
+/// 速率型計數器需要兩次 collect 才有值，handler 改讀這裡的結果就不必在請求中 sleep。
+pub fn start_sampler(counters: Vec<SampledCounter>) {
+    let samples = SAMPLES.get_or_init(Default::default);
+
+    thread::spawn(move || {
+        let store = |key: &'static str, sample: Sample| {
+            let mut samples = samples.write().unwrap();
+            let history = samples.entry(key).or_default();
+            if history.len() == HISTORY_LEN {
+                history.pop_front();
+            }
+            history.push_back(sample);
+        };
+
+        let query = match Query::open() {
+            Ok(q) => q,
+            Err(e) => {
+                for c in &counters {
+                    store(c.key, Err(e.clone()));
+                }
+                return;
+            }
+        };
+
+        // 單一計數器加不進去（例如該機器沒有這個物件）不影響其他計數器
+        let mut added = Vec::new();
+        for c in counters {
+            match query.add(c.path) {
+                Ok(counter) => added.push((c, counter)),
+                Err(e) => store(c.key, Err(e)),
+            }
+        }
+
+        // 第一次 collect 只是建立速率計算的基準點
+        let _ = query.collect();
+        loop {
+            thread::sleep(SAMPLE_INTERVAL);
+            match query.collect() {
+                Ok(()) => {
+                    for (c, counter) in &added {
+                        store(c.key, counter.array(c.format));
+                    }
+                }
+                Err(e) => {
+                    for (c, _) in &added {
+                        store(c.key, Err(e.clone()));
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// 最近一次取樣結果；取樣器未啟動或還沒有資料時回傳 None
+pub fn latest(key: &str) -> Option<Sample> {
+    SAMPLES.get()?.read().unwrap().get(key)?.back().cloned()
+}
+
+/// 最近 n 次取樣結果（最多 HISTORY_LEN），舊的在前；不足 n 次時有多少給多少
+pub fn history(key: &str, n: usize) -> Vec<Sample> {
+    let Some(samples) = SAMPLES.get() else {
+        return Vec::new();
+    };
+    let samples = samples.read().unwrap();
+    let Some(history) = samples.get(key) else {
+        return Vec::new();
+    };
+    history
+        .iter()
+        .skip(history.len().saturating_sub(n))
+        .cloned()
+        .collect()
+}
+
+/* ---------- 單次查詢 ---------- */
+
+/// 每次請求的取樣上限，避免單一請求長時間佔住 blocking 執行緒
+const MAX_SAMPLES: u32 = 10;
+const MAX_INTERVAL_MS: u64 = 5_000;
+const MIN_INTERVAL_MS: u64 = 100;
+
+#[derive(Deserialize)]
+pub struct PathQuery {
+    /// 計數器路徑，例如 `\LogicalDisk(C:)\% Free Space`；instance 可用 `*`
+    pub path: String,
+    /// 取樣次數，預設 1、上限 10
+    pub samples: Option<u32>,
+    /// 取樣間隔，預設 1000，限制在 100–5000 毫秒
+    pub interval_ms: Option<u64>,
+    /// large / double，預設 double
+    pub format: Option<Format>,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum PathSample {
+    Single(Value),
+    /// 萬用字元路徑：instance 名稱 → 值；同名 instance 已由 Counter::array 加上 `#N`
+    Instances(BTreeMap<String, Value>),
+}
+
+pub enum PathError {
+    /// 路徑不正確或本機沒有這個計數器（400）
+    BadPath { status: u32, err: String },
+    Failed(String),
+}
+
+/// 先 collect 一次作為速率的基準點，之後每隔 interval_ms 讀一次，共 samples 筆；會阻塞
+pub fn sample_path(q: &PathQuery) -> Result<Vec<PathSample>, PathError> {
+    let samples = q.samples.unwrap_or(1).clamp(1, MAX_SAMPLES);
+    let interval = Duration::from_millis(
+        q.interval_ms
+            .unwrap_or(1_000)
+            .clamp(MIN_INTERVAL_MS, MAX_INTERVAL_MS),
+    );
+    let format = q.format.unwrap_or(Format::Double);
+    let wildcard = q.path.contains('*');
+
+    let query = Query::open().map_err(PathError::Failed)?;
+    let counter = query.add_status(&q.path).map_err(|status| PathError::BadPath {
+        status,
+        err: format!("PdhAddCounterW({}) failed: {status}", q.path),
+    })?;
+    query.collect().map_err(PathError::Failed)?;
+
+    let mut results = Vec::with_capacity(samples as usize);
+    for _ in 0..samples {
+        thread::sleep(interval);
+        query.collect().map_err(PathError::Failed)?;
+        let sample = if wildcard {
+            let values = counter.array(format).map_err(PathError::Failed)?;
+            PathSample::Instances(values.into_iter().collect())
+        } else {
+            PathSample::Single(counter.value(format).map_err(PathError::Failed)?)
+        };
+        results.push(sample);
+    }
+    Ok(results)
+}
@@ -0,0 +1,224 @@
+//! ICMP ping：IcmpSendEcho2（同步模式），只支援 IPv4
+//!
+//! - /api/v1/probes/ping?target=host&count=N 即時執行，放在 spawn_blocking 內
+//! - CAPTURE_PING_TARGETS 指定的目標由背景執行緒定期 ping，最新結果併入 /api/v1/metrics
+use serde::{Deserialize, Serialize};
+use std::{
+    net::{IpAddr, Ipv4Addr, ToSocketAddrs},
+    sync::{LazyLock, RwLock},
+    thread,
+    time::Duration,
+};
+use windows::Win32::{
+    Foundation::GetLastError,
+    NetworkManagement::IpHelper::{
+        IcmpCloseHandle, IcmpCreateFile, IcmpSendEcho2, ICMP_ECHO_REPLY,
+    },
+};
+
+use crate::{config, MetricError};
+
+/// 單一 echo request 的等待上限（毫秒）
+const REPLY_TIMEOUT_MS: u32 = 1_000;
+const DEFAULT_COUNT: u32 = 4;
+/// 避免 ?count= 過大讓請求掛很久
+const MAX_COUNT: u32 = 20;
+/// 背景 ping 設定目標的間隔
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(30);
+/// 與 Windows ping.exe 相同的 32 bytes 酬載
+const PAYLOAD: [u8; 32] = *b"abcdefghijklmnopqrstuvwabcdefghi";
+
+/// IP_STATUS
+const IP_SUCCESS: u32 = 0;
+
+static TARGETS: LazyLock<Vec<String>> = LazyLock::new(|| config::env_list("CAPTURE_PING_TARGETS"));
+static LATEST: RwLock<Vec<PingResult>> = RwLock::new(Vec::new());
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize, Clone)]
+pub struct PingReply {
+    pub seq: u32,
+    /// 逾時或不可達時為 None
+    pub rtt_ms: Option<u32>,
+    pub ttl: Option<u8>,
+    /// success / timed_out / dest_host_unreachable…
+    pub status: String,
+}
+
+#[derive(Serialize, Clone)]
+pub struct PingResult {
+    pub target: String,
+    /// 實際 ping 的 IPv4 位址；解析失敗時為 None
+    pub address: Option<String>,
+    pub sent: u32,
+    pub received: u32,
+    /// 0–1；目標不可達或解析失敗時為 1
+    pub loss_percent: f32,
+    pub min_ms: Option<u32>,
+    pub avg_ms: Option<f64>,
+    pub max_ms: Option<u32>,
+    pub replies: Vec<PingReply>,
+    pub error: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct PingQuery {
+    pub target: String,
+    pub count: Option<u32>,
+}
+
+/* ---------- 背景取樣 ---------- */
+
+pub fn start_sampler() {
+    if TARGETS.is_empty() {
+        return;
+    }
+    thread::spawn(|| loop {
+        let results: Vec<PingResult> = TARGETS.iter().map(|t| ping(t, None)).collect();
+        *LATEST.write().unwrap() = results;
+        thread::sleep(SAMPLE_INTERVAL);
+    });
+}
+
+/// 設定目標的最新結果；完全收不到回應的目標記一筆 error
+pub fn gather_ping(errors: &mut Vec<MetricError>) -> Vec<PingResult> {
+    let results = LATEST.read().unwrap().clone();
+    for r in &results {
+        if r.received == 0 {
+            errors.push(MetricError {
+                metric: vec![format!("probe.ping.{}", r.target)],
+                err: r
+                    .error
+                    .clone()
+                    .unwrap_or_else(|| format!("{} did not reply", r.target)),
+            });
+        }
+    }
+    results
+}
+
+/* ---------- ICMP ---------- */
+
+/// 會阻塞最多 count × REPLY_TIMEOUT_MS；async 呼叫端請放在 spawn_blocking 內
+pub fn ping(target: &str, count: Option<u32>) -> PingResult {
+    let count = count.unwrap_or(DEFAULT_COUNT).clamp(1, MAX_COUNT);
+    let mut result = PingResult {
+        target: target.into(),
+        address: None,
+        sent: 0,
+        received: 0,
+        loss_percent: 1.0,
+        min_ms: None,
+        avg_ms: None,
+        max_ms: None,
+        replies: Vec::new(),
+        error: None,
+    };
+
+    let addr = match resolve_v4(target) {
+        Ok(a) => a,
+        Err(e) => {
+            result.error = Some(e);
+            return result;
+        }
+    };
+    result.address = Some(addr.to_string());
+
+    let handle = match unsafe { IcmpCreateFile() } {
+        Ok(h) => h,
+        Err(e) => {
+            result.error = Some(format!("IcmpCreateFile failed: {e}"));
+            return result;
+        }
+    };
+
+    // 回覆緩衝區：ICMP_ECHO_REPLY + 酬載 + 8 bytes ICMP 錯誤訊息空間
+    let mut reply_buf =
+        vec![0u64; (std::mem::size_of::<ICMP_ECHO_REPLY>() + PAYLOAD.len() + 8).div_ceil(8)];
+    let dest = u32::from_ne_bytes(addr.octets());
+
+    for seq in 1..=count {
+        result.sent += 1;
+        let n = unsafe {
+            IcmpSendEcho2(
+                handle,
+                None,
+                None,
+                None,
+                dest,
+                PAYLOAD.as_ptr().cast(),
+                PAYLOAD.len() as u16,
+                None,
+                reply_buf.as_mut_ptr().cast(),
+                (reply_buf.len() * 8) as u32,
+                REPLY_TIMEOUT_MS,
+            )
+        };
+        let reply = unsafe { &*(reply_buf.as_ptr() as *const ICMP_ECHO_REPLY) };
+        if n > 0 && reply.Status == IP_SUCCESS {
+            result.received += 1;
+            result.replies.push(PingReply {
+                seq,
+                rtt_ms: Some(reply.RoundTripTime),
+                ttl: Some(reply.Options.Ttl),
+                status: "success".into(),
+            });
+        } else {
+            // 沒有回覆時狀態碼在 GetLastError，通常是 IP_REQ_TIMED_OUT
+            let status = if n > 0 {
+                reply.Status
+            } else {
+                unsafe { GetLastError() }.0
+            };
+            result.replies.push(PingReply {
+                seq,
+                rtt_ms: None,
+                ttl: None,
+                status: status_name(status).into(),
+            });
+        }
+    }
+    unsafe {
+        let _ = IcmpCloseHandle(handle);
+    }
+
+    let rtts: Vec<u32> = result.replies.iter().filter_map(|r| r.rtt_ms).collect();
+    result.loss_percent = 1.0 - result.received as f32 / result.sent as f32;
+    result.min_ms = rtts.iter().copied().min();
+    result.max_ms = rtts.iter().copied().max();
+    result.avg_ms =
+        (!rtts.is_empty()).then(|| rtts.iter().map(|&r| r as f64).sum::<f64>() / rtts.len() as f64);
+    result
+}
+
+fn resolve_v4(target: &str) -> Result<Ipv4Addr, String> {
+    if let Ok(ip) = target.parse::<IpAddr>() {
+        return match ip {
+            IpAddr::V4(v4) => Ok(v4),
+            IpAddr::V6(_) => Err("IPv6 targets are not supported".into()),
+        };
+    }
+    (target, 0)
+        .to_socket_addrs()
+        .map_err(|e| format!("resolve failed: {e}"))?
+        .find_map(|a| match a.ip() {
+            IpAddr::V4(v4) => Some(v4),
+            IpAddr::V6(_) => None,
+        })
+        .ok_or_else(|| format!("{target} has no IPv4 address"))
+}
+
+/// IP_STATUS（11000 起）→ 名稱
+fn status_name(status: u32) -> &'static str {
+    match status {
+        11002 => "dest_net_unreachable",
+        11003 => "dest_host_unreachable",
+        11004 => "dest_prot_unreachable",
+        11005 => "dest_port_unreachable",
+        11010 => "timed_out",
+        11013 => "ttl_expired_transit",
+        11050 => "general_failure",
+        _ => "error",
+    }
+}
@@ -0,0 +1,108 @@
+//! 平台安全：開機模式、Secure Boot 與 TPM（Windows 11 升級條件與資安稽核要看的三項）
+//!
+//! - 開機模式：GetFirmwareType
+//! - Secure Boot：SecureBoot\State 的 UEFISecureBootEnabled（作業系統開機時由韌體變數鏡像而來）；
+//!   legacy BIOS 沒有 Secure Boot，回報 false 並以 boot_mode 區分
+//! - TPM：WMI root\CIMV2\Security\MicrosoftTpm 的 Win32_Tpm，需要系統管理員權限
+use serde::Serialize;
+use windows::Win32::System::{
+    Registry::HKEY_LOCAL_MACHINE,
+    SystemInformation::{FirmwareTypeBios, FirmwareTypeUefi, GetFirmwareType, FIRMWARE_TYPE},
+};
+
+use crate::{win::reg_dword, wmi, MetricError};
+
+const SECURE_BOOT_KEY: &str = "SYSTEM\\CurrentControlSet\\Control\\SecureBoot\\State";
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize, Default)]
+pub struct PlatformSecurityData {
+    /// uefi / legacy；取不到時為 None
+    pub boot_mode: Option<&'static str>,
+    /// legacy BIOS 一律為 false
+    pub secure_boot_enabled: Option<bool>,
+    pub tpm_present: Option<bool>,
+    /// SpecVersion 的第一段，例如 `2.0`、`1.2`
+    pub tpm_version: Option<String>,
+    pub tpm_enabled: Option<bool>,
+    pub tpm_activated: Option<bool>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub async fn gather_platform_security(errors: &mut Vec<MetricError>) -> PlatformSecurityData {
+    let boot_mode = firmware_type();
+    let secure_boot_enabled = match boot_mode {
+        Some("legacy") => Some(false),
+        // 沒有這個值的 UEFI 機器是不支援 Secure Boot 的舊韌體
+        Some(_) => {
+            Some(reg_dword(HKEY_LOCAL_MACHINE, SECURE_BOOT_KEY, "UEFISecureBootEnabled") == Some(1))
+        }
+        None => None,
+    };
+    if boot_mode.is_none() {
+        errors.push(MetricError {
+            metric: vec!["platform_security.boot_mode".into()],
+            err: "GetFirmwareType failed".into(),
+        });
+    }
+
+    let mut data = PlatformSecurityData {
+        boot_mode,
+        secure_boot_enabled,
+        ..Default::default()
+    };
+    let tpm = tokio::task::spawn_blocking(query_tpm)
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+    match tpm {
+        Ok(Some(t)) => {
+            data.tpm_present = Some(true);
+            data.tpm_version = t.version;
+            data.tpm_enabled = t.enabled;
+            data.tpm_activated = t.activated;
+        }
+        Ok(None) => data.tpm_present = Some(false),
+        Err(err) => errors.push(MetricError {
+            metric: vec!["platform_security.tpm".into()],
+            err,
+        }),
+    }
+    data
+}
+
+fn firmware_type() -> Option<&'static str> {
+    let mut ty = FIRMWARE_TYPE::default();
+    unsafe { GetFirmwareType(&mut ty) }.ok()?;
+    if ty == FirmwareTypeUefi {
+        Some("uefi")
+    } else if ty == FirmwareTypeBios {
+        Some("legacy")
+    } else {
+        None
+    }
+}
+
+struct Tpm {
+    version: Option<String>,
+    enabled: Option<bool>,
+    activated: Option<bool>,
+}
+
+/// 沒有 TPM 時 Win32_Tpm 查得到類別但沒有任何物件，回傳 Ok(None)
+fn query_tpm() -> Result<Option<Tpm>, String> {
+    let rows = wmi::query(
+        "root\\CIMV2\\Security\\MicrosoftTpm",
+        "SELECT SpecVersion, IsEnabled_InitialValue, IsActivated_InitialValue FROM Win32_Tpm",
+    )
+    .map_err(|e| format!("{e} (reading Win32_Tpm requires administrator rights)"))?;
+    Ok(rows.first().map(|row| Tpm {
+        // 形如 `2.0, 0, 1.38`
+        version: row
+            .get_string("SpecVersion")
+            .and_then(|v| v.split(',').next().map(|s| s.trim().to_string())),
+        enabled: row.get_bool("IsEnabled_InitialValue"),
+        activated: row.get_bool("IsActivated_InitialValue"),
+    }))
+}
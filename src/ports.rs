@@ -0,0 +1,200 @@
+//! 監聽中的通訊埠：GetExtendedTcpTable / GetExtendedUdpTable（OWNER_PID），附上擁有行程
+use serde::{Deserialize, Serialize};
+use std::net::{Ipv4Addr, Ipv6Addr};
+use windows::Win32::{
+    NetworkManagement::IpHelper::{
+        GetExtendedTcpTable, GetExtendedUdpTable, MIB_TCP6ROW_OWNER_PID, MIB_TCPROW_OWNER_PID,
+        MIB_UDP6ROW_OWNER_PID, MIB_UDPROW_OWNER_PID, TCP_TABLE_OWNER_PID_LISTENER,
+        UDP_TABLE_OWNER_PID,
+    },
+    Networking::WinSock::{AF_INET, AF_INET6},
+};
+
+use crate::{
+    process::process_names,
+    win::{read_table, table_rows},
+    MetricError,
+};
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct ListeningPort {
+    /// tcp / udp
+    pub protocol: String,
+    pub local_address: String,
+    pub port: u16,
+    pub pid: u32,
+    /// 行程已結束或快照讀不到時為 None
+    pub process: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct PortQuery {
+    /// 指定時改回傳單一 PortCheck
+    pub port: Option<u16>,
+    /// tcp / udp；未指定時兩者皆查
+    pub protocol: Option<String>,
+}
+
+/// ?port=N 的精簡回應，給健康檢查用
+#[derive(Serialize)]
+pub struct PortCheck {
+    pub listening: bool,
+    pub pid: Option<u32>,
+    pub process: Option<String>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub fn gather_ports(query: &PortQuery, errors: &mut Vec<MetricError>) -> Vec<ListeningPort> {
+    let want = |proto: &str| {
+        query
+            .protocol
+            .as_deref()
+            .is_none_or(|p| p.eq_ignore_ascii_case(proto))
+    };
+
+    let mut tables = Vec::new();
+    if want("tcp") {
+        tables.push(("tcp4", tcp4_listeners()));
+        tables.push(("tcp6", tcp6_listeners()));
+    }
+    if want("udp") {
+        tables.push(("udp4", udp4_listeners()));
+        tables.push(("udp6", udp6_listeners()));
+    }
+
+    let mut ports = Vec::new();
+    for (key, result) in tables {
+        match result {
+            Ok(list) => ports.extend(list),
+            Err(e) => errors.push(MetricError {
+                metric: vec![format!("ports.{key}")],
+                err: e,
+            }),
+        }
+    }
+    if let Some(port) = query.port {
+        ports.retain(|p| p.port == port);
+    }
+
+    let names = process_names();
+    for p in &mut ports {
+        p.process = names.get(&p.pid).cloned();
+    }
+    ports.sort_by(|a, b| (a.port, &a.protocol).cmp(&(b.port, &b.protocol)));
+    ports
+}
+
+pub fn check_port(query: &PortQuery, errors: &mut Vec<MetricError>) -> PortCheck {
+    let first = gather_ports(query, errors).into_iter().next();
+    PortCheck {
+        listening: first.is_some(),
+        pid: first.as_ref().map(|p| p.pid),
+        process: first.and_then(|p| p.process),
+    }
+}
+
+/// dwLocalPort 只有低 16 位元有效，且為網路位元組順序
+fn port_of(raw: u32) -> u16 {
+    u16::from_be(raw as u16)
+}
+
+fn entry(protocol: &str, local_address: String, raw_port: u32, pid: u32) -> ListeningPort {
+    ListeningPort {
+        protocol: protocol.into(),
+        local_address,
+        port: port_of(raw_port),
+        pid,
+        process: None,
+    }
+}
+
+fn tcp4_listeners() -> Result<Vec<ListeningPort>, String> {
+    let buf = read_table(|p, size| unsafe {
+        GetExtendedTcpTable(
+            Some(p.cast()),
+            size,
+            false,
+            AF_INET.0 as u32,
+            TCP_TABLE_OWNER_PID_LISTENER,
+            0,
+        )
+    })
+    .map_err(|s| format!("GetExtendedTcpTable(AF_INET) failed: {s}"))?;
+    let rows = unsafe { table_rows::<MIB_TCPROW_OWNER_PID>(&buf) };
+    Ok(rows
+        .iter()
+        .map(|r| {
+            let addr = Ipv4Addr::from(r.dwLocalAddr.to_ne_bytes()).to_string();
+            entry("tcp", addr, r.dwLocalPort, r.dwOwningPid)
+        })
+        .collect())
+}
+
+fn tcp6_listeners() -> Result<Vec<ListeningPort>, String> {
+    let buf = read_table(|p, size| unsafe {
+        GetExtendedTcpTable(
+            Some(p.cast()),
+            size,
+            false,
+            AF_INET6.0 as u32,
+            TCP_TABLE_OWNER_PID_LISTENER,
+            0,
+        )
+    })
+    .map_err(|s| format!("GetExtendedTcpTable(AF_INET6) failed: {s}"))?;
+    let rows = unsafe { table_rows::<MIB_TCP6ROW_OWNER_PID>(&buf) };
+    Ok(rows
+        .iter()
+        .map(|r| {
+            let addr = Ipv6Addr::from(r.ucLocalAddr).to_string();
+            entry("tcp", addr, r.dwLocalPort, r.dwOwningPid)
+        })
+        .collect())
+}
+
+fn udp4_listeners() -> Result<Vec<ListeningPort>, String> {
+    let buf = read_table(|p, size| unsafe {
+        GetExtendedUdpTable(
+            Some(p.cast()),
+            size,
+            false,
+            AF_INET.0 as u32,
+            UDP_TABLE_OWNER_PID,
+            0,
+        )
+    })
+    .map_err(|s| format!("GetExtendedUdpTable(AF_INET) failed: {s}"))?;
+    let rows = unsafe { table_rows::<MIB_UDPROW_OWNER_PID>(&buf) };
+    Ok(rows
+        .iter()
+        .map(|r| {
+            let addr = Ipv4Addr::from(r.dwLocalAddr.to_ne_bytes()).to_string();
+            entry("udp", addr, r.dwLocalPort, r.dwOwningPid)
+        })
+        .collect())
+}
+
+fn udp6_listeners() -> Result<Vec<ListeningPort>, String> {
+    let buf = read_table(|p, size| unsafe {
+        GetExtendedUdpTable(
+            Some(p.cast()),
+            size,
+            false,
+            AF_INET6.0 as u32,
+            UDP_TABLE_OWNER_PID,
+            0,
+        )
+    })
+    .map_err(|s| format!("GetExtendedUdpTable(AF_INET6) failed: {s}"))?;
+    let rows = unsafe { table_rows::<MIB_UDP6ROW_OWNER_PID>(&buf) };
+    Ok(rows
+        .iter()
+        .map(|r| {
+            let addr = Ipv6Addr::from(r.ucLocalAddr).to_string();
+            entry("udp", addr, r.dwLocalPort, r.dwOwningPid)
+        })
+        .collect())
+}
@@ -0,0 +1,161 @@
+//! 電源狀態：GetSystemPowerStatus，給筆電 kiosk 與接 UPS 的主機使用
+//! 電源計畫：PowerGetActiveScheme + PowerReadFriendlyName
+use serde::Serialize;
+use windows::{
+    core::GUID,
+    Win32::{
+        Foundation::{LocalFree, HLOCAL},
+        System::{
+            Power::{
+                GetSystemPowerStatus, PowerGetActiveScheme, PowerReadFriendlyName,
+                SYSTEM_POWER_STATUS,
+            },
+            Registry::HKEY,
+        },
+    },
+};
+
+use crate::{win::wide_to_string, MetricError};
+
+/// BatteryFlag 位元
+const BATTERY_CHARGING: u8 = 8;
+const BATTERY_NONE: u8 = 128;
+const BATTERY_UNKNOWN: u8 = 255;
+
+/// 內建電源計畫的固定 GUID → 正規化名稱（friendly name 會隨語系變動，不適合拿來告警）
+const KNOWN_PLANS: &[(GUID, &str)] = &[
+    (
+        GUID::from_u128(0x381b4222_f694_41f0_9685_ff5bb260df2e),
+        "balanced",
+    ),
+    (
+        GUID::from_u128(0x8c5e7fda_e8bf_4a96_9a85_a6e23a8c635c),
+        "high_performance",
+    ),
+    (
+        GUID::from_u128(0xa1841308_3541_4fab_bc81_f71556f20b4a),
+        "power_saver",
+    ),
+    (
+        GUID::from_u128(0xe9a42b02_d5df_448d_aa00_03f14749eb61),
+        "ultimate_performance",
+    ),
+];
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct PowerPlanData {
+    pub guid: String,
+    /// 本地化的顯示名稱
+    pub name: Option<String>,
+    /// balanced / high_performance / power_saver / ultimate_performance / custom
+    pub kind: String,
+}
+
+#[derive(Serialize)]
+pub struct PowerData {
+    pub on_ac_power: bool,
+    /// 0–1；沒有電池時為 None
+    pub battery_percent: Option<f32>,
+    /// charging / discharging / full
+    pub battery_status: Option<String>,
+    /// 只有在放電中且系統能估算時才有值
+    pub estimated_runtime_seconds: Option<u32>,
+    pub power_plan: Option<PowerPlanData>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub fn gather_power(errors: &mut Vec<MetricError>) -> PowerData {
+    let power_plan = match query_power_plan() {
+        Ok(p) => Some(p),
+        Err(e) => {
+            errors.push(MetricError {
+                metric: vec!["power.power_plan".into()],
+                err: e,
+            });
+            None
+        }
+    };
+
+    let mut st = SYSTEM_POWER_STATUS::default();
+    if let Err(e) = unsafe { GetSystemPowerStatus(&mut st) } {
+        errors.push(MetricError {
+            metric: vec!["power".into()],
+            err: format!("GetSystemPowerStatus failed: {e}"),
+        });
+        // 讀不到時當作桌機：插電、無電池
+        st.ACLineStatus = 1;
+        st.BatteryFlag = BATTERY_NONE;
+    }
+
+    // ACLineStatus：0 = 離線、1 = 插電、255 = 未知（桌機常見，視為插電）
+    let on_ac_power = st.ACLineStatus != 0;
+    let has_battery = st.BatteryFlag & BATTERY_NONE == 0 && st.BatteryFlag != BATTERY_UNKNOWN;
+
+    if !has_battery {
+        return PowerData {
+            on_ac_power,
+            battery_percent: None,
+            battery_status: None,
+            estimated_runtime_seconds: None,
+            power_plan,
+        };
+    }
+
+    let percent = (st.BatteryLifePercent <= 100).then(|| st.BatteryLifePercent as f32 / 100.0);
+    let status = if st.BatteryFlag & BATTERY_CHARGING != 0 {
+        "charging"
+    } else if on_ac_power {
+        "full"
+    } else {
+        "discharging"
+    };
+
+    PowerData {
+        on_ac_power,
+        battery_percent: percent,
+        battery_status: Some(status.into()),
+        // u32::MAX 代表無法估算（插電時也是）
+        estimated_runtime_seconds: (st.BatteryLifeTime != u32::MAX).then_some(st.BatteryLifeTime),
+        power_plan,
+    }
+}
+
+fn query_power_plan() -> Result<PowerPlanData, String> {
+    unsafe {
+        let mut ptr: *mut GUID = std::ptr::null_mut();
+        let status = PowerGetActiveScheme(HKEY::default(), &mut ptr);
+        if status.is_err() {
+            return Err(format!("PowerGetActiveScheme failed: {}", status.0));
+        }
+        let guid = *ptr;
+        LocalFree(HLOCAL(ptr.cast()));
+
+        // 先問長度，再讀 UTF-16 字串
+        let mut size = 0u32;
+        let _ = PowerReadFriendlyName(HKEY::default(), Some(&guid), None, None, None, &mut size);
+        let mut buf = vec![0u16; size as usize / 2 + 1];
+        let status = PowerReadFriendlyName(
+            HKEY::default(),
+            Some(&guid),
+            None,
+            None,
+            Some(buf.as_mut_ptr().cast()),
+            &mut size,
+        );
+        let name = status.is_ok().then(|| wide_to_string(&buf));
+
+        let kind = KNOWN_PLANS
+            .iter()
+            .find(|(g, _)| *g == guid)
+            .map_or("custom", |(_, k)| k);
+
+        Ok(PowerPlanData {
+            guid: format!("{guid:?}").to_lowercase(),
+            name,
+            kind: kind.into(),
+        })
+    }
+}
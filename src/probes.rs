@@ -0,0 +1,239 @@
+//! 主動探測：每次請求時即時執行，各目標並行且有逾時上限
+//!
+//! - DNS：CAPTURE_DNS_PROBES 指定的主機名稱，以系統解析器（getaddrinfo）解析
+//! - TCP：CAPTURE_TCP_PROBES 指定的 host:port，只做三向交握，連上即關閉
+//! - HTTP(S)：CAPTURE_HTTP_PROBES 指定的 URL，見 httpprobe
+use serde::Serialize;
+use std::{
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+use tokio::{
+    net::{lookup_host, TcpStream},
+    time::timeout,
+};
+
+use crate::{
+    config,
+    httpprobe::{self, HttpProbe},
+    MetricError,
+};
+
+/// 單一探測的時間上限；壞掉的 DNS 伺服器不會拖慢整個端點
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+static DNS_PROBES: LazyLock<Vec<String>> = LazyLock::new(|| config::env_list("CAPTURE_DNS_PROBES"));
+static TCP_PROBES: LazyLock<Vec<String>> = LazyLock::new(|| config::env_list("CAPTURE_TCP_PROBES"));
+/// TCP 連線（含名稱解析）的時間上限；防火牆直接丟棄 SYN 時不會收到 RST，只能等逾時
+static TCP_TIMEOUT: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(config::env_parse("CAPTURE_TCP_PROBE_TIMEOUT_SECS", 3u64))
+});
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct DnsProbe {
+    pub host: String,
+    pub resolved: bool,
+    pub addresses: Vec<String>,
+    /// 失敗時為到失敗（或逾時）為止的時間
+    pub latency_ms: f64,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct TcpProbe {
+    /// 設定中的 host:port，IPv6 位址需加方括號，例如 `[::1]:1433`
+    pub target: String,
+    pub reachable: bool,
+    /// 完成三向交握的時間；連不上時為 None
+    pub connect_latency_ms: Option<f64>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ProbeData {
+    pub dns: Vec<DnsProbe>,
+    pub tcp: Vec<TcpProbe>,
+    pub http: Vec<HttpProbe>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub async fn gather_probes(errors: &mut Vec<MetricError>) -> ProbeData {
+    // 各組同時進行
+    let ((dns, mut dns_errors), (tcp, mut tcp_errors), (http, mut http_errors)) =
+        tokio::join!(gather_dns(), gather_tcp(), gather_http());
+    errors.append(&mut dns_errors);
+    errors.append(&mut tcp_errors);
+    errors.append(&mut http_errors);
+    ProbeData { dns, tcp, http }
+}
+
+async fn gather_dns() -> (Vec<DnsProbe>, Vec<MetricError>) {
+    // 先全部 spawn 再依序 await，總耗時取決於最慢的一個
+    let tasks: Vec<_> = DNS_PROBES
+        .iter()
+        .map(|host| tokio::spawn(probe_dns(host.clone())))
+        .collect();
+
+    let mut errors = Vec::new();
+    let mut probes = Vec::with_capacity(tasks.len());
+    for (task, host) in tasks.into_iter().zip(DNS_PROBES.iter()) {
+        let probe = task.await.unwrap_or_else(|e| DnsProbe {
+            host: host.clone(),
+            resolved: false,
+            addresses: Vec::new(),
+            latency_ms: 0.0,
+            error: Some(e.to_string()),
+        });
+        if let Some(e) = &probe.error {
+            errors.push(MetricError {
+                metric: vec![format!("probe.dns.{host}")],
+                err: e.clone(),
+            });
+        }
+        probes.push(probe);
+    }
+    (probes, errors)
+}
+
+async fn probe_dns(host: String) -> DnsProbe {
+    let started = Instant::now();
+    // lookup_host 需要 host:port，port 不影響解析結果
+    let result = timeout(PROBE_TIMEOUT, lookup_host((host.as_str(), 0)))
+        .await
+        .map(|r| r.map(|addrs| addrs.map(|a| a.ip().to_string()).collect::<Vec<_>>()));
+    let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+    let (addresses, error) = match result {
+        Ok(Ok(mut list)) => {
+            list.dedup();
+            if list.is_empty() {
+                (list, Some(format!("{host} resolved to no addresses")))
+            } else {
+                (list, None)
+            }
+        }
+        Ok(Err(e)) => (Vec::new(), Some(format!("resolve failed: {e}"))),
+        Err(_) => (
+            Vec::new(),
+            Some(format!("timed out after {}s", PROBE_TIMEOUT.as_secs())),
+        ),
+    };
+
+    DnsProbe {
+        host,
+        resolved: error.is_none(),
+        addresses,
+        latency_ms,
+        error,
+    }
+}
+
+async fn gather_tcp() -> (Vec<TcpProbe>, Vec<MetricError>) {
+    let tasks: Vec<_> = TCP_PROBES
+        .iter()
+        .map(|target| tokio::spawn(probe_tcp(target.clone())))
+        .collect();
+
+    let mut errors = Vec::new();
+    let mut probes = Vec::with_capacity(tasks.len());
+    for (task, target) in tasks.into_iter().zip(TCP_PROBES.iter()) {
+        let probe = task.await.unwrap_or_else(|e| TcpProbe {
+            target: target.clone(),
+            reachable: false,
+            connect_latency_ms: None,
+            error: Some(e.to_string()),
+        });
+        if let Some(e) = &probe.error {
+            errors.push(MetricError {
+                metric: vec![format!("probe.tcp.{target}")],
+                err: e.clone(),
+            });
+        }
+        probes.push(probe);
+    }
+    (probes, errors)
+}
+
+async fn probe_tcp(target: String) -> TcpProbe {
+    let error = if valid_target(&target) {
+        let started = Instant::now();
+        match timeout(*TCP_TIMEOUT, TcpStream::connect(target.as_str())).await {
+            Ok(Ok(_stream)) => {
+                return TcpProbe {
+                    target,
+                    reachable: true,
+                    connect_latency_ms: Some(started.elapsed().as_secs_f64() * 1000.0),
+                    error: None,
+                };
+            }
+            Ok(Err(e)) => format!("connect failed: {e}"),
+            Err(_) => format!("timed out after {}s", TCP_TIMEOUT.as_secs()),
+        }
+    } else {
+        format!("{target} is not in host:port form")
+    };
+    TcpProbe {
+        target,
+        reachable: false,
+        connect_latency_ms: None,
+        error: Some(error),
+    }
+}
+
+/// 設定錯誤（少了 port）在連線前就擋下，錯誤訊息比解析失敗清楚
+fn valid_target(target: &str) -> bool {
+    target
+        .rsplit_once(':')
+        .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok())
+}
+
+async fn gather_http() -> (Vec<HttpProbe>, Vec<MetricError>) {
+    let mut errors = Vec::new();
+    let mut targets = Vec::new();
+    for target in httpprobe::TARGETS.iter() {
+        match target {
+            Ok(target) => targets.push(target),
+            Err((entry, e)) => errors.push(MetricError {
+                metric: vec![format!("probe.http.{entry}")],
+                err: format!("invalid CAPTURE_HTTP_PROBES entry: {e}"),
+            }),
+        }
+    }
+
+    // WinHTTP 同步呼叫會阻塞，每個目標各佔一個 blocking 執行緒
+    let tasks: Vec<_> = targets
+        .iter()
+        .map(|&target| tokio::task::spawn_blocking(move || httpprobe::probe(target)))
+        .collect();
+
+    let mut probes = Vec::with_capacity(tasks.len());
+    for (task, target) in tasks.into_iter().zip(targets) {
+        let probe = task.await.unwrap_or_else(|e| HttpProbe {
+            url: target.url.clone(),
+            method: target.method.clone(),
+            status_code: None,
+            latency_ms: 0.0,
+            tls_days_until_expiry: None,
+            body_matched: None,
+            healthy: false,
+            error: Some(e.to_string()),
+        });
+        if !probe.healthy {
+            errors.push(MetricError {
+                metric: vec![format!("probe.http.{}", target.url)],
+                err: probe
+                    .error
+                    .clone()
+                    .unwrap_or_else(|| match probe.body_matched {
+                        Some(false) => "response did not contain the expected text".into(),
+                        _ => format!("HTTP {}", probe.status_code.unwrap_or_default()),
+                    }),
+            });
+        }
+        probes.push(probe);
+    }
+    (probes, errors)
+}
@@ -0,0 +1,571 @@
+//! 行程清單：背景執行緒定期 refresh 共用的 System，CPU 使用率即兩次 refresh 間的平均
+//!
+//! 磁碟 I/O 另以 GetProcessIoCounters 讀取（sysinfo 讀不到時只會回 0，分不出權限不足），
+//! 同樣在背景執行緒取樣，速率為兩次取樣間的差值
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+use sysinfo::{
+    CpuRefreshKind, Pid, ProcessRefreshKind, ProcessesToUpdate, RefreshKind, System, UpdateKind,
+};
+use windows::{
+    core::{PCWSTR, PWSTR},
+    Win32::{
+        Foundation::HANDLE,
+        Security::{
+            GetTokenInformation, LookupAccountSidW, TokenUser, SID_NAME_USE, TOKEN_QUERY,
+            TOKEN_USER,
+        },
+        System::{
+            Diagnostics::ToolHelp::{
+                CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W,
+                TH32CS_SNAPPROCESS,
+            },
+            ProcessStatus::{
+                GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS, PROCESS_MEMORY_COUNTERS_EX,
+            },
+            Threading::{
+                GetProcessHandleCount, GetProcessIoCounters, OpenProcess, OpenProcessToken,
+                QueryFullProcessImageNameW, IO_COUNTERS, PROCESS_NAME_WIN32,
+                PROCESS_QUERY_LIMITED_INFORMATION,
+            },
+        },
+    },
+};
+
+use crate::{
+    config,
+    win::{wide_to_string, OwnedHandle},
+    MetricError,
+};
+
+/// 背景 refresh 間隔，也就是 cpu_percent 的取樣區間
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(2);
+
+const DEFAULT_LIMIT: usize = 20;
+
+/// 只載入 CPU 清單（行程 CPU 使用率換算需要核心數），行程由背景執行緒填入
+static SYSTEM: LazyLock<Mutex<System>> = LazyLock::new(|| {
+    Mutex::new(System::new_with_specifics(
+        RefreshKind::nothing().with_cpu(CpuRefreshKind::nothing()),
+    ))
+});
+
+/// PID → 最近一次的 I/O 取樣；受保護行程開不了的不在表中
+static IO: LazyLock<Mutex<HashMap<u32, IoSample>>> = LazyLock::new(Default::default);
+
+#[derive(Clone, Copy)]
+struct IoSample {
+    read_bytes: u64,
+    write_bytes: u64,
+    at: Instant,
+    /// 第一次取樣時為 None
+    read_bytes_per_sec: Option<f64>,
+    write_bytes_per_sec: Option<f64>,
+}
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct ProcessData {
+    pub pid: u32,
+    pub name: String,
+    /// 佔整台機器 CPU 的比例（0–1），與 CPUData.usage_percent 同尺度
+    pub cpu_percent: f32,
+    pub memory_bytes: u64,
+    pub status: String,
+    /// Unix epoch 秒
+    pub start_time: u64,
+    /// 行程啟動以來的累計讀寫量（含網路與裝置 I/O，不只磁碟）；受保護行程為 None
+    pub io_read_bytes: Option<u64>,
+    pub io_write_bytes: Option<u64>,
+    /// 最近兩次背景取樣間的速率
+    pub io_read_bytes_per_sec: Option<f64>,
+    pub io_write_bytes_per_sec: Option<f64>,
+}
+
+#[derive(Deserialize, Default, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    #[default]
+    Cpu,
+    Memory,
+    /// 依讀取速率排序，還沒有速率時依累計量
+    IoRead,
+    IoWrite,
+}
+
+#[derive(Deserialize, Default)]
+pub struct ProcessQuery {
+    #[serde(default)]
+    pub sort: SortKey,
+    pub limit: Option<usize>,
+}
+
+/* ---------- 背景取樣 ---------- */
+
+pub fn start_sampler() {
+    thread::spawn(|| loop {
+        let pids: Vec<u32> = {
+            let mut sys = SYSTEM.lock().unwrap();
+            sys.refresh_processes_specifics(
+                ProcessesToUpdate::All,
+                true,
+                ProcessRefreshKind::nothing().with_cpu().with_memory(),
+            );
+            sys.processes().keys().map(|p| p.as_u32()).collect()
+        };
+        sample_io(&pids);
+        thread::sleep(SAMPLE_INTERVAL);
+    });
+}
+
+fn sample_io(pids: &[u32]) {
+    let previous = std::mem::take(&mut *IO.lock().unwrap());
+    let mut current = HashMap::with_capacity(pids.len());
+    for &pid in pids {
+        let Some((read_bytes, write_bytes)) = io_counters(pid) else {
+            continue;
+        };
+        let at = Instant::now();
+        // 累計值變小代表 PID 已被新行程重用，這一輪不算速率
+        let rate = |now: u64, before: u64, prev: &IoSample| {
+            let secs = (at - prev.at).as_secs_f64();
+            (now >= before && secs > 0.0).then(|| (now - before) as f64 / secs)
+        };
+        let prev = previous.get(&pid);
+        current.insert(
+            pid,
+            IoSample {
+                read_bytes,
+                write_bytes,
+                at,
+                read_bytes_per_sec: prev.and_then(|p| rate(read_bytes, p.read_bytes, p)),
+                write_bytes_per_sec: prev.and_then(|p| rate(write_bytes, p.write_bytes, p)),
+            },
+        );
+    }
+    *IO.lock().unwrap() = current;
+}
+
+/// (ReadTransferCount, WriteTransferCount)；開不了行程（受保護、已結束）時為 None
+fn io_counters(pid: u32) -> Option<(u64, u64)> {
+    let handle = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) }
+        .ok()
+        .map(OwnedHandle)?;
+    let mut io = IO_COUNTERS::default();
+    unsafe { GetProcessIoCounters(handle.0, &mut io) }.ok()?;
+    Some((io.ReadTransferCount, io.WriteTransferCount))
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub fn gather_processes(query: &ProcessQuery) -> Vec<ProcessData> {
+    let sys = SYSTEM.lock().unwrap();
+    let io = IO.lock().unwrap();
+    let cpus = sys.cpus().len().max(1) as f32;
+
+    let mut list: Vec<ProcessData> = sys
+        .processes()
+        .values()
+        .map(|p| {
+            let pid = p.pid().as_u32();
+            let io = io.get(&pid);
+            ProcessData {
+                pid,
+                name: p.name().to_string_lossy().into_owned(),
+                cpu_percent: p.cpu_usage() / 100.0 / cpus,
+                memory_bytes: p.memory(),
+                status: p.status().to_string(),
+                start_time: p.start_time(),
+                io_read_bytes: io.map(|s| s.read_bytes),
+                io_write_bytes: io.map(|s| s.write_bytes),
+                io_read_bytes_per_sec: io.and_then(|s| s.read_bytes_per_sec),
+                io_write_bytes_per_sec: io.and_then(|s| s.write_bytes_per_sec),
+            }
+        })
+        .collect();
+    drop(io);
+    drop(sys);
+
+    // 讀不到 I/O 的行程（None）排在最後
+    let io_key = |rate: Option<f64>, total: Option<u64>| {
+        (rate.unwrap_or(-1.0), total.map_or(-1.0, |t| t as f64))
+    };
+    match query.sort {
+        SortKey::Cpu => list.sort_by(|a, b| b.cpu_percent.total_cmp(&a.cpu_percent)),
+        SortKey::Memory => list.sort_by_key(|p| std::cmp::Reverse(p.memory_bytes)),
+        SortKey::IoRead => list.sort_by(|a, b| {
+            let (ka, kb) = (
+                io_key(a.io_read_bytes_per_sec, a.io_read_bytes),
+                io_key(b.io_read_bytes_per_sec, b.io_read_bytes),
+            );
+            kb.0.total_cmp(&ka.0).then(kb.1.total_cmp(&ka.1))
+        }),
+        SortKey::IoWrite => list.sort_by(|a, b| {
+            let (ka, kb) = (
+                io_key(a.io_write_bytes_per_sec, a.io_write_bytes),
+                io_key(b.io_write_bytes_per_sec, b.io_write_bytes),
+            );
+            kb.0.total_cmp(&ka.0).then(kb.1.total_cmp(&ka.1))
+        }),
+    }
+    list.truncate(query.limit.unwrap_or(DEFAULT_LIMIT));
+    list
+}
+
+/// 同名行程的資源使用；命令列讀不到（權限不足）時 args 為空
+pub struct ProcessUsage {
+    pub pid: u32,
+    pub cpu_percent: f32,
+    pub memory_bytes: u64,
+    /// Unix epoch 秒
+    pub start_time: u64,
+    /// 命令列參數，第一個是執行檔
+    pub args: Vec<String>,
+}
+
+/// 以執行檔名稱（不分大小寫，例如 `w3wp.exe`）挑出行程；CPU / 記憶體取自背景取樣
+pub fn usage_by_name(name: &str) -> Vec<ProcessUsage> {
+    let sys = SYSTEM.lock().unwrap();
+    let cpus = sys.cpus().len().max(1) as f32;
+    let mut list: Vec<ProcessUsage> = sys
+        .processes()
+        .values()
+        .filter(|p| p.name().to_string_lossy().eq_ignore_ascii_case(name))
+        .map(|p| ProcessUsage {
+            pid: p.pid().as_u32(),
+            cpu_percent: p.cpu_usage() / 100.0 / cpus,
+            memory_bytes: p.memory(),
+            start_time: p.start_time(),
+            args: Vec::new(),
+        })
+        .collect();
+    drop(sys);
+
+    // 背景取樣不讀命令列（要讀每個行程的 PEB），只替挑出來的行程另外讀
+    let pids: Vec<Pid> = list.iter().map(|p| Pid::from_u32(p.pid)).collect();
+    let mut sys = System::new();
+    sys.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&pids),
+        false,
+        ProcessRefreshKind::nothing().with_cmd(UpdateKind::Always),
+    );
+    for usage in &mut list {
+        if let Some(p) = sys.process(Pid::from_u32(usage.pid)) {
+            usage.args = p
+                .cmd()
+                .iter()
+                .map(|a| a.to_string_lossy().into_owned())
+                .collect();
+        }
+    }
+    list
+}
+
+/* ---------- 監看清單 ---------- */
+
+/// CAPTURE_WATCH_PROCESSES：逗號分隔的 `name` 或 `name|命令列子字串`，例如 `sqlservr.exe,java.exe|kafka`
+static WATCHED: LazyLock<Vec<(String, Option<String>)>> = LazyLock::new(|| {
+    config::env_list("CAPTURE_WATCH_PROCESSES")
+        .iter()
+        .map(|entry| match entry.split_once('|') {
+            Some((name, pattern)) => (
+                name.trim().to_string(),
+                Some(pattern.trim().to_lowercase()).filter(|p| !p.is_empty()),
+            ),
+            None => (entry.clone(), None),
+        })
+        .collect()
+});
+
+#[derive(Serialize)]
+pub struct WatchedProcessData {
+    /// 設定中的執行檔名稱
+    pub name: String,
+    /// 命令列必須包含的子字串（不分大小寫）；命令列讀不到的行程不算符合
+    pub command_line_contains: Option<String>,
+    pub running: bool,
+    pub instance_count: usize,
+    pub total_memory_bytes: u64,
+    /// 最早啟動的那一個，Unix epoch 秒；沒有執行時為 None
+    pub oldest_start_time: Option<u64>,
+}
+
+/// 只回報監看清單中的行程；沒有在執行的各記一筆 error
+pub fn gather_watched_processes(errors: &mut Vec<MetricError>) -> Vec<WatchedProcessData> {
+    WATCHED
+        .iter()
+        .map(|(name, pattern)| {
+            let matched: Vec<ProcessUsage> = usage_by_name(name)
+                .into_iter()
+                .filter(|p| {
+                    pattern
+                        .as_ref()
+                        .is_none_or(|pat| p.args.join(" ").to_lowercase().contains(pat.as_str()))
+                })
+                .collect();
+            if matched.is_empty() {
+                let label = match pattern {
+                    Some(pat) => format!("{name} (command line containing \"{pat}\")"),
+                    None => name.clone(),
+                };
+                errors.push(MetricError {
+                    metric: vec![format!("watched_processes.{name}")],
+                    err: format!("process {label} is not running"),
+                });
+            }
+            WatchedProcessData {
+                name: name.clone(),
+                command_line_contains: pattern.clone(),
+                running: !matched.is_empty(),
+                instance_count: matched.len(),
+                total_memory_bytes: matched.iter().map(|p| p.memory_bytes).sum(),
+                oldest_start_time: matched.iter().map(|p| p.start_time).min(),
+            }
+        })
+        .collect()
+}
+
+/* ---------- 單一行程細節 ---------- */
+
+#[derive(Serialize)]
+pub struct ProcessDetail {
+    pub pid: u32,
+    pub name: String,
+    pub command_line: Option<String>,
+    pub exe_path: Option<String>,
+    pub parent_pid: Option<u32>,
+    pub working_set_bytes: Option<u64>,
+    pub private_bytes: Option<u64>,
+    pub handle_count: Option<u32>,
+    pub thread_count: Option<u32>,
+    pub user_name: Option<String>,
+    /// Unix epoch 秒
+    pub start_time: Option<u64>,
+}
+
+/// Toolhelp 快照中的一列：行程存在與否、父行程、執行緒數都從這裡來
+struct SnapshotEntry {
+    name: String,
+    parent_pid: u32,
+    threads: u32,
+}
+
+/// PID 不存在時回傳 None；受保護行程讀不到的欄位填 None 並記錄在 errors
+pub fn gather_process_detail(pid: u32, errors: &mut Vec<MetricError>) -> Option<ProcessDetail> {
+    let entry = find_in_snapshot(pid)?;
+
+    let mut detail = ProcessDetail {
+        pid,
+        name: entry.name,
+        command_line: None,
+        exe_path: None,
+        parent_pid: Some(entry.parent_pid),
+        working_set_bytes: None,
+        private_bytes: None,
+        handle_count: None,
+        thread_count: Some(entry.threads),
+        user_name: None,
+        start_time: None,
+    };
+
+    let mut fail = |field: &str, err: String| {
+        errors.push(MetricError {
+            metric: vec![format!("process.{field}")],
+            err,
+        });
+    };
+
+    // 命令列要讀 PEB，交給 sysinfo；讀不到時它只會回空陣列
+    let mut sys = System::new();
+    let spid = Pid::from_u32(pid);
+    sys.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[spid]),
+        false,
+        ProcessRefreshKind::nothing().with_cmd(UpdateKind::Always),
+    );
+    if let Some(p) = sys.process(spid) {
+        detail.start_time = Some(p.start_time());
+        let cmd: Vec<String> = p
+            .cmd()
+            .iter()
+            .map(|a| a.to_string_lossy().into_owned())
+            .collect();
+        if cmd.is_empty() {
+            fail(
+                "command_line",
+                "command line unavailable (access denied)".into(),
+            );
+        } else {
+            detail.command_line = Some(cmd.join(" "));
+        }
+    } else {
+        fail("start_time", "process not visible to sysinfo".into());
+    }
+
+    let handle = match unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, pid) } {
+        Ok(h) => OwnedHandle(h),
+        Err(e) => {
+            let err = format!("OpenProcess failed: {e}");
+            for field in [
+                "exe_path",
+                "working_set_bytes",
+                "private_bytes",
+                "handle_count",
+                "user_name",
+            ] {
+                fail(field, err.clone());
+            }
+            return Some(detail);
+        }
+    };
+
+    match query_image_path(&handle) {
+        Ok(v) => detail.exe_path = Some(v),
+        Err(e) => fail("exe_path", e),
+    }
+
+    let mut mem = PROCESS_MEMORY_COUNTERS_EX {
+        cb: std::mem::size_of::<PROCESS_MEMORY_COUNTERS_EX>() as u32,
+        ..Default::default()
+    };
+    match unsafe {
+        GetProcessMemoryInfo(
+            handle.0,
+            &mut mem as *mut _ as *mut PROCESS_MEMORY_COUNTERS,
+            mem.cb,
+        )
+    } {
+        Ok(()) => {
+            detail.working_set_bytes = Some(mem.WorkingSetSize as u64);
+            detail.private_bytes = Some(mem.PrivateUsage as u64);
+        }
+        Err(e) => {
+            let err = format!("GetProcessMemoryInfo failed: {e}");
+            fail("working_set_bytes", err.clone());
+            fail("private_bytes", err);
+        }
+    }
+
+    let mut handles = 0u32;
+    match unsafe { GetProcessHandleCount(handle.0, &mut handles) } {
+        Ok(()) => detail.handle_count = Some(handles),
+        Err(e) => fail("handle_count", format!("GetProcessHandleCount failed: {e}")),
+    }
+
+    match query_user_name(&handle) {
+        Ok(v) => detail.user_name = Some(v),
+        Err(e) => fail("user_name", e),
+    }
+
+    Some(detail)
+}
+
+fn find_in_snapshot(pid: u32) -> Option<SnapshotEntry> {
+    let mut found = None;
+    walk_snapshot(|entry| {
+        if entry.th32ProcessID != pid {
+            return true;
+        }
+        found = Some(SnapshotEntry {
+            name: wide_to_string(&entry.szExeFile),
+            parent_pid: entry.th32ParentProcessID,
+            threads: entry.cntThreads,
+        });
+        false
+    });
+    found
+}
+
+/// PID → 執行檔名稱，給需要一次對應大量 PID 的呼叫端（例如通訊埠清單）
+pub fn process_names() -> HashMap<u32, String> {
+    let mut names = HashMap::new();
+    walk_snapshot(|entry| {
+        names.insert(entry.th32ProcessID, wide_to_string(&entry.szExeFile));
+        true
+    });
+    names
+}
+
+/// 逐筆走訪 Toolhelp 行程快照；`f` 回傳 false 時提前結束
+fn walk_snapshot(mut f: impl FnMut(&PROCESSENTRY32W) -> bool) {
+    let Ok(snap) = (unsafe { CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) }) else {
+        return;
+    };
+    let snap = OwnedHandle(snap);
+    let mut entry = PROCESSENTRY32W {
+        dwSize: std::mem::size_of::<PROCESSENTRY32W>() as u32,
+        ..Default::default()
+    };
+
+    let mut ok = unsafe { Process32FirstW(snap.0, &mut entry) }.is_ok();
+    while ok && f(&entry) {
+        ok = unsafe { Process32NextW(snap.0, &mut entry) }.is_ok();
+    }
+}
+
+fn query_image_path(handle: &OwnedHandle) -> Result<String, String> {
+    let mut buf = [0u16; 1024];
+    let mut len = buf.len() as u32;
+    unsafe {
+        QueryFullProcessImageNameW(
+            handle.0,
+            PROCESS_NAME_WIN32,
+            PWSTR(buf.as_mut_ptr()),
+            &mut len,
+        )
+    }
+    .map_err(|e| format!("QueryFullProcessImageNameW failed: {e}"))?;
+    Ok(String::from_utf16_lossy(&buf[..len as usize]))
+}
+
+/// 行程 token 的擁有者，格式為 DOMAIN\user
+fn query_user_name(handle: &OwnedHandle) -> Result<String, String> {
+    unsafe {
+        let mut token = HANDLE::default();
+        OpenProcessToken(handle.0, TOKEN_QUERY, &mut token)
+            .map_err(|e| format!("OpenProcessToken failed: {e}"))?;
+        let token = OwnedHandle(token);
+
+        // 先問長度再讀；第一次呼叫必定回 ERROR_INSUFFICIENT_BUFFER
+        let mut len = 0u32;
+        let _ = GetTokenInformation(token.0, TokenUser, None, 0, &mut len);
+        let mut buf = vec![0u8; len as usize];
+        GetTokenInformation(
+            token.0,
+            TokenUser,
+            Some(buf.as_mut_ptr().cast()),
+            len,
+            &mut len,
+        )
+        .map_err(|e| format!("GetTokenInformation failed: {e}"))?;
+        let user = &*(buf.as_ptr() as *const TOKEN_USER);
+
+        let mut name = [0u16; 256];
+        let mut domain = [0u16; 256];
+        let mut name_len = name.len() as u32;
+        let mut domain_len = domain.len() as u32;
+        let mut sid_use = SID_NAME_USE::default();
+        LookupAccountSidW(
+            PCWSTR::null(),
+            user.User.Sid,
+            PWSTR(name.as_mut_ptr()),
+            &mut name_len,
+            PWSTR(domain.as_mut_ptr()),
+            &mut domain_len,
+            &mut sid_use,
+        )
+        .map_err(|e| format!("LookupAccountSidW failed: {e}"))?;
+
+        Ok(format!(
+            "{}\\{}",
+            String::from_utf16_lossy(&domain[..domain_len as usize]),
+            String::from_utf16_lossy(&name[..name_len as usize]),
+        ))
+    }
+}
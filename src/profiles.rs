@@ -0,0 +1,140 @@
+//! 使用者設定檔：HKLM\...\ProfileList 列出本機的使用者設定檔（只含 S-1-5-21 開頭的一般帳號），
+//! 每次請求重新讀取清單；大小由背景執行緒每 CAPTURE_PROFILE_SCAN_INTERVAL_SECS（預設 21600）秒走訪一次
+//!
+//! 終端機伺服器上的設定檔動輒數十 GB，走一輪可能很久，每走完一個設定檔就更新結果。
+//! 沒有權限的子資料夾略過不算，此時 size_partial 為 true，大小偏低。
+use chrono::Utc;
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    path::Path,
+    sync::{LazyLock, RwLock},
+    thread,
+    time::Duration,
+};
+use windows::Win32::System::Registry::{HKEY_LOCAL_MACHINE, HKEY_USERS};
+
+use crate::{
+    config, dirs,
+    win::{filetime_to_rfc3339, reg_dword, reg_key_exists, reg_string, reg_subkeys},
+    MetricError,
+};
+
+const PROFILE_LIST: &str = r"SOFTWARE\Microsoft\Windows NT\CurrentVersion\ProfileList";
+
+static INTERVAL: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(config::env_parse(
+        "CAPTURE_PROFILE_SCAN_INTERVAL_SECS",
+        21600,
+    ))
+});
+/// SID → 最近一次走訪結果
+static SIZES: LazyLock<RwLock<HashMap<String, ProfileSize>>> = LazyLock::new(Default::default);
+
+#[derive(Clone)]
+struct ProfileSize {
+    size_bytes: u64,
+    skipped_dirs: u64,
+    scanned_at: String,
+}
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct ProfileData {
+    pub sid: String,
+    /// 例如 `C:\Users\alice`
+    pub path: String,
+    /// 目前載入中（使用者登入中或有服務以該帳號執行）
+    pub loaded: bool,
+    /// 最後一次載入或卸載的時間，RFC3339（UTC）；Windows 10 以前沒有記錄時為 None
+    pub last_use_time: Option<String>,
+    /// 第一次走訪完成前為 None
+    pub size_bytes: Option<u64>,
+    /// 有子資料夾讀不到，size_bytes 只是部分大小
+    pub size_partial: Option<bool>,
+    pub skipped_dirs: Option<u64>,
+    /// 該設定檔走訪完成的時間，RFC3339（UTC）
+    pub size_scanned_at: Option<String>,
+}
+
+/* ---------- 背景取樣 ---------- */
+
+pub fn start_sampler() {
+    thread::spawn(|| loop {
+        for (sid, path) in list_profiles() {
+            let tree = dirs::tree_size(Path::new(&path), None);
+            SIZES.write().unwrap().insert(
+                sid,
+                ProfileSize {
+                    size_bytes: tree.size_bytes,
+                    skipped_dirs: tree.skipped_dirs,
+                    scanned_at: Utc::now().to_rfc3339(),
+                },
+            );
+        }
+        thread::sleep(*INTERVAL);
+    });
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub fn gather_profiles(errors: &mut Vec<MetricError>) -> Vec<ProfileData> {
+    let sizes = SIZES.read().unwrap();
+    list_profiles()
+        .into_iter()
+        .map(|(sid, path)| {
+            let key = format!(r"{PROFILE_LIST}\{sid}");
+            let size = sizes.get(&sid);
+            if let Some(s) = size
+                && s.skipped_dirs > 0
+            {
+                errors.push(MetricError {
+                    metric: vec![format!("profiles.{sid}")],
+                    err: format!(
+                        "{} folder(s) under {path} could not be read; size is partial",
+                        s.skipped_dirs
+                    ),
+                });
+            }
+            ProfileData {
+                loaded: reg_key_exists(HKEY_USERS, &sid),
+                last_use_time: last_use_time(&key),
+                size_bytes: size.map(|s| s.size_bytes),
+                size_partial: size.map(|s| s.skipped_dirs > 0),
+                skipped_dirs: size.map(|s| s.skipped_dirs),
+                size_scanned_at: size.map(|s| s.scanned_at.clone()),
+                sid,
+                path,
+            }
+        })
+        .collect()
+}
+
+/// (SID, 設定檔路徑)；系統帳號（S-1-5-18/19/20）與沒有路徑的項目略過
+fn list_profiles() -> Vec<(String, String)> {
+    reg_subkeys(HKEY_LOCAL_MACHINE, PROFILE_LIST)
+        .into_iter()
+        .filter(|sid| sid.starts_with("S-1-5-21-"))
+        .filter_map(|sid| {
+            // REG_EXPAND_SZ，RegGetValueW 會展開 %SystemDrive% 之類的變數
+            let path = reg_string(
+                HKEY_LOCAL_MACHINE,
+                &format!(r"{PROFILE_LIST}\{sid}"),
+                "ProfileImagePath",
+            )?;
+            Some((sid, path))
+        })
+        .collect()
+}
+
+/// LocalProfileLoadTime / LocalProfileUnloadTime 各以 High、Low 兩個 DWORD 存 FILETIME，取較晚者
+fn last_use_time(key: &str) -> Option<String> {
+    let filetime = |name: &str| {
+        let high = reg_dword(HKEY_LOCAL_MACHINE, key, &format!("{name}High"))?;
+        let low = reg_dword(HKEY_LOCAL_MACHINE, key, &format!("{name}Low"))?;
+        Some(((high as i64) << 32) | low as i64)
+    };
+    let latest = filetime("LocalProfileLoadTime").max(filetime("LocalProfileUnloadTime"))?;
+    filetime_to_rfc3339(latest)
+}
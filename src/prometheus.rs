@@ -0,0 +1,425 @@
+//! Prometheus 文字格式（exposition format 0.0.4）與 OpenMetrics 1.0.0：/metrics
+//!
+//! 資料來自 samples::collect，與 /api/v1/metrics 同一份蒐集結果；指標名稱加上 `capture_` 前綴。
+//! Accept 帶 application/openmetrics-text 時改輸出 OpenMetrics：TYPE 行用不含 `_total` 的
+//! 家族名稱、名稱以單位結尾時加 `# UNIT`、最後以 `# EOF` 結束。不輸出 exemplar。
+use std::fmt::Write;
+
+use crate::samples::{Kind, Sample};
+
+const PREFIX: &str = "capture_";
+/// 名稱以 `_<單位>` 結尾時，OpenMetrics 輸出 `# UNIT`；`_per_second` 之類的速率不算
+const UNITS: &[&str] = &["bytes", "seconds", "ratio", "hertz", "celsius"];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Format {
+    Prometheus,
+    OpenMetrics,
+}
+
+impl Format {
+    /// 依 Accept 標頭選擇：各格式取最具體的相符範圍的 q，q 高者勝；q 相同時比具體程度，
+    /// 再相同時用 Prometheus。q=0 代表明確拒絕
+    pub fn negotiate(accept: Option<&str>) -> Self {
+        let Some(accept) = accept else {
+            return Format::Prometheus;
+        };
+        let (om_q, om_specificity) = preference(accept, "application", "openmetrics-text");
+        let (prom_q, prom_specificity) = preference(accept, "text", "plain");
+        if om_q > 0.0 && (om_q > prom_q || (om_q == prom_q && om_specificity > prom_specificity)) {
+            Format::OpenMetrics
+        } else {
+            Format::Prometheus
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Format::Prometheus => "text/plain; version=0.0.4; charset=utf-8",
+            Format::OpenMetrics => "application/openmetrics-text; version=1.0.0; charset=utf-8",
+        }
+    }
+}
+
+/// (q, 具體程度)：完全相符為 3、`type/*` 為 2、`*/*` 為 1；沒有相符的範圍時為 (0, 0)
+fn preference(accept: &str, ty: &str, subtype: &str) -> (f32, u8) {
+    accept
+        .split(',')
+        .filter_map(|range| {
+            let mut parts = range.split(';').map(str::trim);
+            let (t, s) = parts.next()?.split_once('/')?;
+            let specificity = if !t.eq_ignore_ascii_case(ty) {
+                (t == "*" && s == "*").then_some(1)?
+            } else if s.eq_ignore_ascii_case(subtype) {
+                3
+            } else {
+                (s == "*").then_some(2)?
+            };
+            let q = parts
+                .find_map(|p| p.strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.parse::<f32>().ok())?;
+            Some((specificity, q))
+        })
+        .max_by_key(|(specificity, _)| *specificity)
+        .map_or((0.0, 0), |(specificity, q)| (q, specificity))
+}
+
+pub fn render(samples: &[Sample], format: Format) -> String {
+    let mut out = String::new();
+    let mut current = "";
+    for s in samples {
+        let family = sanitize_name(&format!("{PREFIX}{}", s.name));
+        let name = match s.kind {
+            Kind::Gauge => family.clone(),
+            Kind::Counter => format!("{family}_total"),
+        };
+        if s.name != current {
+            current = s.name;
+            let kind = match s.kind {
+                Kind::Gauge => "gauge",
+                Kind::Counter => "counter",
+            };
+            // Prometheus 0.0.4 的 HELP / TYPE 用樣本名稱，OpenMetrics 用家族名稱
+            let header = match format {
+                Format::Prometheus => &name,
+                Format::OpenMetrics => &family,
+            };
+            let _ = writeln!(out, "# HELP {header} {}", escape_help(s.help, format));
+            let _ = writeln!(out, "# TYPE {header} {kind}");
+            if format == Format::OpenMetrics
+                && let Some(unit) = UNITS.iter().find(|u| family.ends_with(&format!("_{u}")))
+            {
+                let _ = writeln!(out, "# UNIT {family} {unit}");
+            }
+        }
+        let _ = writeln!(out, "{name}{} {}", labels(&s.labels), value(s.value));
+    }
+    if format == Format::OpenMetrics {
+        out.push_str("# EOF\n");
+    }
+    out
+}
+
+/// `{a="1",b="2"}`；沒有標籤時為空字串
+pub fn labels(labels: &[(&'static str, String)]) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+    let pairs: Vec<String> = labels
+        .iter()
+        .map(|(k, v)| format!("{}=\"{}\"", sanitize_name(k), escape_label(v)))
+        .collect();
+    format!("{{{}}}", pairs.join(","))
+}
+
+/// 只保留 `[a-zA-Z0-9_:]`，其他字元換成 `_`；不能以數字開頭
+pub fn sanitize_name(name: &str) -> String {
+    let mut out: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == ':' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    if out.starts_with(|c: char| c.is_ascii_digit()) {
+        out.insert(0, '_');
+    }
+    out
+}
+
+/// 標籤值跳脫 `\`、`"` 與換行；介面名稱與裝置代號可能含反斜線
+pub fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', "\\\"")
+        .replace('\n', r"\n")
+}
+
+/// OpenMetrics 另外要跳脫雙引號
+fn escape_help(help: &str, format: Format) -> String {
+    let help = help.replace('\\', r"\\").replace('\n', r"\n");
+    match format {
+        Format::Prometheus => help,
+        Format::OpenMetrics => help.replace('"', "\\\""),
+    }
+}
+
+pub fn value(v: f64) -> String {
+    if v.is_nan() {
+        "NaN".into()
+    } else if v.is_infinite() {
+        if v > 0.0 { "+Inf" } else { "-Inf" }.into()
+    } else {
+        v.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    //! 沒有能離線取得的 OpenMetrics 解析套件，parse_openmetrics 是依規格的 ABNF 手寫的嚴格解析器。
+    //! 它和 render 出自同一份對規格的理解，只能當自我檢查；與參考解析器（例如 prometheus_client
+    //! 的 parser）比對的一致性測試仍待補上
+    use super::*;
+    use std::collections::HashMap;
+
+    struct Family {
+        kind: String,
+        unit: Option<String>,
+        samples: Vec<ParsedSample>,
+    }
+
+    struct ParsedSample {
+        name: String,
+        labels: Vec<(String, String)>,
+        value: f64,
+    }
+
+    /// 不符合這裡理解的 OpenMetrics 文字格式時回傳 Err；不是參考實作
+    fn parse_openmetrics(text: &str) -> Result<Vec<(String, Family)>, String> {
+        let body = text
+            .strip_suffix("# EOF\n")
+            .ok_or("output must end with `# EOF`")?;
+        let mut families: Vec<(String, Family)> = Vec::new();
+        for line in body.lines() {
+            if let Some(meta) = line.strip_prefix("# ") {
+                let mut parts = meta.splitn(3, ' ');
+                let (keyword, name, rest) = (
+                    parts.next().unwrap_or_default(),
+                    parts.next().ok_or(format!("missing name: {line}"))?,
+                    parts.next().unwrap_or_default(),
+                );
+                valid_name(name)?;
+                match keyword {
+                    "TYPE" => {
+                        if families.iter().any(|(n, _)| n == name) {
+                            return Err(format!("family {name} declared twice"));
+                        }
+                        if !["gauge", "counter"].contains(&rest) {
+                            return Err(format!("unexpected type {rest}"));
+                        }
+                        if rest == "counter" && name.ends_with("_total") {
+                            return Err(format!("counter family {name} must not end in _total"));
+                        }
+                        families.push((
+                            name.to_string(),
+                            Family {
+                                kind: rest.to_string(),
+                                unit: None,
+                                samples: Vec::new(),
+                            },
+                        ));
+                    }
+                    "UNIT" => {
+                        let (family_name, family) =
+                            families.last_mut().ok_or("UNIT before TYPE")?;
+                        if family_name != name || !name.ends_with(&format!("_{rest}")) {
+                            return Err(format!("unit {rest} is not a suffix of {name}"));
+                        }
+                        family.unit = Some(rest.to_string());
+                    }
+                    "HELP" => {}
+                    _ => return Err(format!("unknown descriptor: {line}")),
+                }
+                continue;
+            }
+
+            let (family_name, family) = families.last_mut().ok_or("sample before TYPE")?;
+            let (name_and_labels, value) = line
+                .rsplit_once(' ')
+                .ok_or(format!("missing value: {line}"))?;
+            let (name, labels) = match name_and_labels.split_once('{') {
+                Some((n, l)) => (n, parse_labels(l.strip_suffix('}').ok_or("unclosed {")?)?),
+                None => (name_and_labels, Vec::new()),
+            };
+            let expected = match family.kind.as_str() {
+                "counter" => format!("{family_name}_total"),
+                _ => family_name.clone(),
+            };
+            if name != expected {
+                return Err(format!(
+                    "sample {name} does not belong to family {family_name}"
+                ));
+            }
+            let value = match value {
+                "NaN" => f64::NAN,
+                "+Inf" => f64::INFINITY,
+                "-Inf" => f64::NEG_INFINITY,
+                v => v.parse().map_err(|_| format!("bad value {v}"))?,
+            };
+            family.samples.push(ParsedSample {
+                name: name.to_string(),
+                labels,
+                value,
+            });
+        }
+        Ok(families)
+    }
+
+    fn valid_name(name: &str) -> Result<(), String> {
+        let ok = name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+            && !name.starts_with(|c: char| c.is_ascii_digit());
+        if ok {
+            Ok(())
+        } else {
+            Err(format!("invalid name {name}"))
+        }
+    }
+
+    /// `a="x",b="y"`；值內只允許 `\\`、`\"`、`\n` 三種跳脫
+    fn parse_labels(s: &str) -> Result<Vec<(String, String)>, String> {
+        let mut labels = Vec::new();
+        let mut chars = s.chars().peekable();
+        while chars.peek().is_some() {
+            let name: String = chars.by_ref().take_while(|&c| c != '=').collect();
+            valid_name(&name)?;
+            if chars.next() != Some('"') {
+                return Err(format!("label {name} is not quoted"));
+            }
+            let mut value = String::new();
+            loop {
+                match chars.next().ok_or("unterminated label value")? {
+                    '"' => break,
+                    '\\' => match chars.next() {
+                        Some('\\') => value.push('\\'),
+                        Some('"') => value.push('"'),
+                        Some('n') => value.push('\n'),
+                        other => return Err(format!("bad escape {other:?}")),
+                    },
+                    '\n' => return Err("raw newline in label value".into()),
+                    c => value.push(c),
+                }
+            }
+            labels.push((name, value));
+            match chars.next() {
+                None => break,
+                Some(',') => {}
+                Some(c) => return Err(format!("unexpected {c} after label")),
+            }
+        }
+        Ok(labels)
+    }
+
+    fn fixture() -> Vec<Sample> {
+        let sample = |name, kind, labels: &[(&'static str, &str)], value| Sample {
+            name,
+            help: "Help with \"quotes\" and a \\ backslash.",
+            kind,
+            labels: labels.iter().map(|(k, v)| (*k, v.to_string())).collect(),
+            value,
+        };
+        vec![
+            sample("cpu_usage_ratio", Kind::Gauge, &[], 0.42),
+            sample("disk_free_bytes", Kind::Gauge, &[("device", "C:")], 1024.0),
+            sample(
+                "disk_free_bytes",
+                Kind::Gauge,
+                &[("device", r#"\\?\Vol"x""#)],
+                0.0,
+            ),
+            sample(
+                "net_bytes",
+                Kind::Counter,
+                &[("interface", "Ethernet 2"), ("direction", "rx")],
+                123.0,
+            ),
+            sample(
+                "net_bytes",
+                Kind::Counter,
+                &[("interface", "Ethernet 2"), ("direction", "tx")],
+                456.0,
+            ),
+            sample("cpu_context_switches_per_second", Kind::Gauge, &[], 1e3),
+            sample("time_offset_seconds", Kind::Gauge, &[], f64::NAN),
+        ]
+    }
+
+    #[test]
+    fn openmetrics_output_passes_self_check() {
+        let text = render(&fixture(), Format::OpenMetrics);
+        let families = parse_openmetrics(&text).unwrap_or_else(|e| panic!("{e}\n{text}"));
+        let families: HashMap<_, _> = families.into_iter().collect();
+
+        let net = &families["capture_net_bytes"];
+        assert_eq!(net.kind, "counter");
+        assert_eq!(net.unit.as_deref(), Some("bytes"));
+        assert!(net
+            .samples
+            .iter()
+            .all(|s| s.name == "capture_net_bytes_total"));
+        assert_eq!(net.samples.len(), 2);
+
+        assert_eq!(
+            families["capture_cpu_usage_ratio"].unit.as_deref(),
+            Some("ratio")
+        );
+        assert_eq!(
+            families["capture_time_offset_seconds"].unit.as_deref(),
+            Some("seconds")
+        );
+        assert!(families["capture_time_offset_seconds"].samples[0]
+            .value
+            .is_nan());
+        assert_eq!(
+            families["capture_cpu_context_switches_per_second"].unit,
+            None
+        );
+
+        let disk = &families["capture_disk_free_bytes"];
+        assert_eq!(
+            disk.samples[1].labels,
+            vec![("device".into(), r#"\\?\Vol"x""#.into())]
+        );
+        assert!(text.ends_with("\n# EOF\n"));
+        assert_eq!(text.matches("# EOF").count(), 1);
+    }
+
+    #[test]
+    fn prometheus_output_has_no_openmetrics_markers() {
+        let text = render(&fixture(), Format::Prometheus);
+        assert!(!text.contains("# EOF"));
+        assert!(!text.contains("# UNIT"));
+        assert!(text.contains("# TYPE capture_net_bytes_total counter\n"));
+    }
+
+    #[test]
+    fn accept_negotiation() {
+        let om = Format::OpenMetrics;
+        let prom = Format::Prometheus;
+        let cases = [
+            (None, prom),
+            (Some("text/plain"), prom),
+            (Some("*/*"), prom),
+            (Some("application/openmetrics-text"), om),
+            (
+                Some("application/openmetrics-text;version=1.0.0,text/plain;q=0.5"),
+                om,
+            ),
+            (
+                Some("text/plain, Application/OpenMetrics-Text; q=0.3"),
+                prom,
+            ),
+            (
+                Some("application/openmetrics-text;q=0.5, text/plain;q=0.5"),
+                prom,
+            ),
+            (Some("application/openmetrics-text, */*;q=0.1"), om),
+            (Some("text/*;q=0.2, application/*"), om),
+            (
+                Some("application/openmetrics-text;q=0.9, text/plain;q=0, */*"),
+                om,
+            ),
+            (Some("application/openmetrics-text; q=0"), prom),
+        ];
+        for (accept, expected) in cases {
+            assert!(Format::negotiate(accept) == expected, "{accept:?}");
+        }
+        assert!(om
+            .content_type()
+            .starts_with("application/openmetrics-text; version=1.0.0"));
+    }
+}
@@ -0,0 +1,138 @@
+//! 推送模式：設定 CAPTURE_PUSH_URL 時，每 CAPTURE_PUSH_INTERVAL_SECS（預設 60）秒把
+//! /api/v1/metrics 的 JSON POST 到該 URL；給 NAT 後面、無法被拉取的機器用，HTTP 服務照常提供
+//!
+//! CAPTURE_PUSH_AUTH_HEADER 為完整的標頭行（例如 `Authorization: Bearer …`），
+//! 每次推送最多等 CAPTURE_PUSH_TIMEOUT_SECS（預設 10）秒。連線失敗或狀態碼 >= 400 時
+//! 從 INITIAL_BACKOFF 開始加倍重試，最長 MAX_BACKOFF，成功後回到正常間隔。
+//! 最近一次結果放在 capture.push，推送默默壞掉時看得出來。未設定 URL 時不推送。
+//!
+//! CAPTURE_PUSH_FORMAT=influx 時改送 InfluxDB line protocol，可直接指向 /api/v2/write
+//! （權杖用 `CAPTURE_PUSH_AUTH_HEADER=Authorization: Token …`）。
+use chrono::Utc;
+use serde::Serialize;
+use std::{
+    sync::{LazyLock, RwLock},
+    time::Duration,
+};
+
+use crate::{config, httpprobe, influx, samples};
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(5);
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+static URL: LazyLock<Option<String>> = LazyLock::new(|| config::env_string("CAPTURE_PUSH_URL"));
+static INTERVAL: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(config::env_parse("CAPTURE_PUSH_INTERVAL_SECS", 60u64).max(1))
+});
+static TIMEOUT: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_secs(config::env_parse("CAPTURE_PUSH_TIMEOUT_SECS", 10u64)));
+static INFLUX: LazyLock<bool> = LazyLock::new(|| {
+    config::env_string("CAPTURE_PUSH_FORMAT").is_some_and(|f| f.eq_ignore_ascii_case("influx"))
+});
+static AUTH_HEADER: LazyLock<Option<String>> =
+    LazyLock::new(|| config::env_string("CAPTURE_PUSH_AUTH_HEADER"));
+static STATUS: RwLock<Option<PushStatus>> = RwLock::new(None);
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize, Clone, Default)]
+pub struct PushStatus {
+    /// 不含查詢字串，避免把放在 URL 裡的權杖帶出去
+    pub url: String,
+    /// RFC3339（UTC）；第一次推送前為 None
+    pub last_attempt_time: Option<String>,
+    pub last_success_time: Option<String>,
+    /// 最近一次收到的狀態碼；連線失敗時保留上一次的值
+    pub last_status_code: Option<u32>,
+    /// 最近一次失敗的原因；成功後清除
+    pub last_error: Option<String>,
+    pub consecutive_failures: u32,
+}
+
+/* ---------- 推送迴圈 ---------- */
+
+/// 未設定 CAPTURE_PUSH_URL 時什麼都不做
+pub fn start() {
+    let Some(url) = URL.clone() else {
+        return;
+    };
+    *STATUS.write().unwrap() = Some(PushStatus {
+        url: url.split('?').next().unwrap_or_default().to_string(),
+        ..Default::default()
+    });
+
+    tokio::spawn(async move {
+        let mut failures = 0u32;
+        loop {
+            let result = push(&url).await;
+            let now = Utc::now().to_rfc3339();
+            {
+                let mut status = STATUS.write().unwrap();
+                let status = status.get_or_insert_with(Default::default);
+                status.last_attempt_time = Some(now.clone());
+                match &result {
+                    Ok(code) => {
+                        status.last_status_code = Some(*code);
+                        status.last_success_time = Some(now);
+                        status.last_error = None;
+                    }
+                    Err((code, e)) => {
+                        status.last_status_code = code.or(status.last_status_code);
+                        status.last_error = Some(e.clone());
+                    }
+                }
+                failures = if result.is_ok() { 0 } else { failures + 1 };
+                status.consecutive_failures = failures;
+            }
+            tokio::time::sleep(if failures == 0 {
+                *INTERVAL
+            } else {
+                backoff(failures)
+            })
+            .await;
+        }
+    });
+}
+
+/// 推送未啟用時為 None
+pub fn status() -> Option<PushStatus> {
+    STATUS.read().unwrap().clone()
+}
+
+/// 成功時為狀態碼；失敗時為 (有收到回應時的狀態碼, 原因)
+async fn push(url: &str) -> Result<u32, (Option<u32>, String)> {
+    let all = crate::collect_all_blocking().await.map_err(|e| (None, e))?;
+    let (content_type, body) = if *INFLUX {
+        let samples = samples::collect(&all);
+        (
+            influx::CONTENT_TYPE,
+            influx::render(&samples, all.hostname.as_deref()).into_bytes(),
+        )
+    } else {
+        (
+            "application/json",
+            serde_json::to_vec(&all).map_err(|e| (None, e.to_string()))?,
+        )
+    };
+
+    let url = url.to_string();
+    let code = tokio::task::spawn_blocking(move || {
+        httpprobe::post(&url, content_type, AUTH_HEADER.as_deref(), &body, *TIMEOUT)
+    })
+    .await
+    .unwrap_or_else(|e| Err(e.to_string()))
+    .map_err(|e| (None, e))?;
+
+    if code >= 400 {
+        Err((Some(code), format!("collector responded with HTTP {code}")))
+    } else {
+        Ok(code)
+    }
+}
+
+/// 連續失敗 failures 次後的等待時間：5s、10s、20s …，最長 MAX_BACKOFF；Graphite 也用這個
+pub fn backoff(failures: u32) -> Duration {
+    INITIAL_BACKOFF
+        .saturating_mul(1 << (failures - 1).min(16))
+        .min(MAX_BACKOFF)
+}
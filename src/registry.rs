@@ -0,0 +1,224 @@
+//! 登錄值監看：CAPTURE_REGISTRY_VALUES 列出的值每次請求重新讀取
+//!
+//! 每一項為 `機碼路徑|值名稱|型別`，例如
+//! `HKLM\SYSTEM\CurrentControlSet\Control\Terminal Server\WinStations\RDP-Tcp|UserAuthentication|dword`。
+//! 型別為 dword / qword / sz / expand_sz / multi_sz；值名稱留空代表機碼的預設值。
+//! 機碼或值不存在時照樣列出（exists = false），不從結果中省略。
+use serde::Serialize;
+use std::sync::LazyLock;
+use windows::{
+    core::HSTRING,
+    Win32::{
+        Foundation::ERROR_FILE_NOT_FOUND,
+        System::Registry::{
+            RegGetValueW, HKEY, HKEY_CLASSES_ROOT, HKEY_CURRENT_CONFIG, HKEY_CURRENT_USER,
+            HKEY_LOCAL_MACHINE, HKEY_USERS, REG_DWORD, REG_EXPAND_SZ, REG_MULTI_SZ, REG_QWORD,
+            REG_SZ, REG_VALUE_TYPE, RRF_NOEXPAND, RRF_RT_ANY,
+        },
+    },
+};
+
+use crate::{config, win::reg_key_exists, MetricError};
+
+const HIVES: &[(&str, &str, HKEY)] = &[
+    ("HKLM", "HKEY_LOCAL_MACHINE", HKEY_LOCAL_MACHINE),
+    ("HKCU", "HKEY_CURRENT_USER", HKEY_CURRENT_USER),
+    ("HKU", "HKEY_USERS", HKEY_USERS),
+    ("HKCR", "HKEY_CLASSES_ROOT", HKEY_CLASSES_ROOT),
+    ("HKCC", "HKEY_CURRENT_CONFIG", HKEY_CURRENT_CONFIG),
+];
+
+const TYPES: &[(&str, REG_VALUE_TYPE)] = &[
+    ("dword", REG_DWORD),
+    ("qword", REG_QWORD),
+    ("sz", REG_SZ),
+    ("expand_sz", REG_EXPAND_SZ),
+    ("multi_sz", REG_MULTI_SZ),
+];
+
+/// (設定原文, 機碼路徑, 值名稱, 預期型別)；格式錯誤的項目在讀取時回報
+static WATCHED: LazyLock<Vec<(String, String, String, String)>> = LazyLock::new(|| {
+    config::env_list("CAPTURE_REGISTRY_VALUES")
+        .into_iter()
+        .map(|item| {
+            let mut parts = item.splitn(3, '|').map(str::trim);
+            let key = parts.next().unwrap_or_default().to_string();
+            let value = parts.next().unwrap_or_default().to_string();
+            let kind = parts.next().unwrap_or_default().to_ascii_lowercase();
+            (item, key, value, kind)
+        })
+        .collect()
+});
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum RegData {
+    Number(u64),
+    Text(String),
+    List(Vec<String>),
+}
+
+#[derive(Serialize)]
+pub struct RegistryValue {
+    /// 設定中的機碼路徑，例如 `HKLM\SOFTWARE\…`
+    pub key: String,
+    pub value: String,
+    pub expected_type: String,
+    pub key_exists: bool,
+    pub exists: bool,
+    /// 實際型別；與 expected_type 不同時會記一筆 error
+    pub actual_type: Option<String>,
+    pub data: Option<RegData>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub fn gather_registry(errors: &mut Vec<MetricError>) -> Vec<RegistryValue> {
+    if WATCHED.is_empty() {
+        errors.push(MetricError {
+            metric: vec!["registry".into()],
+            err: "set CAPTURE_REGISTRY_VALUES to watch registry values".into(),
+        });
+        return Vec::new();
+    }
+
+    let mut values = Vec::new();
+    for (item, key, value, kind) in WATCHED.iter() {
+        let metric = vec![format!("registry.{key}\\{value}")];
+        let Some((root, subkey)) = split_hive(key) else {
+            errors.push(MetricError {
+                metric,
+                err: format!("unknown registry hive in {item:?}"),
+            });
+            continue;
+        };
+        if !TYPES.iter().any(|(name, _)| name == kind) {
+            errors.push(MetricError {
+                metric,
+                err: format!("unsupported type {kind:?}, expected dword / qword / sz / multi_sz"),
+            });
+            continue;
+        }
+
+        let mut entry = RegistryValue {
+            key: key.clone(),
+            value: value.clone(),
+            expected_type: kind.clone(),
+            key_exists: reg_key_exists(root, subkey),
+            exists: false,
+            actual_type: None,
+            data: None,
+        };
+        if entry.key_exists {
+            match read_value(root, subkey, value) {
+                Ok(Some((ty, data))) => {
+                    entry.exists = true;
+                    entry.actual_type = Some(type_name(ty));
+                    entry.data = decode(ty, &data);
+                    if entry.actual_type.as_deref() != Some(kind.as_str()) {
+                        errors.push(MetricError {
+                            metric,
+                            err: format!("expected {kind}, found {}", type_name(ty)),
+                        });
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => errors.push(MetricError { metric, err: e }),
+            }
+        }
+        values.push(entry);
+    }
+    values
+}
+
+/// `HKLM\SOFTWARE\…` → (HKEY_LOCAL_MACHINE, `SOFTWARE\…`)
+fn split_hive(key: &str) -> Option<(HKEY, &str)> {
+    let (hive, rest) = key.split_once('\\').unwrap_or((key, ""));
+    HIVES
+        .iter()
+        .find(|(short, long, _)| {
+            hive.eq_ignore_ascii_case(short) || hive.eq_ignore_ascii_case(long)
+        })
+        .map(|&(_, _, root)| (root, rest))
+}
+
+/// 值不存在時回傳 Ok(None)；REG_EXPAND_SZ 不展開，回傳原文
+fn read_value(
+    root: HKEY,
+    subkey: &str,
+    value: &str,
+) -> Result<Option<(REG_VALUE_TYPE, Vec<u8>)>, String> {
+    let (subkey, value) = (HSTRING::from(subkey), HSTRING::from(value));
+    let flags = RRF_RT_ANY | RRF_NOEXPAND;
+    let mut ty = REG_VALUE_TYPE::default();
+    let mut size = 0u32;
+    unsafe {
+        let rc = RegGetValueW(
+            root,
+            &subkey,
+            &value,
+            flags,
+            Some(&mut ty),
+            None,
+            Some(&mut size),
+        );
+        if rc == ERROR_FILE_NOT_FOUND {
+            return Ok(None);
+        }
+        rc.ok().map_err(|e| format!("RegGetValueW failed: {e}"))?;
+        let mut buf = vec![0u8; size as usize];
+        RegGetValueW(
+            root,
+            &subkey,
+            &value,
+            flags,
+            Some(&mut ty),
+            Some(buf.as_mut_ptr().cast()),
+            Some(&mut size),
+        )
+        .ok()
+        .map_err(|e| format!("RegGetValueW failed: {e}"))?;
+        buf.truncate(size as usize);
+        Ok(Some((ty, buf)))
+    }
+}
+
+fn type_name(ty: REG_VALUE_TYPE) -> String {
+    TYPES
+        .iter()
+        .find(|(_, t)| *t == ty)
+        .map_or_else(|| format!("type_{}", ty.0), |(name, _)| name.to_string())
+}
+
+/// 其他型別（REG_BINARY 等）只回報 actual_type，不解碼
+fn decode(ty: REG_VALUE_TYPE, data: &[u8]) -> Option<RegData> {
+    let wide = || -> Vec<u16> {
+        data.chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .collect()
+    };
+    match ty {
+        REG_DWORD => Some(RegData::Number(
+            u32::from_le_bytes(data.get(..4)?.try_into().ok()?) as u64,
+        )),
+        REG_QWORD => Some(RegData::Number(u64::from_le_bytes(
+            data.get(..8)?.try_into().ok()?,
+        ))),
+        REG_SZ | REG_EXPAND_SZ => {
+            let w = wide();
+            let len = w.iter().position(|&c| c == 0).unwrap_or(w.len());
+            Some(RegData::Text(String::from_utf16_lossy(&w[..len])))
+        }
+        // 以 NUL 分隔，連續兩個 NUL 結尾
+        REG_MULTI_SZ => Some(RegData::List(
+            wide()
+                .split(|&c| c == 0)
+                .filter(|s| !s.is_empty())
+                .map(String::from_utf16_lossy)
+                .collect(),
+        )),
+        _ => None,
+    }
+}
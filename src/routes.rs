@@ -0,0 +1,171 @@
+//! 路由表：GetIpForwardTable2（IPv4 + IPv6），有效 metric = 路由 metric + 介面 metric（與 route print 一致）
+use serde::Serialize;
+use std::collections::HashMap;
+use windows::Win32::{
+    NetworkManagement::IpHelper::{
+        FreeMibTable, GetIpForwardTable2, GetIpInterfaceTable, MIB_IPFORWARD_TABLE2,
+        MIB_IPINTERFACE_TABLE,
+    },
+    Networking::WinSock::{AF_INET, AF_UNSPEC, SOCKADDR},
+};
+
+use crate::{net::interface_alias, win::sockaddr_to_ip, MetricError};
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct RouteEntry {
+    /// CIDR，例如 `0.0.0.0/0`、`fe80::/64`
+    pub destination: String,
+    /// on-link 路由為 `0.0.0.0` / `::`
+    pub next_hop: String,
+    pub interface: Option<String>,
+    /// 路由 metric 加上介面 metric；介面 metric 讀不到時只有路由 metric
+    pub metric: u32,
+    /// local / netmgmt（手動設定）/ dhcp / ospf / bgp…
+    pub protocol: String,
+}
+
+#[derive(Serialize)]
+pub struct RouteData {
+    /// 0.0.0.0/0 中有效 metric 最低者的閘道；VPN 接管預設路由時會變成 VPN 端的位址
+    pub default_gateway_v4: Option<String>,
+    pub default_gateway_v6: Option<String>,
+    pub routes: Vec<RouteEntry>,
+}
+
+/// 排序與挑預設閘道用，不輸出
+struct RawRoute {
+    entry: RouteEntry,
+    is_default: bool,
+    v6: bool,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub fn gather_routes(errors: &mut Vec<MetricError>) -> RouteData {
+    let raw = match query_routes() {
+        Ok(r) => r,
+        Err(e) => {
+            errors.push(MetricError {
+                metric: vec!["routes".into()],
+                err: e,
+            });
+            Vec::new()
+        }
+    };
+
+    let gateway = |v6: bool| {
+        raw.iter()
+            .filter(|r| r.is_default && r.v6 == v6)
+            .min_by_key(|r| r.entry.metric)
+            .map(|r| r.entry.next_hop.clone())
+    };
+    let default_gateway_v4 = gateway(false);
+    let default_gateway_v6 = gateway(true);
+
+    let mut routes: Vec<RouteEntry> = raw.into_iter().map(|r| r.entry).collect();
+    routes.sort_by(|a, b| (a.metric, &a.destination).cmp(&(b.metric, &b.destination)));
+
+    RouteData {
+        default_gateway_v4,
+        default_gateway_v6,
+        routes,
+    }
+}
+
+fn query_routes() -> Result<Vec<RawRoute>, String> {
+    let if_metrics = interface_metrics();
+
+    unsafe {
+        let mut table: *mut MIB_IPFORWARD_TABLE2 = std::ptr::null_mut();
+        let status = GetIpForwardTable2(AF_UNSPEC, &mut table);
+        if status.is_err() {
+            return Err(format!("GetIpForwardTable2 failed: {}", status.0));
+        }
+        let rows =
+            std::slice::from_raw_parts((*table).Table.as_ptr(), (*table).NumEntries as usize);
+
+        let mut aliases: HashMap<u64, Option<String>> = HashMap::new();
+        let routes = rows
+            .iter()
+            .filter_map(|r| {
+                let prefix = &r.DestinationPrefix;
+                let dest = sockaddr_to_ip(&prefix.Prefix as *const _ as *const SOCKADDR)?;
+                let next_hop = sockaddr_to_ip(&r.NextHop as *const _ as *const SOCKADDR)?;
+                let luid = r.InterfaceLuid.Value;
+                let interface = aliases
+                    .entry(luid)
+                    .or_insert_with(|| interface_alias(&r.InterfaceLuid))
+                    .clone();
+                let if_metric = if_metrics
+                    .get(&(luid, dest.is_ipv6()))
+                    .copied()
+                    .unwrap_or(0);
+                Some(RawRoute {
+                    // on-link 的 ::/0（例如 Teredo）沒有閘道，不算預設路由
+                    is_default: prefix.PrefixLength == 0 && !next_hop.is_unspecified(),
+                    v6: dest.is_ipv6(),
+                    entry: RouteEntry {
+                        destination: format!("{dest}/{}", prefix.PrefixLength),
+                        next_hop: next_hop.to_string(),
+                        interface,
+                        metric: r.Metric.saturating_add(if_metric),
+                        protocol: protocol_name(r.Protocol.0).into(),
+                    },
+                })
+            })
+            .collect();
+
+        FreeMibTable(table as *const _);
+        Ok(routes)
+    }
+}
+
+/// (LUID, 是否 IPv6) → 介面 metric；讀不到時回傳空表，路由 metric 照常輸出
+fn interface_metrics() -> HashMap<(u64, bool), u32> {
+    let mut out = HashMap::new();
+    unsafe {
+        let mut table: *mut MIB_IPINTERFACE_TABLE = std::ptr::null_mut();
+        if GetIpInterfaceTable(AF_UNSPEC, &mut table).is_err() {
+            return out;
+        }
+        let rows =
+            std::slice::from_raw_parts((*table).Table.as_ptr(), (*table).NumEntries as usize);
+        for r in rows {
+            let v6 = r.Family != AF_INET;
+            out.insert((r.InterfaceLuid.Value, v6), r.Metric);
+        }
+        FreeMibTable(table as *const _);
+    }
+    out
+}
+
+/// NL_ROUTE_PROTOCOL（MIB_IPPROTO_*）
+fn protocol_name(proto: i32) -> &'static str {
+    match proto {
+        1 => "other",
+        2 => "local",
+        3 => "netmgmt",
+        4 => "icmp",
+        5 => "egp",
+        6 => "ggp",
+        7 => "hello",
+        8 => "rip",
+        9 => "is_is",
+        10 => "es_is",
+        11 => "cisco",
+        12 => "bbn",
+        13 => "ospf",
+        14 => "bgp",
+        15 => "idpr",
+        16 => "eigrp",
+        17 => "dvmrp",
+        18 => "rpl",
+        19 => "dhcp",
+        10002 => "nt_autostatic",
+        10006 => "nt_static",
+        10007 => "nt_static_non_dod",
+        _ => "unknown",
+    }
+}
@@ -0,0 +1,468 @@
+//! 扁平化指標：把 AllMetrics 轉成 名稱 + 標籤 + 數值 的樣本，給 Prometheus 等文字格式共用
+//!
+//! 只收數值型欄位；字串、清單與 None 不輸出。名稱不含 `capture_` 前綴，單位一律換成基本單位
+//! （bytes、seconds、hertz、0–1 的 ratio）。計數器的名稱不含 `_total`，由各輸出格式自行加上。
+//! 同名樣本保持相鄰，輸出時不需要再分組。
+use crate::{disk::DiskData, net::NetData, AllMetrics};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Kind {
+    Gauge,
+    /// 開機以來單調遞增
+    Counter,
+}
+
+pub struct Sample {
+    pub name: &'static str,
+    pub help: &'static str,
+    pub kind: Kind,
+    pub labels: Vec<(&'static str, String)>,
+    pub value: f64,
+}
+
+type DiskField = (&'static str, &'static str, fn(&DiskData) -> Option<f64>);
+/// (名稱, 說明, rx, tx)；以 direction 標籤區分
+type NetField = (
+    &'static str,
+    &'static str,
+    fn(&NetData) -> Option<f64>,
+    fn(&NetData) -> Option<f64>,
+);
+
+const DISK_FIELDS: &[DiskField] = &[
+    ("disk_total_bytes", "Volume capacity in bytes.", |d| {
+        d.total_bytes.map(|v| v as f64)
+    }),
+    ("disk_free_bytes", "Free space in bytes.", |d| {
+        d.free_bytes.map(|v| v as f64)
+    }),
+    ("disk_used_bytes", "Used space in bytes.", |d| {
+        d.used_bytes.map(|v| v as f64)
+    }),
+    (
+        "disk_usage_ratio",
+        "Used space as a fraction of capacity (0-1).",
+        |d| d.usage_percent.map(f64::from),
+    ),
+    (
+        "disk_read_bytes_per_second",
+        "Bytes read per second.",
+        |d| d.read_bytes_per_sec.map(|v| v as f64),
+    ),
+    (
+        "disk_write_bytes_per_second",
+        "Bytes written per second.",
+        |d| d.write_bytes_per_sec.map(|v| v as f64),
+    ),
+    (
+        "disk_reads_per_second",
+        "Read operations per second.",
+        |d| d.reads_per_sec.map(|v| v as f64),
+    ),
+    (
+        "disk_writes_per_second",
+        "Write operations per second.",
+        |d| d.writes_per_sec.map(|v| v as f64),
+    ),
+    ("disk_queue_length", "Average disk queue length.", |d| {
+        d.avg_disk_queue_length
+    }),
+    (
+        "disk_read_latency_seconds",
+        "Average seconds per read.",
+        |d| d.avg_sec_per_read,
+    ),
+    (
+        "disk_write_latency_seconds",
+        "Average seconds per write.",
+        |d| d.avg_sec_per_write,
+    ),
+    (
+        "disk_recycle_bin_bytes",
+        "Bytes held in the recycle bin.",
+        |d| d.recycle_bin_bytes.map(|v| v as f64),
+    ),
+    (
+        "disk_temp_bytes",
+        "Bytes held in temp folders on this drive.",
+        |d| d.temp_bytes.map(|v| v as f64),
+    ),
+];
+
+const NET_COUNTERS: &[NetField] = &[
+    (
+        "net_bytes",
+        "Bytes received (rx) or sent (tx).",
+        |n| Some(n.bytes_recv as f64),
+        |n| Some(n.bytes_sent as f64),
+    ),
+    (
+        "net_packets",
+        "Packets received (rx) or sent (tx).",
+        |n| Some(n.packets_recv as f64),
+        |n| Some(n.packets_sent as f64),
+    ),
+    (
+        "net_errors",
+        "Packets with errors.",
+        |n| Some(n.err_in as f64),
+        |n| Some(n.err_out as f64),
+    ),
+    (
+        "net_drops",
+        "Packets discarded.",
+        |n| Some(n.drop_in as f64),
+        |n| Some(n.drop_out as f64),
+    ),
+];
+
+/// 依 AllMetrics 的區段順序輸出
+pub fn collect(m: &AllMetrics) -> Vec<Sample> {
+    let mut out = Samples(Vec::new());
+    let d = &m.data;
+
+    let cpu = &d.cpu;
+    out.gauge(
+        "cpu_usage_ratio",
+        "CPU usage across all logical cores (0-1).",
+        vec![],
+        Some(cpu.usage_percent.into()),
+    );
+    for (core, usage) in cpu.per_core_usage.iter().enumerate() {
+        out.gauge(
+            "cpu_core_usage_ratio",
+            "CPU usage per logical core (0-1).",
+            vec![("core", core.to_string())],
+            Some((*usage).into()),
+        );
+    }
+    out.gauge(
+        "cpu_user_ratio",
+        "CPU time spent in user mode (0-1).",
+        vec![],
+        cpu.user_percent,
+    );
+    out.gauge(
+        "cpu_kernel_ratio",
+        "CPU time spent in kernel mode, including interrupts and DPCs (0-1).",
+        vec![],
+        cpu.kernel_percent,
+    );
+    out.gauge(
+        "cpu_frequency_hertz",
+        "Current CPU frequency, highest across logical cores.",
+        vec![],
+        cpu.current_frequency.map(|mhz| mhz as f64 * 1e6),
+    );
+    out.gauge(
+        "cpu_max_frequency_hertz",
+        "Rated CPU frequency.",
+        vec![],
+        Some(cpu.frequency as f64 * 1e6),
+    );
+    out.gauge(
+        "cpu_temperature_celsius",
+        "CPU package temperature.",
+        vec![],
+        cpu.temperature_c.map(f64::from),
+    );
+    out.gauge(
+        "cpu_logical_cores",
+        "Number of logical cores.",
+        vec![],
+        Some(cpu.logical_core as f64),
+    );
+    out.gauge(
+        "cpu_context_switches_per_second",
+        "Context switches per second.",
+        vec![],
+        cpu.context_switches_per_sec,
+    );
+    out.gauge(
+        "cpu_interrupts_per_second",
+        "Hardware interrupts per second.",
+        vec![],
+        cpu.interrupts_per_sec,
+    );
+
+    let mem = &d.memory;
+    out.gauge(
+        "memory_total_bytes",
+        "Physical memory installed.",
+        vec![],
+        Some(mem.total_bytes as f64),
+    );
+    out.gauge(
+        "memory_available_bytes",
+        "Physical memory available to processes.",
+        vec![],
+        Some(mem.available_bytes as f64),
+    );
+    out.gauge(
+        "memory_used_bytes",
+        "Physical memory in use.",
+        vec![],
+        Some(mem.used_bytes as f64),
+    );
+    out.gauge(
+        "memory_usage_ratio",
+        "Physical memory in use as a fraction of total (0-1).",
+        vec![],
+        Some(mem.usage_percent.into()),
+    );
+    out.gauge(
+        "memory_committed_bytes",
+        "Commit charge.",
+        vec![],
+        mem.committed_bytes.map(|v| v as f64),
+    );
+    out.gauge(
+        "memory_commit_limit_bytes",
+        "Commit limit.",
+        vec![],
+        mem.commit_limit_bytes.map(|v| v as f64),
+    );
+    out.gauge(
+        "memory_paged_pool_bytes",
+        "Kernel paged pool.",
+        vec![],
+        mem.paged_pool_bytes.map(|v| v as f64),
+    );
+    out.gauge(
+        "memory_nonpaged_pool_bytes",
+        "Kernel nonpaged pool.",
+        vec![],
+        mem.nonpaged_pool_bytes.map(|v| v as f64),
+    );
+    out.gauge(
+        "memory_pages_per_second",
+        "Hard page faults resolved per second.",
+        vec![],
+        mem.pages_per_sec,
+    );
+
+    out.gauge(
+        "swap_total_bytes",
+        "Page file capacity.",
+        vec![],
+        Some(d.swap.total_bytes as f64),
+    );
+    out.gauge(
+        "swap_used_bytes",
+        "Page file in use.",
+        vec![],
+        Some(d.swap.used_bytes as f64),
+    );
+
+    for (name, help, field) in DISK_FIELDS {
+        for disk in &d.disk {
+            let device = disk.device.trim_end_matches('\\').to_string();
+            out.gauge(name, help, vec![("device", device)], field(disk));
+        }
+    }
+
+    for (name, help, rx, tx) in NET_COUNTERS {
+        for net in &d.net {
+            for (direction, value) in [("rx", rx(net)), ("tx", tx(net))] {
+                out.counter(
+                    name,
+                    help,
+                    vec![
+                        ("interface", net.name.clone()),
+                        ("direction", direction.into()),
+                    ],
+                    value,
+                );
+            }
+        }
+    }
+    for net in &d.net {
+        for (direction, value) in [
+            ("rx", net.rx_utilization_percent),
+            ("tx", net.tx_utilization_percent),
+        ] {
+            out.gauge(
+                "net_utilization_ratio",
+                "Throughput as a fraction of link speed (0-1).",
+                vec![
+                    ("interface", net.name.clone()),
+                    ("direction", direction.into()),
+                ],
+                value.map(f64::from),
+            );
+        }
+    }
+    for net in &d.net {
+        out.gauge(
+            "net_speed_bits_per_second",
+            "Link speed.",
+            vec![("interface", net.name.clone())],
+            net.speed_bps.map(|v| v as f64),
+        );
+    }
+    for net in &d.net {
+        out.gauge(
+            "net_up",
+            "1 if the interface is operationally up.",
+            vec![("interface", net.name.clone())],
+            Some(net.is_up.into()),
+        );
+    }
+
+    for ping in &d.ping {
+        out.gauge(
+            "ping_loss_ratio",
+            "Packet loss of the latest background ping (0-1).",
+            vec![("target", ping.target.clone())],
+            Some(ping.loss_percent.into()),
+        );
+    }
+    for ping in &d.ping {
+        out.gauge(
+            "ping_rtt_seconds",
+            "Average round-trip time of the latest background ping.",
+            vec![("target", ping.target.clone())],
+            ping.avg_ms.map(|ms| ms / 1000.0),
+        );
+    }
+
+    for gpu in &d.gpu {
+        out.gauge(
+            "gpu_usage_ratio",
+            "Utilization of the busiest GPU engine (0-1).",
+            vec![("adapter", gpu.adapter_luid.clone())],
+            Some(gpu.utilization_percent),
+        );
+    }
+    for gpu in &d.gpu {
+        out.gauge(
+            "gpu_dedicated_memory_used_bytes",
+            "Dedicated GPU memory in use.",
+            vec![("adapter", gpu.adapter_luid.clone())],
+            gpu.dedicated_memory_used_bytes.map(|v| v as f64),
+        );
+    }
+
+    let system = &d.system;
+    out.gauge(
+        "system_processes",
+        "Number of processes.",
+        vec![],
+        system.process_count.map(f64::from),
+    );
+    out.gauge(
+        "system_threads",
+        "Number of threads.",
+        vec![],
+        system.thread_count.map(f64::from),
+    );
+    out.gauge(
+        "system_handles",
+        "Number of open handles.",
+        vec![],
+        system.handle_count.map(f64::from),
+    );
+    out.gauge(
+        "system_processor_queue_length",
+        "Threads waiting for a processor.",
+        vec![],
+        system.processor_queue_length.map(f64::from),
+    );
+
+    out.gauge(
+        "host_uptime_seconds",
+        "Seconds since boot.",
+        vec![],
+        Some(d.host.uptime_seconds as f64),
+    );
+    out.gauge(
+        "host_boot_time_seconds",
+        "Boot time as a Unix timestamp.",
+        vec![],
+        chrono::DateTime::parse_from_rfc3339(&d.host.boot_time)
+            .ok()
+            .map(|t| t.timestamp() as f64),
+    );
+    out.gauge(
+        "time_offset_seconds",
+        "Clock offset from the time source.",
+        vec![],
+        d.time.offset_seconds,
+    );
+    out.gauge(
+        "updates_pending",
+        "Pending Windows updates.",
+        vec![],
+        d.updates.pending_update_count.map(f64::from),
+    );
+    out.gauge(
+        "updates_reboot_pending",
+        "1 if a reboot is pending.",
+        vec![],
+        Some(d.updates.pending_reboot.into()),
+    );
+    out.gauge(
+        "eventlog_system_errors",
+        "Error events in the System log within the window.",
+        vec![],
+        d.event_log.system_errors.map(|v| v as f64),
+    );
+    out.gauge(
+        "eventlog_application_errors",
+        "Error events in the Application log within the window.",
+        vec![],
+        d.event_log.application_errors.map(|v| v as f64),
+    );
+
+    out.gauge(
+        "collect_errors",
+        "Number of metrics that failed to collect in this scrape.",
+        vec![],
+        Some(m.errors.len() as f64),
+    );
+    out.0
+}
+
+struct Samples(Vec<Sample>);
+
+impl Samples {
+    fn gauge(
+        &mut self,
+        name: &'static str,
+        help: &'static str,
+        labels: Vec<(&'static str, String)>,
+        value: Option<f64>,
+    ) {
+        self.push(name, help, Kind::Gauge, labels, value);
+    }
+
+    fn counter(
+        &mut self,
+        name: &'static str,
+        help: &'static str,
+        labels: Vec<(&'static str, String)>,
+        value: Option<f64>,
+    ) {
+        self.push(name, help, Kind::Counter, labels, value);
+    }
+
+    /// None 不輸出
+    fn push(
+        &mut self,
+        name: &'static str,
+        help: &'static str,
+        kind: Kind,
+        labels: Vec<(&'static str, String)>,
+        value: Option<f64>,
+    ) {
+        if let Some(value) = value {
+            self.0.push(Sample {
+                name,
+                help,
+                kind,
+                labels,
+                value,
+            });
+        }
+    }
+}
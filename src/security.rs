@@ -0,0 +1,111 @@
+//! 防毒狀態：Security Center 註冊的防毒產品（root\SecurityCenter2 AntiVirusProduct）
+//! 與 Defender 的細節（root\Microsoft\Windows\Defender MSFT_MpComputerStatus）
+//!
+//! Server SKU 沒有 Security Center，只回報 Defender 的資料並在 errors 註明。
+use serde::Serialize;
+
+use crate::{wmi, MetricError};
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct AntivirusProduct {
+    pub name: String,
+    pub enabled: bool,
+    /// 病毒碼是否為最新
+    pub up_to_date: bool,
+    /// 原始 productState，未公開的位元格式，留給需要自行解讀的人
+    pub product_state: u32,
+}
+
+#[derive(Serialize)]
+pub struct DefenderStatus {
+    pub antivirus_enabled: Option<bool>,
+    pub real_time_protection_enabled: Option<bool>,
+    pub signature_age_days: Option<u32>,
+    /// 以下皆為 RFC3339（UTC）；從未掃描過時為 None
+    pub signature_last_updated: Option<String>,
+    pub last_quick_scan_time: Option<String>,
+    pub last_full_scan_time: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SecurityData {
+    /// Security Center 無法使用時為空陣列
+    pub antivirus: Vec<AntivirusProduct>,
+    /// 沒有安裝 Defender 或查詢失敗時為 None
+    pub defender: Option<DefenderStatus>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub async fn gather_security(errors: &mut Vec<MetricError>) -> SecurityData {
+    // 兩個 WMI 查詢都會阻塞，一起放進同一個 blocking 執行緒
+    let (antivirus, defender) = tokio::task::spawn_blocking(|| (antivirus_products(), defender()))
+        .await
+        .unwrap_or_else(|e| (Err(e.to_string()), Err(e.to_string())));
+
+    let antivirus = antivirus.unwrap_or_else(|e| {
+        errors.push(MetricError {
+            metric: vec!["security.antivirus".into()],
+            err: format!("{e} (Security Center is not available on Windows Server; only Defender is reported)"),
+        });
+        Vec::new()
+    });
+    let defender = defender
+        .map_err(|e| {
+            errors.push(MetricError {
+                metric: vec!["security.defender".into()],
+                err: e,
+            })
+        })
+        .ok();
+
+    SecurityData {
+        antivirus,
+        defender,
+    }
+}
+
+fn antivirus_products() -> Result<Vec<AntivirusProduct>, String> {
+    let rows = wmi::query(
+        "root\\SecurityCenter2",
+        "SELECT displayName, productState FROM AntiVirusProduct",
+    )?;
+    Ok(rows
+        .iter()
+        .filter_map(|r| {
+            let state = r.get_u32("productState")?;
+            Some(AntivirusProduct {
+                name: r.get_string("displayName")?,
+                // 第二個位元組：0x10 / 0x11 為啟用；最低位元組：0x00 為病毒碼最新、0x10 為過期
+                enabled: (state >> 8) & 0x10 != 0,
+                up_to_date: state & 0xff == 0,
+                product_state: state,
+            })
+        })
+        .collect())
+}
+
+fn defender() -> Result<DefenderStatus, String> {
+    let rows = wmi::query(
+        "root\\Microsoft\\Windows\\Defender",
+        "SELECT AntivirusEnabled, RealTimeProtectionEnabled, AntivirusSignatureAge, \
+         AntivirusSignatureLastUpdated, QuickScanEndTime, FullScanEndTime \
+         FROM MSFT_MpComputerStatus",
+    )?;
+    let r = rows
+        .first()
+        .ok_or("no MSFT_MpComputerStatus instance (Defender not installed?)")?;
+    Ok(DefenderStatus {
+        antivirus_enabled: r.get_bool("AntivirusEnabled"),
+        real_time_protection_enabled: r.get_bool("RealTimeProtectionEnabled"),
+        // 從未更新過病毒碼時為 65535
+        signature_age_days: r
+            .get_u32("AntivirusSignatureAge")
+            .filter(|&d| d != u16::MAX as u32),
+        signature_last_updated: r.get_datetime("AntivirusSignatureLastUpdated"),
+        last_quick_scan_time: r.get_datetime("QuickScanEndTime"),
+        last_full_scan_time: r.get_datetime("FullScanEndTime"),
+    })
+}
@@ -0,0 +1,88 @@
+//! 風扇轉速：Win32_Fan，以及有安裝時的 OpenHardwareMonitor / LibreHardwareMonitor WMI provider
+//!
+//! 多數消費級主機板的 Win32_Fan 是空的或沒有轉速；完全讀不到時回傳空陣列並記一筆 error，
+//! 不捏造 0 RPM 的風扇。
+use serde::Serialize;
+
+use crate::{wmi, MetricError};
+
+/// 與 OpenHardwareMonitor 相容的 namespace（LibreHardwareMonitor 沿用同一套 Sensor 類別）
+const HARDWARE_MONITOR_NAMESPACES: &[(&str, &str)] = &[
+    ("root\\LibreHardwareMonitor", "librehardwaremonitor"),
+    ("root\\OpenHardwareMonitor", "openhardwaremonitor"),
+];
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct FanReading {
+    pub name: String,
+    pub rpm: f64,
+    /// win32_fan / librehardwaremonitor / openhardwaremonitor
+    pub source: &'static str,
+}
+
+#[derive(Serialize)]
+pub struct SensorsData {
+    pub fans: Vec<FanReading>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub async fn gather_sensors(errors: &mut Vec<MetricError>) -> SensorsData {
+    let fans = tokio::task::spawn_blocking(query_fans)
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+    let fans = fans.unwrap_or_else(|e| {
+        errors.push(MetricError {
+            metric: vec!["sensors.fans".into()],
+            err: e,
+        });
+        Vec::new()
+    });
+    SensorsData { fans }
+}
+
+fn query_fans() -> Result<Vec<FanReading>, String> {
+    // 硬體監控 provider 的讀值比 Win32_Fan 可靠，有就優先使用；沒安裝時 namespace 不存在，直接略過
+    for &(namespace, source) in HARDWARE_MONITOR_NAMESPACES {
+        let Ok(rows) = wmi::query(
+            namespace,
+            "SELECT Name, Value FROM Sensor WHERE SensorType = 'Fan'",
+        ) else {
+            continue;
+        };
+        let fans: Vec<FanReading> = rows
+            .iter()
+            .filter_map(|r| {
+                Some(FanReading {
+                    name: r.get_string("Name")?,
+                    rpm: r.get_f64("Value")?,
+                    source,
+                })
+            })
+            .collect();
+        if !fans.is_empty() {
+            return Ok(fans);
+        }
+    }
+
+    let rows = wmi::query("root\\CIMV2", "SELECT Name, DesiredSpeed FROM Win32_Fan")?;
+    let fans: Vec<FanReading> = rows
+        .iter()
+        .filter_map(|r| {
+            Some(FanReading {
+                name: r.get_string("Name").unwrap_or_else(|| "Fan".into()),
+                // 韌體沒有回報轉速的 Win32_Fan 實例沒有參考價值
+                rpm: r.get_u64("DesiredSpeed").filter(|&s| s > 0)? as f64,
+                source: "win32_fan",
+            })
+        })
+        .collect();
+    if fans.is_empty() {
+        return Err("no fan speed available (Win32_Fan reports no speed and no \
+             OpenHardwareMonitor/LibreHardwareMonitor WMI provider is running)"
+            .into());
+    }
+    Ok(fans)
+}
@@ -0,0 +1,261 @@
+//! Windows 服務狀態：EnumServicesStatusExW 列舉，啟動類型另以 QueryServiceConfigW 逐一查詢
+use serde::{Deserialize, Serialize};
+use std::sync::LazyLock;
+use windows::{
+    core::{HSTRING, PCWSTR},
+    Win32::{
+        Foundation::ERROR_MORE_DATA,
+        Security::SC_HANDLE,
+        System::Services::{
+            CloseServiceHandle, EnumServicesStatusExW, OpenSCManagerW, OpenServiceW,
+            QueryServiceConfigW, ENUM_SERVICE_STATUS_PROCESSW, QUERY_SERVICE_CONFIGW,
+            SC_ENUM_PROCESS_INFO, SC_MANAGER_ENUMERATE_SERVICE, SERVICE_QUERY_CONFIG,
+            SERVICE_STATE_ALL, SERVICE_WIN32,
+        },
+    },
+};
+
+use crate::{config, MetricError};
+
+/// 第一次列舉用的緩衝區大小；不夠時依 pcbBytesNeeded 加大後接續
+const INITIAL_BUFFER: usize = 64 * 1024;
+
+/// Drop 時 CloseServiceHandle 的 SC_HANDLE
+struct ScHandle(SC_HANDLE);
+
+impl Drop for ScHandle {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = CloseServiceHandle(self.0);
+        }
+    }
+}
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct ServiceData {
+    /// 服務的短名稱（sc.exe 使用的名稱），例如 `MSSQLSERVER`
+    pub name: String,
+    pub display_name: String,
+    /// stopped / start_pending / stop_pending / running / continue_pending / pause_pending / paused
+    pub state: String,
+    /// boot / system / automatic / manual / disabled；權限不足讀不到設定時為 None
+    pub start_type: Option<String>,
+    /// 未執行時為 None
+    pub pid: Option<u32>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct ServiceQuery {
+    /// 只回傳指定狀態，例如 `running`、`stopped`
+    pub state: Option<String>,
+    /// 服務名稱前綴（不分大小寫）
+    pub name: Option<String>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub fn gather_services(query: &ServiceQuery, errors: &mut Vec<MetricError>) -> Vec<ServiceData> {
+    let prefix = query.name.as_deref().map(str::to_lowercase);
+    let keep = |name: &str| {
+        prefix
+            .as_ref()
+            .is_none_or(|p| name.to_lowercase().starts_with(p.as_str()))
+    };
+    match enum_services(query.state.as_deref(), keep) {
+        Ok(list) => list,
+        Err(e) => {
+            errors.push(MetricError {
+                metric: vec!["services".into()],
+                err: e,
+            });
+            Vec::new()
+        }
+    }
+}
+
+/// `keep` 依服務名稱篩選；只有留下的服務才會另外查詢啟動類型
+fn enum_services(
+    state: Option<&str>,
+    keep: impl Fn(&str) -> bool,
+) -> Result<Vec<ServiceData>, String> {
+    let scm =
+        unsafe { OpenSCManagerW(PCWSTR::null(), PCWSTR::null(), SC_MANAGER_ENUMERATE_SERVICE) }
+            .map(ScHandle)
+            .map_err(|e| format!("OpenSCManagerW failed: {e}"))?;
+
+    let mut services = Vec::new();
+
+    // u64 緩衝區確保結構對齊
+    let mut buf = vec![0u64; INITIAL_BUFFER / 8];
+    let mut resume = 0u32;
+    loop {
+        let mut needed = 0u32;
+        let mut returned = 0u32;
+        let result = unsafe {
+            EnumServicesStatusExW(
+                scm.0,
+                SC_ENUM_PROCESS_INFO,
+                SERVICE_WIN32,
+                SERVICE_STATE_ALL,
+                Some(std::slice::from_raw_parts_mut(
+                    buf.as_mut_ptr().cast(),
+                    buf.len() * 8,
+                )),
+                &mut needed,
+                &mut returned,
+                Some(&mut resume),
+                PCWSTR::null(),
+            )
+        };
+
+        let rows = unsafe {
+            std::slice::from_raw_parts(
+                buf.as_ptr() as *const ENUM_SERVICE_STATUS_PROCESSW,
+                returned as usize,
+            )
+        };
+        for row in rows {
+            let name = unsafe { row.lpServiceName.to_string() }.unwrap_or_default();
+            if !keep(&name) {
+                continue;
+            }
+            let status = &row.ServiceStatusProcess;
+            let current = state_name(status.dwCurrentState.0);
+            if state.is_some_and(|want| !want.eq_ignore_ascii_case(current)) {
+                continue;
+            }
+            services.push(ServiceData {
+                start_type: query_start_type(&scm, &name),
+                display_name: unsafe { row.lpDisplayName.to_string() }.unwrap_or_default(),
+                state: current.into(),
+                pid: (status.dwProcessId != 0).then_some(status.dwProcessId),
+                name,
+            });
+        }
+
+        match result {
+            Ok(()) => break,
+            // 還有更多服務：依回報的大小加大緩衝區，resume handle 讓下一次接續
+            Err(e) if e.code() == ERROR_MORE_DATA.to_hresult() => {
+                let want = (needed as usize).div_ceil(8);
+                if want > buf.len() {
+                    buf.resize(want, 0);
+                }
+            }
+            Err(e) => return Err(format!("EnumServicesStatusExW failed: {e}")),
+        }
+    }
+
+    services.sort_by_key(|s| s.name.to_lowercase());
+    Ok(services)
+}
+
+/// 單一服務目前的狀態（running / stopped…）；服務不存在時為 None
+pub fn service_state(name: &str) -> Result<Option<String>, String> {
+    let found = enum_services(None, |n| n.eq_ignore_ascii_case(name))?;
+    Ok(found.into_iter().next().map(|s| s.state))
+}
+
+/* ---------- 監看清單 ---------- */
+
+/// CAPTURE_WATCH_SERVICES：逗號分隔的服務短名稱，例如 `MSSQLSERVER,W32Time`
+static WATCHED: LazyLock<Vec<String>> =
+    LazyLock::new(|| config::env_list("CAPTURE_WATCH_SERVICES"));
+
+#[derive(Serialize)]
+pub struct WatchedServiceData {
+    #[serde(flatten)]
+    pub service: ServiceData,
+    /// 啟動類型為 automatic 卻沒有在執行時為 false
+    pub healthy: bool,
+}
+
+/// 只回報監看清單中的服務；不健康或找不到的服務各記一筆 error
+pub fn gather_watched_services(errors: &mut Vec<MetricError>) -> Vec<WatchedServiceData> {
+    if WATCHED.is_empty() {
+        return Vec::new();
+    }
+
+    let keep = |name: &str| WATCHED.iter().any(|w| w.eq_ignore_ascii_case(name));
+    let found = match enum_services(None, keep) {
+        Ok(list) => list,
+        Err(e) => {
+            errors.push(MetricError {
+                metric: vec!["services".into()],
+                err: e,
+            });
+            return Vec::new();
+        }
+    };
+
+    for w in WATCHED.iter() {
+        if !found.iter().any(|s| s.name.eq_ignore_ascii_case(w)) {
+            errors.push(MetricError {
+                metric: vec![format!("services.{w}")],
+                err: format!("service {w} not found"),
+            });
+        }
+    }
+
+    found
+        .into_iter()
+        .map(|service| {
+            let healthy =
+                service.start_type.as_deref() != Some("automatic") || service.state == "running";
+            if !healthy {
+                errors.push(MetricError {
+                    metric: vec![format!("services.{}", service.name)],
+                    err: format!(
+                        "service {} is set to automatic but is {}",
+                        service.name, service.state
+                    ),
+                });
+            }
+            WatchedServiceData { service, healthy }
+        })
+        .collect()
+}
+
+/// 部分系統服務的設定對一般使用者不開放，讀不到就回 None
+fn query_start_type(scm: &ScHandle, name: &str) -> Option<String> {
+    unsafe {
+        let svc = ScHandle(OpenServiceW(scm.0, &HSTRING::from(name), SERVICE_QUERY_CONFIG).ok()?);
+
+        let mut needed = 0u32;
+        let _ = QueryServiceConfigW(svc.0, None, 0, &mut needed);
+        let mut buf = vec![0u64; (needed as usize).div_ceil(8)];
+        QueryServiceConfigW(
+            svc.0,
+            Some(buf.as_mut_ptr().cast()),
+            (buf.len() * 8) as u32,
+            &mut needed,
+        )
+        .ok()?;
+        let config = &*(buf.as_ptr() as *const QUERY_SERVICE_CONFIGW);
+
+        let kind = match config.dwStartType.0 {
+            0 => "boot",
+            1 => "system",
+            2 => "automatic",
+            3 => "manual",
+            4 => "disabled",
+            _ => return None,
+        };
+        Some(kind.into())
+    }
+}
+
+fn state_name(state: u32) -> &'static str {
+    match state {
+        1 => "stopped",
+        2 => "start_pending",
+        3 => "stop_pending",
+        4 => "running",
+        5 => "continue_pending",
+        6 => "pause_pending",
+        7 => "paused",
+        _ => "unknown",
+    }
+}
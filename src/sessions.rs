@@ -0,0 +1,211 @@
+//! 登入工作階段：WTSEnumerateSessionsW 列舉，WTSQuerySessionInformationW 補上使用者、用戶端與登入時間
+//!
+//! 閒置時間：agent 自己所在的工作階段用 GetLastInputInfo；其他工作階段用 WTSINFO 的 LastInputTime，
+//! 但主控台工作階段通常不填這個值，此時 idle_seconds 為 None 並記一筆 error（以服務執行時最常見）。
+//! 鎖定狀態來自 WTSSessionInfoEx 的 SessionFlags。
+use serde::Serialize;
+use windows::{
+    core::PWSTR,
+    Win32::{
+        System::{
+            RemoteDesktop::{
+                ProcessIdToSessionId, WTSClientName, WTSEnumerateSessionsW, WTSFreeMemory,
+                WTSQuerySessionInformationW, WTSSessionInfo, WTSSessionInfoEx, WTSINFOEXW,
+                WTSINFOW, WTS_CURRENT_SERVER_HANDLE, WTS_INFO_CLASS, WTS_SESSIONSTATE_LOCK,
+                WTS_SESSIONSTATE_UNLOCK, WTS_SESSION_INFOW,
+            },
+            SystemInformation::GetTickCount,
+            Threading::GetCurrentProcessId,
+        },
+        UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO},
+    },
+};
+
+use crate::{
+    win::{filetime_to_rfc3339, wide_to_string},
+    MetricError,
+};
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct SessionData {
+    pub session_id: u32,
+    pub user_name: String,
+    pub domain: String,
+    /// active / disconnected / connected / idle…；RDP 斷線但未登出的工作階段為 disconnected
+    pub state: String,
+    /// 主控台為 `Console`，RDP 為 `RDP-Tcp#N`
+    pub station: String,
+    /// RDP 用戶端電腦名稱；本機主控台為 None
+    pub client_name: Option<String>,
+    /// RFC3339（UTC）
+    pub logon_time: Option<String>,
+    /// 距最後一次鍵盤／滑鼠輸入的秒數；取不到時為 None（見 errors）
+    pub idle_seconds: Option<u64>,
+    /// 斷線的工作階段與系統回報 unknown 時為 None
+    pub session_locked: Option<bool>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+/// 只列出有使用者的工作階段（略過服務用的 session 0 與 RDP listener）
+pub fn gather_sessions(errors: &mut Vec<MetricError>) -> Vec<SessionData> {
+    let mut list: *mut WTS_SESSION_INFOW = std::ptr::null_mut();
+    let mut count = 0u32;
+    if let Err(e) =
+        unsafe { WTSEnumerateSessionsW(WTS_CURRENT_SERVER_HANDLE, 0, 1, &mut list, &mut count) }
+    {
+        errors.push(MetricError {
+            metric: vec!["sessions".into()],
+            err: format!("WTSEnumerateSessionsW failed: {e}"),
+        });
+        return Vec::new();
+    }
+
+    let mut own_session = 0u32;
+    let own_session = unsafe { ProcessIdToSessionId(GetCurrentProcessId(), &mut own_session) }
+        .ok()
+        .map(|_| own_session);
+
+    let infos = unsafe { std::slice::from_raw_parts(list, count as usize) };
+    let mut sessions = Vec::new();
+    for s in infos {
+        let info = match query_info(s.SessionId) {
+            Ok(i) => i,
+            Err(e) => {
+                errors.push(MetricError {
+                    metric: vec![format!("sessions.{}", s.SessionId)],
+                    err: e,
+                });
+                continue;
+            }
+        };
+        let user_name = wide_to_string(&info.UserName);
+        if user_name.is_empty() {
+            continue;
+        }
+        let idle_seconds = if own_session == Some(s.SessionId) {
+            own_idle_seconds()
+        } else {
+            wts_idle_seconds(&info)
+        };
+        if idle_seconds.is_none() {
+            errors.push(MetricError {
+                metric: vec![format!("sessions.{}.idle_seconds", s.SessionId)],
+                err: "last input time is only available for the agent's own session or RDP sessions; run the agent in the user's session to measure console idle time".into(),
+            });
+        }
+        sessions.push(SessionData {
+            session_id: s.SessionId,
+            user_name,
+            domain: wide_to_string(&info.Domain),
+            state: state_name(info.State.0).into(),
+            station: unsafe { s.pWinStationName.to_string() }.unwrap_or_default(),
+            client_name: query_string(s.SessionId, WTSClientName).filter(|c| !c.is_empty()),
+            logon_time: filetime_to_rfc3339(info.LogonTime),
+            idle_seconds,
+            session_locked: session_locked(s.SessionId),
+        });
+    }
+    unsafe { WTSFreeMemory(list.cast()) };
+    sessions
+}
+
+fn query_info(session_id: u32) -> Result<WTSINFOW, String> {
+    unsafe {
+        let mut buf = PWSTR::null();
+        let mut len = 0u32;
+        WTSQuerySessionInformationW(
+            WTS_CURRENT_SERVER_HANDLE,
+            session_id,
+            WTSSessionInfo,
+            &mut buf,
+            &mut len,
+        )
+        .map_err(|e| format!("WTSQuerySessionInformationW failed: {e}"))?;
+        let info = *(buf.0 as *const WTSINFOW);
+        WTSFreeMemory(buf.0.cast());
+        Ok(info)
+    }
+}
+
+/// GetLastInputInfo 只看得到呼叫端所在工作階段的輸入
+fn own_idle_seconds() -> Option<u64> {
+    let mut lii = LASTINPUTINFO {
+        cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+        dwTime: 0,
+    };
+    if !unsafe { GetLastInputInfo(&mut lii) }.as_bool() {
+        return None;
+    }
+    // 兩者都是開機後的毫秒數，約 49.7 天溢位一次
+    Some(unsafe { GetTickCount() }.wrapping_sub(lii.dwTime) as u64 / 1000)
+}
+
+/// LastInputTime 與 CurrentTime 都是 FILETIME；主控台工作階段 LastInputTime 通常為 0
+fn wts_idle_seconds(info: &WTSINFOW) -> Option<u64> {
+    (info.LastInputTime > 0 && info.CurrentTime >= info.LastInputTime)
+        .then(|| ((info.CurrentTime - info.LastInputTime) / 10_000_000) as u64)
+}
+
+/// Windows 7 / Server 2008 R2 的 SessionFlags 鎖定值相反，這裡以新版系統為準
+fn session_locked(session_id: u32) -> Option<bool> {
+    unsafe {
+        let mut buf = PWSTR::null();
+        let mut len = 0u32;
+        WTSQuerySessionInformationW(
+            WTS_CURRENT_SERVER_HANDLE,
+            session_id,
+            WTSSessionInfoEx,
+            &mut buf,
+            &mut len,
+        )
+        .ok()?;
+        let info = *(buf.0 as *const WTSINFOEXW);
+        WTSFreeMemory(buf.0.cast());
+        if info.Level != 1 {
+            return None;
+        }
+        match info.Data.WTSInfoExLevel1.SessionFlags as u32 {
+            WTS_SESSIONSTATE_LOCK => Some(true),
+            WTS_SESSIONSTATE_UNLOCK => Some(false),
+            _ => None,
+        }
+    }
+}
+
+fn query_string(session_id: u32, class: WTS_INFO_CLASS) -> Option<String> {
+    unsafe {
+        let mut buf = PWSTR::null();
+        let mut len = 0u32;
+        WTSQuerySessionInformationW(
+            WTS_CURRENT_SERVER_HANDLE,
+            session_id,
+            class,
+            &mut buf,
+            &mut len,
+        )
+        .ok()?;
+        let s = buf.to_string().ok();
+        WTSFreeMemory(buf.0.cast());
+        s
+    }
+}
+
+/// WTS_CONNECTSTATE_CLASS
+fn state_name(state: i32) -> &'static str {
+    match state {
+        0 => "active",
+        1 => "connected",
+        2 => "connect_query",
+        3 => "shadow",
+        4 => "disconnected",
+        5 => "idle",
+        6 => "listen",
+        7 => "reset",
+        8 => "down",
+        9 => "init",
+        _ => "unknown",
+    }
+}
@@ -0,0 +1,378 @@
+//! 實體磁碟健康狀態：逐一開啟 \\.\PhysicalDriveN 以 DeviceIoControl 查詢
+//!
+//! - SATA / SAS：IOCTL_STORAGE_PREDICT_FAILURE，VendorSpecific 即 SMART 屬性表
+//! - NVMe：IOCTL_STORAGE_QUERY_PROPERTY 讀取 SMART / Health Information log page（0x02）
+//!
+//! - 溫度：優先用 StorageDeviceTemperatureProperty（Windows 10+），
+//!   其次 SMART 屬性，最後 MSFT_StorageReliabilityCounter（WMI）
+//!
+//! USB 外接盒與部分 RAID 控制器不轉送 SMART 指令，這時欄位為 None 並記錄 error
+use serde::Serialize;
+use std::{collections::HashMap, mem::size_of};
+use windows::{
+    core::HSTRING,
+    Win32::{
+        Foundation::{ERROR_FILE_NOT_FOUND, ERROR_PATH_NOT_FOUND, HANDLE},
+        Storage::FileSystem::{
+            BusTypeNvme, CreateFileW, FILE_FLAGS_AND_ATTRIBUTES, FILE_SHARE_READ, FILE_SHARE_WRITE,
+            OPEN_EXISTING,
+        },
+        System::{
+            Ioctl::{
+                NVMeDataTypeLogPage, PropertyStandardQuery, ProtocolTypeNvme,
+                StorageDeviceProperty, StorageDeviceProtocolSpecificProperty,
+                StorageDeviceTemperatureProperty, IOCTL_STORAGE_PREDICT_FAILURE,
+                IOCTL_STORAGE_QUERY_PROPERTY, STORAGE_DEVICE_DESCRIPTOR, STORAGE_PREDICT_FAILURE,
+                STORAGE_PROPERTY_QUERY, STORAGE_PROTOCOL_DATA_DESCRIPTOR,
+                STORAGE_PROTOCOL_SPECIFIC_DATA, STORAGE_TEMPERATURE_DATA_DESCRIPTOR,
+            },
+            IO::DeviceIoControl,
+        },
+    },
+};
+
+use crate::{win::OwnedHandle, wmi, MetricError};
+
+/// PhysicalDrive 編號可能不連續（拔掉的 USB 碟），固定掃描到這個上限
+pub const MAX_DISKS: u32 = 32;
+/// NVMe Health Information log page 的識別碼與長度
+const NVME_LOG_PAGE_HEALTH_INFO: u32 = 0x02;
+const NVME_HEALTH_LOG_LEN: usize = 512;
+/// SMART 屬性 ID
+const ATTR_REALLOCATED_SECTORS: u8 = 5;
+const ATTR_POWER_ON_HOURS: u8 = 9;
+const ATTR_AIRFLOW_TEMPERATURE: u8 = 190;
+const ATTR_TEMPERATURE: u8 = 194;
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize, Default)]
+pub struct SmartData {
+    /// 磁碟自行判斷即將故障；NVMe 為 critical warning 不為 0
+    pub predict_failure: Option<bool>,
+    pub temperature_c: Option<i32>,
+    pub power_on_hours: Option<u64>,
+    /// NVMe 沒有此屬性，固定為 None
+    pub reallocated_sectors: Option<u64>,
+}
+
+#[derive(Serialize)]
+pub struct DiskHealthData {
+    /// \\.\PhysicalDriveN 的 N，與 PhysicalDisk 計數器 instance 開頭的編號相同
+    pub disk: u32,
+    pub model: Option<String>,
+    pub serial: Option<String>,
+    /// sata / sas / nvme / usb / raid…
+    pub bus_type: String,
+    /// 目前溫度；沒有感測器的磁碟為 None
+    pub temperature_c: Option<i32>,
+    /// 磁碟回報的警告 / 臨界溫度門檻（NVMe 的 WCTEMP / CCTEMP），只有溫度屬性查詢提供
+    pub temperature_warning_c: Option<i32>,
+    pub temperature_critical_c: Option<i32>,
+    pub smart: SmartData,
+}
+
+/// StorageDeviceTemperatureProperty 的結果
+struct Temperature {
+    current: Option<i32>,
+    warning: Option<i32>,
+    critical: Option<i32>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub fn gather_disk_health(errors: &mut Vec<MetricError>) -> Vec<DiskHealthData> {
+    let mut disks = Vec::new();
+    for n in 0..MAX_DISKS {
+        let handle = match open_disk(n) {
+            Ok(Some(h)) => h,
+            Ok(None) => continue,
+            Err(e) => {
+                errors.push(MetricError {
+                    metric: vec![format!("disk_health.{n}")],
+                    err: e,
+                });
+                continue;
+            }
+        };
+
+        let (model, serial, bus) = match query_descriptor(&handle) {
+            Ok(d) => d,
+            Err(e) => {
+                errors.push(MetricError {
+                    metric: vec![format!("disk_health.{n}")],
+                    err: e,
+                });
+                (None, None, 0)
+            }
+        };
+
+        let smart = if bus == BusTypeNvme.0 {
+            query_nvme_health(&handle)
+        } else {
+            query_ata_smart(&handle)
+        };
+        let smart = smart.unwrap_or_else(|e| {
+            errors.push(MetricError {
+                metric: vec![format!("disk_health.{n}.smart")],
+                err: e,
+            });
+            SmartData::default()
+        });
+
+        let temp = query_temperature(&handle).unwrap_or(Temperature {
+            current: None,
+            warning: None,
+            critical: None,
+        });
+
+        disks.push(DiskHealthData {
+            disk: n,
+            model,
+            serial,
+            bus_type: bus_name(bus).into(),
+            temperature_c: temp.current.or(smart.temperature_c),
+            temperature_warning_c: temp.warning,
+            temperature_critical_c: temp.critical,
+            smart,
+        });
+    }
+
+    // 舊系統或驅動不支援上面兩種方式時，才付出一次 WMI 查詢的成本
+    if disks.iter().any(|d| d.temperature_c.is_none()) {
+        let wmi_temps = reliability_temperatures();
+        for d in disks.iter_mut().filter(|d| d.temperature_c.is_none()) {
+            d.temperature_c = wmi_temps.get(&d.disk).copied();
+        }
+    }
+    disks
+}
+
+/// 不存在的編號回傳 Ok(None)；存取權 0 即可送出查詢類 IOCTL，不需系統管理員
+pub fn open_disk(n: u32) -> Result<Option<OwnedHandle>, String> {
+    let path = HSTRING::from(format!("\\\\.\\PhysicalDrive{n}"));
+    match unsafe {
+        CreateFileW(
+            &path,
+            0,
+            FILE_SHARE_READ | FILE_SHARE_WRITE,
+            None,
+            OPEN_EXISTING,
+            FILE_FLAGS_AND_ATTRIBUTES(0),
+            HANDLE::default(),
+        )
+    } {
+        Ok(h) => Ok(Some(OwnedHandle(h))),
+        Err(e)
+            if e.code() == ERROR_FILE_NOT_FOUND.to_hresult()
+                || e.code() == ERROR_PATH_NOT_FOUND.to_hresult() =>
+        {
+            Ok(None)
+        }
+        Err(e) => Err(format!("CreateFileW(PhysicalDrive{n}) failed: {e}")),
+    }
+}
+
+/// 同一個緩衝區同時當輸入與輸出（METHOD_BUFFERED）
+pub fn ioctl(
+    handle: &OwnedHandle,
+    code: u32,
+    buf: &mut [u64],
+    in_len: usize,
+) -> windows::core::Result<()> {
+    let mut returned = 0u32;
+    unsafe {
+        DeviceIoControl(
+            handle.0,
+            code,
+            (in_len > 0).then_some(buf.as_ptr().cast()),
+            in_len as u32,
+            Some(buf.as_mut_ptr().cast()),
+            (buf.len() * 8) as u32,
+            Some(&mut returned),
+            None,
+        )
+    }
+}
+
+/// (model, serial, STORAGE_BUS_TYPE)
+fn query_descriptor(handle: &OwnedHandle) -> Result<(Option<String>, Option<String>, i32), String> {
+    let mut buf = vec![0u64; 128];
+    unsafe {
+        let query = &mut *(buf.as_mut_ptr() as *mut STORAGE_PROPERTY_QUERY);
+        query.PropertyId = StorageDeviceProperty;
+        query.QueryType = PropertyStandardQuery;
+        ioctl(
+            handle,
+            IOCTL_STORAGE_QUERY_PROPERTY,
+            &mut buf,
+            size_of::<STORAGE_PROPERTY_QUERY>(),
+        )
+        .map_err(|e| format!("IOCTL_STORAGE_QUERY_PROPERTY failed: {e}"))?;
+
+        let desc = &*(buf.as_ptr() as *const STORAGE_DEVICE_DESCRIPTOR);
+        let bytes = std::slice::from_raw_parts(buf.as_ptr() as *const u8, buf.len() * 8);
+        Ok((
+            ascii_at(bytes, desc.ProductIdOffset),
+            ascii_at(bytes, desc.SerialNumberOffset),
+            desc.BusType.0,
+        ))
+    }
+}
+
+/// 不支援此屬性（Windows 10 以前或驅動未實作）時回傳 None
+fn query_temperature(handle: &OwnedHandle) -> Option<Temperature> {
+    let mut buf = vec![0u64; 64];
+    unsafe {
+        let query = &mut *(buf.as_mut_ptr() as *mut STORAGE_PROPERTY_QUERY);
+        query.PropertyId = StorageDeviceTemperatureProperty;
+        query.QueryType = PropertyStandardQuery;
+        ioctl(
+            handle,
+            IOCTL_STORAGE_QUERY_PROPERTY,
+            &mut buf,
+            size_of::<STORAGE_PROPERTY_QUERY>(),
+        )
+        .ok()?;
+
+        let desc = &*(buf.as_ptr() as *const STORAGE_TEMPERATURE_DATA_DESCRIPTOR);
+        // 512 bytes 的緩衝區放得下 30 筆感測器資料，超出的不讀
+        let infos = std::slice::from_raw_parts(
+            desc.TemperatureInfo.as_ptr(),
+            (desc.InfoCount as usize).min(30),
+        );
+        // Index 0 為整顆磁碟的綜合溫度，其餘是個別感測器
+        let current = infos
+            .iter()
+            .find(|i| i.Index == 0)
+            .or(infos.first())
+            .map(|i| i.Temperature as i32);
+        let threshold = |t: i16| (t > 0).then_some(t as i32);
+        Some(Temperature {
+            current,
+            warning: threshold(desc.WarningTemperature),
+            critical: threshold(desc.CriticalTemperature),
+        })
+    }
+}
+
+/// PhysicalDrive 編號 → 溫度；Storage 管理 API（Windows 8+）沒有資料時回傳空表
+fn reliability_temperatures() -> HashMap<u32, i32> {
+    let Ok(rows) = wmi::query(
+        "root\\Microsoft\\Windows\\Storage",
+        "SELECT DeviceId, Temperature FROM MSFT_StorageReliabilityCounter",
+    ) else {
+        return HashMap::new();
+    };
+    rows.iter()
+        .filter_map(|r| {
+            let disk = r.get_string("DeviceId")?.parse().ok()?;
+            let temp = r.get_u32("Temperature").filter(|&t| t > 0)?;
+            Some((disk, temp as i32))
+        })
+        .collect()
+}
+
+/// 描述元中以 offset 指向的 NUL 結尾 ASCII 字串；offset 0 代表沒有
+fn ascii_at(bytes: &[u8], offset: u32) -> Option<String> {
+    let tail = bytes.get(offset as usize..).filter(|_| offset != 0)?;
+    let end = tail.iter().position(|&b| b == 0).unwrap_or(tail.len());
+    let s = String::from_utf8_lossy(&tail[..end]).trim().to_string();
+    (!s.is_empty()).then_some(s)
+}
+
+fn query_ata_smart(handle: &OwnedHandle) -> Result<SmartData, String> {
+    let mut buf = vec![0u64; size_of::<STORAGE_PREDICT_FAILURE>().div_ceil(8)];
+    ioctl(handle, IOCTL_STORAGE_PREDICT_FAILURE, &mut buf, 0)
+        .map_err(|e| format!("IOCTL_STORAGE_PREDICT_FAILURE failed: {e}"))?;
+    let pf = unsafe { &*(buf.as_ptr() as *const STORAGE_PREDICT_FAILURE) };
+
+    // 屬性表：2 bytes 版本後接 30 筆 12 bytes 的屬性
+    // [id, flags(2), current, worst, raw(6), reserved]
+    let raw = |id: u8| {
+        pf.VendorSpecific[2..]
+            .chunks_exact(12)
+            .take(30)
+            .find(|a| a[0] == id)
+            .map(|a| u32::from_le_bytes([a[5], a[6], a[7], a[8]]))
+    };
+    // 部分廠商把最高 / 最低溫度放在 raw 的高位元組，只取最低位元組
+    let temperature_c = raw(ATTR_TEMPERATURE)
+        .or_else(|| raw(ATTR_AIRFLOW_TEMPERATURE))
+        .map(|r| (r & 0xff) as i32);
+
+    Ok(SmartData {
+        predict_failure: Some(pf.PredictFailure != 0),
+        temperature_c,
+        power_on_hours: raw(ATTR_POWER_ON_HOURS).map(u64::from),
+        reallocated_sectors: raw(ATTR_REALLOCATED_SECTORS).map(u64::from),
+    })
+}
+
+fn query_nvme_health(handle: &OwnedHandle) -> Result<SmartData, String> {
+    // STORAGE_PROPERTY_QUERY 的 AdditionalParameters 位置放 STORAGE_PROTOCOL_SPECIFIC_DATA，之後是 log 資料
+    let header = std::mem::offset_of!(STORAGE_PROPERTY_QUERY, AdditionalParameters);
+    let len = header + size_of::<STORAGE_PROTOCOL_SPECIFIC_DATA>() + NVME_HEALTH_LOG_LEN;
+    let mut buf = vec![0u64; len.div_ceil(8)];
+    unsafe {
+        let query = &mut *(buf.as_mut_ptr() as *mut STORAGE_PROPERTY_QUERY);
+        query.PropertyId = StorageDeviceProtocolSpecificProperty;
+        query.QueryType = PropertyStandardQuery;
+        let proto = &mut *((buf.as_mut_ptr() as *mut u8).add(header)
+            as *mut STORAGE_PROTOCOL_SPECIFIC_DATA);
+        proto.ProtocolType = ProtocolTypeNvme;
+        proto.DataType = NVMeDataTypeLogPage.0 as u32;
+        proto.ProtocolDataRequestValue = NVME_LOG_PAGE_HEALTH_INFO;
+        proto.ProtocolDataOffset = size_of::<STORAGE_PROTOCOL_SPECIFIC_DATA>() as u32;
+        proto.ProtocolDataLength = NVME_HEALTH_LOG_LEN as u32;
+
+        ioctl(handle, IOCTL_STORAGE_QUERY_PROPERTY, &mut buf, len)
+            .map_err(|e| format!("NVMe health log query failed: {e}"))?;
+
+        let desc = &*(buf.as_ptr() as *const STORAGE_PROTOCOL_DATA_DESCRIPTOR);
+        let data = &desc.ProtocolSpecificData;
+        let start = std::mem::offset_of!(STORAGE_PROTOCOL_DATA_DESCRIPTOR, ProtocolSpecificData)
+            + data.ProtocolDataOffset as usize;
+        let bytes = std::slice::from_raw_parts(buf.as_ptr() as *const u8, buf.len() * 8);
+        let log = bytes
+            .get(start..start + NVME_HEALTH_LOG_LEN)
+            .filter(|_| data.ProtocolDataLength as usize >= NVME_HEALTH_LOG_LEN)
+            .ok_or("NVMe health log query returned a short buffer")?;
+
+        // byte 0 critical warning、1–2 溫度（Kelvin）、128–143 power-on hours（128 位元，取低 64 位元）
+        let kelvin = u16::from_le_bytes([log[1], log[2]]);
+        let hours = u64::from_le_bytes(log[128..136].try_into().unwrap());
+        Ok(SmartData {
+            predict_failure: Some(log[0] != 0),
+            temperature_c: (kelvin != 0).then(|| kelvin as i32 - 273),
+            power_on_hours: Some(hours),
+            reallocated_sectors: None,
+        })
+    }
+}
+
+/// STORAGE_BUS_TYPE
+fn bus_name(bus: i32) -> &'static str {
+    match bus {
+        1 => "scsi",
+        2 => "atapi",
+        3 => "ata",
+        4 => "1394",
+        5 => "ssa",
+        6 => "fibre",
+        7 => "usb",
+        8 => "raid",
+        9 => "iscsi",
+        10 => "sas",
+        11 => "sata",
+        12 => "sd",
+        13 => "mmc",
+        14 => "virtual",
+        15 => "file_backed_virtual",
+        16 => "spaces",
+        17 => "nvme",
+        18 => "scm",
+        19 => "ufs",
+        _ => "unknown",
+    }
+}
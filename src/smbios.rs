@@ -0,0 +1,103 @@
+//! SMBIOS：GetSystemFirmwareTable('RSMB') 取得原始表格，拆成個別結構
+//!
+//! 韌體表格開機後不會改變，只讀取一次並快取。
+use std::sync::LazyLock;
+use windows::Win32::System::SystemInformation::{GetSystemFirmwareTable, RSMB};
+
+/// RawSMBIOSData 標頭：呼叫方式、主次版本、DMI 版本（各 1 byte）+ 表格長度（4 bytes）
+const RAW_HEADER_LEN: usize = 8;
+/// Type 127 = End-of-Table
+const END_OF_TABLE: u8 = 127;
+
+static TABLE: LazyLock<Result<Vec<u8>, String>> = LazyLock::new(read_table);
+
+/// 一個 SMBIOS 結構：formatted 區段（含 4 bytes 標頭）與後面的字串集合
+pub struct Structure<'a> {
+    pub kind: u8,
+    data: &'a [u8],
+    strings: Vec<&'a [u8]>,
+}
+
+impl Structure<'_> {
+    pub fn byte(&self, offset: usize) -> Option<u8> {
+        self.data.get(offset).copied()
+    }
+
+    pub fn word(&self, offset: usize) -> Option<u16> {
+        let b = self.data.get(offset..offset + 2)?;
+        Some(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    pub fn dword(&self, offset: usize) -> Option<u32> {
+        let b = self.data.get(offset..offset + 4)?;
+        Some(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    /// `offset` 處的 byte 是字串編號（從 1 開始，0 代表沒有）；
+    /// 「To be filled by O.E.M.」之類的佔位字串照原樣回傳
+    pub fn string(&self, offset: usize) -> Option<String> {
+        let index = self.byte(offset)? as usize;
+        let s = self.strings.get(index.checked_sub(1)?)?;
+        Some(String::from_utf8_lossy(s).trim().to_string())
+    }
+}
+
+/// 所有結構；讀不到表格時回傳錯誤訊息
+pub fn structures() -> Result<Vec<Structure<'static>>, String> {
+    let table = TABLE.as_ref().map_err(Clone::clone)?;
+    Ok(parse(table))
+}
+
+fn read_table() -> Result<Vec<u8>, String> {
+    let size = unsafe { GetSystemFirmwareTable(RSMB, 0, None) };
+    if size == 0 {
+        return Err("GetSystemFirmwareTable(RSMB) returned no data".into());
+    }
+    let mut buf = vec![0u8; size as usize];
+    let written = unsafe { GetSystemFirmwareTable(RSMB, 0, Some(&mut buf)) };
+    if written == 0 || written > size {
+        return Err("GetSystemFirmwareTable(RSMB) failed".into());
+    }
+
+    let len = u32::from_le_bytes(buf[4..8].try_into().unwrap()) as usize;
+    let end = (RAW_HEADER_LEN + len).min(written as usize);
+    Ok(buf[RAW_HEADER_LEN..end].to_vec())
+}
+
+fn parse(table: &[u8]) -> Vec<Structure<'_>> {
+    let mut out = Vec::new();
+    let mut pos = 0;
+    while pos + 4 <= table.len() {
+        let kind = table[pos];
+        let len = table[pos + 1] as usize;
+        if len < 4 || pos + len > table.len() {
+            break;
+        }
+        let data = &table[pos..pos + len];
+
+        // 字串集合：連續的 NUL 結尾字串，以兩個 NUL 結束（沒有字串時只有兩個 NUL）
+        let mut strings = Vec::new();
+        let mut cur = pos + len;
+        while cur < table.len() && table[cur] != 0 {
+            let start = cur;
+            while cur < table.len() && table[cur] != 0 {
+                cur += 1;
+            }
+            strings.push(&table[start..cur]);
+            cur += 1;
+        }
+        // 跳過結尾的 NUL（沒有字串時是兩個）
+        cur = (cur + if strings.is_empty() { 2 } else { 1 }).min(table.len());
+
+        out.push(Structure {
+            kind,
+            data,
+            strings,
+        });
+        if kind == END_OF_TABLE {
+            break;
+        }
+        pos = cur;
+    }
+    out
+}
@@ -0,0 +1,93 @@
+//! 已安裝軟體清單：讀取登錄檔的 Uninstall 機碼（即「程式和功能」的資料來源）
+//!
+//! 不使用 Win32_Product：查詢它會讓 Windows Installer 對每個 MSI 做一致性檢查，甚至觸發修復。
+//! HKCU 是執行 agent 的帳號的設定檔；以服務執行時通常看不到一般使用者自行安裝的程式。
+use serde::{Deserialize, Serialize};
+use windows::Win32::System::Registry::{HKEY, HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE};
+
+use crate::win::{reg_dword, reg_string, reg_subkeys};
+
+const SOURCES: &[(HKEY, &str, &str)] = &[
+    (
+        HKEY_LOCAL_MACHINE,
+        "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Uninstall",
+        "machine",
+    ),
+    (
+        HKEY_LOCAL_MACHINE,
+        "SOFTWARE\\WOW6432Node\\Microsoft\\Windows\\CurrentVersion\\Uninstall",
+        "machine_x86",
+    ),
+    (
+        HKEY_CURRENT_USER,
+        "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Uninstall",
+        "user",
+    ),
+];
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct SoftwareData {
+    pub display_name: String,
+    pub version: Option<String>,
+    pub publisher: Option<String>,
+    /// `YYYY-MM-DD`；安裝程式沒寫或格式不是 YYYYMMDD 時為 None
+    pub install_date: Option<String>,
+    /// 安裝程式自行估計的大小，不一定準確
+    pub estimated_size_bytes: Option<u64>,
+    /// machine（64 位元）/ machine_x86（WOW6432Node）/ user
+    pub source: &'static str,
+}
+
+#[derive(Deserialize, Default)]
+pub struct SoftwareQuery {
+    /// display_name 的子字串，不分大小寫
+    pub name: Option<String>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub fn gather_software(q: &SoftwareQuery) -> Vec<SoftwareData> {
+    let needle = q.name.as_deref().map(str::to_lowercase);
+    let mut list: Vec<SoftwareData> = SOURCES
+        .iter()
+        .flat_map(|&(root, path, source)| {
+            reg_subkeys(root, path)
+                .into_iter()
+                .filter_map(move |name| read_entry(root, &format!("{path}\\{name}"), source))
+        })
+        .filter(|s| {
+            needle
+                .as_deref()
+                .is_none_or(|n| s.display_name.to_lowercase().contains(n))
+        })
+        .collect();
+    list.sort_by_key(|s| s.display_name.to_lowercase());
+    list
+}
+
+/// 沒有 DisplayName 的項目、系統元件與掛在其他產品下的更新不會出現在「程式和功能」，一併略過
+fn read_entry(root: HKEY, key: &str, source: &'static str) -> Option<SoftwareData> {
+    let display_name = reg_string(root, key, "DisplayName").filter(|n| !n.trim().is_empty())?;
+    if reg_dword(root, key, "SystemComponent") == Some(1)
+        || reg_string(root, key, "ParentKeyName").is_some()
+    {
+        return None;
+    }
+    Some(SoftwareData {
+        display_name: display_name.trim().to_string(),
+        version: reg_string(root, key, "DisplayVersion").filter(|v| !v.is_empty()),
+        publisher: reg_string(root, key, "Publisher").filter(|p| !p.is_empty()),
+        install_date: reg_string(root, key, "InstallDate").and_then(|d| parse_install_date(&d)),
+        // EstimatedSize 單位為 KB
+        estimated_size_bytes: reg_dword(root, key, "EstimatedSize").map(|kb| kb as u64 * 1024),
+        source,
+    })
+}
+
+fn parse_install_date(raw: &str) -> Option<String> {
+    let raw = raw.trim();
+    (raw.len() == 8 && raw.bytes().all(|b| b.is_ascii_digit()))
+        .then(|| format!("{}-{}-{}", &raw[..4], &raw[4..6], &raw[6..]))
+}
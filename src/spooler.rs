@@ -0,0 +1,192 @@
+//! 列印佇列：EnumPrintersW 列出本機印表機，EnumJobsW 讀每個佇列的工作
+//!
+//! Spooler 服務沒在執行時 API 只會失敗或回空清單，先看服務狀態，直接回報停止而不是假裝沒有工作。
+use chrono::{NaiveDate, Utc};
+use serde::Serialize;
+use windows::{
+    core::{HSTRING, PCWSTR},
+    Win32::{
+        Foundation::{HANDLE, SYSTEMTIME},
+        Graphics::Printing::{
+            ClosePrinter, EnumJobsW, EnumPrintersW, OpenPrinterW, JOB_INFO_1W, JOB_STATUS_ERROR,
+            PRINTER_ENUM_LOCAL, PRINTER_INFO_2W, PRINTER_STATUS_ERROR, PRINTER_STATUS_OFFLINE,
+            PRINTER_STATUS_PAPER_JAM, PRINTER_STATUS_PAPER_OUT, PRINTER_STATUS_PAUSED,
+        },
+    },
+};
+
+use crate::{services, MetricError};
+
+const PRINTER_STATUS_NAMES: &[(u32, &str)] = &[
+    (PRINTER_STATUS_PAUSED, "paused"),
+    (PRINTER_STATUS_ERROR, "error"),
+    (PRINTER_STATUS_PAPER_JAM, "paper_jam"),
+    (PRINTER_STATUS_PAPER_OUT, "paper_out"),
+    (PRINTER_STATUS_OFFLINE, "offline"),
+];
+
+/// Drop 時 ClosePrinter
+struct Printer(HANDLE);
+
+impl Drop for Printer {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = ClosePrinter(self.0);
+        }
+    }
+}
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct PrinterQueue {
+    pub name: String,
+    pub jobs: u32,
+    pub error_jobs: u32,
+    /// 最早送出的工作已等待的秒數；搭配 jobs 判斷佇列是否卡住
+    pub oldest_job_age_seconds: Option<i64>,
+    /// paused / error / paper_jam / paper_out / offline；正常時為空陣列
+    pub status: Vec<&'static str>,
+}
+
+#[derive(Serialize)]
+pub struct SpoolerData {
+    /// Spooler 服務狀態（running / stopped…）；服務不存在時為 None
+    pub service_state: Option<String>,
+    /// 服務沒在執行時以下皆為 None / 空陣列
+    pub total_jobs: Option<u32>,
+    pub error_jobs: Option<u32>,
+    pub printers: Vec<PrinterQueue>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub fn gather_spooler(errors: &mut Vec<MetricError>) -> SpoolerData {
+    let mut data = SpoolerData {
+        service_state: None,
+        total_jobs: None,
+        error_jobs: None,
+        printers: Vec::new(),
+    };
+    match services::service_state("Spooler") {
+        Ok(state) => data.service_state = state,
+        Err(e) => errors.push(MetricError {
+            metric: vec!["spooler.service_state".into()],
+            err: e,
+        }),
+    }
+    if data.service_state.as_deref() != Some("running") {
+        errors.push(MetricError {
+            metric: vec!["spooler".into()],
+            err: match &data.service_state {
+                Some(state) => format!("Spooler service is {state}"),
+                None => "Spooler service not found".into(),
+            },
+        });
+        return data;
+    }
+
+    let printers = match enum_printers() {
+        Ok(p) => p,
+        Err(e) => {
+            errors.push(MetricError {
+                metric: vec!["spooler".into()],
+                err: e,
+            });
+            return data;
+        }
+    };
+
+    let now = Utc::now().naive_utc();
+    for (name, status) in printers {
+        let jobs = match enum_jobs(&name) {
+            Ok(j) => j,
+            Err(e) => {
+                errors.push(MetricError {
+                    metric: vec![format!("spooler.{name}")],
+                    err: e,
+                });
+                continue;
+            }
+        };
+        let oldest = jobs.iter().filter_map(|(_, t)| systemtime(t)).min();
+        data.printers.push(PrinterQueue {
+            jobs: jobs.len() as u32,
+            error_jobs: jobs
+                .iter()
+                .filter(|(status, _)| status & JOB_STATUS_ERROR != 0)
+                .count() as u32,
+            oldest_job_age_seconds: oldest.map(|t| (now - t).num_seconds().max(0)),
+            status: PRINTER_STATUS_NAMES
+                .iter()
+                .filter(|(bit, _)| status & bit != 0)
+                .map(|(_, n)| *n)
+                .collect(),
+            name,
+        });
+    }
+    data.total_jobs = Some(data.printers.iter().map(|p| p.jobs).sum());
+    data.error_jobs = Some(data.printers.iter().map(|p| p.error_jobs).sum());
+    data
+}
+
+/// (印表機名稱, Status 位元)
+fn enum_printers() -> Result<Vec<(String, u32)>, String> {
+    let (buf, count) = enum_buffer(|buf, needed, returned| unsafe {
+        EnumPrintersW(PRINTER_ENUM_LOCAL, PCWSTR::null(), 2, buf, needed, returned)
+    })
+    .map_err(|e| format!("EnumPrintersW failed: {e}"))?;
+    let items =
+        unsafe { std::slice::from_raw_parts(buf.as_ptr() as *const PRINTER_INFO_2W, count) };
+    Ok(items
+        .iter()
+        .map(|p| {
+            (
+                unsafe { p.pPrinterName.to_string() }.unwrap_or_default(),
+                p.Status,
+            )
+        })
+        .collect())
+}
+
+/// (Status 位元, 送出時間)
+fn enum_jobs(printer: &str) -> Result<Vec<(u32, SYSTEMTIME)>, String> {
+    let mut handle = HANDLE::default();
+    unsafe { OpenPrinterW(&HSTRING::from(printer), &mut handle, None) }
+        .map_err(|e| format!("OpenPrinterW failed: {e}"))?;
+    let handle = Printer(handle);
+    let (buf, count) = enum_buffer(|buf, needed, returned| unsafe {
+        EnumJobsW(handle.0, 0, u32::MAX, 1, buf, needed, returned)
+    })
+    .map_err(|e| format!("EnumJobsW failed: {e}"))?;
+    // 字串指標指向 buf 內部，只複製數值欄位給呼叫端
+    let items = unsafe { std::slice::from_raw_parts(buf.as_ptr() as *const JOB_INFO_1W, count) };
+    Ok(items.iter().map(|j| (j.Status, j.Submitted)).collect())
+}
+
+/// EnumPrinters / EnumJobs 共用的「先問大小再讀」；回傳 u64 緩衝區以確保結構對齊與筆數
+fn enum_buffer(
+    mut call: impl FnMut(Option<&mut [u8]>, &mut u32, &mut u32) -> windows::core::Result<()>,
+) -> windows::core::Result<(Vec<u64>, usize)> {
+    let (mut needed, mut returned) = (0u32, 0u32);
+    // 沒有任何項目時第一次呼叫就會成功；失敗但沒回報所需大小的是真正的錯誤
+    match call(None, &mut needed, &mut returned) {
+        Ok(()) => return Ok((Vec::new(), 0)),
+        Err(e) if needed == 0 => return Err(e),
+        Err(_) => {}
+    }
+    let mut buf = vec![0u64; (needed as usize).div_ceil(8)];
+    let bytes =
+        unsafe { std::slice::from_raw_parts_mut(buf.as_mut_ptr() as *mut u8, needed as usize) };
+    call(Some(bytes), &mut needed, &mut returned)?;
+    Ok((buf, returned as usize))
+}
+
+/// JOB_INFO_1 的 Submitted 為 UTC
+fn systemtime(st: &SYSTEMTIME) -> Option<chrono::NaiveDateTime> {
+    NaiveDate::from_ymd_opt(st.wYear as i32, st.wMonth as u32, st.wDay as u32)?.and_hms_opt(
+        st.wHour as u32,
+        st.wMinute as u32,
+        st.wSecond as u32,
+    )
+}
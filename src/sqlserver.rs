@@ -0,0 +1,119 @@
+//! SQL Server 效能計數器：設定 CAPTURE_SQLSERVER_INSTANCE 才啟用
+//!
+//! 預設執行個體的物件名稱是 `SQLServer:Buffer Manager`，具名執行個體是 `MSSQL$名稱:Buffer Manager`；
+//! 啟動時以 PdhEnumObjectsW 找出實際的前綴，再把計數器註冊到背景取樣器。
+use serde::Serialize;
+use std::sync::LazyLock;
+
+use crate::{
+    config,
+    pdh::{self, Format, SampledCounter},
+    MetricError,
+};
+
+/// 執行個體名稱；預設執行個體填 MSSQLSERVER
+static INSTANCE: LazyLock<Option<String>> =
+    LazyLock::new(|| config::env_string("CAPTURE_SQLSERVER_INSTANCE"));
+
+/// 找到的物件前綴（`SQLServer` 或 `MSSQL$名稱`）；只在啟用時才會查詢
+static PREFIX: LazyLock<Result<String, String>> = LazyLock::new(discover_prefix);
+
+/// (key, 物件, 計數器, 格式)
+const COUNTERS: &[(&str, &str, &str, Format)] = &[
+    ("sqlserver.buffer_cache_hit_ratio", "Buffer Manager", "Buffer cache hit ratio", Format::Double),
+    ("sqlserver.page_life_expectancy", "Buffer Manager", "Page life expectancy", Format::Large),
+    ("sqlserver.batch_requests_per_sec", "SQL Statistics", "Batch Requests/sec", Format::Double),
+    ("sqlserver.user_connections", "General Statistics", "User Connections", Format::Large),
+];
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct SqlServerData {
+    pub instance: String,
+    /// 0–1；低於 0.95 通常代表記憶體不足
+    pub buffer_cache_hit_ratio: Option<f64>,
+    /// 秒
+    pub page_life_expectancy: Option<u64>,
+    pub batch_requests_per_sec: Option<f64>,
+    pub user_connections: Option<u64>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+/// 背景取樣器要註冊的計數器；未啟用或找不到物件時為空
+///
+/// 路徑在啟動時才決定，只建立一次，所以直接 leak 成 'static
+pub fn pdh_counters() -> Vec<SampledCounter> {
+    let Some(Ok(prefix)) = INSTANCE.as_ref().map(|_| PREFIX.as_ref()) else {
+        return Vec::new();
+    };
+    COUNTERS
+        .iter()
+        .map(|&(key, object, counter, format)| {
+            let path: &'static str = format!("\\{prefix}:{object}\\{counter}").leak();
+            SampledCounter::new(key, path, format)
+        })
+        .collect()
+}
+
+/// 未啟用時回傳 None
+pub fn gather_sqlserver(errors: &mut Vec<MetricError>) -> Option<SqlServerData> {
+    let instance = INSTANCE.clone()?;
+    if let Err(e) = PREFIX.as_ref() {
+        errors.push(MetricError {
+            metric: vec!["sqlserver".into()],
+            err: e.clone(),
+        });
+    }
+
+    let mut value = |key: &str| match pdh::latest(key)? {
+        Ok(values) => values.first().map(|(_, v)| *v),
+        Err(e) => {
+            errors.push(MetricError {
+                metric: vec![key.into()],
+                err: e,
+            });
+            None
+        }
+    };
+
+    Some(SqlServerData {
+        instance,
+        buffer_cache_hit_ratio: value("sqlserver.buffer_cache_hit_ratio")
+            .map(|v| v.as_f64() / 100.0),
+        page_life_expectancy: value("sqlserver.page_life_expectancy").map(|v| v.as_i64() as u64),
+        batch_requests_per_sec: value("sqlserver.batch_requests_per_sec").map(|v| v.as_f64()),
+        user_connections: value("sqlserver.user_connections").map(|v| v.as_i64() as u64),
+    })
+}
+
+fn discover_prefix() -> Result<String, String> {
+    let instance = INSTANCE.as_deref().unwrap_or_default();
+    let wanted = if instance.eq_ignore_ascii_case("MSSQLSERVER") {
+        "SQLServer".to_string()
+    } else {
+        format!("MSSQL${instance}")
+    };
+
+    let objects = pdh::objects()?;
+    let prefixes: Vec<&str> = objects
+        .iter()
+        .filter_map(|o| o.strip_suffix(":Buffer Manager"))
+        .collect();
+    // 物件名稱的大小寫不一定與設定相同，以實際列舉到的為準
+    prefixes
+        .iter()
+        .find(|p| p.eq_ignore_ascii_case(&wanted))
+        .map(|p| p.to_string())
+        .ok_or_else(|| {
+            if prefixes.is_empty() {
+                "no SQL Server performance counters found on this machine".into()
+            } else {
+                format!(
+                    "no counters for instance {instance} (found: {})",
+                    prefixes.join(", ")
+                )
+            }
+        })
+}
@@ -0,0 +1,227 @@
+//! StatsD：設定 CAPTURE_STATSD_ADDR（host:port）時，每 CAPTURE_STATSD_INTERVAL_SECS（預設 10）秒
+//! 以 UDP 送出 CPU、記憶體、分頁檔、磁碟與網路的 gauge，計數器（網路位元組、封包等）以 `|c` 送出與上一輪的差值
+//!
+//! 名稱為 CAPTURE_STATSD_PREFIX（預設 capture）加上與 Graphite 相同的路徑，例如 `capture.web01.disk.C_.free_bytes:123|g`。
+//! CAPTURE_STATSD_TAGS 開啟時改用 DogStatsD 標籤：`capture.disk.free_bytes:123|g|#host:web01,device:C:`。
+//! 多行併成一個封包，每個封包不超過 MAX_PACKET_BYTES；UDP 不確認送達，只統計送出失敗的次數（capture.statsd）。
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    net::{ToSocketAddrs, UdpSocket},
+    sync::{LazyLock, RwLock},
+    time::Duration,
+};
+
+use crate::{
+    config, graphite,
+    samples::{self, Kind, Sample},
+};
+
+/// 常見的 1500 MTU 扣掉 IP / UDP 標頭與部分網路的額外封裝
+const MAX_PACKET_BYTES: usize = 1432;
+/// 只送這些區段的樣本
+const SECTIONS: &[&str] = &["cpu", "memory", "swap", "disk", "net"];
+
+static ADDR: LazyLock<Option<String>> = LazyLock::new(|| config::env_string("CAPTURE_STATSD_ADDR"));
+static PREFIX: LazyLock<String> = LazyLock::new(|| {
+    config::env_string("CAPTURE_STATSD_PREFIX").unwrap_or_else(|| "capture".into())
+});
+static INTERVAL: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(config::env_parse("CAPTURE_STATSD_INTERVAL_SECS", 10u64).max(1))
+});
+static TAGS: LazyLock<bool> = LazyLock::new(|| config::env_flag("CAPTURE_STATSD_TAGS"));
+static STATUS: RwLock<Option<StatsdStatus>> = RwLock::new(None);
+
+#[derive(Serialize, Clone, Default)]
+pub struct StatsdStatus {
+    pub addr: String,
+    /// 啟動以來累計
+    pub packets_sent: u64,
+    pub send_errors: u64,
+    pub last_error: Option<String>,
+}
+
+/* ---------- 輸出 ---------- */
+
+/// last 為各計數器上一輪送出時的累計值，以不含數值的整行為鍵
+fn lines(samples: &[Sample], host: &str, last: &mut HashMap<String, f64>) -> Vec<String> {
+    samples
+        .iter()
+        .filter(|s| s.value.is_finite())
+        .filter_map(|s| {
+            let (group, rest) = s.name.split_once('_')?;
+            if !SECTIONS.contains(&group) {
+                return None;
+            }
+            let (name, tags) = if *TAGS {
+                let tags: Vec<String> = std::iter::once(("host", host))
+                    .chain(s.labels.iter().map(|(k, v)| (*k, v.as_str())))
+                    .map(|(k, v)| format!("{k}:{}", tag_value(v)))
+                    .collect();
+                (
+                    format!("{}.{group}.{rest}", *PREFIX),
+                    format!("|#{}", tags.join(",")),
+                )
+            } else {
+                let mut path = format!("{}.{}.{group}", *PREFIX, graphite::component(host));
+                for (_, v) in &s.labels {
+                    path.push('.');
+                    path.push_str(&graphite::component(v));
+                }
+                (format!("{path}.{rest}"), String::new())
+            };
+            let (value, kind) = match s.kind {
+                Kind::Gauge => (s.value, "g"),
+                // 第一輪只記下基準；累計值變小（重開機、介面重設）時這一輪不送，下一輪從新的基準算
+                Kind::Counter => {
+                    let previous = last.insert(format!("{name}{tags}"), s.value)?;
+                    if s.value < previous {
+                        return None;
+                    }
+                    (s.value - previous, "c")
+                }
+            };
+            Some(format!("{name}:{value}|{kind}{tags}"))
+        })
+        .collect()
+}
+
+/// DogStatsD 標籤值不能有 `,`、`|`、`#` 與換行
+fn tag_value(v: &str) -> String {
+    v.chars()
+        .map(|c| match c {
+            ',' | '|' | '#' | '\n' => '_',
+            _ => c,
+        })
+        .collect()
+}
+
+/// 以換行串接，每個封包不超過 MAX_PACKET_BYTES；單行就超過時獨立成一個封包
+fn packets(lines: &[String]) -> Vec<String> {
+    let mut packets = Vec::new();
+    let mut current = String::new();
+    for line in lines {
+        if !current.is_empty() && current.len() + 1 + line.len() > MAX_PACKET_BYTES {
+            packets.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.push_str(line);
+    }
+    if !current.is_empty() {
+        packets.push(current);
+    }
+    packets
+}
+
+/* ---------- 背景傳送 ---------- */
+
+/// 未設定 CAPTURE_STATSD_ADDR 時什麼都不做
+pub fn start() {
+    let Some(addr) = ADDR.clone() else {
+        return;
+    };
+    *STATUS.write().unwrap() = Some(StatsdStatus {
+        addr: addr.clone(),
+        ..Default::default()
+    });
+    tokio::spawn(async move {
+        let mut last = HashMap::new();
+        loop {
+            let (sent, errors) = match crate::collect_all_blocking().await {
+                Ok(all) => {
+                    let host = all.hostname.as_deref().unwrap_or("unknown");
+                    let packets = packets(&lines(&samples::collect(&all), host, &mut last));
+                    let addr = addr.clone();
+                    tokio::task::spawn_blocking(move || send(&addr, &packets))
+                        .await
+                        .unwrap_or_else(|e| (0, vec![e.to_string()]))
+                }
+                Err(e) => (0, vec![format!("collect: {e}")]),
+            };
+            {
+                let mut status = STATUS.write().unwrap();
+                let status = status.get_or_insert_with(Default::default);
+                status.packets_sent += sent;
+                status.send_errors += errors.len() as u64;
+                if let Some(e) = errors.into_iter().last() {
+                    status.last_error = Some(e);
+                }
+            }
+            tokio::time::sleep(*INTERVAL).await;
+        }
+    });
+}
+
+/// 未設定 CAPTURE_STATSD_ADDR 時為 None
+pub fn status() -> Option<StatsdStatus> {
+    STATUS.read().unwrap().clone()
+}
+
+/// (送出的封包數, 每次失敗的原因)；解析或建立 socket 失敗時整輪算一次失敗
+fn send(addr: &str, packets: &[String]) -> (u64, Vec<String>) {
+    let socket = match open(addr) {
+        Ok(s) => s,
+        Err(e) => return (0, vec![e]),
+    };
+    let mut sent = 0;
+    let mut errors = Vec::new();
+    for packet in packets {
+        match socket.send(packet.as_bytes()) {
+            Ok(_) => sent += 1,
+            Err(e) => errors.push(format!("send to {addr}: {e}")),
+        }
+    }
+    (sent, errors)
+}
+
+/// 依解析結果的位址族綁定本機埠；Windows 的 IPv6 socket 預設不接受 IPv4 位址
+fn open(addr: &str) -> Result<UdpSocket, String> {
+    let target = addr
+        .to_socket_addrs()
+        .map_err(|e| format!("resolve {addr}: {e}"))?
+        .next()
+        .ok_or_else(|| format!("{addr} did not resolve to any address"))?;
+    let local = if target.is_ipv4() {
+        "0.0.0.0:0"
+    } else {
+        "[::]:0"
+    };
+    let socket = UdpSocket::bind(local).map_err(|e| format!("bind {local}: {e}"))?;
+    socket
+        .connect(target)
+        .map_err(|e| format!("connect {target}: {e}"))?;
+    Ok(socket)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counter(value: f64) -> Sample {
+        Sample {
+            name: "net_bytes",
+            help: "",
+            kind: Kind::Counter,
+            labels: vec![("interface", "Ethernet".into()), ("direction", "rx".into())],
+            value,
+        }
+    }
+
+    #[test]
+    fn counters_are_sent_as_deltas() {
+        let mut last = HashMap::new();
+        assert!(lines(&[counter(100.0)], "web01", &mut last).is_empty());
+        assert_eq!(
+            lines(&[counter(150.0)], "web01", &mut last),
+            vec!["capture.web01.net.Ethernet.rx.bytes:50|c"]
+        );
+        // 累計值倒退時跳過一輪，之後從新的基準算
+        assert!(lines(&[counter(10.0)], "web01", &mut last).is_empty());
+        assert_eq!(
+            lines(&[counter(15.0)], "web01", &mut last),
+            vec!["capture.web01.net.Ethernet.rx.bytes:5|c"]
+        );
+    }
+}
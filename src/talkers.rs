@@ -0,0 +1,326 @@
+//! 各行程的網路流量：CAPTURE_NET_TALKERS 開啟時，背景執行緒對每條已建立的 TCP 連線開啟 ESTATS 統計
+//! （SetPerTcpConnectionEStats），定期以 GetPerTcpConnectionEStats 讀取收送位元組，依擁有行程彙總成速率
+//!
+//! - 開啟統計需要系統管理員；沒有權限時只回報各行程的連線數，速率為 None
+//! - 統計從開啟那一刻才開始累計，新連線要到下一輪取樣才有速率
+//! - 只涵蓋 TCP；UDP 沒有對應的每連線統計
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, RwLock},
+    thread,
+    time::{Duration, Instant},
+};
+use windows::Win32::{
+    Foundation::{BOOLEAN, ERROR_ACCESS_DENIED},
+    NetworkManagement::IpHelper::{
+        GetExtendedTcpTable, GetPerTcp6ConnectionEStats, GetPerTcpConnectionEStats,
+        SetPerTcp6ConnectionEStats, SetPerTcpConnectionEStats, TCP_ESTATS_DATA_ROD_v0,
+        TCP_ESTATS_DATA_RW_v0, TcpConnectionEstatsData, MIB_TCP6ROW, MIB_TCP6ROW_OWNER_PID,
+        MIB_TCPROW_LH, MIB_TCPROW_LH_0, MIB_TCPROW_OWNER_PID, MIB_TCP_STATE, MIB_TCP_STATE_ESTAB,
+        TCP_TABLE_OWNER_PID_CONNECTIONS,
+    },
+    Networking::WinSock::{AF_INET, AF_INET6, IN6_ADDR, IN6_ADDR_0},
+};
+
+use crate::{
+    config,
+    process::process_names,
+    win::{read_table, table_rows},
+    MetricError,
+};
+
+const SAMPLE_INTERVAL: Duration = Duration::from_secs(5);
+const DEFAULT_LIMIT: usize = 10;
+
+static ENABLED: LazyLock<bool> = LazyLock::new(|| config::env_flag("CAPTURE_NET_TALKERS"));
+static LATEST: RwLock<Option<Snapshot>> = RwLock::new(None);
+
+/// (本機位址, 本機 port, 遠端位址, 遠端 port)；IPv4 只用前 4 個位元組
+type ConnKey = ([u8; 16], u32, [u8; 16], u32);
+
+enum Row {
+    V4(MIB_TCPROW_LH),
+    V6(MIB_TCP6ROW),
+}
+
+#[derive(Clone)]
+struct Snapshot {
+    talkers: Vec<(u32, Talker)>,
+    /// 至少有一條連線成功開啟統計
+    estats_available: bool,
+    error: Option<String>,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Talker {
+    connections: u32,
+    bytes_in_per_sec: f64,
+    bytes_out_per_sec: f64,
+}
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct NetworkTalker {
+    pub pid: u32,
+    pub name: Option<String>,
+    /// 已建立（ESTABLISHED）的 TCP 連線數
+    pub connections: u32,
+    /// 沒有 ESTATS 統計（權限不足）時為 None
+    pub bytes_in_per_sec: Option<f64>,
+    pub bytes_out_per_sec: Option<f64>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct TalkerQuery {
+    pub limit: Option<usize>,
+}
+
+/* ---------- 背景取樣 ---------- */
+
+pub fn start_sampler() {
+    if !*ENABLED {
+        return;
+    }
+    thread::spawn(|| {
+        let mut previous: HashMap<ConnKey, (u64, u64)> = HashMap::new();
+        let mut previous_at = Instant::now();
+        loop {
+            let now = Instant::now();
+            let secs = (now - previous_at).as_secs_f64().max(0.001);
+            let snapshot = match connections() {
+                Ok(conns) => {
+                    let mut current = HashMap::with_capacity(conns.len());
+                    let mut talkers: HashMap<u32, Talker> = HashMap::new();
+                    let (mut available, mut denied) = (false, false);
+                    for (key, pid, row) in conns {
+                        let t = talkers.entry(pid).or_default();
+                        t.connections += 1;
+                        // 新連線先開啟統計，下一輪才有差值
+                        let Some(&(prev_in, prev_out)) = previous.get(&key) else {
+                            match enable(&row) {
+                                0 => available = true,
+                                rc if rc == ERROR_ACCESS_DENIED.0 => denied = true,
+                                _ => {}
+                            }
+                            current.insert(key, read(&row).unwrap_or((0, 0)));
+                            continue;
+                        };
+                        // 讀取失敗時保留上一輪的值；從 previous 拿掉會被當成新連線再開一次統計
+                        let Some((bytes_in, bytes_out)) = read(&row) else {
+                            current.insert(key, (prev_in, prev_out));
+                            continue;
+                        };
+                        available = true;
+                        t.bytes_in_per_sec += bytes_in.saturating_sub(prev_in) as f64 / secs;
+                        t.bytes_out_per_sec += bytes_out.saturating_sub(prev_out) as f64 / secs;
+                        current.insert(key, (bytes_in, bytes_out));
+                    }
+                    previous = current;
+                    Snapshot {
+                        talkers: talkers.into_iter().collect(),
+                        estats_available: available,
+                        error: (denied && !available).then(|| {
+                            "enabling per-connection TCP statistics requires administrator rights"
+                                .into()
+                        }),
+                    }
+                }
+                Err(e) => Snapshot {
+                    talkers: Vec::new(),
+                    estats_available: false,
+                    error: Some(e),
+                },
+            };
+            previous_at = now;
+            *LATEST.write().unwrap() = Some(snapshot);
+            thread::sleep(SAMPLE_INTERVAL);
+        }
+    });
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+/// 依收送合計排序；沒有統計時依連線數排序
+pub fn gather_talkers(q: &TalkerQuery, errors: &mut Vec<MetricError>) -> Vec<NetworkTalker> {
+    if !*ENABLED {
+        errors.push(MetricError {
+            metric: vec!["network_talkers".into()],
+            err: "set CAPTURE_NET_TALKERS=1 to collect per-process network usage".into(),
+        });
+        return Vec::new();
+    }
+    let Some(snapshot) = LATEST.read().unwrap().clone() else {
+        errors.push(MetricError {
+            metric: vec!["network_talkers".into()],
+            err: "first sample has not completed yet".into(),
+        });
+        return Vec::new();
+    };
+    if let Some(err) = snapshot.error {
+        errors.push(MetricError {
+            metric: vec!["network_talkers".into()],
+            err,
+        });
+    }
+
+    let names = process_names();
+    let mut list: Vec<NetworkTalker> = snapshot
+        .talkers
+        .into_iter()
+        .map(|(pid, t)| NetworkTalker {
+            pid,
+            name: names.get(&pid).cloned(),
+            connections: t.connections,
+            bytes_in_per_sec: snapshot.estats_available.then_some(t.bytes_in_per_sec),
+            bytes_out_per_sec: snapshot.estats_available.then_some(t.bytes_out_per_sec),
+        })
+        .collect();
+    list.sort_by(|a, b| {
+        let total = |t: &NetworkTalker| {
+            t.bytes_in_per_sec.unwrap_or(0.0) + t.bytes_out_per_sec.unwrap_or(0.0)
+        };
+        total(b)
+            .total_cmp(&total(a))
+            .then(b.connections.cmp(&a.connections))
+    });
+    list.truncate(q.limit.unwrap_or(DEFAULT_LIMIT));
+    list
+}
+
+/* ---------- TCP 連線與 ESTATS ---------- */
+
+/// 只取 ESTABLISHED 的連線
+fn connections() -> Result<Vec<(ConnKey, u32, Row)>, String> {
+    let mut conns = Vec::new();
+    let v4 = read_table(|p, size| unsafe {
+        GetExtendedTcpTable(
+            Some(p.cast()),
+            size,
+            false,
+            AF_INET.0 as u32,
+            TCP_TABLE_OWNER_PID_CONNECTIONS,
+            0,
+        )
+    })
+    .map_err(|s| format!("GetExtendedTcpTable(AF_INET) failed: {s}"))?;
+    for r in unsafe { table_rows::<MIB_TCPROW_OWNER_PID>(&v4) } {
+        if r.dwState != MIB_TCP_STATE_ESTAB.0 as u32 {
+            continue;
+        }
+        let mut local = [0u8; 16];
+        let mut remote = [0u8; 16];
+        local[..4].copy_from_slice(&r.dwLocalAddr.to_ne_bytes());
+        remote[..4].copy_from_slice(&r.dwRemoteAddr.to_ne_bytes());
+        let row = MIB_TCPROW_LH {
+            Anonymous: MIB_TCPROW_LH_0 {
+                State: MIB_TCP_STATE(r.dwState as i32),
+            },
+            dwLocalAddr: r.dwLocalAddr,
+            dwLocalPort: r.dwLocalPort,
+            dwRemoteAddr: r.dwRemoteAddr,
+            dwRemotePort: r.dwRemotePort,
+        };
+        conns.push((
+            (local, r.dwLocalPort, remote, r.dwRemotePort),
+            r.dwOwningPid,
+            Row::V4(row),
+        ));
+    }
+
+    let v6 = read_table(|p, size| unsafe {
+        GetExtendedTcpTable(
+            Some(p.cast()),
+            size,
+            false,
+            AF_INET6.0 as u32,
+            TCP_TABLE_OWNER_PID_CONNECTIONS,
+            0,
+        )
+    })
+    .map_err(|s| format!("GetExtendedTcpTable(AF_INET6) failed: {s}"))?;
+    for r in unsafe { table_rows::<MIB_TCP6ROW_OWNER_PID>(&v6) } {
+        if r.dwState != MIB_TCP_STATE_ESTAB.0 as u32 {
+            continue;
+        }
+        let row = MIB_TCP6ROW {
+            State: MIB_TCP_STATE(r.dwState as i32),
+            LocalAddr: IN6_ADDR {
+                u: IN6_ADDR_0 {
+                    Byte: r.ucLocalAddr,
+                },
+            },
+            dwLocalScopeId: r.dwLocalScopeId,
+            dwLocalPort: r.dwLocalPort,
+            RemoteAddr: IN6_ADDR {
+                u: IN6_ADDR_0 {
+                    Byte: r.ucRemoteAddr,
+                },
+            },
+            dwRemoteScopeId: r.dwRemoteScopeId,
+            dwRemotePort: r.dwRemotePort,
+        };
+        conns.push((
+            (r.ucLocalAddr, r.dwLocalPort, r.ucRemoteAddr, r.dwRemotePort),
+            r.dwOwningPid,
+            Row::V6(row),
+        ));
+    }
+    Ok(conns)
+}
+
+/// 回傳 Win32 錯誤碼，0 為成功
+fn enable(row: &Row) -> u32 {
+    let rw = TCP_ESTATS_DATA_RW_v0 {
+        EnableCollection: BOOLEAN(1),
+    };
+    let bytes = unsafe {
+        std::slice::from_raw_parts(
+            (&rw as *const TCP_ESTATS_DATA_RW_v0).cast::<u8>(),
+            std::mem::size_of::<TCP_ESTATS_DATA_RW_v0>(),
+        )
+    };
+    unsafe {
+        match row {
+            Row::V4(r) => SetPerTcpConnectionEStats(r, TcpConnectionEstatsData, bytes, 0, 0),
+            Row::V6(r) => SetPerTcp6ConnectionEStats(r, TcpConnectionEstatsData, bytes, 0, 0),
+        }
+    }
+}
+
+/// (DataBytesIn, DataBytesOut)；統計未開啟或連線已關閉時為 None
+fn read(row: &Row) -> Option<(u64, u64)> {
+    let mut rod = TCP_ESTATS_DATA_ROD_v0::default();
+    let bytes = unsafe {
+        std::slice::from_raw_parts_mut(
+            (&mut rod as *mut TCP_ESTATS_DATA_ROD_v0).cast::<u8>(),
+            std::mem::size_of::<TCP_ESTATS_DATA_ROD_v0>(),
+        )
+    };
+    let rc = unsafe {
+        match row {
+            Row::V4(r) => GetPerTcpConnectionEStats(
+                r,
+                TcpConnectionEstatsData,
+                None,
+                0,
+                None,
+                0,
+                Some(bytes),
+                0,
+            ),
+            Row::V6(r) => GetPerTcp6ConnectionEStats(
+                r,
+                TcpConnectionEstatsData,
+                None,
+                0,
+                None,
+                0,
+                Some(bytes),
+                0,
+            ),
+        }
+    };
+    (rc == 0).then_some((rod.DataBytesIn, rod.DataBytesOut))
+}
@@ -0,0 +1,158 @@
+//! 工作排程器：ITaskService 列出 CAPTURE_TASK_FOLDERS（預設 `\`）底下的工作
+//!
+//! 只讀設定的資料夾本身、不往子資料夾遞迴；`\Microsoft\Windows` 底下有數百個系統工作，需要時請明確列出。
+//! COM 呼叫放在 spawn_blocking 的執行緒上，由 ComGuard 初始化 MTA。
+use chrono::{Local, NaiveDateTime, TimeZone};
+use serde::Serialize;
+use std::sync::LazyLock;
+use windows::{
+    core::{BSTR, VARIANT},
+    Win32::System::{
+        Com::{CoCreateInstance, CLSCTX_INPROC_SERVER},
+        TaskScheduler::{
+            ITaskFolder, ITaskService, TaskScheduler, TASK_ENUM_HIDDEN, TASK_STATE,
+            TASK_STATE_DISABLED, TASK_STATE_QUEUED, TASK_STATE_READY, TASK_STATE_RUNNING,
+        },
+    },
+};
+
+use crate::{com::ComGuard, config, win::ole_date_to_naive, MetricError};
+
+/// SCHED_S_TASK_READY / RUNNING / HAS_NOT_RUN / QUEUED：非零但不是失敗，不計入 failed_count
+const INFORMATIONAL_RESULTS: &[u32] = &[0x0004_1300, 0x0004_1301, 0x0004_1303, 0x0004_1325];
+
+static FOLDERS: LazyLock<Vec<String>> = LazyLock::new(|| {
+    let folders = config::env_list("CAPTURE_TASK_FOLDERS");
+    if folders.is_empty() {
+        vec!["\\".into()]
+    } else {
+        folders
+    }
+});
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct TaskData {
+    /// 完整路徑，例如 `\Backup\Nightly`
+    pub path: String,
+    pub name: String,
+    /// disabled / queued / ready / running / unknown
+    pub state: &'static str,
+    /// RFC3339；從未執行時為 None
+    pub last_run_time: Option<String>,
+    /// HRESULT（以無號整數表示，與工作排程器顯示的 0x… 相同）；0 為成功
+    pub last_task_result: u32,
+    /// RFC3339；沒有排定下次執行時為 None
+    pub next_run_time: Option<String>,
+}
+
+#[derive(Serialize, Default)]
+pub struct TasksData {
+    pub tasks: Vec<TaskData>,
+    /// last_task_result 非零（排除「執行中」「尚未執行」等資訊性代碼）的工作數
+    pub failed_count: usize,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub async fn gather_tasks(errors: &mut Vec<MetricError>) -> TasksData {
+    let result = tokio::task::spawn_blocking(query_tasks)
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+    match result {
+        Ok((data, mut errs)) => {
+            errors.append(&mut errs);
+            data
+        }
+        Err(e) => {
+            errors.push(MetricError {
+                metric: vec!["tasks".into()],
+                err: e,
+            });
+            TasksData::default()
+        }
+    }
+}
+
+fn query_tasks() -> Result<(TasksData, Vec<MetricError>), String> {
+    let _com = ComGuard::init()?;
+    let service: ITaskService =
+        unsafe { CoCreateInstance(&TaskScheduler, None, CLSCTX_INPROC_SERVER) }
+            .map_err(|e| format!("CoCreateInstance(TaskScheduler) failed: {e}"))?;
+    let none = VARIANT::default();
+    unsafe { service.Connect(&none, &none, &none, &none) }
+        .map_err(|e| format!("ITaskService::Connect failed: {e}"))?;
+
+    let mut data = TasksData::default();
+    let mut errors = Vec::new();
+    for path in FOLDERS.iter() {
+        let folder = match unsafe { service.GetFolder(&BSTR::from(path.as_str())) } {
+            Ok(f) => f,
+            Err(e) => {
+                errors.push(MetricError {
+                    metric: vec![format!("tasks.{path}")],
+                    err: format!("GetFolder failed: {e}"),
+                });
+                continue;
+            }
+        };
+        match read_folder(&folder) {
+            Ok(mut tasks) => data.tasks.append(&mut tasks),
+            Err(e) => errors.push(MetricError {
+                metric: vec![format!("tasks.{path}")],
+                err: e,
+            }),
+        }
+    }
+    data.failed_count = data
+        .tasks
+        .iter()
+        .filter(|t| t.last_task_result != 0 && !INFORMATIONAL_RESULTS.contains(&t.last_task_result))
+        .count();
+    Ok((data, errors))
+}
+
+fn read_folder(folder: &ITaskFolder) -> Result<Vec<TaskData>, String> {
+    unsafe {
+        let collection = folder
+            .GetTasks(TASK_ENUM_HIDDEN.0)
+            .map_err(|e| format!("GetTasks failed: {e}"))?;
+        let count = collection.Count().unwrap_or(0);
+        let mut tasks = Vec::new();
+        // 集合索引從 1 開始
+        for i in 1..=count {
+            let Ok(task) = collection.get_Item(&VARIANT::from(i)) else {
+                continue;
+            };
+            tasks.push(TaskData {
+                path: task.Path().map(|s| s.to_string()).unwrap_or_default(),
+                name: task.Name().map(|s| s.to_string()).unwrap_or_default(),
+                state: task.State().map(state_name).unwrap_or("unknown"),
+                last_run_time: task.LastRunTime().ok().and_then(local_rfc3339),
+                last_task_result: task.LastTaskResult().unwrap_or(0) as u32,
+                next_run_time: task.NextRunTime().ok().and_then(local_rfc3339),
+            });
+        }
+        Ok(tasks)
+    }
+}
+
+fn state_name(state: TASK_STATE) -> &'static str {
+    match state {
+        TASK_STATE_DISABLED => "disabled",
+        TASK_STATE_QUEUED => "queued",
+        TASK_STATE_READY => "ready",
+        TASK_STATE_RUNNING => "running",
+        _ => "unknown",
+    }
+}
+
+/// 工作排程器的時間是本機時間；從未執行時回傳 1899-12-30（DATE 0）
+fn local_rfc3339(date: f64) -> Option<String> {
+    let naive: NaiveDateTime = ole_date_to_naive(date)?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .map(|t| t.to_rfc3339())
+}
@@ -0,0 +1,157 @@
+//! TCP 連線統計：GetTcpTable2 / GetTcp6Table2 依狀態計數，GetTcpStatisticsEx 提供累計開啟與重置次數
+use serde::Serialize;
+use windows::Win32::{
+    NetworkManagement::IpHelper::{
+        GetTcp6Table2, GetTcpStatisticsEx, GetTcpTable2, MIB_TCP6ROW2, MIB_TCPROW2, MIB_TCPSTATS_LH,
+    },
+    Networking::WinSock::{AF_INET, AF_INET6},
+};
+
+use crate::{
+    win::{read_table, table_rows},
+    MetricError,
+};
+
+/* ---------- 資料結構 ---------- */
+
+/// 各 MIB_TCP_STATE 的連線數
+#[derive(Serialize, Default, Clone, Copy)]
+pub struct TcpStateCounts {
+    pub established: u32,
+    pub syn_sent: u32,
+    pub syn_received: u32,
+    pub fin_wait1: u32,
+    pub fin_wait2: u32,
+    pub close_wait: u32,
+    pub closing: u32,
+    pub last_ack: u32,
+    pub time_wait: u32,
+    pub listen: u32,
+    pub closed: u32,
+    pub delete_tcb: u32,
+}
+
+#[derive(Serialize, Default, Clone, Copy)]
+pub struct TcpFamilyData {
+    pub states: TcpStateCounts,
+    /// 以下為開機以來的累計值
+    pub active_opens: u64,
+    pub passive_opens: u64,
+    pub attempt_fails: u64,
+    /// ESTABLISHED / CLOSE_WAIT 直接被重置的次數
+    pub established_resets: u64,
+    /// 送出的 RST 區段數
+    pub resets_sent: u64,
+}
+
+#[derive(Serialize)]
+pub struct TcpData {
+    /// IPv4 + IPv6 加總；某一族讀取失敗時只含讀得到的部分
+    pub total: TcpFamilyData,
+    pub ipv4: Option<TcpFamilyData>,
+    pub ipv6: Option<TcpFamilyData>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub fn gather_tcp(errors: &mut Vec<MetricError>) -> TcpData {
+    let mut read = |family: &str, result: Result<TcpFamilyData, String>| match result {
+        Ok(v) => Some(v),
+        Err(e) => {
+            errors.push(MetricError {
+                metric: vec![format!("tcp.{family}")],
+                err: e,
+            });
+            None
+        }
+    };
+    let ipv4 = read("ipv4", query_family(false));
+    let ipv6 = read("ipv6", query_family(true));
+
+    let mut total = TcpFamilyData::default();
+    for f in [ipv4, ipv6].into_iter().flatten() {
+        total.add(&f);
+    }
+    TcpData { total, ipv4, ipv6 }
+}
+
+impl TcpStateCounts {
+    /// MIB_TCP_STATE：1 = CLOSED … 12 = DELETE_TCB
+    fn count(&mut self, state: u32) {
+        let slot = match state {
+            1 => &mut self.closed,
+            2 => &mut self.listen,
+            3 => &mut self.syn_sent,
+            4 => &mut self.syn_received,
+            5 => &mut self.established,
+            6 => &mut self.fin_wait1,
+            7 => &mut self.fin_wait2,
+            8 => &mut self.close_wait,
+            9 => &mut self.closing,
+            10 => &mut self.last_ack,
+            11 => &mut self.time_wait,
+            12 => &mut self.delete_tcb,
+            _ => return,
+        };
+        *slot += 1;
+    }
+}
+
+impl TcpFamilyData {
+    fn add(&mut self, o: &TcpFamilyData) {
+        let (s, t) = (&mut self.states, &o.states);
+        s.established += t.established;
+        s.syn_sent += t.syn_sent;
+        s.syn_received += t.syn_received;
+        s.fin_wait1 += t.fin_wait1;
+        s.fin_wait2 += t.fin_wait2;
+        s.close_wait += t.close_wait;
+        s.closing += t.closing;
+        s.last_ack += t.last_ack;
+        s.time_wait += t.time_wait;
+        s.listen += t.listen;
+        s.closed += t.closed;
+        s.delete_tcb += t.delete_tcb;
+        self.active_opens += o.active_opens;
+        self.passive_opens += o.passive_opens;
+        self.attempt_fails += o.attempt_fails;
+        self.established_resets += o.established_resets;
+        self.resets_sent += o.resets_sent;
+    }
+}
+
+fn query_family(v6: bool) -> Result<TcpFamilyData, String> {
+    let mut data = TcpFamilyData::default();
+
+    let states = if v6 { tcp6_states()? } else { tcp4_states()? };
+    for s in states {
+        data.states.count(s);
+    }
+
+    let family = if v6 { AF_INET6 } else { AF_INET };
+    let mut stats = MIB_TCPSTATS_LH::default();
+    let status = unsafe { GetTcpStatisticsEx(&mut stats, family.0 as u32) };
+    if status != 0 {
+        return Err(format!("GetTcpStatisticsEx failed: {status}"));
+    }
+    data.active_opens = stats.dwActiveOpens as u64;
+    data.passive_opens = stats.dwPassiveOpens as u64;
+    data.attempt_fails = stats.dwAttemptFails as u64;
+    data.established_resets = stats.dwEstabResets as u64;
+    data.resets_sent = stats.dwOutRsts as u64;
+    Ok(data)
+}
+
+fn tcp4_states() -> Result<Vec<u32>, String> {
+    let buf = read_table(|p, size| unsafe { GetTcpTable2(Some(p.cast()), size, false) })
+        .map_err(|s| format!("GetTcpTable2 failed: {s}"))?;
+    let rows = unsafe { table_rows::<MIB_TCPROW2>(&buf) };
+    Ok(rows.iter().map(|r| r.dwState).collect())
+}
+
+fn tcp6_states() -> Result<Vec<u32>, String> {
+    let buf = read_table(|p, size| unsafe { GetTcp6Table2(p.cast(), size, false) })
+        .map_err(|s| format!("GetTcp6Table2 failed: {s}"))?;
+    let rows = unsafe { table_rows::<MIB_TCP6ROW2>(&buf) };
+    Ok(rows.iter().map(|r| r.State.0 as u32).collect())
+}
@@ -0,0 +1,111 @@
+//! 暫存資料夾大小：%SystemRoot%\Temp 與每個使用者設定檔的 AppData\Local\Temp，
+//! 由背景執行緒每 CAPTURE_TEMP_SCAN_INTERVAL_SECS（預設 900）秒走訪一次，依所在磁碟機合計
+//!
+//! 暫存資料夾可能塞了上百萬個檔案，每輪走訪最多 CAPTURE_TEMP_SCAN_MAX_SECS（預設 60）秒、
+//! MAX_ENTRIES 個項目，超過就停下並標記 truncated，已累計的大小照常回報。
+use chrono::Utc;
+use std::{
+    collections::HashMap,
+    fs,
+    path::PathBuf,
+    sync::{LazyLock, RwLock},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    config,
+    dirs::{self, WalkLimit},
+};
+
+/// 每輪走訪的項目上限（所有暫存資料夾合計）
+const MAX_ENTRIES: u64 = 1_000_000;
+
+static INTERVAL: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(config::env_parse("CAPTURE_TEMP_SCAN_INTERVAL_SECS", 900))
+});
+static MAX_DURATION: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_secs(config::env_parse("CAPTURE_TEMP_SCAN_MAX_SECS", 60)));
+static LATEST: RwLock<Option<TempScan>> = RwLock::new(None);
+
+/// 一輪走訪的結果
+#[derive(Clone)]
+pub struct TempScan {
+    /// 磁碟機代號（大寫，例如 `C:`）→ 位元組數
+    pub bytes_by_drive: HashMap<String, u64>,
+    pub truncated: bool,
+    /// 走訪完成的時間，RFC3339（UTC）
+    pub completed_at: String,
+}
+
+/* ---------- 背景取樣 ---------- */
+
+pub fn start_sampler() {
+    thread::spawn(|| loop {
+        let scan = scan();
+        *LATEST.write().unwrap() = Some(scan);
+        thread::sleep(*INTERVAL);
+    });
+}
+
+/// 第一輪走訪完成前為 None
+pub fn latest() -> Option<TempScan> {
+    LATEST.read().unwrap().clone()
+}
+
+fn scan() -> TempScan {
+    let limit = WalkLimit {
+        deadline: Instant::now() + *MAX_DURATION,
+        max_entries: MAX_ENTRIES,
+    };
+    let mut bytes_by_drive: HashMap<String, u64> = HashMap::new();
+    let mut truncated = false;
+    let mut entries = 0;
+    for dir in temp_dirs() {
+        let Some(drive) = dir
+            .to_str()
+            .and_then(|p| p.get(..2))
+            .filter(|d| d.ends_with(':'))
+            .map(str::to_ascii_uppercase)
+        else {
+            continue;
+        };
+        // 上限是所有資料夾共用的，已用掉的項目數要扣掉
+        let remaining = WalkLimit {
+            deadline: limit.deadline,
+            max_entries: limit.max_entries.saturating_sub(entries),
+        };
+        let tree = dirs::tree_size(&dir, Some(&remaining));
+        entries += tree.file_count + tree.dir_count;
+        *bytes_by_drive.entry(drive).or_default() += tree.size_bytes;
+        if tree.truncated {
+            truncated = true;
+            break;
+        }
+    }
+    TempScan {
+        bytes_by_drive,
+        truncated,
+        completed_at: Utc::now().to_rfc3339(),
+    }
+}
+
+/// %SystemRoot%\Temp 加上使用者資料夾（%PUBLIC% 的上一層）下每個設定檔的 AppData\Local\Temp
+fn temp_dirs() -> Vec<PathBuf> {
+    let root = std::env::var("SystemRoot").unwrap_or_else(|_| r"C:\Windows".into());
+    let mut dirs = vec![PathBuf::from(root).join("Temp")];
+
+    let users = std::env::var("PUBLIC")
+        .ok()
+        .and_then(|p| PathBuf::from(p).parent().map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from(r"C:\Users"));
+    if let Ok(entries) = fs::read_dir(users) {
+        for entry in entries.flatten() {
+            let temp = entry.path().join(r"AppData\Local\Temp");
+            if temp.is_dir() {
+                dirs.push(temp);
+            }
+        }
+    }
+    dirs
+}
@@ -0,0 +1,231 @@
+//! 時間同步：w32time 的設定、最近同步時間與估計偏移，可選擇直接以 SNTP 量測
+//!
+//! - 同步來源：登錄檔 W32Time\Parameters 的 Type / NtpServer
+//! - 最近同步：System 記錄中 Time-Service 最近一次 35 / 37 事件（開始向來源同步 / 收到有效時間資料）
+//! - 偏移估計：\Windows Time Service\Computed Time Offset（微秒）
+//! - SNTP：CAPTURE_NTP_SERVER 指定時，每次請求送一個查詢封包（2 s 逾時）
+use serde::Serialize;
+use std::{
+    sync::LazyLock,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tokio::{net::UdpSocket, time::timeout};
+use windows::Win32::System::Registry::HKEY_LOCAL_MACHINE;
+
+use crate::{
+    config, eventlog,
+    pdh::{self, Format},
+    services,
+    win::reg_string,
+    MetricError,
+};
+
+const W32TIME_PARAMETERS: &str = "SYSTEM\\CurrentControlSet\\Services\\W32Time\\Parameters";
+const SYNC_EVENTS_XPATH: &str =
+    "*[System[Provider[@Name='Microsoft-Windows-Time-Service'] and (EventID=35 or EventID=37)]]";
+const SNTP_TIMEOUT: Duration = Duration::from_secs(2);
+/// NTP 時間從 1900-01-01 起算
+const NTP_UNIX_OFFSET_SECS: f64 = 2_208_988_800.0;
+
+static NTP_SERVER: LazyLock<Option<String>> =
+    LazyLock::new(|| config::env_string("CAPTURE_NTP_SERVER"));
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct SntpData {
+    pub server: String,
+    /// 正值代表本機時鐘落後伺服器
+    pub offset_seconds: Option<f64>,
+    pub round_trip_ms: Option<f64>,
+    pub error: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct TimeData {
+    /// w32time 服務狀態（running / stopped…）；服務不存在時為 None
+    pub w32time_state: Option<String>,
+    /// 設定的 NTP 伺服器（去掉 `,0x9` 旗標），網域成員為 `domain hierarchy`
+    pub sync_source: Option<String>,
+    /// RFC3339（UTC）；來源沒有變動時 w32time 不會每次同步都記事件，只能當成參考
+    pub last_sync_time: Option<String>,
+    /// w32time 估計的本機偏移；服務停止時為 None（不回報 0）
+    pub offset_seconds: Option<f64>,
+    /// 只有設定 CAPTURE_NTP_SERVER 時才有
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sntp: Option<SntpData>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub async fn gather_time(errors: &mut Vec<MetricError>) -> TimeData {
+    let w32time_state = match services::service_state("W32Time") {
+        Ok(s) => s,
+        Err(e) => {
+            errors.push(MetricError {
+                metric: vec!["time.w32time_state".into()],
+                err: e,
+            });
+            None
+        }
+    };
+    let running = w32time_state.as_deref() == Some("running");
+    if !running {
+        errors.push(MetricError {
+            metric: vec!["time.offset_seconds".into()],
+            err: match &w32time_state {
+                Some(state) => format!("w32time service is {state}"),
+                None => "w32time service not found".into(),
+            },
+        });
+    }
+
+    let offset_seconds = if running {
+        match query_offset() {
+            Ok(v) => Some(v),
+            Err(e) => {
+                errors.push(MetricError {
+                    metric: vec!["time.offset_seconds".into()],
+                    err: e,
+                });
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let last_sync_time = match eventlog::latest_event_time("System", SYNC_EVENTS_XPATH) {
+        Ok(t) => t,
+        Err(e) => {
+            errors.push(MetricError {
+                metric: vec!["time.last_sync_time".into()],
+                err: e,
+            });
+            None
+        }
+    };
+
+    let sntp = match NTP_SERVER.as_deref() {
+        Some(server) => {
+            let r = query_sntp(server).await;
+            if let Some(e) = &r.error {
+                errors.push(MetricError {
+                    metric: vec![format!("time.sntp.{server}")],
+                    err: e.clone(),
+                });
+            }
+            Some(r)
+        }
+        None => None,
+    };
+
+    TimeData {
+        w32time_state,
+        sync_source: sync_source(),
+        last_sync_time,
+        offset_seconds,
+        sntp,
+    }
+}
+
+fn sync_source() -> Option<String> {
+    let kind = reg_string(HKEY_LOCAL_MACHINE, W32TIME_PARAMETERS, "Type")?;
+    match kind.to_ascii_uppercase().as_str() {
+        "NT5DS" => Some("domain hierarchy".into()),
+        "NOSYNC" => Some("none".into()),
+        _ => {
+            let servers = reg_string(HKEY_LOCAL_MACHINE, W32TIME_PARAMETERS, "NtpServer")?;
+            let list: Vec<&str> = servers
+                .split_whitespace()
+                .map(|s| s.split(',').next().unwrap_or(s))
+                .collect();
+            (!list.is_empty()).then(|| list.join(" "))
+        }
+    }
+}
+
+fn query_offset() -> Result<f64, String> {
+    let query = pdh::Query::open()?;
+    let counter = query.add("\\Windows Time Service\\Computed Time Offset")?;
+    query.collect()?;
+    Ok(counter.value(Format::Large)?.as_i64() as f64 / 1_000_000.0)
+}
+
+/* ---------- SNTP ---------- */
+
+async fn query_sntp(server: &str) -> SntpData {
+    let mut data = SntpData {
+        server: server.into(),
+        offset_seconds: None,
+        round_trip_ms: None,
+        error: None,
+    };
+    match timeout(SNTP_TIMEOUT, sntp_exchange(server)).await {
+        Ok(Ok((offset, delay))) => {
+            data.offset_seconds = Some(offset);
+            data.round_trip_ms = Some(delay * 1000.0);
+        }
+        Ok(Err(e)) => data.error = Some(e),
+        Err(_) => data.error = Some(format!("timed out after {}s", SNTP_TIMEOUT.as_secs())),
+    }
+    data
+}
+
+/// 回傳 (offset, round-trip delay)，單位秒；算法同 RFC 4330
+async fn sntp_exchange(server: &str) -> Result<(f64, f64), String> {
+    let socket = UdpSocket::bind("0.0.0.0:0")
+        .await
+        .map_err(|e| format!("bind failed: {e}"))?;
+    socket
+        .connect((server, 123))
+        .await
+        .map_err(|e| format!("connect failed: {e}"))?;
+
+    // LI = 0、VN = 4、Mode = 3（client）；Transmit Timestamp 放 t1，伺服器會原樣放回 Originate
+    let mut packet = [0u8; 48];
+    packet[0] = 0x23;
+    let t1 = now_secs();
+    packet[40..48].copy_from_slice(&to_ntp(t1).to_be_bytes());
+    socket
+        .send(&packet)
+        .await
+        .map_err(|e| format!("send failed: {e}"))?;
+
+    let mut reply = [0u8; 48];
+    let n = socket
+        .recv(&mut reply)
+        .await
+        .map_err(|e| format!("recv failed: {e}"))?;
+    let t4 = now_secs();
+    if n < 48 || reply[0] & 0x7 != 4 {
+        return Err("invalid SNTP reply".into());
+    }
+    if reply[1] == 0 {
+        return Err("server sent kiss-o'-death (stratum 0)".into());
+    }
+
+    let t2 = from_ntp(u64::from_be_bytes(reply[32..40].try_into().unwrap()));
+    let t3 = from_ntp(u64::from_be_bytes(reply[40..48].try_into().unwrap()));
+    let offset = ((t2 - t1) + (t3 - t4)) / 2.0;
+    let delay = (t4 - t1) - (t3 - t2);
+    Ok((offset, delay))
+}
+
+/// Unix epoch 起算的秒數
+fn now_secs() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+/// 64 位元 NTP 時間：高 32 位元為秒、低 32 位元為小數
+fn to_ntp(unix: f64) -> u64 {
+    let t = unix + NTP_UNIX_OFFSET_SECS;
+    ((t.trunc() as u64) << 32) | ((t.fract() * 4_294_967_296.0) as u64)
+}
+
+fn from_ntp(ntp: u64) -> f64 {
+    (ntp >> 32) as f64 + (ntp & 0xffff_ffff) as f64 / 4_294_967_296.0 - NTP_UNIX_OFFSET_SECS
+}
@@ -0,0 +1,223 @@
+//! Windows Update：待安裝更新數、是否待重開機、最近一次安裝時間
+//!
+//! - 待安裝更新：WUA（IUpdateSearcher）`IsInstalled=0` 查詢，可能要幾十秒，由背景執行緒定期執行
+//! - 待重開機：登錄檔 RebootRequired / RebootPending 機碼與 PendingFileRenameOperations，每次請求重新讀取
+//!
+//! wuauserv 被停用時 Search 會直接失敗，記一筆 error，不會卡住請求。
+use chrono::Utc;
+use serde::Serialize;
+use std::{
+    sync::{LazyLock, RwLock},
+    thread,
+    time::Duration,
+};
+use windows::{
+    core::BSTR,
+    Win32::{
+        Foundation::VARIANT_FALSE,
+        System::{
+            Com::{CoCreateInstance, CLSCTX_INPROC_SERVER},
+            Registry::HKEY_LOCAL_MACHINE,
+            UpdateAgent::{
+                orcSucceeded, orcSucceededWithErrors, uoInstallation, IUpdateSearcher,
+                IUpdateSession, UpdateSession,
+            },
+        },
+    },
+};
+
+use crate::{
+    com::ComGuard,
+    config,
+    win::{ole_date_to_naive, reg_key_exists, reg_value_exists},
+    MetricError,
+};
+
+/// 每次最多往回翻的更新歷程筆數
+const HISTORY_LIMIT: i32 = 50;
+
+const REBOOT_KEYS: &[&str] = &[
+    "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\WindowsUpdate\\Auto Update\\RebootRequired",
+    "SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Component Based Servicing\\RebootPending",
+];
+const SESSION_MANAGER: &str = "SYSTEM\\CurrentControlSet\\Control\\Session Manager";
+
+/// 背景檢查的間隔，預設一小時
+static INTERVAL: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(config::env_parse("CAPTURE_UPDATE_CHECK_INTERVAL_SECS", 3600))
+});
+static LATEST: RwLock<Option<Scan>> = RwLock::new(None);
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct UpdatesData {
+    /// 尚未安裝且適用本機的更新數；背景檢查尚未完成或失敗時為 None
+    pub pending_update_count: Option<u32>,
+    pub pending_reboot: bool,
+    /// 最近一次成功安裝更新的時間，RFC3339（UTC）
+    pub last_install_time: Option<String>,
+    /// pending_update_count / last_install_time 的取得時間
+    pub collected_at: Option<String>,
+}
+
+#[derive(Clone)]
+struct Scan {
+    pending_update_count: Option<u32>,
+    last_install_time: Option<String>,
+    collected_at: String,
+    errors: Vec<(&'static str, String)>,
+}
+
+/* ---------- 背景取樣 ---------- */
+
+pub fn start_sampler() {
+    thread::spawn(|| {
+        // COM 要在這條執行緒上初始化；失敗也照樣記錄結果，讓 /api/v1/metrics 看得到原因
+        let com = ComGuard::init();
+        loop {
+            let scan = match &com {
+                Ok(_) => scan(),
+                Err(e) => Scan {
+                    pending_update_count: None,
+                    last_install_time: None,
+                    collected_at: Utc::now().to_rfc3339(),
+                    errors: vec![("updates", e.clone())],
+                },
+            };
+            *LATEST.write().unwrap() = Some(scan);
+            thread::sleep(*INTERVAL);
+        }
+    });
+}
+
+pub fn gather_updates(errors: &mut Vec<MetricError>) -> UpdatesData {
+    let latest = LATEST.read().unwrap().clone();
+    let (pending_update_count, last_install_time, collected_at) = match latest {
+        Some(scan) => {
+            for (key, err) in scan.errors {
+                errors.push(MetricError {
+                    metric: vec![key.into()],
+                    err,
+                });
+            }
+            (
+                scan.pending_update_count,
+                scan.last_install_time,
+                Some(scan.collected_at),
+            )
+        }
+        None => {
+            errors.push(MetricError {
+                metric: vec!["updates".into()],
+                err: "update check has not completed yet".into(),
+            });
+            (None, None, None)
+        }
+    };
+
+    UpdatesData {
+        pending_update_count,
+        pending_reboot: pending_reboot(),
+        last_install_time,
+        collected_at,
+    }
+}
+
+fn pending_reboot() -> bool {
+    REBOOT_KEYS
+        .iter()
+        .any(|k| reg_key_exists(HKEY_LOCAL_MACHINE, k))
+        || reg_value_exists(
+            HKEY_LOCAL_MACHINE,
+            SESSION_MANAGER,
+            "PendingFileRenameOperations",
+        )
+}
+
+/* ---------- WUA ---------- */
+
+/// 呼叫端需已初始化 COM
+fn scan() -> Scan {
+    let mut errors = Vec::new();
+    let searcher = unsafe {
+        CoCreateInstance::<_, IUpdateSession>(&UpdateSession, None, CLSCTX_INPROC_SERVER)
+            .and_then(|s| s.CreateUpdateSearcher())
+    }
+    .map_err(|e| format!("CreateUpdateSearcher failed: {e}"));
+
+    let (pending_update_count, last_install_time) = match searcher {
+        Ok(searcher) => {
+            let count = pending_count(&searcher)
+                .map_err(|e| errors.push(("updates.pending_update_count", e)))
+                .ok();
+            let last = last_install(&searcher)
+                .map_err(|e| errors.push(("updates.last_install_time", e)))
+                .ok()
+                .flatten();
+            (count, last)
+        }
+        Err(e) => {
+            errors.push(("updates", e));
+            (None, None)
+        }
+    };
+
+    Scan {
+        pending_update_count,
+        last_install_time,
+        collected_at: Utc::now().to_rfc3339(),
+        errors,
+    }
+}
+
+fn pending_count(searcher: &IUpdateSearcher) -> Result<u32, String> {
+    unsafe {
+        // 只看上次自動掃描留下的本機資料，不主動連線到更新伺服器
+        searcher
+            .SetOnline(VARIANT_FALSE)
+            .map_err(|e| format!("IUpdateSearcher::SetOnline failed: {e}"))?;
+        let result = searcher
+            .Search(&BSTR::from("IsInstalled=0 and IsHidden=0"))
+            .map_err(|e| format!("IUpdateSearcher::Search failed: {e}"))?;
+        let count = result
+            .Updates()
+            .and_then(|u| u.Count())
+            .map_err(|e| format!("ISearchResult::Updates failed: {e}"))?;
+        Ok(count.max(0) as u32)
+    }
+}
+
+/// 歷程依時間新到舊排列，取第一筆成功的安裝
+fn last_install(searcher: &IUpdateSearcher) -> Result<Option<String>, String> {
+    unsafe {
+        let total = searcher
+            .GetTotalHistoryCount()
+            .map_err(|e| format!("GetTotalHistoryCount failed: {e}"))?;
+        if total <= 0 {
+            return Ok(None);
+        }
+        let history = searcher
+            .QueryHistory(0, total.min(HISTORY_LIMIT))
+            .map_err(|e| format!("QueryHistory failed: {e}"))?;
+        let count = history.Count().unwrap_or(0);
+        for i in 0..count {
+            let Ok(entry) = history.get_Item(i) else {
+                continue;
+            };
+            let installed = entry.Operation().is_ok_and(|op| op == uoInstallation)
+                && entry
+                    .ResultCode()
+                    .is_ok_and(|rc| rc == orcSucceeded || rc == orcSucceededWithErrors);
+            if installed {
+                return Ok(entry.Date().ok().and_then(ole_date_to_rfc3339));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// 更新歷程的 Date 為 UTC
+fn ole_date_to_rfc3339(date: f64) -> Option<String> {
+    ole_date_to_naive(date).map(|t| t.and_utc().to_rfc3339())
+}
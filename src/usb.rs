@@ -0,0 +1,132 @@
+//! USB 裝置清單：SetupDi 列舉 USB 匯流排上目前連接的裝置，每次請求重新列舉
+//!
+//! 以 enumerator `USB` 列舉所有類別，而不是 USB 類別 GUID（後者只有集線器與控制器，
+//! 加密鎖、條碼掃描器等會落在 HID、SmartCardReader 等類別）。
+//! 複合裝置的介面（`&MI_xx`）與沒有 VID 的根集線器不列出，每個實體裝置一筆。
+use serde::Serialize;
+use windows::{
+    core::w,
+    Win32::Devices::DeviceAndDriverInstallation::{
+        SetupDiDestroyDeviceInfoList, SetupDiEnumDeviceInfo, SetupDiGetClassDevsW,
+        SetupDiGetDeviceInstanceIdW, SetupDiGetDeviceRegistryPropertyW, DIGCF_ALLCLASSES,
+        DIGCF_PRESENT, HDEVINFO, SETUP_DI_REGISTRY_PROPERTY, SPDRP_DEVICEDESC, SPDRP_FRIENDLYNAME,
+        SPDRP_MFG, SP_DEVINFO_DATA,
+    },
+};
+
+use crate::{win::wide_to_string, MetricError};
+
+/// Drop 時釋放的裝置資訊集
+struct DevInfoSet(HDEVINFO);
+
+impl Drop for DevInfoSet {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = SetupDiDestroyDeviceInfoList(self.0);
+        }
+    }
+}
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct UsbDevice {
+    /// 例如 `USB\VID_046D&PID_C52B\5&2A1B3C4D&0&2`
+    pub instance_id: String,
+    /// 四位十六進位，例如 `046D`
+    pub vendor_id: String,
+    pub product_id: Option<String>,
+    /// FriendlyName，沒有時用裝置描述
+    pub description: Option<String>,
+    pub manufacturer: Option<String>,
+    /// 裝置回報的序號；沒有序號的裝置 Windows 會以連接埠產生 ID（含 `&`），此時為 None
+    pub serial: Option<String>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub fn gather_usb(errors: &mut Vec<MetricError>) -> Vec<UsbDevice> {
+    let set = match unsafe {
+        SetupDiGetClassDevsW(None, w!("USB"), None, DIGCF_ALLCLASSES | DIGCF_PRESENT)
+    } {
+        Ok(h) => DevInfoSet(h),
+        Err(e) => {
+            errors.push(MetricError {
+                metric: vec!["usb".into()],
+                err: format!("SetupDiGetClassDevsW failed: {e}"),
+            });
+            return Vec::new();
+        }
+    };
+
+    let mut devices = Vec::new();
+    for index in 0.. {
+        let mut info = SP_DEVINFO_DATA {
+            cbSize: std::mem::size_of::<SP_DEVINFO_DATA>() as u32,
+            ..Default::default()
+        };
+        if unsafe { SetupDiEnumDeviceInfo(set.0, index, &mut info) }.is_err() {
+            break;
+        }
+        let Some(instance_id) = instance_id(&set, &info) else {
+            continue;
+        };
+        let Some((vendor_id, product_id, serial)) = parse_instance_id(&instance_id) else {
+            continue;
+        };
+        devices.push(UsbDevice {
+            vendor_id,
+            product_id,
+            description: property(&set, &info, SPDRP_FRIENDLYNAME)
+                .or_else(|| property(&set, &info, SPDRP_DEVICEDESC)),
+            manufacturer: property(&set, &info, SPDRP_MFG),
+            serial,
+            instance_id,
+        });
+    }
+    devices.sort_by(|a, b| a.instance_id.cmp(&b.instance_id));
+    devices
+}
+
+fn instance_id(set: &DevInfoSet, info: &SP_DEVINFO_DATA) -> Option<String> {
+    // 裝置執行個體 ID 最長 200 字元（MAX_DEVICE_ID_LEN）
+    let mut buf = [0u16; 201];
+    unsafe { SetupDiGetDeviceInstanceIdW(set.0, info, Some(&mut buf), None) }.ok()?;
+    Some(wide_to_string(&buf))
+}
+
+/// REG_SZ 屬性；裝置沒有該屬性時為 None
+fn property(
+    set: &DevInfoSet,
+    info: &SP_DEVINFO_DATA,
+    prop: SETUP_DI_REGISTRY_PROPERTY,
+) -> Option<String> {
+    let mut buf = [0u8; 1024];
+    unsafe { SetupDiGetDeviceRegistryPropertyW(set.0, info, prop, None, Some(&mut buf), None) }
+        .ok()?;
+    let wide: Vec<u16> = buf
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    Some(wide_to_string(&wide)).filter(|s| !s.trim().is_empty())
+}
+
+/// `USB\VID_046D&PID_C52B\序號` → (VID, PID, 序號)；根集線器與複合裝置的介面回傳 None
+fn parse_instance_id(id: &str) -> Option<(String, Option<String>, Option<String>)> {
+    let mut parts = id.splitn(3, '\\');
+    let _bus = parts.next()?;
+    let hardware = parts.next()?.to_ascii_uppercase();
+    let instance = parts.next().unwrap_or_default();
+    if hardware.contains("&MI_") {
+        return None;
+    }
+    let field = |prefix: &str| {
+        hardware
+            .split('&')
+            .find_map(|f| f.strip_prefix(prefix))
+            .map(str::to_string)
+    };
+    let vendor_id = field("VID_")?;
+    let serial = (!instance.is_empty() && !instance.contains('&')).then(|| instance.to_string());
+    Some((vendor_id, field("PID_"), serial))
+}
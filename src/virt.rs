@@ -0,0 +1,127 @@
+//! 虛擬化偵測：CPUID 的 hypervisor 位元與廠商 leaf（0x40000000），
+//! 再以 SMBIOS 系統製造商 / 型號（即 Win32_ComputerSystem 的 Manufacturer / Model）補強
+//!
+//! 啟用 Hyper-V 角色的實體主機本身也跑在 hypervisor 上（root partition），
+//! CPUID 看起來和 Hyper-V 客體一樣，需再看 partition 權限才能分辨。
+use serde::Serialize;
+use std::sync::LazyLock;
+
+use crate::hardware;
+
+static CACHE: LazyLock<VirtInfo> = LazyLock::new(detect);
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize, Clone)]
+pub struct VirtInfo {
+    pub is_virtual: bool,
+    /// hyper-v / vmware / kvm / xen / virtualbox / none；其他 hypervisor 為 unknown
+    pub hypervisor: String,
+    /// 本機是啟用 Hyper-V 的主機（parent partition）；此時 CPU 計數器只涵蓋 root partition 的 VP
+    pub hyperv_host: bool,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub fn gather_virt() -> VirtInfo {
+    CACHE.clone()
+}
+
+fn detect() -> VirtInfo {
+    let cpuid = cpuid_hypervisor();
+    let hyperv_host = cpuid.as_ref().is_some_and(|c| c.root_partition);
+
+    // root partition 的 CPUID 也會回報 Microsoft Hv，但機器本身是實體的
+    let from_cpuid = cpuid
+        .filter(|c| !c.root_partition)
+        .map(|c| vendor_name(&c.vendor));
+    let from_smbios = smbios_hint();
+
+    let hypervisor = match (from_cpuid, from_smbios) {
+        // CPUID 只能確定「有 hypervisor」時，讓 SMBIOS 決定是哪一家
+        (Some("unknown"), Some(h)) => h,
+        (Some(h), _) => h,
+        // 巢狀虛擬化：客體內又啟用 Hyper-V，以 SMBIOS 為準
+        (None, Some(h)) => h,
+        _ => "none",
+    };
+
+    VirtInfo {
+        is_virtual: hypervisor != "none",
+        hypervisor: hypervisor.into(),
+        hyperv_host,
+    }
+}
+
+struct CpuidHypervisor {
+    vendor: String,
+    root_partition: bool,
+}
+
+/// hypervisor 位元（leaf 1 ECX bit 31）沒設時回傳 None
+#[cfg(target_arch = "x86_64")]
+fn cpuid_hypervisor() -> Option<CpuidHypervisor> {
+    use std::arch::x86_64::__cpuid;
+
+    if __cpuid(1).ecx & (1 << 31) == 0 {
+        return None;
+    }
+    let leaf = __cpuid(0x4000_0000);
+    let bytes: Vec<u8> = [leaf.ebx, leaf.ecx, leaf.edx]
+        .iter()
+        .flat_map(|r| r.to_le_bytes())
+        .collect();
+    let vendor = String::from_utf8_lossy(&bytes)
+        .trim_end_matches('\0')
+        .to_string();
+
+    // Hyper-V 的 partition 權限（leaf 0x40000003 EBX）：bit 0 CreatePartitions 只有 root partition 才有
+    let root_partition =
+        vendor == "Microsoft Hv" && leaf.eax >= 0x4000_0003 && __cpuid(0x4000_0003).ebx & 1 != 0;
+
+    Some(CpuidHypervisor {
+        vendor,
+        root_partition,
+    })
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn cpuid_hypervisor() -> Option<CpuidHypervisor> {
+    None
+}
+
+fn vendor_name(vendor: &str) -> &'static str {
+    match vendor {
+        "Microsoft Hv" => "hyper-v",
+        "VMwareVMware" => "vmware",
+        "KVMKVMKVM" | "TCGTCGTCGTCG" => "kvm",
+        "XenVMMXenVMM" => "xen",
+        "VBoxVBoxVBox" => "virtualbox",
+        _ => "unknown",
+    }
+}
+
+/// 系統製造商 / 型號的常見字樣
+fn smbios_hint() -> Option<&'static str> {
+    let hw = hardware::cached();
+    let text = format!(
+        "{} {}",
+        hw.manufacturer.as_deref().unwrap_or_default(),
+        hw.product_name.as_deref().unwrap_or_default()
+    )
+    .to_ascii_lowercase();
+
+    if text.contains("vmware") {
+        Some("vmware")
+    } else if text.contains("virtualbox") || text.contains("innotek") {
+        Some("virtualbox")
+    } else if text.contains("kvm") || text.contains("qemu") {
+        Some("kvm")
+    } else if text.contains("xen") || text.contains("hvm domu") {
+        Some("xen")
+    } else if text.contains("microsoft corporation") && text.contains("virtual machine") {
+        Some("hyper-v")
+    } else {
+        None
+    }
+}
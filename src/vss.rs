@@ -0,0 +1,100 @@
+//! 磁碟區陰影複製：Win32_ShadowStorage 的差異區域用量，Win32_ShadowCopy 計算每個磁碟區的複本數
+//!
+//! 沒有設定陰影儲存區的磁碟區不列出。部分系統需要系統管理員權限才查得到，
+//! 權限不足時記錄在 errors，不回傳空陣列假裝沒有使用。
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::{disk, wmi, MetricError};
+
+/// MaxSpace 為 UNBOUNDED 時的值
+const UNBOUNDED: u64 = u64::MAX;
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct VssVolume {
+    /// 被保護的磁碟區 GUID 路徑
+    pub volume: String,
+    pub mount_points: Vec<String>,
+    /// 差異區域所在的磁碟區；通常與 volume 相同
+    pub storage_volume: String,
+    pub used_bytes: Option<u64>,
+    pub allocated_bytes: Option<u64>,
+    /// 上限；設為無限制時為 None
+    pub maximum_bytes: Option<u64>,
+    pub shadow_copy_count: usize,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+pub async fn gather_vss(errors: &mut Vec<MetricError>) -> Vec<VssVolume> {
+    let result = tokio::task::spawn_blocking(query_vss)
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+    result.unwrap_or_else(|e| {
+        errors.push(MetricError {
+            metric: vec!["vss".into()],
+            err: e,
+        });
+        Vec::new()
+    })
+}
+
+fn query_vss() -> Result<Vec<VssVolume>, String> {
+    let storage = wmi::query(
+        "root\\CIMV2",
+        "SELECT Volume, DiffVolume, UsedSpace, AllocatedSpace, MaxSpace FROM Win32_ShadowStorage",
+    )
+    .map_err(|e| describe("Win32_ShadowStorage", e))?;
+    if storage.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for row in wmi::query("root\\CIMV2", "SELECT VolumeName FROM Win32_ShadowCopy")
+        .map_err(|e| describe("Win32_ShadowCopy", e))?
+    {
+        if let Some(name) = row.get_string("VolumeName") {
+            *counts.entry(name.to_lowercase()).or_default() += 1;
+        }
+    }
+
+    let mut volumes: Vec<VssVolume> = storage
+        .iter()
+        .filter_map(|row| {
+            let volume = reference_device_id(&row.get_string("Volume")?)?;
+            let storage_volume = row
+                .get_string("DiffVolume")
+                .and_then(|r| reference_device_id(&r))
+                .unwrap_or_else(|| volume.clone());
+            Some(VssVolume {
+                mount_points: disk::mount_points(&volume),
+                shadow_copy_count: counts.get(&volume.to_lowercase()).copied().unwrap_or(0),
+                used_bytes: row.get_u64("UsedSpace"),
+                allocated_bytes: row.get_u64("AllocatedSpace"),
+                maximum_bytes: row.get_u64("MaxSpace").filter(|&m| m != UNBOUNDED),
+                storage_volume,
+                volume,
+            })
+        })
+        .collect();
+    volumes.sort_by(|a, b| a.mount_points.cmp(&b.mount_points));
+    Ok(volumes)
+}
+
+/// `Win32_Volume.DeviceID="\\\\?\\Volume{…}\\"` → `\\?\Volume{…}\`；參照中的反斜線會加倍跳脫
+fn reference_device_id(reference: &str) -> Option<String> {
+    let start = reference.find('"')? + 1;
+    let end = reference.rfind('"')?;
+    (end > start).then(|| reference[start..end].replace("\\\\", "\\"))
+}
+
+/// WBEM_E_ACCESS_DENIED 補上需要系統管理員權限的提示
+fn describe(class: &str, err: String) -> String {
+    if err.contains("0x80041003") {
+        format!("{class}: access denied, administrator rights are required ({err})")
+    } else {
+        format!("{class}: {err}")
+    }
+}
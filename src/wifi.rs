@@ -0,0 +1,165 @@
+//! Wi-Fi 連線資訊：WlanEnumInterfaces 列舉無線介面，WlanQueryInterface 讀取目前連線、RSSI 與頻道
+use serde::Serialize;
+use windows::{
+    core::GUID,
+    Win32::{
+        Foundation::HANDLE,
+        NetworkManagement::WiFi::{
+            wlan_interface_state_connected, wlan_intf_opcode_channel_number,
+            wlan_intf_opcode_current_connection, wlan_intf_opcode_rssi, WlanCloseHandle,
+            WlanEnumInterfaces, WlanFreeMemory, WlanOpenHandle, WlanQueryInterface,
+            WLAN_CONNECTION_ATTRIBUTES, WLAN_INTERFACE_INFO_LIST, WLAN_INTF_OPCODE,
+        },
+    },
+};
+
+use crate::{
+    win::{format_mac, wide_to_string},
+    MetricError,
+};
+
+/// WlanOpenHandle 的用戶端版本：2 = Vista 以後的 API
+const WLAN_CLIENT_VERSION: u32 = 2;
+
+/// Drop 時 WlanCloseHandle 的用戶端 handle
+struct WlanHandle(HANDLE);
+
+impl Drop for WlanHandle {
+    fn drop(&mut self) {
+        unsafe {
+            WlanCloseHandle(self.0, None);
+        }
+    }
+}
+
+/* ---------- 資料結構 ---------- */
+
+#[derive(Serialize)]
+pub struct WifiData {
+    /// 介面卡描述，例如 `Intel(R) Wi-Fi 6 AX201 160MHz`
+    pub interface: String,
+    pub connected: bool,
+    /// 以下欄位只有在已連線時才有值
+    pub ssid: Option<String>,
+    /// 例如 `AA:BB:CC:DD:EE:FF`
+    pub bssid: Option<String>,
+    /// 0–1，對應 wlanSignalQuality 的 0–100
+    pub signal_quality_percent: Option<f32>,
+    pub rssi_dbm: Option<i32>,
+    pub channel: Option<u32>,
+    /// 802.11n / 802.11ac / 802.11ax…
+    pub phy_type: Option<String>,
+}
+
+/* ---------- 指標蒐集 ---------- */
+
+/// 沒有無線網卡時回傳空陣列；WLAN AutoConfig 服務未啟動時記錄 error
+pub fn gather_wifi(errors: &mut Vec<MetricError>) -> Vec<WifiData> {
+    match query_interfaces() {
+        Ok(list) => list,
+        Err(e) => {
+            errors.push(MetricError {
+                metric: vec!["wifi".into()],
+                err: e,
+            });
+            Vec::new()
+        }
+    }
+}
+
+fn query_interfaces() -> Result<Vec<WifiData>, String> {
+    unsafe {
+        let mut version = 0u32;
+        let mut handle = HANDLE::default();
+        let status = WlanOpenHandle(WLAN_CLIENT_VERSION, None, &mut version, &mut handle);
+        if status != 0 {
+            return Err(format!("WlanOpenHandle failed: {status}"));
+        }
+        let handle = WlanHandle(handle);
+
+        let mut list: *mut WLAN_INTERFACE_INFO_LIST = std::ptr::null_mut();
+        let status = WlanEnumInterfaces(handle.0, None, &mut list);
+        if status != 0 {
+            return Err(format!("WlanEnumInterfaces failed: {status}"));
+        }
+        let infos = std::slice::from_raw_parts(
+            (*list).InterfaceInfo.as_ptr(),
+            (*list).dwNumberOfItems as usize,
+        );
+
+        let mut out = Vec::new();
+        for info in infos {
+            let mut wifi = WifiData {
+                interface: wide_to_string(&info.strInterfaceDescription),
+                connected: info.isState == wlan_interface_state_connected,
+                ssid: None,
+                bssid: None,
+                signal_quality_percent: None,
+                rssi_dbm: None,
+                channel: None,
+                phy_type: None,
+            };
+            if wifi.connected {
+                fill_connection(&handle, &info.InterfaceGuid, &mut wifi);
+            }
+            out.push(wifi);
+        }
+        WlanFreeMemory(list as *const _);
+        Ok(out)
+    }
+}
+
+/// 已連線介面的細節；個別 opcode 失敗（例如驅動不支援 RSSI）只留 None
+fn fill_connection(handle: &WlanHandle, guid: &GUID, wifi: &mut WifiData) {
+    unsafe {
+        if let Some(ptr) = query_opcode(handle, guid, wlan_intf_opcode_current_connection) {
+            let conn = &*(ptr as *const WLAN_CONNECTION_ATTRIBUTES);
+            let assoc = &conn.wlanAssociationAttributes;
+            let len = (assoc.dot11Ssid.uSSIDLength as usize).min(assoc.dot11Ssid.ucSSID.len());
+            wifi.ssid = Some(String::from_utf8_lossy(&assoc.dot11Ssid.ucSSID[..len]).into_owned());
+            wifi.bssid = Some(format_mac(&assoc.dot11Bssid));
+            wifi.signal_quality_percent = Some(assoc.wlanSignalQuality.min(100) as f32 / 100.0);
+            wifi.phy_type = phy_name(assoc.dot11PhyType.0).map(String::from);
+            WlanFreeMemory(ptr);
+        }
+        if let Some(ptr) = query_opcode(handle, guid, wlan_intf_opcode_rssi) {
+            wifi.rssi_dbm = Some(*(ptr as *const i32));
+            WlanFreeMemory(ptr);
+        }
+        if let Some(ptr) = query_opcode(handle, guid, wlan_intf_opcode_channel_number) {
+            wifi.channel = Some(*(ptr as *const u32));
+            WlanFreeMemory(ptr);
+        }
+    }
+}
+
+/// 成功時回傳需以 WlanFreeMemory 釋放的指標
+fn query_opcode(
+    handle: &WlanHandle,
+    guid: &GUID,
+    opcode: WLAN_INTF_OPCODE,
+) -> Option<*const core::ffi::c_void> {
+    let mut size = 0u32;
+    let mut ptr: *mut core::ffi::c_void = std::ptr::null_mut();
+    let status =
+        unsafe { WlanQueryInterface(handle.0, guid, opcode, None, &mut size, &mut ptr, None) };
+    (status == 0 && !ptr.is_null()).then_some(ptr as *const _)
+}
+
+/// DOT11_PHY_TYPE → IEEE 標準名稱
+fn phy_name(phy: i32) -> Option<&'static str> {
+    Some(match phy {
+        1 => "802.11 FHSS",
+        2 => "802.11 DSSS",
+        3 => "802.11 IR",
+        4 => "802.11a",
+        5 => "802.11b",
+        6 => "802.11g",
+        7 => "802.11n",
+        8 => "802.11ac",
+        9 => "802.11ad",
+        10 => "802.11ax",
+        11 => "802.11be",
+        _ => return None,
+    })
+}
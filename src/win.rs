@@ -0,0 +1,213 @@
+//! 共用的 Win32 小工具：HANDLE 自動關閉、UTF-16 字串轉換、IP Helper 表格讀取、位址與時間格式化、登錄檔讀取
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use windows::{
+    core::{HSTRING, PWSTR},
+    Win32::{
+        Foundation::{CloseHandle, ERROR_BUFFER_OVERFLOW, ERROR_INSUFFICIENT_BUFFER, HANDLE},
+        Networking::WinSock::{AF_INET, AF_INET6, SOCKADDR, SOCKADDR_IN, SOCKADDR_IN6},
+        System::Registry::{
+            RegCloseKey, RegEnumKeyExW, RegGetValueW, RegOpenKeyExW, HKEY, KEY_READ, RRF_RT_ANY,
+            RRF_RT_REG_DWORD, RRF_RT_REG_SZ,
+        },
+    },
+};
+
+/// Drop 時 CloseHandle 的 HANDLE
+pub struct OwnedHandle(pub HANDLE);
+
+impl Drop for OwnedHandle {
+    fn drop(&mut self) {
+        if !self.0.is_invalid() {
+            unsafe {
+                let _ = CloseHandle(self.0);
+            }
+        }
+    }
+}
+
+/// 以 NUL 結尾的 UTF-16 固定長度陣列轉 String
+pub fn wide_to_string(buf: &[u16]) -> String {
+    let len = buf.iter().position(|&c| c == 0).unwrap_or(buf.len());
+    String::from_utf16_lossy(&buf[..len])
+}
+
+/// FILETIME（1601-01-01 起的 100ns）與 Unix epoch 的差，單位秒
+const FILETIME_UNIX_OFFSET_SECS: i64 = 11_644_473_600;
+
+/// FILETIME → UTC 時間；0 代表未設定，回傳 None
+pub fn filetime_to_datetime(ft: i64) -> Option<DateTime<Utc>> {
+    if ft <= 0 {
+        return None;
+    }
+    let secs = ft / 10_000_000 - FILETIME_UNIX_OFFSET_SECS;
+    DateTime::<Utc>::from_timestamp(secs, 0)
+}
+
+/// FILETIME → RFC3339（UTC）；0 代表未設定，回傳 None
+pub fn filetime_to_rfc3339(ft: i64) -> Option<String> {
+    filetime_to_datetime(ft).map(|t| t.to_rfc3339())
+}
+
+/// OLE Automation DATE（1899-12-30 起的天數）與 Unix epoch 的差，單位天
+const OLE_DATE_UNIX_OFFSET_DAYS: f64 = 25_569.0;
+
+/// OLE Automation DATE → 不帶時區的時間；0 代表未設定，回傳 None
+///
+/// DATE 本身不含時區，是 UTC 還是本地時間由提供資料的 API 決定
+pub fn ole_date_to_naive(date: f64) -> Option<NaiveDateTime> {
+    if date <= 0.0 {
+        return None;
+    }
+    let secs = (date - OLE_DATE_UNIX_OFFSET_DAYS) * 86_400.0;
+    DateTime::<Utc>::from_timestamp(secs as i64, 0).map(|t| t.naive_utc())
+}
+
+/// IP Helper 的 GetXxxTable 系列：表格大小會在兩次呼叫間變動，緩衝區不足時依新大小重試
+/// （多數 API 回 ERROR_INSUFFICIENT_BUFFER，GetAdaptersAddresses 回 ERROR_BUFFER_OVERFLOW）
+///
+/// 回傳 u64 緩衝區以確保結構對齊；失敗時回傳 Win32 錯誤碼
+pub fn read_table(mut call: impl FnMut(*mut u64, &mut u32) -> u32) -> Result<Vec<u64>, u32> {
+    let mut buf = vec![0u64; 2048];
+    let mut size = (buf.len() * 8) as u32;
+    loop {
+        match call(buf.as_mut_ptr(), &mut size) {
+            0 => return Ok(buf),
+            s if s == ERROR_INSUFFICIENT_BUFFER.0 || s == ERROR_BUFFER_OVERFLOW.0 => {
+                buf = vec![0; (size as usize).div_ceil(8)]
+            }
+            s => return Err(s),
+        }
+    }
+}
+
+/// `{ dwNumEntries: u32, table: [T; ANY_SIZE] }` 形式的表格內容
+///
+/// # Safety
+/// `buf` 必須是對應 API 以 `T` 的表格格式填好的緩衝區
+pub unsafe fn table_rows<T>(buf: &[u64]) -> &[T] {
+    unsafe {
+        let count = *(buf.as_ptr() as *const u32) as usize;
+        let offset = std::mem::size_of::<u32>().next_multiple_of(std::mem::align_of::<T>());
+        std::slice::from_raw_parts((buf.as_ptr() as *const u8).add(offset) as *const T, count)
+    }
+}
+
+/// 硬體位址格式化為 `AA:BB:CC:DD:EE:FF`
+pub fn format_mac(bytes: &[u8]) -> String {
+    let parts: Vec<String> = bytes.iter().map(|b| format!("{b:02X}")).collect();
+    parts.join(":")
+}
+
+/// SOCKADDR（或 SOCKADDR_INET）→ IpAddr；不是 IPv4/IPv6 時回傳 None
+///
+/// # Safety
+/// `sa` 必須為 null 或指向依 sa_family 填好的位址結構
+pub unsafe fn sockaddr_to_ip(sa: *const SOCKADDR) -> Option<IpAddr> {
+    unsafe {
+        let family = sa.as_ref()?.sa_family;
+        if family == AF_INET {
+            let sin = &*(sa as *const SOCKADDR_IN);
+            Some(Ipv4Addr::from(sin.sin_addr.S_un.S_addr.to_ne_bytes()).into())
+        } else if family == AF_INET6 {
+            let sin6 = &*(sa as *const SOCKADDR_IN6);
+            Some(Ipv6Addr::from(sin6.sin6_addr.u.Byte).into())
+        } else {
+            None
+        }
+    }
+}
+
+/// 讀取 REG_SZ；值不存在或型別不符時回傳 None
+pub fn reg_string(root: HKEY, subkey: &str, value: &str) -> Option<String> {
+    let (subkey, value) = (HSTRING::from(subkey), HSTRING::from(value));
+    unsafe {
+        let mut size = 0u32;
+        RegGetValueW(root, &subkey, &value, RRF_RT_REG_SZ, None, None, Some(&mut size)).ok().ok()?;
+        let mut buf = vec![0u16; (size as usize).div_ceil(2)];
+        RegGetValueW(
+            root,
+            &subkey,
+            &value,
+            RRF_RT_REG_SZ,
+            None,
+            Some(buf.as_mut_ptr().cast()),
+            Some(&mut size),
+        )
+        .ok()
+        .ok()?;
+        Some(wide_to_string(&buf))
+    }
+}
+
+
+/// 讀取 REG_DWORD；值不存在或型別不符時回傳 None
+pub fn reg_dword(root: HKEY, subkey: &str, value: &str) -> Option<u32> {
+    let (subkey, value) = (HSTRING::from(subkey), HSTRING::from(value));
+    let mut data = 0u32;
+    let mut size = std::mem::size_of::<u32>() as u32;
+    unsafe {
+        RegGetValueW(
+            root,
+            &subkey,
+            &value,
+            RRF_RT_REG_DWORD,
+            None,
+            Some((&mut data as *mut u32).cast()),
+            Some(&mut size),
+        )
+    }
+    .ok()
+    .ok()?;
+    Some(data)
+}
+
+/// 列出機碼下一層的子機碼名稱；機碼不存在時為空陣列
+pub fn reg_subkeys(root: HKEY, subkey: &str) -> Vec<String> {
+    let mut key = HKEY::default();
+    let mut names = Vec::new();
+    unsafe {
+        if RegOpenKeyExW(root, &HSTRING::from(subkey), 0, KEY_READ, &mut key).is_err() {
+            return names;
+        }
+        // 機碼名稱上限 255 字元
+        let mut buf = [0u16; 256];
+        for index in 0.. {
+            let mut len = buf.len() as u32;
+            let rc = RegEnumKeyExW(
+                key,
+                index,
+                PWSTR(buf.as_mut_ptr()),
+                &mut len,
+                None,
+                PWSTR::null(),
+                None,
+                None,
+            );
+            if rc.is_err() {
+                break;
+            }
+            names.push(String::from_utf16_lossy(&buf[..len as usize]));
+        }
+        let _ = RegCloseKey(key);
+    }
+    names
+}
+
+/// 登錄機碼是否存在
+pub fn reg_key_exists(root: HKEY, subkey: &str) -> bool {
+    let mut key = HKEY::default();
+    unsafe {
+        if RegOpenKeyExW(root, &HSTRING::from(subkey), 0, KEY_READ, &mut key).is_err() {
+            return false;
+        }
+        let _ = RegCloseKey(key);
+    }
+    true
+}
+
+/// 登錄值是否存在，不限型別
+pub fn reg_value_exists(root: HKEY, subkey: &str, value: &str) -> bool {
+    let (subkey, value) = (HSTRING::from(subkey), HSTRING::from(value));
+    unsafe { RegGetValueW(root, &subkey, &value, RRF_RT_ANY, None, None, None) }.is_ok()
+}
@@ -0,0 +1,143 @@
+//! WMI 查詢：連線 namespace 後執行 WQL，逐列讀取屬性
+//!
+//! 所有函式都會阻塞（WMI 可能花上數百毫秒），呼叫端請放在 spawn_blocking 內。
+use chrono::NaiveDateTime;
+use std::rc::Rc;
+use windows::{
+    core::{BSTR, HSTRING, VARIANT},
+    Win32::System::{
+        Com::{
+            CoCreateInstance, CoSetProxyBlanket, CLSCTX_INPROC_SERVER, EOAC_NONE,
+            RPC_C_AUTHN_LEVEL_CALL, RPC_C_IMP_LEVEL_IMPERSONATE,
+        },
+        Rpc::{RPC_C_AUTHN_WINNT, RPC_C_AUTHZ_NONE},
+        Wmi::{
+            IWbemClassObject, IWbemLocator, WbemLocator, WBEM_FLAG_FORWARD_ONLY,
+            WBEM_FLAG_RETURN_IMMEDIATELY, WBEM_S_TIMEDOUT,
+        },
+    },
+};
+
+use crate::com::ComGuard;
+
+/// 單次 IEnumWbemClassObject::Next 等待上限（毫秒），避免壞掉的 provider 卡死 worker
+const NEXT_TIMEOUT_MS: i32 = 5_000;
+
+/// 查詢結果中的一列（一個 WMI 物件）
+///
+/// 每列共用查詢時的 ComGuard，最後一列釋放後才 CoUninitialize；
+/// 欄位依宣告順序釋放，物件先於 guard。Rc 讓它無法被帶到別的執行緒。
+pub struct WmiRow {
+    obj: IWbemClassObject,
+    _com: Rc<ComGuard>,
+}
+
+impl WmiRow {
+    fn get(&self, name: &str) -> Option<VARIANT> {
+        let mut v = VARIANT::new();
+        unsafe { self.obj.Get(&HSTRING::from(name), 0, &mut v, None, None) }.ok()?;
+        if v.is_empty() {
+            None
+        } else {
+            Some(v)
+        }
+    }
+
+    pub fn get_u32(&self, name: &str) -> Option<u32> {
+        self.get(name).and_then(|v| u32::try_from(&v).ok())
+    }
+
+    /// WMI 的 uint64 以字串（BSTR）傳回，兩種型別都接受
+    pub fn get_u64(&self, name: &str) -> Option<u64> {
+        let v = self.get(name)?;
+        u64::try_from(&v)
+            .ok()
+            .or_else(|| BSTR::try_from(&v).ok()?.to_string().parse().ok())
+    }
+
+    pub fn get_string(&self, name: &str) -> Option<String> {
+        self.get(name)
+            .and_then(|v| BSTR::try_from(&v).ok())
+            .map(|b| b.to_string())
+    }
+
+    pub fn get_f64(&self, name: &str) -> Option<f64> {
+        self.get(name).and_then(|v| f64::try_from(&v).ok())
+    }
+
+    pub fn get_bool(&self, name: &str) -> Option<bool> {
+        self.get(name).and_then(|v| bool::try_from(&v).ok())
+    }
+
+    /// CIM datetime（`yyyymmddHHMMSS.ffffff±UUU`，UUU 為 UTC 偏移分鐘數）→ RFC3339（UTC）
+    pub fn get_datetime(&self, name: &str) -> Option<String> {
+        let raw = self.get_string(name)?;
+        let local = NaiveDateTime::parse_from_str(raw.get(..14)?, "%Y%m%d%H%M%S").ok()?;
+        let offset: i64 = raw.get(21..25)?.parse().ok()?;
+        let utc = local - chrono::Duration::minutes(offset);
+        Some(utc.and_utc().to_rfc3339())
+    }
+}
+
+/// 在 `namespace`（例如 `root\CIMV2`）執行 WQL 查詢
+pub fn query(namespace: &str, wql: &str) -> Result<Vec<WmiRow>, String> {
+    let com = Rc::new(ComGuard::init()?);
+
+    unsafe {
+        let locator: IWbemLocator = CoCreateInstance(&WbemLocator, None, CLSCTX_INPROC_SERVER)
+            .map_err(|e| format!("CoCreateInstance(WbemLocator) failed: {e}"))?;
+
+        let services = locator
+            .ConnectServer(
+                &BSTR::from(namespace),
+                &BSTR::new(),
+                &BSTR::new(),
+                &BSTR::new(),
+                0,
+                &BSTR::new(),
+                None,
+            )
+            .map_err(|e| format!("ConnectServer({namespace}) failed: {e}"))?;
+
+        CoSetProxyBlanket(
+            &services,
+            RPC_C_AUTHN_WINNT,
+            RPC_C_AUTHZ_NONE,
+            None,
+            RPC_C_AUTHN_LEVEL_CALL,
+            RPC_C_IMP_LEVEL_IMPERSONATE,
+            None,
+            EOAC_NONE,
+        )
+        .map_err(|e| format!("CoSetProxyBlanket failed: {e}"))?;
+
+        let enumerator = services
+            .ExecQuery(
+                &BSTR::from("WQL"),
+                &BSTR::from(wql),
+                WBEM_FLAG_FORWARD_ONLY | WBEM_FLAG_RETURN_IMMEDIATELY,
+                None,
+            )
+            .map_err(|e| format!("ExecQuery failed: {e}"))?;
+
+        let mut rows = Vec::new();
+        loop {
+            let mut objs = [None];
+            let mut returned = 0;
+            let hr = enumerator.Next(NEXT_TIMEOUT_MS, &mut objs, &mut returned);
+            if hr.0 == WBEM_S_TIMEDOUT.0 {
+                return Err(format!("WMI query timed out after {NEXT_TIMEOUT_MS}ms"));
+            }
+            hr.ok()
+                .map_err(|e| format!("IEnumWbemClassObject::Next failed: {e}"))?;
+            match objs[0].take() {
+                Some(obj) if returned > 0 => rows.push(WmiRow {
+                    obj,
+                    _com: com.clone(),
+                }),
+                _ => break,
+            }
+        }
+        Ok(rows)
+    }
+}